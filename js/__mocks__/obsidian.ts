@@ -64,6 +64,8 @@ export class Vault {
 
   trash = jest.fn();
 
+  read = jest.fn();
+
   create = jest.fn();
 
   createBinary = jest.fn();
@@ -1,7 +1,12 @@
 import { App, Modal, Notice, Plugin, PluginSettingTab, request, RequestUrlParam, Setting, Vault } from "obsidian";
 import { LocalStorageSettings } from "src/localStorageSettings";
 import wasm from "../../rust/Cargo.toml";
-import { DEFAULT_SETTINGS, get_slack_message_modal_on_close_helper, ObsidianSlackPluginSettings } from "./utils";
+import {
+  DEFAULT_SETTINGS,
+  get_slack_message_modal_on_close_helper,
+  MergeSavedNote,
+  ObsidianSlackPluginSettings,
+} from "./utils";
 
 export default class ObsidianSlackPlugin extends Plugin {
   settings: ObsidianSlackPluginSettings;
@@ -17,7 +22,13 @@ export default class ObsidianSlackPlugin extends Plugin {
       id: "get-slack-message",
       name: "Get Slack Message by URL",
       callback: () => {
-        new GetSlackMessageModal(this.app, this, exports.get_slack_message).open();
+        new GetSlackMessageModal(
+          this.app,
+          this,
+          exports.get_slack_message,
+          exports.take_internal_bug,
+          exports.merge_saved_note,
+        ).open();
       },
     });
 
@@ -43,6 +54,8 @@ export class GetSlackMessageModal extends Modal {
     feature_flags: any,
     request_func: (params: RequestUrlParam) => Promise<string>,
   ) => any;
+  take_internal_bug: () => string | null;
+  merge_saved_note: MergeSavedNote;
 
   constructor(
     app: App,
@@ -54,9 +67,13 @@ export class GetSlackMessageModal extends Modal {
       feature_flags: any,
       request_func: (params: RequestUrlParam) => Promise<string>,
     ) => any,
+    take_internal_bug: () => string | null,
+    merge_saved_note: MergeSavedNote,
   ) {
     super(app);
     this.get_slack_message = get_slack_message;
+    this.take_internal_bug = take_internal_bug;
+    this.merge_saved_note = merge_saved_note;
     this.plugin = plugin;
   }
 
@@ -97,6 +114,8 @@ export class GetSlackMessageModal extends Modal {
       this.get_slack_message,
       this.plugin.settings,
       this.app.vault,
+      this.take_internal_bug,
+      this.merge_saved_note,
     );
     contentEl.empty();
   }
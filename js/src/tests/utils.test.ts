@@ -170,6 +170,48 @@ describe("process result logic", () => {
     expect(mockNotice).toBeCalledTimes(2);
   });
 
+  test("merges the note saved before into the result when the json file already exists", async () => {
+    let mock_result = { message_and_thread: { thread: [{ ts: "2" }] }, file_name: "mock_filename" };
+    let saved = { message_and_thread: { thread: [{ ts: "1" }] }, file_name: "mock_filename" };
+    let merged = { message_and_thread: { thread: [{ ts: "1" }, { ts: "2" }] }, file_name: "mock_filename" };
+    let merge_saved_note = jest.fn((saved: any, result: any, strip_nulls: boolean) => merged);
+    let mock_vault = new Vault();
+
+    window.alert = jest.fn();
+    jest.spyOn(mock_vault, "getConfig")
+      .mockImplementation(() => {
+        return "abc";
+      });
+    jest.spyOn(mock_vault, "create")
+      .mockImplementationOnce(() => {
+        throw new Error("File already exists");
+      })
+      .mockImplementationOnce(() => {
+        return Promise.resolve(new TFile());
+      });
+    jest.spyOn(mock_vault, "read")
+      .mockImplementation(() => {
+        return Promise.resolve(JSON.stringify(saved, utils.replacer, 2));
+      });
+    jest.spyOn(mock_vault, "getFiles")
+      .mockImplementation(() => {
+        let tfile = new TFile();
+        tfile.path = mock_result.file_name;
+        return [tfile];
+      });
+
+    await utils.process_result("cookie", mock_result, mock_vault, merge_saved_note);
+
+    expect(window.alert).toBeCalledTimes(0);
+    expect(merge_saved_note).toBeCalledWith(saved, mock_result, false);
+    expect(mock_vault.trash).toBeCalledTimes(1);
+    expect(mock_vault.create).toHaveBeenNthCalledWith(
+      2,
+      "abc/mock_filename",
+      JSON.stringify(merged, utils.replacer, 2),
+    );
+  });
+
   test("sends notice on success result and successful save of result, save of attachments, and copies to clipboard even if attachments files already exists", async () => {
     let mock_result = { message_and_thread: {}, file_name: "mock_filename", file_links: new Map([["file1", "link"]]) };
     let mock_vault = new Vault();
@@ -379,7 +421,7 @@ describe("process result logic", () => {
     expect(window.alert).toBeCalledTimes(1);
     expect(navigator.clipboard.writeText).toBeCalledTimes(0);
     expect(utils.save_result).toBeCalledTimes(1);
-    expect(utils.save_result).toBeCalledWith("cookie", mock_result, mock_vault);
+    expect(utils.save_result).toBeCalledWith("cookie", mock_result, mock_vault, undefined);
     expect(mockNotice).toBeCalledTimes(0);
   });
 });
@@ -423,7 +465,40 @@ describe("get slack message logic", () => {
 
     expect(window.alert).toBeCalledTimes(0);
     expect(utils.process_result).toBeCalledTimes(1);
-    expect(utils.process_result).toBeCalledWith(cookie, "resolve", mock_vault);
+    expect(utils.process_result).toBeCalledWith(cookie, "resolve", mock_vault, undefined);
+  });
+
+  test("a call aborted by a bug shows the bug from the wasm module", async () => {
+    let get_slack_message_func = (
+      apiToken: string,
+      cookie: string,
+      url: string,
+      feature_flags: any,
+      request_func: (params: RequestUrlParam) => Promise<string>,
+    ): Promise<string> => {
+      return Promise.reject(new Error("RuntimeError: unreachable"));
+    };
+    let take_internal_bug = jest.fn(() => "[INTERNAL] Ran into a bug: index out of bounds");
+    let mock_vault = new Vault();
+
+    window.alert = jest.fn();
+    jest.spyOn(utils, "process_result");
+
+    await utils.get_slack_message_modal_on_close_helper(
+      "an api token",
+      "a cookie",
+      "a url",
+      get_slack_message_func,
+      utils.DEFAULT_SETTINGS,
+      mock_vault,
+      take_internal_bug,
+    );
+
+    expect(take_internal_bug).toBeCalledTimes(1);
+    expect(window.alert).toBeCalledWith(
+      "[INTERNAL] Ran into a bug: index out of bounds\nPlease reload the plugin",
+    );
+    expect(utils.process_result).toBeCalledTimes(0);
   });
 
   test("empty api token shows alert and doesn't attempt to process result", async () => {
@@ -23,14 +23,17 @@ export const DEFAULT_SETTINGS: ObsidianSlackPluginSettings = {
   get_team_info: false,
 };
 
-export async function process_result(cookie: string, result: any, vault: Vault) {
+// merges the thread of a note saved before into a result, see `merge_saved_note` of the wasm module
+export type MergeSavedNote = (saved: any, result: any, strip_nulls: boolean) => any;
+
+export async function process_result(cookie: string, result: any, vault: Vault, merge_saved_note?: MergeSavedNote) {
   try {
     if (typeof (result) === "string") {
       alert(result);
       return;
     }
 
-    let file_saved = await mod.save_result(cookie, result, vault);
+    let file_saved = await mod.save_result(cookie, result, vault, merge_saved_note);
 
     if (file_saved) {
       await navigator.clipboard.writeText(result.file_name);
@@ -47,7 +50,12 @@ export async function process_result(cookie: string, result: any, vault: Vault)
   }
 }
 
-export async function save_result(cookie: string, result: any, vault: Vault): Promise<boolean> {
+export async function save_result(
+  cookie: string,
+  result: any,
+  vault: Vault,
+  merge_saved_note?: MergeSavedNote,
+): Promise<boolean> {
   console.log(result);
 
   let attachment_path = vault.getConfig("attachmentFolderPath");
@@ -60,6 +68,15 @@ export async function save_result(cookie: string, result: any, vault: Vault): Pr
     if (e instanceof Error) {
       if (e.message.includes("File already exists")) {
         let tfile = vault.getFiles().filter((file) => file_path.includes(file.path))[0];
+        if (merge_saved_note) {
+          // a refresh keeps the messages of the saved note, e.g. deleted ones
+          let merged = merge_saved_note(JSON.parse(await vault.read(tfile), reviver), result, false);
+          if (typeof (merged) === "string") {
+            console.log("Could not merge the saved note: " + merged);
+          } else {
+            result_data = JSON.stringify(merged, replacer, 2);
+          }
+        }
         await vault.trash(tfile, true);
         tfiles = [await vault.create(file_path, result_data)];
       } else {
@@ -111,25 +128,50 @@ export async function get_slack_message_modal_on_close_helper(
   ) => any,
   settings: ObsidianSlackPluginSettings,
   vault: Vault,
+  take_internal_bug?: () => string | null,
+  merge_saved_note?: MergeSavedNote,
 ): Promise<any> {
   if (api_token && cookie) {
     // do nothing on empty url
     if (url) {
-      let result = await get_slack_message_func(api_token, cookie, url, {
-        "get_users": settings.get_users,
-        "get_channel_info": settings.get_channel_info,
-        "get_file_data": settings.get_file_data,
-        "get_team_info": settings.get_team_info,
-      }, request);
+      let result;
+      try {
+        result = await get_slack_message_func(api_token, cookie, url, {
+          "get_users": settings.get_users,
+          "get_channel_info": settings.get_channel_info,
+          "get_file_data": settings.get_file_data,
+          "get_team_info": settings.get_team_info,
+        }, request);
+      } catch (e) {
+        // get_slack_message can throw: a bug in the wasm module traps and aborts the
+        // call with a RuntimeError instead of returning an error. Rust only catches
+        // panics outside of wasm, here the module keeps just the panic's message for
+        // the bug report
+        let bug = take_internal_bug ? take_internal_bug() : null;
+        alert((bug ?? "Getting the slack message failed: " + e) + "\nPlease reload the plugin");
+        return;
+      }
 
-      await mod.process_result(cookie, result, vault);
+      await mod.process_result(cookie, result, vault, merge_saved_note);
     }
   } else {
     alert("apiToken or cookie or url was null, undefined, or empty. Aborting operation");
   }
 }
 
+// the inverse of `replacer`, for reading a saved note back
+export function reviver(key: any, value: any) {
+  if (value && value.dataType === "Map") {
+    return new Map(value.value);
+  }
+  return value;
+}
+
 export function replacer(key: any, value: any[]) {
+  // credentials must never end up in the vault
+  if (key === "refreshed_credentials") {
+    return undefined;
+  }
   if (value instanceof Map) {
     return {
       dataType: "Map",
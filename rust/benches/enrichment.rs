@@ -0,0 +1,171 @@
+//! Benchmarks of the enrichment of large exports, run natively with `cargo bench`.
+//! Every benchmark runs over the same synthetic thread of [`MESSAGES`] messages
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use obsidian_slack::{
+    components::{FileName, ObsidianSlackComponents, ObsidianSlackComponentsBuilder},
+    finalize_options::FinalizeOptions,
+    messages::{Message, MessageAndThread, Messages},
+    note_links::{link_saved_notes, SavedNote},
+    render::render_note,
+    users::{CollectUsers, User, Users},
+};
+use serde_json::json;
+
+const MESSAGES: usize = 10_000;
+const USERS: usize = 200;
+const CHANNEL_ID: &str = "C0000000000";
+const NOW_MS: f64 = 1_700_000_000_000.0;
+
+fn user_id(index: usize) -> String {
+    format!("U{:010}", index % USERS)
+}
+
+fn ts(index: usize) -> String {
+    format!("{}.{:06}", 1_700_000_000 + index, index % 1_000_000)
+}
+
+fn users() -> Users {
+    (0..USERS)
+        .map(|index| {
+            let user = User {
+                id: user_id(index),
+                name: Some(format!("user{}", index)),
+                real_name: Some(format!("User Number {}", index)),
+                tz_offset: Some(3600),
+                ..User::default()
+            };
+            (user.id.clone(), user)
+        })
+        .collect()
+}
+
+/// A thread where every message mentions a user, links to another message and to
+/// an issue, and has a few reactions
+fn thread() -> Messages {
+    Messages(
+        (0..MESSAGES)
+            .map(|index| {
+                serde_json::from_value::<Message>(json!({
+                    "type": "message",
+                    "user": user_id(index),
+                    "text": format!(
+                        "Reply {} for <@{}>, see <https://workspace.slack.com/archives/{}/p{}|this thread> and <https://github.com/owner/repo/issues/{}|the issue> #deploy",
+                        index,
+                        user_id(index + 1),
+                        CHANNEL_ID,
+                        ts(index % 100).replace('.', ""),
+                        index,
+                    ),
+                    "ts": ts(index),
+                    "thread_ts": ts(0),
+                    "reactions": [
+                        { "name": "eyes", "users": [user_id(index + 2), user_id(index + 3)], "count": 2 },
+                        { "name": "tada", "users": [user_id(index + 4)], "count": 1 },
+                    ],
+                }))
+                .expect("Expected a valid synthetic message")
+            })
+            .collect(),
+    )
+}
+
+fn components(thread: Messages) -> ObsidianSlackComponents {
+    ObsidianSlackComponentsBuilder::default()
+        .message_and_thread(MessageAndThread {
+            message: Messages(thread.iter().take(1).cloned().collect()),
+            thread,
+        })
+        .file_name(FileName(format!("{}-{}", CHANNEL_ID, ts(0))))
+        .users(Some(users()))
+        .build()
+        .expect("Expected to build the components")
+}
+
+fn finalize_messages(c: &mut Criterion) {
+    let thread = thread();
+    let users = users();
+    let options = FinalizeOptions::default();
+    c.bench_function("finalize_messages", |b| {
+        b.iter_batched(
+            || MessageAndThread {
+                message: Messages(thread.iter().take(1).cloned().collect()),
+                thread: thread.clone(),
+            },
+            |mut message_and_thread| {
+                message_and_thread
+                    .finalize_message_and_thread(Some(&users), &options, NOW_MS)
+                    .expect("Expected to finalize the messages");
+                message_and_thread
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn collect_users(c: &mut Criterion) {
+    let thread = thread();
+    c.bench_function("collect_users", |b| {
+        b.iter(|| {
+            black_box(&thread)
+                .collect_users()
+                .expect("Expected every message to have a user")
+        })
+    });
+}
+
+fn mrkdwn_conversion(c: &mut Criterion) {
+    let thread = thread();
+    let saved_notes = (0..100)
+        .map(|index| SavedNote {
+            channel_id: CHANNEL_ID.to_string(),
+            thread_ts: ts(index),
+            note: format!("Slack/Thread {}", index),
+        })
+        .collect::<Vec<SavedNote>>();
+    c.bench_function("link_saved_notes", |b| {
+        b.iter(|| {
+            thread
+                .iter()
+                .filter_map(|message| message.text.as_deref())
+                .map(|text| link_saved_notes(black_box(text), &saved_notes))
+                .collect::<Vec<String>>()
+        })
+    });
+}
+
+fn serialization(c: &mut Criterion) {
+    let components = ObsidianSlackComponents::finalize(
+        components(thread()),
+        &FinalizeOptions::default(),
+        NOW_MS,
+    )
+    .expect("Expected to finalize the components");
+    c.bench_function("serialize_components", |b| {
+        b.iter(|| serde_json::to_vec(black_box(&components)).expect("Expected to serialize"))
+    });
+    c.bench_function("render_note", |b| {
+        b.iter(|| render_note(black_box(&components), true))
+    });
+}
+
+fn finalize_components(c: &mut Criterion) {
+    let components = components(thread());
+    let options = FinalizeOptions::default();
+    c.bench_function("finalize_components", |b| {
+        b.iter_batched(
+            || components.clone(),
+            |components| {
+                ObsidianSlackComponents::finalize(components, &options, NOW_MS)
+                    .expect("Expected to finalize the components")
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group! {
+    name = enrichment;
+    config = Criterion::default().sample_size(10);
+    targets = finalize_messages, collect_users, mrkdwn_conversion, serialization, finalize_components
+}
+criterion_main!(enrichment);
@@ -0,0 +1,18 @@
+//! Records the commit the crate is built from for `build_info::BuildInfo`, when
+//! it is built from a git checkout
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+    let git_sha = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(git_sha) = git_sha {
+        println!("cargo:rustc-env=OBSIDIAN_SLACK_GIT_SHA={}", git_sha.trim());
+    }
+}
@@ -0,0 +1,144 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::messages::{Message, MessageAndThread};
+
+/// Reactions that mark a thread as resolved unless overridden by `resolved_emoji`
+pub const DEFAULT_RESOLVED_EMOJI: [&str; 3] = ["white_check_mark", "heavy_check_mark", "resolved"];
+
+const MS_PER_DAY: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// How recently the thread was active, to suggest archiving threads that are
+/// done. Only assessed when `finished_after_days` is set
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Display)]
+#[display(Debug)]
+pub struct ThreadActivity {
+    /// ts of the newest message of the thread
+    pub last_activity_ts: Option<String>,
+    /// Whole days from the newest message to the save
+    pub days_since_last_activity: Option<u64>,
+    /// Whether a message of the thread was reacted with one of the resolved emoji
+    pub is_resolved: bool,
+    /// Resolved, or without replies for `finished_after_days`
+    pub is_likely_finished: bool,
+}
+
+impl ThreadActivity {
+    /// The activity of the thread at `now_ms`, unix milliseconds. Skin tones of
+    /// reactions are ignored, `resolved_emoji` may be written with or without
+    /// colons
+    pub fn assess(
+        message_and_thread: &MessageAndThread,
+        now_ms: f64,
+        finished_after_days: u64,
+        resolved_emoji: &[String],
+    ) -> ThreadActivity {
+        let last_activity_ts = message_and_thread
+            .thread
+            .iter()
+            .filter_map(|message| message.ts.as_ref())
+            .filter_map(|ts| ts.parse::<f64>().ok().map(|seconds| (seconds, ts)))
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        let days_since_last_activity = last_activity_ts
+            .map(|(seconds, _)| ((now_ms - seconds * 1000.0) / MS_PER_DAY).max(0.0) as u64);
+
+        let is_resolved = message_and_thread
+            .thread
+            .iter()
+            .any(|message| is_reacted_with_any(message, resolved_emoji));
+        let is_idle = days_since_last_activity.is_some_and(|days| days >= finished_after_days);
+
+        ThreadActivity {
+            last_activity_ts: last_activity_ts.map(|(_, ts)| ts.to_string()),
+            days_since_last_activity,
+            is_resolved,
+            is_likely_finished: is_resolved || is_idle,
+        }
+    }
+}
+
+fn is_reacted_with_any(message: &Message, emoji: &[String]) -> bool {
+    message
+        .reactions
+        .iter()
+        .flat_map(|reactions| reactions.iter())
+        .map(|reaction| reaction.name.split("::").next().unwrap_or(&reaction.name))
+        .any(|name| emoji.iter().any(|emoji| emoji.trim_matches(':') == name))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of telling whether a thread is likely finished, run with `cargo test`
+
+    use crate::{activity::ThreadActivity, messages::MessageAndThread};
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    /// 2024-01-31T12:00:00Z
+    const NOW_MS: f64 = 1_706_702_400_000.0;
+    /// 2024-01-01T00:00:00Z
+    const FIRST_OF_JANUARY: &str = "1704067200.000100";
+    /// 2024-01-30T00:00:00Z
+    const DAY_BEFORE: &str = "1706572800.000200";
+
+    fn thread(messages: Value) -> MessageAndThread {
+        serde_json::from_value(json!({ "message": [], "thread": messages }))
+            .expect("Expected a valid thread")
+    }
+
+    fn resolved_emoji() -> Vec<String> {
+        vec!["white_check_mark".to_string(), ":done:".to_string()]
+    }
+
+    #[rstest]
+    #[case::idle(json!([{ "ts": FIRST_OF_JANUARY }]), Some(30), false, true)]
+    #[case::active(
+    json!([{ "ts": FIRST_OF_JANUARY }, { "ts": DAY_BEFORE }]),
+    Some(1),
+    false,
+    false
+)]
+    #[case::resolved(
+    json!([{ "ts": DAY_BEFORE, "reactions": [{ "name": "white_check_mark", "users": [], "count": 1 }] }]),
+    Some(1),
+    true,
+    true
+)]
+    #[case::resolved_with_skin_tone(
+    json!([{ "ts": DAY_BEFORE, "reactions": [{ "name": "done::skin-tone-2", "users": [], "count": 1 }] }]),
+    Some(1),
+    true,
+    true
+)]
+    #[case::other_reaction(
+    json!([{ "ts": DAY_BEFORE, "reactions": [{ "name": "eyes", "users": [], "count": 1 }] }]),
+    Some(1),
+    false,
+    false
+)]
+    #[case::without_ts(json!([{ "text": "no ts" }]), None, false, false)]
+    fn assesses_the_thread(
+        #[case] messages: Value,
+        #[case] days_since_last_activity: Option<u64>,
+        #[case] is_resolved: bool,
+        #[case] is_likely_finished: bool,
+    ) {
+        let activity = ThreadActivity::assess(&thread(messages), NOW_MS, 14, &resolved_emoji());
+
+        assert_eq!(days_since_last_activity, activity.days_since_last_activity);
+        assert_eq!(is_resolved, activity.is_resolved);
+        assert_eq!(is_likely_finished, activity.is_likely_finished);
+    }
+
+    #[test]
+    fn takes_the_newest_message_as_the_last_activity() {
+        let activity = ThreadActivity::assess(
+            &thread(json!([{ "ts": DAY_BEFORE }, { "ts": FIRST_OF_JANUARY }])),
+            NOW_MS,
+            14,
+            &resolved_emoji(),
+        );
+
+        assert_eq!(Some(DAY_BEFORE.to_string()), activity.last_activity_ts);
+    }
+}
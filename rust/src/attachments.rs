@@ -0,0 +1,124 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shrinkwraprs::Shrinkwrap;
+use std::collections::BTreeMap;
+
+use crate::messages::{FileLinks, FilesData};
+
+/// Name each content was saved under, by its SHA-256. A save returns the ones
+/// of the saves before it and its own, for the next save of the batch to pass in
+/// the `saved_contents` option
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct SavedContents(pub BTreeMap<String, String>);
+
+/// Files with the same content as one saved before, by the name they would be
+/// saved under, with the name of the one that was saved
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct DuplicateFiles(pub BTreeMap<String, String>);
+
+/// SHA-256 of `data`, in hex
+pub fn content_hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Keeps one file of each content, in this save or the ones in
+/// `saved_contents`, which this save's are added to. The others are left out of
+/// `files_data`, so they are not saved again, and their entry in `file_links`
+/// points at the name of the one that is saved instead of at slack
+pub fn dedupe(
+    files_data: FilesData,
+    file_links: FileLinks,
+    saved_contents: &mut SavedContents,
+) -> (FilesData, FileLinks, DuplicateFiles) {
+    let mut file_links = file_links;
+    let mut duplicates = DuplicateFiles::default();
+    let files_data = files_data
+        .0
+        .into_iter()
+        .filter(|(name, data)| {
+            let saved_as = saved_contents
+                .0
+                .entry(content_hash(&data.0))
+                .or_insert_with(|| name.clone());
+            if saved_as == name {
+                return true;
+            }
+            log::info!("rust|dedupe|duplicate|{}|saved as {}", name, saved_as);
+            file_links.0.insert(name.clone(), saved_as.clone());
+            duplicates.0.insert(name.clone(), saved_as.clone());
+            false
+        })
+        .collect();
+    (FilesData(files_data), file_links, duplicates)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::{content_hash, dedupe, SavedContents};
+    use crate::messages::{FileData, FileLinks, FilesData};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    #[test]
+    fn files_with_the_same_content_are_saved_once_across_saves() {
+        let data = |names: &[(&str, &[u8])]| -> (FilesData, FileLinks) {
+            (
+                FilesData(
+                    names
+                        .iter()
+                        .map(|(name, data)| (name.to_string(), FileData(data.to_vec())))
+                        .collect(),
+                ),
+                names
+                    .iter()
+                    .map(|(name, _)| {
+                        (
+                            name.to_string(),
+                            format!("https://files.slack.com/{}", name),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+        let mut saved_contents = SavedContents::default();
+
+        let (first_data, first_links) = data(&[("a.png", PNG), ("b.png", PNG), ("c.png", b"c")]);
+        let (files_data, file_links, duplicates) =
+            dedupe(first_data, first_links, &mut saved_contents);
+        assert_eq!(
+            vec!["a.png", "c.png"],
+            files_data.keys().collect::<Vec<_>>()
+        );
+        assert_eq!("https://files.slack.com/a.png", file_links["a.png"]);
+        assert_eq!("a.png", file_links["b.png"]);
+        assert_eq!("a.png", duplicates["b.png"]);
+        assert_eq!(2, saved_contents.len());
+
+        // a later save of the batch, e.g. the thread the image was reshared to
+        let (second_data, second_links) = data(&[("a.png", PNG), ("d.png", PNG)]);
+        let (files_data, file_links, duplicates) =
+            dedupe(second_data, second_links, &mut saved_contents.clone());
+        assert_eq!(vec!["a.png"], files_data.keys().collect::<Vec<_>>());
+        assert_eq!("a.png", file_links["d.png"]);
+        assert_eq!("a.png", duplicates["d.png"]);
+
+        // a.png was deleted from the vault, the caller leaves it out
+        let (third_data, third_links) = data(&[("d.png", PNG)]);
+        let (files_data, file_links, duplicates) =
+            dedupe(third_data, third_links, &mut SavedContents::default());
+        assert_eq!(vec!["d.png"], files_data.keys().collect::<Vec<_>>());
+        assert_eq!("https://files.slack.com/d.png", file_links["d.png"]);
+        assert!(duplicates.is_empty());
+
+        assert_eq!(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            content_hash(b"")
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Saves slack threads without obsidian, e.g. to archive many threads from a
+//! script. Build it with `cargo build --features cli`. Threads are gathered and
+//! rendered by the same code as in the plugin, so the notes match the plugin's
+use clap::Parser;
+use futures::{
+    executor::block_on,
+    future::{self, FutureExt, LocalBoxFuture},
+};
+use obsidian_slack::{
+    attachments::SavedContents,
+    export_message,
+    finalize_options::FinalizeOptions,
+    rate_limit::RateLimiter,
+    render::{self, OutputFormat},
+    slack_http_client::{
+        resolve_api_base, ApiBudget, RequestUrlParam, SlackHttpClient, SlackHttpClientConfig,
+        SlackHttpClientConfigFeatureFlags, SlackHttpClientConfigOptions, RESPONSE_TOO_LARGE_ERROR,
+    },
+    slack_url::SlackUrl,
+};
+use std::{
+    error::Error,
+    fs,
+    io::Read,
+    path::PathBuf,
+    process::ExitCode,
+    rc::Rc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+type Response = LocalBoxFuture<'static, Result<Vec<u8>, String>>;
+
+/// Saves slack threads as notes, the way the obsidian-slack plugin does
+#[derive(Parser)]
+#[command(name = "obsidian-slack", version)]
+struct Args {
+    /// Token of slack's web app, starting with `xoxc`
+    #[arg(long)]
+    token: String,
+
+    /// The `d` cookie of slack's web app, starting with `xoxd`
+    #[arg(long)]
+    cookie: String,
+
+    /// Directory the notes are written to
+    #[arg(long, default_value = ".")]
+    out: PathBuf,
+
+    /// `json`, the plugin's notes, or `org`
+    #[arg(long, default_value = "json")]
+    format: String,
+
+    /// Json file with the plugin's options, e.g. `{"unwrap_links": true}`
+    #[arg(long)]
+    options: Option<PathBuf>,
+
+    /// Fetch the names of the thread's users
+    #[arg(long)]
+    users: bool,
+
+    /// Fetch the name and topic of the channel
+    #[arg(long)]
+    channel_info: bool,
+
+    /// Fetch the names of the teams of the thread's users
+    #[arg(long)]
+    team_info: bool,
+
+    /// Fetch the contents of the thread's files, saved next to the note
+    #[arg(long)]
+    file_data: bool,
+
+    /// Links to the threads to save
+    #[arg(required = true)]
+    urls: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let mut failed = false;
+    // files saved by the threads before, for the `dedupe_file_data` option
+    let mut saved_contents = SavedContents::default();
+    for url in &args.urls {
+        match save(&args, url, &mut saved_contents) {
+            Ok(path) => println!("{} -> {}", url, path.display()),
+            Err(err) => {
+                eprintln!("{} failed: {}", url, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Saves the thread at `url` to `args.out`, returning the path of its note. A json
+/// note saved there before is merged in. The contents of its files are added to
+/// `saved_contents`
+fn save(
+    args: &Args,
+    url: &str,
+    saved_contents: &mut SavedContents,
+) -> Result<PathBuf, Box<dyn Error>> {
+    // the client and the note take their settings from the same file
+    let options_json = match &args.options {
+        Some(path) => fs::read_to_string(path)?,
+        None => "{}".to_string(),
+    };
+    let mut options = serde_json::from_str::<SlackHttpClientConfigOptions>(&options_json)?;
+    let finalize_options = serde_json::from_str::<FinalizeOptions>(&options_json)?;
+    options.embed_file_data |= args.file_data;
+    options.saved_contents = saved_contents.clone();
+    let output_format =
+        serde_json::from_value::<OutputFormat>(serde_json::Value::from(args.format.as_str()))?;
+    let feature_flags = SlackHttpClientConfigFeatureFlags {
+        get_users: args.users,
+        get_channel_info: args.channel_info,
+        get_team_info: args.team_info,
+        get_file_data: args.file_data,
+    };
+
+    let slack_url = SlackUrl::new(url)?;
+    let api_base = resolve_api_base(options.api_base.as_deref(), slack_url.host())?;
+    let config = SlackHttpClientConfig::new(
+        api_base,
+        args.token.clone(),
+        args.cookie.clone(),
+        feature_flags,
+        options,
+    )?;
+    let strip_nulls = finalize_options.strip_nulls;
+    let pacing = config.options.clone();
+    let api_budget = Rc::new(ApiBudget::new(config.options.max_api_calls));
+    let client_api_budget = api_budget.clone();
+    let http = reqwest::blocking::Client::new();
+    let request_func = move |params: RequestUrlParam| -> Response {
+        let response = if client_api_budget.spend() {
+            pace(&pacing, &params);
+            send(&http, &params)
+        } else {
+            Err("The max_api_calls budget is used up, the request was not sent".to_string())
+        };
+        future::ready(response).boxed_local()
+    };
+    let client = SlackHttpClient::new(config, Box::new(request_func), api_budget);
+
+    let mut components = block_on(export_message(
+        client,
+        slack_url,
+        finalize_options,
+        now_ms(),
+    ))?;
+    if let Some(saved) = &components.saved_contents {
+        *saved_contents = saved.clone();
+    }
+    let file_name = match output_format {
+        OutputFormat::Json => components.file_name.0.clone(),
+        OutputFormat::Org => {
+            let file_name = components.file_name.0.as_str();
+            format!(
+                "{}.org",
+                file_name.strip_suffix(".json").unwrap_or(file_name)
+            )
+        }
+    };
+    for (name, data) in components.files_data.iter().flat_map(|files| files.iter()) {
+        // titles of files are chosen by whoever shared them
+        fs::write(args.out.join(name.replace(['/', '\\'], "_")), &data.0)?;
+    }
+    let path = args.out.join(file_name);
+    // a thread saved before is refreshed, not replaced
+    if output_format == OutputFormat::Json && path.exists() {
+        components.merge_saved_note(serde_json::from_str(&fs::read_to_string(&path)?)?);
+    }
+    fs::write(
+        &path,
+        render::render(&components, output_format, strip_nulls),
+    )?;
+    Ok(path)
+}
+
+/// Waits until `params` may be sent, see
+/// [`SlackHttpClientConfigOptions::rate_limit_of`]. The threads are saved one after
+/// another, so waiting blocks
+fn pace(options: &SlackHttpClientConfigOptions, params: &RequestUrlParam) {
+    let rate_limit = params
+        .endpoint()
+        .and_then(|endpoint| options.rate_limit_of(&endpoint));
+    if let Some((key, limit)) = rate_limit {
+        let wait_ms = RateLimiter::shared(&key, limit).reserve(now_ms());
+        thread::sleep(Duration::from_millis(wait_ms.ceil() as u64));
+    }
+}
+
+/// The time of the clock in unix milliseconds, like js's `Date.now()`
+fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |now| now.as_millis() as f64)
+}
+
+/// Sends a request like obsidian's `requestUrl` function does: the body of a
+/// successful response, or why there was none. A body larger than the request's
+/// `max_response_bytes` is not read past it
+fn send(http: &reqwest::blocking::Client, params: &RequestUrlParam) -> Result<Vec<u8>, String> {
+    let method =
+        reqwest::Method::from_bytes(params.method().as_bytes()).map_err(|err| err.to_string())?;
+    let mut request = http.request(method, params.url());
+    for (name, value) in params.headers() {
+        request = request.header(name, value);
+    }
+    if let Some(body) = params.body() {
+        request = request.body(body.to_string());
+    }
+    let response = request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.to_string())?;
+    let max_bytes = match params.max_response_bytes() {
+        Some(max_bytes) => max_bytes,
+        None => {
+            return response
+                .bytes()
+                .map(|body| body.to_vec())
+                .map_err(|err| err.to_string())
+        }
+    };
+    let too_large = |size: u64| format!("{}: {} bytes", RESPONSE_TOO_LARGE_ERROR, size);
+    if let Some(length) = response
+        .content_length()
+        .filter(|length| *length > max_bytes)
+    {
+        return Err(too_large(length));
+    }
+    let mut body = Vec::new();
+    response
+        .take(max_bytes + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| err.to_string())?;
+    if body.len() as u64 > max_bytes {
+        return Err(too_large(body.len() as u64));
+    }
+    Ok(body)
+}
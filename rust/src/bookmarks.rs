@@ -0,0 +1,95 @@
+use amplify_derive::Display;
+use do_notation::m;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt, Snafu};
+use std::fmt::Debug;
+
+use crate::{
+    response::{self, SlackEnvelope},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
+
+    #[snafu(display("{source}"))]
+    CouldNotDeserializeBookmarkResponse { source: response::Error },
+
+    #[snafu(display("The bookmark response was not ok. - source: {source}"))]
+    InvalidBookmarkResponse { source: response::Error },
+
+    #[snafu(display("Could not parse json from bookmark response string - source: {source}"))]
+    CouldNotParseJsonFromBookmarkResponse { source: response::Error },
+
+    #[snafu(display(
+        "Slack did not allow adding a bookmark to channel {channel_id}: {error}. Bookmarking needs a member of the channel with permission to edit its bookmarks"
+    ))]
+    BookmarkPermissionDenied { channel_id: String, error: String },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Slack error codes that mean the user may not add bookmarks to the channel
+const PERMISSION_ERRORS: [&str; 5] = [
+    "not_in_channel",
+    "restricted_action",
+    "missing_scope",
+    "not_allowed_token_type",
+    "channel_not_found",
+];
+
+/// Bookmarks `link` in the channel under `title`
+pub async fn add_bookmark_to_api<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+    title: &str,
+    link: &str,
+) -> Result<Bookmark>
+where
+    T: TransportResponse,
+{
+    let result = client
+        .add_bookmark(channel_id, title, link)
+        .into_body()
+        .await;
+    let bookmark_response: BookmarkResponse = m! {
+        // mapping error instead of using snafu context because the request's error is a string, not an Error
+        val <- result.map_err(|error| Error::RequestFailed { error });
+        json <- response::parse_response_body(val).context(CouldNotParseJsonFromBookmarkResponseSnafu);
+        bookmark_response <- response::defined_from_json(json).context(CouldNotDeserializeBookmarkResponseSnafu);
+        return bookmark_response;
+    }?;
+
+    let error = bookmark_response.error.clone().unwrap_or_default();
+    ensure!(
+        !PERMISSION_ERRORS.contains(&error.as_str()),
+        BookmarkPermissionDeniedSnafu { channel_id, error }
+    );
+    let valid_response = client
+        .validate_response(bookmark_response)
+        .context(InvalidBookmarkResponseSnafu)?;
+    Ok(valid_response
+        .data
+        .bookmark
+        .expect("Expected a bookmark in the bookmark response, but got None. This is a bug"))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct Bookmark {
+    pub id: String,
+    pub channel_id: Option<String>,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    pub r#type: Option<String>,
+}
+
+pub type BookmarkResponse = SlackEnvelope<BookmarkResponseData>;
+
+/// The fields of a `bookmarks.add` response, see [`BookmarkResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BookmarkResponseData {
+    pub bookmark: Option<Bookmark>,
+}
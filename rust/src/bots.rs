@@ -0,0 +1,132 @@
+use amplify_derive::Display;
+use do_notation::m;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use shrinkwraprs::Shrinkwrap;
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+
+use crate::{
+    messages::MessageAndThread,
+    response::{self, SlackEnvelope},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    utils::{sanitize_text, SanitizeText},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
+
+    #[snafu(display("{source}"))]
+    CouldNotDeserializeBotResponse { source: response::Error },
+
+    #[snafu(display("The bot response was not ok. - source: {source}"))]
+    InvalidBotResponse { source: response::Error },
+
+    #[snafu(display("Could not parse json from bot response string - source: {source}"))]
+    CouldNotParseJsonFromBotResponse { source: response::Error },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+pub async fn get_bots_from_api<T>(bot_ids: &[String], client: &SlackHttpClient<T>) -> Result<Bots>
+where
+    T: TransportResponse,
+{
+    let bots = bot_ids
+        .iter()
+        .map(|bot_id| client.get_bots_info(bot_id).into_body())
+        .collect::<Vec<_>>();
+
+    let bot_responses = join_all(bots)
+        .await
+        .into_iter()
+        .map(|result| {
+            m! {
+                // mapping error instead of using snafu context because the request's error is a string, not an Error
+                val <- result.map_err(|error| Error::RequestFailed { error });
+                json <- response::parse_response_body(val).context(CouldNotParseJsonFromBotResponseSnafu);
+                bot_response <- response::defined_from_json(json).context(CouldNotDeserializeBotResponseSnafu);
+                valid_response <- client.validate_response(bot_response).context(InvalidBotResponseSnafu);
+                return valid_response;
+            }
+        })
+        .collect::<Result<Vec<BotResponse>>>()?;
+
+    Ok(Bots(
+        bot_ids
+            .iter()
+            .map(String::to_string)
+            .zip(bot_responses)
+            .filter_map(|(bot_id, bot_response)| bot_response.data.bot.map(|bot| (bot_id, bot)))
+            .collect(),
+    ))
+}
+
+/// Ids of the bots that wrote a message of the thread, each once
+pub fn collect_bot_ids(message_and_thread: &MessageAndThread) -> Vec<String> {
+    let mut bot_ids = message_and_thread
+        .message
+        .iter()
+        .chain(message_and_thread.thread.iter())
+        .filter_map(|message| message.bot_id.clone())
+        .collect::<Vec<String>>();
+    bot_ids.sort();
+    bot_ids.dedup();
+    bot_ids
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct Bots(pub HashMap<String, Bot>);
+
+impl Bots {
+    pub fn sanitize_text(&mut self) {
+        self.0.values_mut().for_each(SanitizeText::sanitize_text);
+    }
+}
+
+pub type BotResponse = SlackEnvelope<BotResponseData>;
+
+/// The fields of a `bots.info` response, see [`BotResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BotResponseData {
+    pub bot: Option<Bot>,
+}
+
+/// A bot or app as returned by `bots.info`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct Bot {
+    pub id: String,
+    pub name: String,
+    /// Set when the bot belongs to an app, e.g. the GitHub app
+    pub app_id: Option<String>,
+    pub icons: Option<BotIcons>,
+}
+
+/// Urls of the bot's icon in a few sizes
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct BotIcons {
+    pub image_36: Option<String>,
+    pub image_48: Option<String>,
+    pub image_72: Option<String>,
+}
+
+impl Bot {
+    /// The name as slack shows it, e.g. `GitHub APP` for bots of an app
+    pub fn display_name(&self) -> String {
+        match self.app_id {
+            Some(_) => format!("{} APP", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl SanitizeText for Bot {
+    fn sanitize_text(&mut self) {
+        self.name = sanitize_text(&self.name);
+    }
+}
@@ -0,0 +1,70 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::render::NOTE_FORMAT_VERSION;
+
+/// Cargo features the crate can be built with, see `Cargo.toml`
+const FEATURES: [(&str, bool); 3] = [
+    (
+        "console_error_panic_hook",
+        cfg!(feature = "console_error_panic_hook"),
+    ),
+    ("encryption", cfg!(feature = "encryption")),
+    ("language_detection", cfg!(feature = "language_detection")),
+];
+
+/// What the running wasm was built from, for the plugin to check it is
+/// compatible and to add to bug reports and saved notes
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct BuildInfo {
+    /// Version of the crate, e.g. `0.1.0`
+    pub version: String,
+    /// Commit the crate was built from, when it was built from a git checkout
+    pub git_sha: Option<String>,
+    /// Enabled cargo features, e.g. `encryption`
+    pub features: Vec<String>,
+    /// See [`NOTE_FORMAT_VERSION`]
+    pub note_format_version: u64,
+}
+
+impl BuildInfo {
+    pub fn current() -> BuildInfo {
+        BuildInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: option_env!("OBSIDIAN_SLACK_GIT_SHA").map(str::to_string),
+            features: FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(feature, _)| feature.to_string())
+                .collect(),
+            note_format_version: NOTE_FORMAT_VERSION,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of the build info the plugin checks compatibility with, run with
+    //! `cargo test`
+
+    use crate::{build_info::BuildInfo, render::NOTE_FORMAT_VERSION};
+
+    #[test]
+    fn build_info_describes_this_build() {
+        let build_info = BuildInfo::current();
+
+        assert_eq!(env!("CARGO_PKG_VERSION"), build_info.version);
+        assert_eq!(NOTE_FORMAT_VERSION, build_info.note_format_version);
+        assert_eq!(
+            cfg!(feature = "encryption"),
+            build_info.features.contains(&"encryption".to_string())
+        );
+        assert_eq!(
+            cfg!(feature = "language_detection"),
+            build_info
+                .features
+                .contains(&"language_detection".to_string())
+        );
+    }
+}
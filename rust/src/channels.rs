@@ -3,25 +3,29 @@ use do_notation::m;
 
 use serde::{Deserialize, Serialize};
 use shrinkwraprs::Shrinkwrap;
-use snafu::{ResultExt, Snafu};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 
 use crate::{
+    fetch_stats::FetchStats,
+    finalize_options::FinalizeOptions,
     messages::Message,
-    response::{self, convert_result_string_to_object, SlackResponseValidator},
-    slack_http_client::SlackHttpClient,
-    users::{CollectUsers, User, UserIds, Users},
+    response::{self, PaginatedResponse, Paginator, ResponseMetadata, SlackEnvelope},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    slack_url::ChannelReference,
+    users::{self, CollectUsers, User, UserIds, Users},
+    utils::{sanitize_optional_text, SanitizeText},
 };
 
 #[derive(Debug, Snafu)]
 pub enum Error {
-    #[snafu(display("When mapping user ids from response to retrieved user info, user id was not in user map. user_id: {user_id} - user_map: {user_map}"))]
-    UserIdNotFoundInUserMap { user_id: String, user_map: String },
+    #[snafu(display("When mapping user ids from response to retrieved user info, user id was not in user map. user_id: {user_id} - users in map: {user_count}"))]
+    UserIdNotFoundInUserMap { user_id: String, user_count: usize },
 
     #[snafu(display("User was none in channel response, indicating this channel is not a direct message: {channel}"))]
     UserInChannelWasNone { channel: Channel },
 
-    #[snafu(display("Awaiting a JsFuture returned an error: {error}"))]
-    WasmErrorFromJsFuture { error: String },
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
 
     #[snafu(display("The channel response was not ok. - source: {source}"))]
     InvalidChannelResponse { source: response::Error },
@@ -30,7 +34,18 @@ pub enum Error {
     CouldNotParseJsonFromChannelResponse { source: response::Error },
 
     #[snafu(display("{source}"))]
-    SerdeWasmBindgenCouldNotParseChannelResponse { source: response::Error },
+    CouldNotDeserializeChannelResponse { source: response::Error },
+
+    #[snafu(display(
+        "{channel_id} is a direct message, and the allow_dm_export option forbids saving direct messages"
+    ))]
+    DirectMessageExportNotAllowed { channel_id: String },
+
+    #[snafu(display("No channel named #{name} among the channels you can see"))]
+    ChannelNameNotFound { name: String },
+
+    #[snafu(display("Could not get the users of the channel - source: {source}"))]
+    CouldNotGetChannelUsersFromApi { source: users::Error },
 }
 
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -40,33 +55,203 @@ pub async fn get_channel_from_api<T>(
     channel_id: &str,
 ) -> Result<Channel>
 where
-    wasm_bindgen_futures::JsFuture: std::convert::From<T>,
+    T: TransportResponse,
 {
-    let awaited_val =
-        wasm_bindgen_futures::JsFuture::from(client.get_conversations_info(channel_id))
-            .await
-            // mapping error instead of using snafu context because jsvalue is not an Error from parse method
-            .map_err(|err| Error::WasmErrorFromJsFuture {
-                error: format!("{:#?}", err),
-            })?;
-
-    let response = m! {
-        js_obj <- convert_result_string_to_object(awaited_val).context(CouldNotParseJsonFromChannelResponseSnafu);
-        message_response <- response::defined_from_js_object(js_obj).context(SerdeWasmBindgenCouldNotParseChannelResponseSnafu);
-        valid_response <- ChannelResponse::validate_response(message_response).context(InvalidChannelResponseSnafu);
+    let awaited_val = client
+        .get_conversations_info(channel_id)
+        .into_body()
+        .await
+        // mapping error instead of using snafu context because the request's error is a string, not an Error
+        .map_err(|error| Error::RequestFailed { error })?;
+
+    let response: ChannelResponse = m! {
+        json <- response::parse_response_body(awaited_val).context(CouldNotParseJsonFromChannelResponseSnafu);
+        message_response <- response::defined_from_json(json).context(CouldNotDeserializeChannelResponseSnafu);
+        valid_response <- client.validate_response(message_response).context(InvalidChannelResponseSnafu);
         return valid_response;
     }?;
 
     Ok(response
+        .data
         .channel
         .expect("Expected Channel but got None, this is a bug"))
 }
 
+/// The channel with its members when it is a group DM, and the users who created
+/// it and set its topic and purpose, without any of its messages
+pub async fn get_channel_info_from_api<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+) -> Result<ChannelInfo>
+where
+    T: TransportResponse,
+{
+    let mut channel = get_channel_from_api(client, channel_id).await?;
+    if channel.is_mpim.unwrap_or(false) {
+        channel.members = Some(get_channel_members_from_api(client, channel_id).await?);
+    }
+
+    let mut user_ids = channel.collect_users()?.0;
+    user_ids.extend(
+        channel
+            .creator
+            .iter()
+            .chain(
+                channel
+                    .topic
+                    .iter()
+                    .chain(channel.purpose.iter())
+                    .filter_map(|aux_data| aux_data.creator.as_ref()),
+            )
+            .filter(|user_id| !user_id.is_empty())
+            .cloned(),
+    );
+    user_ids.sort();
+    user_ids.dedup();
+    let users = if user_ids.is_empty() {
+        None
+    } else {
+        Some(
+            users::get_users_from_api(&user_ids, client, &mut Users::default())
+                .await
+                .context(CouldNotGetChannelUsersFromApiSnafu)?,
+        )
+    };
+
+    Ok(ChannelInfo {
+        channel,
+        label: None,
+        users,
+        fetch_stats: FetchStats::default(),
+    })
+}
+
+/// Types of conversation listed by [`list_conversations_from_api`] unless others
+/// are asked for
+pub const DEFAULT_CONVERSATION_TYPES: &str = "public_channel,private_channel";
+
+/// The conversations of `types` you are a member of, e.g. `public_channel,im`,
+/// paging through `users.conversations` until there are `limit` of them. Archived
+/// conversations are left out
+pub async fn list_conversations_from_api<T>(
+    client: &SlackHttpClient<T>,
+    types: &str,
+    limit: Option<usize>,
+) -> Result<Vec<ConversationEntry>>
+where
+    T: TransportResponse,
+{
+    let mut conversations = page_conversations(
+        client,
+        |cursor| client.get_users_conversations(types, cursor),
+        |conversations| limit.is_some_and(|limit| conversations.len() >= limit),
+    )
+    .await?;
+    conversations.truncate(limit.unwrap_or(conversations.len()));
+    Ok(conversations)
+}
+
+/// The id of the channel `reference` points to. A `#name` is looked up among the
+/// workspace's public channels and the private ones you are in with
+/// `conversations.list`, which the `session_cache` option keeps for later lookups
+pub async fn resolve_channel_id<T>(
+    client: &SlackHttpClient<T>,
+    reference: &ChannelReference,
+) -> Result<String>
+where
+    T: TransportResponse,
+{
+    let name = match reference {
+        ChannelReference::Url(slack_channel_url) => {
+            return Ok(slack_channel_url.channel_id.clone())
+        }
+        ChannelReference::Name(name) => name,
+    };
+    let is_named = |conversation: &ConversationEntry| {
+        conversation
+            .name
+            .as_deref()
+            .is_some_and(|conversation_name| conversation_name.eq_ignore_ascii_case(name))
+    };
+    page_conversations(
+        client,
+        |cursor| client.get_conversations_list(DEFAULT_CONVERSATION_TYPES, cursor),
+        |conversations| conversations.iter().any(is_named),
+    )
+    .await?
+    .into_iter()
+    .find(is_named)
+    .map(|conversation| conversation.id)
+    .context(ChannelNameNotFoundSnafu { name })
+}
+
+/// Pages through a listing of conversations until `is_done` with the ones so far
+/// or until the last page
+async fn page_conversations<T>(
+    client: &SlackHttpClient<T>,
+    request_page: impl Fn(Option<&str>) -> T,
+    is_done: impl Fn(&[ConversationEntry]) -> bool,
+) -> Result<Vec<ConversationEntry>>
+where
+    T: TransportResponse,
+{
+    let mut conversations = vec![];
+    let mut paginator = Paginator::default();
+    loop {
+        let awaited_val = request_page(paginator.cursor())
+            .into_body()
+            .await
+            // mapping error instead of using snafu context because the request's error is a string, not an Error
+            .map_err(|error| Error::RequestFailed { error })?;
+
+        let response: ConversationsResponse = m! {
+            json <- response::parse_response_body(awaited_val).context(CouldNotParseJsonFromChannelResponseSnafu);
+            conversations_response <- response::defined_from_json(json).context(CouldNotDeserializeChannelResponseSnafu);
+            valid_response <- client.validate_response(conversations_response).context(InvalidChannelResponseSnafu);
+            return valid_response;
+        }?;
+
+        let has_next_page = paginator.advance(&response);
+        conversations.extend(
+            response
+                .data
+                .channels
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(ConversationEntry::new),
+        );
+        if !has_next_page || is_done(&conversations) {
+            return Ok(conversations);
+        }
+    }
+}
+
+/// Fails if the conversation is a direct message, one-to-one or group, and the
+/// `allow_dm_export` option is off. One-to-one ids start with `D`, any other id
+/// takes a `conversations.info` request to tell
+pub async fn ensure_export_allowed<T>(client: &SlackHttpClient<T>, channel_id: &str) -> Result<()>
+where
+    T: TransportResponse,
+{
+    if client.config.options.allow_dm_export.unwrap_or(true) {
+        return Ok(());
+    }
+    let is_direct_message = channel_id.starts_with('D') || {
+        let channel = get_channel_from_api(client, channel_id).await?;
+        channel.is_im.unwrap_or(false) || channel.is_mpim.unwrap_or(false)
+    };
+    ensure!(
+        !is_direct_message,
+        DirectMessageExportNotAllowedSnafu { channel_id }
+    );
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Display, Shrinkwrap, PartialEq, Eq)]
 #[display(Debug)]
 pub struct ChannelId(pub String);
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Display)]
 #[display(Debug)]
 pub struct Channel {
     pub id: Option<String>,
@@ -98,17 +283,112 @@ pub struct Channel {
     pub unread_count_display: Option<i64>,
     pub is_open: Option<bool>,
     pub priority: Option<f64>,
+    /// Member ids of a group DM, from `conversations.members`. Not fetched for
+    /// any other kind of conversation
+    pub members: Option<Vec<String>>,
 }
 
 impl CollectUsers<Error> for Channel {
     fn collect_users(&self) -> Result<UserIds> {
-        self.user.as_ref().map_or(Ok(vec![].into()), |user_id| {
-            Ok(vec![user_id.to_owned()].into())
-        })
+        Ok(self
+            .user
+            .iter()
+            .chain(self.members.iter().flatten())
+            .cloned()
+            .collect::<Vec<String>>()
+            .into())
     }
 }
 
+/// How much of the channel is kept in the saved note, see the
+/// `channel_detail_level` option
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelDetailLevel {
+    /// The id, name and topic
+    Minimal,
+    /// Everything describing the channel, without your read state such as
+    /// `last_read` or `unread_count` and the latest message
+    Standard,
+    /// Everything slack returned
+    #[default]
+    Full,
+}
+
 impl Channel {
+    /// Drops the fields `level` does not keep
+    pub fn trim_to(&mut self, level: ChannelDetailLevel) {
+        match level {
+            ChannelDetailLevel::Full => {}
+            ChannelDetailLevel::Standard => {
+                self.last_read = None;
+                self.latest = None;
+                self.unread_count = None;
+                self.unread_count_display = None;
+                self.is_open = None;
+                self.is_member = None;
+                self.priority = None;
+            }
+            ChannelDetailLevel::Minimal => {
+                *self = Channel {
+                    id: self.id.take(),
+                    name: self.name.take(),
+                    topic: self.topic.take(),
+                    ..Channel::default()
+                }
+            }
+        }
+    }
+
+    /// Readable name of a group DM made of its members' names, e.g.
+    /// `alice-bob-carol`. Falls back to the member ids for users not in `users`,
+    /// and to the names in the channel's own name (`mpdm-alice--bob--carol-1`)
+    /// when the members are unknown. None for any other kind of conversation
+    pub fn group_dm_label(&self, users: Option<&Users>) -> Option<String> {
+        if !self.is_mpim.unwrap_or(false) {
+            return None;
+        }
+        let names = match self.members.as_ref() {
+            Some(members) if !members.is_empty() => members
+                .iter()
+                .map(|member| {
+                    users
+                        .and_then(|users| users.get(member))
+                        .and_then(|user| user.name.clone())
+                        .unwrap_or_else(|| member.to_string())
+                })
+                .collect::<Vec<String>>(),
+            _ => self
+                .name
+                .as_deref()?
+                .strip_prefix("mpdm-")?
+                .rsplit_once('-')?
+                .0
+                .split("--")
+                .map(str::to_string)
+                .collect(),
+        };
+        Some(names.join("-")).filter(|label| !label.is_empty())
+    }
+
+    /// Leaves the users in `user_ids` out of the channel: the other user of a DM,
+    /// and the members of a group DM. Their users are not fetched, see
+    /// [`crate::finalize_options::FinalizeOptions::ignored_users`]
+    pub fn hide_users(&mut self, user_ids: &[String]) {
+        if self
+            .user
+            .as_ref()
+            .is_some_and(|user_id| user_ids.contains(user_id))
+        {
+            self.user = None;
+            self.user_info = None;
+        }
+        if let Some(members) = self.members.as_mut() {
+            members.retain(|member| !user_ids.contains(member));
+        }
+    }
+
     pub fn finalize_channel(mut channel: Channel, users: Option<&Users>) -> Result<Channel> {
         match (&channel.user, users) {
             (Some(user_id), Some(users)) => {
@@ -120,7 +400,7 @@ impl Channel {
                 } else {
                     UserIdNotFoundInUserMapSnafu {
                         user_id,
-                        user_map: format!("{:#?}", users),
+                        user_count: users.len(),
                     }
                     .fail()
                 }
@@ -130,6 +410,66 @@ impl Channel {
     }
 }
 
+impl SanitizeText for Channel {
+    fn sanitize_text(&mut self) {
+        sanitize_optional_text(&mut self.name);
+        self.topic
+            .iter_mut()
+            .chain(self.purpose.iter_mut())
+            .for_each(|aux_data| sanitize_optional_text(&mut aux_data.value));
+        if let Some(latest) = self.latest.as_mut() {
+            latest.sanitize_text();
+        }
+    }
+}
+
+/// A conversation to pick from, see [`list_conversations_from_api`]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ConversationEntry {
+    pub id: String,
+    /// The channel's name, or for a direct message the id of the user it is with
+    pub name: Option<String>,
+}
+
+impl ConversationEntry {
+    fn new(channel: Channel) -> Option<ConversationEntry> {
+        Some(ConversationEntry {
+            name: channel.name.or(channel.user),
+            id: channel.id?,
+        })
+    }
+}
+
+/// A channel on its own, e.g. for an index note of the channel
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display)]
+#[display(Debug)]
+pub struct ChannelInfo {
+    pub channel: Channel,
+    /// Readable name of a group DM, see [`Channel::group_dm_label`]
+    pub label: Option<String>,
+    /// The channel's creator, the users who set its topic and purpose, and the
+    /// members of a DM
+    pub users: Option<Users>,
+    /// Requests made for the result, see [`FetchStats`]
+    pub fetch_stats: FetchStats,
+}
+
+impl ChannelInfo {
+    pub fn finalize(mut info: ChannelInfo, options: &FinalizeOptions) -> Result<ChannelInfo> {
+        if !options.disable_text_sanitization {
+            info.channel.sanitize_text();
+            if let Some(users) = info.users.as_mut() {
+                users.sanitize_text();
+            }
+        }
+        info.label = info.channel.group_dm_label(info.users.as_ref());
+        info.channel = Channel::finalize_channel(info.channel, info.users.as_ref())?;
+        info.channel.trim_to(options.channel_detail_level);
+        Ok(info)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display)]
 #[display(Debug)]
 pub struct ChannelAuxData {
@@ -138,16 +478,159 @@ pub struct ChannelAuxData {
     last_set: Option<i64>,
 }
 
+/// Every member of the channel, paging through `conversations.members`
+pub async fn get_channel_members_from_api<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+) -> Result<Vec<String>>
+where
+    T: TransportResponse,
+{
+    let mut members = vec![];
+    let mut paginator = Paginator::default();
+    loop {
+        let awaited_val = client
+            .get_conversations_members(channel_id, paginator.cursor())
+            .into_body()
+            .await
+            // mapping error instead of using snafu context because the request's error is a string, not an Error
+            .map_err(|error| Error::RequestFailed { error })?;
+
+        let response: MembersResponse = m! {
+            json <- response::parse_response_body(awaited_val).context(CouldNotParseJsonFromChannelResponseSnafu);
+            members_response <- response::defined_from_json(json).context(CouldNotDeserializeChannelResponseSnafu);
+            valid_response <- client.validate_response(members_response).context(InvalidChannelResponseSnafu);
+            return valid_response;
+        }?;
+
+        let has_next_page = paginator.advance(&response);
+        members.extend(response.data.members.unwrap_or_default());
+        if !has_next_page {
+            return Ok(members);
+        }
+    }
+}
+
+/// Marks the channel as read up to the message at `ts` with `conversations.mark`
+pub async fn mark_read_in_api<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+    ts: &str,
+) -> Result<()>
+where
+    T: TransportResponse,
+{
+    let awaited_val = client
+        .mark_conversation(channel_id, ts)
+        .into_body()
+        .await
+        // mapping error instead of using snafu context because the request's error is a string, not an Error
+        .map_err(|error| Error::RequestFailed { error })?;
+
+    m! {
+        json <- response::parse_response_body(awaited_val).context(CouldNotParseJsonFromChannelResponseSnafu);
+        mark_response <- response::defined_from_json::<MarkResponse>(json).context(CouldNotDeserializeChannelResponseSnafu);
+        _valid_response <- client.validate_response(mark_response).context(InvalidChannelResponseSnafu);
+        return ();
+    }
+}
+
+pub type ChannelResponse = SlackEnvelope<ChannelResponseData>;
+
+/// The fields of a `conversations.info` response, see [`ChannelResponse`]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display)]
 #[display(Debug)]
-pub struct ChannelResponse {
-    pub ok: Option<bool>,
-    pub error: Option<String>,
+pub struct ChannelResponseData {
     pub channel: Option<Channel>,
 }
 
-impl SlackResponseValidator for ChannelResponse {
-    fn ok(&self) -> Option<bool> {
-        self.ok
+pub type MembersResponse = SlackEnvelope<MembersResponseData>;
+
+/// The fields of a `conversations.members` response, see [`MembersResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MembersResponseData {
+    pub members: Option<Vec<String>>,
+    pub response_metadata: Option<ResponseMetadata>,
+}
+
+impl PaginatedResponse for MembersResponseData {
+    fn response_metadata(&self) -> Option<&ResponseMetadata> {
+        self.response_metadata.as_ref()
+    }
+}
+
+pub type ConversationsResponse = SlackEnvelope<ConversationsResponseData>;
+
+/// The fields of a `users.conversations` or `conversations.list` response, see
+/// [`ConversationsResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConversationsResponseData {
+    pub channels: Option<Vec<Channel>>,
+    pub response_metadata: Option<ResponseMetadata>,
+}
+
+impl PaginatedResponse for ConversationsResponseData {
+    fn response_metadata(&self) -> Option<&ResponseMetadata> {
+        self.response_metadata.as_ref()
+    }
+}
+
+pub type MarkResponse = SlackEnvelope<MarkResponseData>;
+
+/// `conversations.mark` answers with nothing but the envelope, see [`MarkResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarkResponseData {}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use serde_json::json;
+
+    use super::resolve_channel_id;
+    use crate::{
+        slack_http_client::SlackHttpClientConfigOptions, slack_url::ChannelReference,
+        test_support::client,
+    };
+
+    #[async_std::test]
+    async fn resolve_channel_id_pages_conversations_list_until_the_name_is_found() {
+        let (client, requested_urls) = client(SlackHttpClientConfigOptions::default(), |url| {
+            Ok(if url.contains("cursor=page2") {
+                json!({
+                    "ok": true,
+                    "channels": [{ "id": "C2", "name": "random" }],
+                    "response_metadata": { "next_cursor": "page3" }
+                })
+            } else {
+                json!({
+                    "ok": true,
+                    "channels": [{ "id": "C1", "name": "general" }],
+                    "response_metadata": { "next_cursor": "page2" }
+                })
+            })
+        });
+        let reference = ChannelReference::new("#Random").expect("Should parse a channel name");
+
+        let channel_id = resolve_channel_id(&client, &reference)
+            .await
+            .expect("Should find the channel");
+
+        assert_eq!("C2", channel_id);
+        assert_eq!(2, requested_urls.borrow().len());
+        assert!(requested_urls
+            .borrow()
+            .iter()
+            .all(|url| url.contains("conversations.list")));
+    }
+
+    #[async_std::test]
+    async fn resolve_channel_id_fails_for_an_unknown_name() {
+        let (client, _) = client(SlackHttpClientConfigOptions::default(), |_| {
+            Ok(json!({ "ok": true, "channels": [{ "id": "C1", "name": "general" }] }))
+        });
+        let reference = ChannelReference::new("#missing").expect("Should parse a channel name");
+
+        let result = resolve_channel_id(&client, &reference).await;
+
+        assert!(result.unwrap_err().to_string().contains("#missing"));
     }
 }
@@ -0,0 +1,179 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::digest::{Digest, ThreadIndexEntry, ThreadSummary};
+
+/// Size of the nodes of [`Chronicle::canvas`], in canvas pixels
+const DAY_WIDTH: i64 = 420;
+const DAY_GAP: i64 = 80;
+const DAY_PADDING: i64 = 20;
+const STATISTICS_HEIGHT: i64 = 100;
+const THREAD_HEIGHT: i64 = 140;
+
+/// Day of the threads started before any known date
+const UNDATED: &str = "undated";
+
+/// A channel export grouped by the day each thread started, only when
+/// `group_by_day` is on
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct Chronicle {
+    /// Oldest first
+    pub days: Vec<ChronicleDay>,
+    /// The days as an obsidian json canvas, a group per day with its threads
+    /// stacked below its statistics. Save it as a `.canvas` file
+    pub canvas: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ChronicleDay {
+    /// `YYYY-MM-DD` in UTC
+    pub date: String,
+    pub thread_count: usize,
+    pub message_count: usize,
+    /// Distinct authors of the day's messages
+    pub participant_count: usize,
+    /// Oldest first
+    pub threads: Vec<ChronicleThread>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ChronicleThread {
+    pub thread_ts: Option<String>,
+    pub title_suggestion: Option<String>,
+    pub root_author: Option<String>,
+    pub message_count: usize,
+    /// Name of the thread's note, see [`ThreadIndexEntry::file_name`]
+    pub note: Option<String>,
+}
+
+impl Chronicle {
+    /// Groups the finalized `digest`, see [`Digest::finalize`]
+    pub fn build(digest: &Digest) -> Chronicle {
+        let mut days = BTreeMap::<String, Vec<&ThreadSummary>>::new();
+        digest.summaries.iter().for_each(|summary| {
+            let date = summary
+                .date
+                .as_deref()
+                .map_or(UNDATED.to_string(), |date| date.chars().take(10).collect());
+            days.entry(date).or_default().push(summary);
+        });
+
+        let days = days
+            .into_iter()
+            .map(|(date, summaries)| ChronicleDay::new(date, &summaries, &digest.index))
+            .collect::<Vec<ChronicleDay>>();
+        Chronicle {
+            canvas: canvas(&days),
+            days,
+        }
+    }
+}
+
+impl ChronicleDay {
+    fn new(date: String, summaries: &[&ThreadSummary], index: &[ThreadIndexEntry]) -> ChronicleDay {
+        let participants = summaries
+            .iter()
+            .flat_map(|summary| summary.participants.iter())
+            .map(|participant| participant.id.as_str())
+            .collect::<BTreeSet<&str>>();
+        ChronicleDay {
+            date,
+            thread_count: summaries.len(),
+            message_count: summaries.iter().map(|summary| summary.message_count).sum(),
+            participant_count: participants.len(),
+            threads: summaries
+                .iter()
+                .map(|summary| ChronicleThread {
+                    thread_ts: summary.thread_ts.clone(),
+                    title_suggestion: summary.title_suggestion.clone(),
+                    root_author: summary.root_author.clone(),
+                    message_count: summary.message_count,
+                    note: index
+                        .iter()
+                        .find(|entry| summary.thread_ts.as_ref() == Some(&entry.root_ts))
+                        .map(|entry| {
+                            let file_name = entry.file_name.0.as_str();
+                            file_name
+                                .strip_suffix(".json")
+                                .unwrap_or(file_name)
+                                .to_string()
+                        }),
+                })
+                .collect(),
+        }
+    }
+
+    fn statistics(&self) -> String {
+        format!(
+            "## {}\n{} threads · {} messages · {} participants",
+            self.date, self.thread_count, self.message_count, self.participant_count
+        )
+    }
+}
+
+impl ChronicleThread {
+    fn card(&self) -> String {
+        let title = self
+            .title_suggestion
+            .clone()
+            .or_else(|| self.thread_ts.clone())
+            .unwrap_or_default();
+        let mut lines = vec![format!("### {}", title)];
+        lines.push(match self.root_author.as_deref() {
+            Some(author) => format!("{} · {} messages", author, self.message_count),
+            None => format!("{} messages", self.message_count),
+        });
+        if let Some(note) = self.note.as_deref() {
+            lines.push(format!("[[{}]]", note));
+        }
+        lines.join("\n")
+    }
+}
+
+/// See https://jsoncanvas.org
+fn canvas(days: &[ChronicleDay]) -> String {
+    let mut nodes = vec![];
+    for (column, day) in days.iter().enumerate() {
+        let x = column as i64 * (DAY_WIDTH + DAY_GAP);
+        let inner_width = DAY_WIDTH - 2 * DAY_PADDING;
+        let height = 2 * DAY_PADDING
+            + STATISTICS_HEIGHT
+            + day.threads.len() as i64 * (THREAD_HEIGHT + DAY_PADDING);
+        nodes.push(json!({
+            "id": format!("day-{}", day.date),
+            "type": "group",
+            "label": day.date,
+            "x": x,
+            "y": 0,
+            "width": DAY_WIDTH,
+            "height": height,
+        }));
+        nodes.push(json!({
+            "id": format!("day-{}-statistics", day.date),
+            "type": "text",
+            "text": day.statistics(),
+            "x": x + DAY_PADDING,
+            "y": DAY_PADDING,
+            "width": inner_width,
+            "height": STATISTICS_HEIGHT,
+        }));
+        for (row, thread) in day.threads.iter().enumerate() {
+            nodes.push(json!({
+                "id": format!("thread-{}-{}", day.date, row),
+                "type": "text",
+                "text": thread.card(),
+                "x": x + DAY_PADDING,
+                "y": 2 * DAY_PADDING + STATISTICS_HEIGHT + row as i64 * (THREAD_HEIGHT + DAY_PADDING),
+                "width": inner_width,
+                "height": THREAD_HEIGHT,
+            }));
+        }
+    }
+    serde_json::to_string_pretty(&json!({ "nodes": nodes, "edges": [] }))
+        .expect("Expected to serialize object with serde, but was unable to. This is a bug")
+}
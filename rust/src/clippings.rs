@@ -0,0 +1,175 @@
+use amplify_derive::Display;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+use crate::{
+    channels,
+    components::FileName,
+    fetch_stats::FetchStats,
+    finalize_options::FinalizeOptions,
+    messages::{self, Message, Messages},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    slack_url::SlackUrl,
+    users::{self, CollectUsers, SharedUsers, Users},
+    utils::SanitizeText,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("{source}"))]
+    ExportNotAllowed { source: channels::Error },
+
+    #[snafu(display("Could not get message for {url} - source: {source}"))]
+    CouldNotGetMessageFromApi {
+        url: String,
+        source: messages::Error,
+    },
+
+    #[snafu(display("Slack returned no message for {url}"))]
+    MessageNotFound { url: String },
+
+    #[snafu(display("Could not get users from clipped messages - source: {source}"))]
+    CouldNotCollectUsersFromMessages { source: messages::Error },
+
+    #[snafu(display("Could not get users from api - source: {source}"))]
+    CouldNotGetUsersFromApi { source: users::Error },
+
+    #[snafu(display("Could not finalize clipped message - source: {source}"))]
+    CouldNotFinalizeMessage { source: messages::Error },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Gets the single message each permalink points to, not its thread, along with
+/// the messages' users if `get_users` is on
+pub async fn get_clippings_from_api<T>(
+    client: &SlackHttpClient<T>,
+    slack_urls: &[(String, SlackUrl)],
+) -> Result<ObsidianSlackClippings>
+where
+    T: TransportResponse,
+{
+    let mut channel_ids = slack_urls
+        .iter()
+        .map(|(_, slack_url)| slack_url.channel_id.as_str())
+        .collect::<Vec<&str>>();
+    channel_ids.sort_unstable();
+    channel_ids.dedup();
+    join_all(
+        channel_ids
+            .into_iter()
+            .map(|channel_id| channels::ensure_export_allowed(client, channel_id)),
+    )
+    .await
+    .into_iter()
+    .collect::<Result<Vec<()>, channels::Error>>()
+    .context(ExportNotAllowedSnafu)?;
+
+    let clippings = join_all(
+        slack_urls
+            .iter()
+            .map(|(_, slack_url)| messages::get_messages_from_api(client, slack_url)),
+    )
+    .await
+    .into_iter()
+    .zip(slack_urls)
+    .map(|(result, (url, slack_url))| {
+        let message = result
+            .context(CouldNotGetMessageFromApiSnafu { url })?
+            .message
+            .0
+            .into_iter()
+            .next()
+            .map_or(MessageNotFoundSnafu { url }.fail(), Ok)?;
+        Ok(Clipping {
+            permalink: url.to_string(),
+            channel_id: slack_url.channel_id.to_string(),
+            message,
+        })
+    })
+    .collect::<Result<Vec<Clipping>>>()?;
+
+    let users = if client.config.feature_flags.get_users {
+        let user_ids = Messages(
+            clippings
+                .iter()
+                .map(|clipping| clipping.message.clone())
+                .collect(),
+        )
+        .collect_users()
+        .context(CouldNotCollectUsersFromMessagesSnafu)?;
+        Some(
+            users::get_users_from_api(&user_ids, client, &mut Users::default())
+                .await
+                .context(CouldNotGetUsersFromApiSnafu)?,
+        )
+    } else {
+        None
+    };
+
+    Ok(ObsidianSlackClippings {
+        file_name: create_clippings_file_name(slack_urls),
+        clippings,
+        users,
+        fetch_stats: FetchStats::default(),
+    })
+}
+
+/// `clippings-<channel id>-<ts>.json` for the first permalink
+fn create_clippings_file_name(slack_urls: &[(String, SlackUrl)]) -> FileName {
+    let (_, first) = slack_urls
+        .first()
+        .expect("Expected at least one permalink to name clippings after. This is a bug");
+    FileName(format!("clippings-{}-{}.json", first.channel_id, first.ts))
+}
+
+/// One message picked out of slack by its permalink
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Display)]
+#[display(Debug)]
+pub struct Clipping {
+    pub permalink: String,
+    pub channel_id: String,
+    pub message: Message,
+}
+
+/// A bundle of individually picked messages, possibly from different channels,
+/// in the order their permalinks were given
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Display)]
+#[display(Debug)]
+pub struct ObsidianSlackClippings {
+    pub clippings: Vec<Clipping>,
+    pub users: Option<Users>,
+    pub file_name: FileName,
+    /// Requests made for the result, see [`FetchStats`]
+    pub fetch_stats: FetchStats,
+}
+
+impl ObsidianSlackClippings {
+    /// Enriches the gathered clippings as the options ask, fetched at
+    /// `fetched_at_ms`, unix milliseconds
+    pub fn finalize(
+        mut clippings: ObsidianSlackClippings,
+        options: &FinalizeOptions,
+        fetched_at_ms: f64,
+    ) -> Result<ObsidianSlackClippings> {
+        if !options.disable_text_sanitization {
+            clippings
+                .clippings
+                .iter_mut()
+                .for_each(|clipping| clipping.message.sanitize_text());
+            if let Some(users) = clippings.users.as_mut() {
+                users.sanitize_text();
+            }
+        }
+
+        let users = clippings.users.as_ref().map(SharedUsers::new);
+        clippings.clippings.iter_mut().try_for_each(|clipping| {
+            clipping
+                .message
+                .finalize_message(users.as_ref(), options, fetched_at_ms)
+                .context(CouldNotFinalizeMessageSnafu)
+        })?;
+        Ok(clippings)
+    }
+}
@@ -5,10 +5,31 @@ use shrinkwraprs::Shrinkwrap;
 use snafu::{ResultExt, Snafu};
 
 use crate::{
+    activity::{ThreadActivity, DEFAULT_RESOLVED_EMOJI},
+    attachments::{DuplicateFiles, SavedContents},
+    bots::Bots,
     channels::{self, Channel},
-    messages::{self, FileLinks, MessageAndThread},
+    due_dates::DueTasks,
+    extractors::{Extractions, Extractor, PrefixExtractor},
+    fetch_stats::FetchStats,
+    finalize_options::{FinalizeOptions, NoteTitleStyle},
+    graph::ParticipationGraph,
+    highlights::{self, Highlighter, Highlights},
+    messages::{
+        self, FileLinks, FilesData, Message, MessageAndThread, SkippedFiles, Tags, TextStatistics,
+    },
+    mrkdwn,
+    pii::PiiReport,
+    references::References,
+    reminders::ThreadReminders,
+    slack_http_client::SlackCredentials,
     team::{CollectTeams, TeamIds, Teams},
-    users::{self, CollectUsers, UserIds, Users},
+    usergroups::MentionedGroups,
+    users::{
+        self, CollectUsers, Participants, Presences, SharedUsers, User, UserDetailLevel, UserIds,
+        Users,
+    },
+    utils::{create_file_name_from_title, obsidian_uri, sanitize_note_title, SanitizeText},
 };
 
 #[derive(Debug, Snafu)]
@@ -30,6 +51,9 @@ pub enum Error {
 
     #[snafu(display("Could not finalize users - source: {source}"))]
     CouldNotFinalizeUsers { source: users::Error },
+
+    #[snafu(display("Could not build highlighter from options - source: {source}"))]
+    CouldNotBuildHighlighter { source: highlights::Error },
 }
 
 type Result<T, E = Error> = std::result::Result<T, E>;
@@ -38,6 +62,35 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 #[display(Debug)]
 pub struct FileName(pub String);
 
+/// Path of the saved note relative to the vault root
+#[derive(Debug, Serialize, Deserialize, Clone, Display, Shrinkwrap, PartialEq, Eq)]
+#[display(Debug)]
+pub struct SavePath(pub String);
+
+impl SavePath {
+    /// Joins the attachment folder and file name. None if the folder is relative to
+    /// the active note (`./`), since that note is unknown here
+    pub fn new(attachment_folder: Option<&str>, file_name: &FileName) -> Option<SavePath> {
+        let folder = attachment_folder.unwrap_or_default();
+        if folder == "." || folder.starts_with("./") {
+            return None;
+        }
+        let folder = folder.trim_matches('/');
+        Some(SavePath(if folder.is_empty() {
+            file_name.to_string()
+        } else {
+            format!("{}/{}", folder, file_name.0)
+        }))
+    }
+}
+
+/// The thread of a note saved before, read from the note's json, for
+/// [`ObsidianSlackComponents::merge_saved_note`]
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct SavedThread {
+    pub message_and_thread: MessageAndThread,
+}
+
 #[derive(Debug, Clone, Builder, Serialize, Deserialize, PartialEq, Display)]
 #[display(Debug)]
 #[builder(field(public))]
@@ -56,12 +109,175 @@ pub struct ObsidianSlackComponents {
     #[builder(default)]
     pub teams: Option<Teams>,
 
+    /// Bots that wrote messages of the thread, only when `include_bot_profiles` is
+    /// on
+    #[builder(default)]
+    pub bots: Option<Bots>,
+
     #[builder(default)]
     pub file_links: Option<FileLinks>,
+
+    /// Contents of the files in `file_links`, only when `embed_file_data` is on
+    #[builder(default)]
+    pub files_data: Option<FilesData>,
+
+    /// Files left out of `files_data` for having the same content as another,
+    /// only when `dedupe_file_data` is on
+    #[builder(default)]
+    pub duplicate_files: Option<DuplicateFiles>,
+
+    /// The `saved_contents` option with the contents of this save added, to pass
+    /// to the next save of a batch, only when `dedupe_file_data` is on
+    #[builder(default)]
+    pub saved_contents: Option<SavedContents>,
+
+    /// Files of the thread left out of `file_links`, e.g. for being larger than
+    /// `max_file_size_bytes`
+    #[builder(default)]
+    pub skipped_files: SkippedFiles,
+
+    /// Credentials returned by the plugin's refresh callback during this call, if
+    /// the original ones were rejected. The plugin should store these and must not
+    /// save them to the vault
+    #[builder(default)]
+    pub refreshed_credentials: Option<SlackCredentials>,
+
+    /// First line of the root message, see [`Message::title_suggestion`]
+    #[builder(default)]
+    pub title_suggestion: Option<String>,
+
+    /// Author of the root message, see [`Message::author_name`]
+    #[builder(default)]
+    pub root_author: Option<String>,
+
+    /// Date of the root message, see [`Message::date`]
+    #[builder(default)]
+    pub root_date: Option<String>,
+
+    /// Tags found in the thread, see [`MessageAndThread::collect_tags`]
+    #[builder(default)]
+    pub tags: Tags,
+
+    /// Word count and reading time of the thread
+    #[builder(default)]
+    pub text_statistics: TextStatistics,
+
+    /// Summary of everyone in the thread, only when users were retrieved
+    #[builder(default)]
+    pub participants: Option<Participants>,
+
+    /// Best guess at the locale of the thread, e.g. `en-US`: the channel's locale,
+    /// falling back to the root message author's
+    #[builder(default)]
+    pub locale: Option<String>,
+
+    /// Usergroups mentioned in the thread and their members, only when
+    /// `expand_usergroups` is on
+    #[builder(default)]
+    pub mentioned_groups: Option<MentionedGroups>,
+
+    /// Reminders you set on messages of the thread, only when `include_reminders`
+    /// is on
+    #[builder(default)]
+    pub reminders: Option<ThreadReminders>,
+
+    /// Where the plugin saves the note, see [`SavePath::new`]
+    #[builder(default)]
+    pub save_path: Option<SavePath>,
+
+    /// Link that opens the saved note, only when `vault_name` is set
+    #[builder(default)]
+    pub obsidian_uri: Option<String>,
+
+    /// Items such as decisions and action items found in the thread, see
+    /// [`Extractor`]
+    #[builder(default)]
+    pub extractions: Extractions,
+
+    /// Matches of the `highlight_keywords` and `highlight_patterns` options in the
+    /// thread
+    #[builder(default)]
+    pub highlights: Highlights,
+
+    /// Warnings slack returned with its responses, e.g. `missing_charset`. The
+    /// note is complete, but the plugin may want to surface them
+    #[builder(default)]
+    pub warnings: Vec<String>,
+
+    /// Requests made for the note, see [`FetchStats`]
+    #[builder(default)]
+    pub fetch_stats: FetchStats,
+
+    /// Emails, phone numbers and credentials found in the thread, only when
+    /// `detect_pii` is on
+    #[builder(default)]
+    pub pii_report: Option<PiiReport>,
+
+    /// Github issues and pull requests and jira tickets unfurled in the thread
+    #[builder(default)]
+    pub references: References,
+
+    /// Who replied after and reacted to whom, for analysing how the team talks
+    #[builder(default)]
+    pub graph: ParticipationGraph,
+
+    /// Follow ups of the thread for the obsidian tasks plugin, only when
+    /// `emit_due_tasks` is on
+    #[builder(default)]
+    pub due_tasks: Option<DueTasks>,
+
+    /// Whether the thread looks done, only when `finished_after_days` is set
+    #[builder(default)]
+    pub activity: Option<ThreadActivity>,
+
+    /// Presence of the users at the time of the save, only when `capture_presence`
+    /// is on. Kept in the participants, see [`Participants::capture_presence`]
+    #[builder(default)]
+    #[serde(skip)]
+    pub presences: Option<Presences>,
+
+    /// Link to the channel of the thread, for the `permalink_footnotes` option
+    #[builder(default)]
+    #[serde(skip)]
+    pub channel_link: Option<String>,
 }
 
 impl ObsidianSlackComponents {
-    pub fn finalize(mut components: ObsidianSlackComponents) -> Result<ObsidianSlackComponents> {
+    /// Enriches the gathered components as the options ask. `now_ms`, unix
+    /// milliseconds, is the time of the save, e.g. for `finished_after_days`
+    pub fn finalize(
+        components: ObsidianSlackComponents,
+        options: &FinalizeOptions,
+        now_ms: f64,
+    ) -> Result<ObsidianSlackComponents> {
+        let extractors = options
+            .extractors
+            .clone()
+            .unwrap_or_else(PrefixExtractor::defaults)
+            .into_iter()
+            .map(|extractor| Box::new(extractor) as Box<dyn Extractor>)
+            .collect::<Vec<Box<dyn Extractor>>>();
+        ObsidianSlackComponents::finalize_with_extractors(components, options, &extractors, now_ms)
+    }
+
+    /// Like [`ObsidianSlackComponents::finalize`], but with custom extractors
+    /// instead of the ones from the options
+    pub fn finalize_with_extractors(
+        mut components: ObsidianSlackComponents,
+        options: &FinalizeOptions,
+        extractors: &[Box<dyn Extractor>],
+        now_ms: f64,
+    ) -> Result<ObsidianSlackComponents> {
+        if options.detect_language && !cfg!(feature = "language_detection") {
+            log::warn!(
+                "rust|finalize|detect_language is set, but this build does not include the language_detection feature"
+            );
+        }
+
+        if !options.disable_text_sanitization {
+            components.sanitize_text();
+        }
+
         components.users = if let Some(users) = components.users {
             if let Some(ref teams) = components.teams {
                 Some(Users::finalize_users(users, teams).context(CouldNotFinalizeUsersSnafu)?)
@@ -72,11 +288,187 @@ impl ObsidianSlackComponents {
             components.users
         };
 
-        components.message_and_thread = MessageAndThread::finalize_message_and_thread(
-            components.message_and_thread,
+        components
+            .message_and_thread
+            .finalize_message_and_thread(components.users.as_ref(), options, now_ms)
+            .context(CouldNotFinalizeMesagesSnafu)?;
+        let deleted_count = components
+            .message_and_thread
+            .thread
+            .iter()
+            .filter(|message| message.is_deleted())
+            .count();
+        components
+            .warnings
+            .retain(|warning| !warning.starts_with(messages::DELETED_MESSAGES_WARNING));
+        if deleted_count > 0 {
+            components.warnings.push(format!(
+                "{}={}",
+                messages::DELETED_MESSAGES_WARNING,
+                deleted_count
+            ));
+        }
+        if let Some(bots) = components.bots.as_ref() {
+            components
+                .message_and_thread
+                .message
+                .iter_mut()
+                .chain(components.message_and_thread.thread.iter_mut())
+                .for_each(|message| {
+                    message.bot_info = message
+                        .bot_id
+                        .as_ref()
+                        .and_then(|bot_id| bots.get(bot_id))
+                        .cloned();
+                });
+        }
+        components.references = References::collect(&components.message_and_thread);
+        if options.link_references && !components.references.is_empty() {
+            let references = &components.references;
+            components
+                .message_and_thread
+                .message
+                .iter_mut()
+                .chain(components.message_and_thread.thread.iter_mut())
+                .for_each(|message| {
+                    message.text = message.text.as_deref().map(|text| references.link(text));
+                });
+        }
+        if options.unwrap_links {
+            components
+                .message_and_thread
+                .message
+                .iter_mut()
+                .chain(components.message_and_thread.thread.iter_mut())
+                .for_each(|message| {
+                    message.text = message.text.as_deref().map(mrkdwn::unwrap_links);
+                });
+        }
+        if let Some(users) = components.users.as_mut() {
+            users
+                .0
+                .retain(|user_id, _| !options.ignored_users.contains(user_id));
+        }
+        if let Some(channel) = components.channel.as_mut() {
+            channel.hide_users(&options.ignored_users);
+        }
+
+        let highlighter =
+            Highlighter::new(&options.highlight_keywords, &options.highlight_patterns)
+                .context(CouldNotBuildHighlighterSnafu)?;
+        if !highlighter.is_empty() {
+            components
+                .message_and_thread
+                .message
+                .iter_mut()
+                .chain(components.message_and_thread.thread.iter_mut())
+                .for_each(|message| {
+                    message.highlighted_text = message
+                        .text
+                        .as_deref()
+                        .and_then(|text| highlighter.highlight(text));
+                });
+            components.highlights =
+                Highlights::collect(&components.message_and_thread, &highlighter);
+        }
+
+        components.graph = ParticipationGraph::build(&components.message_and_thread);
+
+        if options.detect_pii {
+            components.pii_report = Some(PiiReport::scan(&components.message_and_thread));
+        }
+
+        let root_message = components.message_and_thread.root_message();
+        components.title_suggestion = root_message.and_then(Message::title_suggestion);
+        components.root_author = root_message.and_then(Message::author_name);
+        components.root_date = root_message.and_then(Message::date);
+        components.locale = components
+            .channel
+            .as_ref()
+            .and_then(|channel| channel.locale.clone())
+            .or_else(|| {
+                root_message
+                    .and_then(|message| message.user_info.as_ref())
+                    .and_then(|user| user.locale.clone())
+            });
+
+        components.tags = components
+            .message_and_thread
+            .collect_tags(&options.keyword_tags, &options.reaction_tags);
+        components.text_statistics = components.message_and_thread.text_statistics();
+        components.extractions = Extractions::extract(&components.message_and_thread, extractors);
+        if options.emit_due_tasks {
+            components.due_tasks = Some(DueTasks::collect(
+                &components.message_and_thread,
+                components.reminders.as_ref(),
+            ));
+        }
+        if let Some(finished_after_days) = options.finished_after_days {
+            let resolved_emoji = options.resolved_emoji.clone().unwrap_or_else(|| {
+                DEFAULT_RESOLVED_EMOJI
+                    .iter()
+                    .map(|emoji| emoji.to_string())
+                    .collect()
+            });
+            components.activity = Some(ThreadActivity::assess(
+                &components.message_and_thread,
+                now_ms,
+                finished_after_days,
+                &resolved_emoji,
+            ));
+        }
+        components.participants = components
+            .users
+            .as_ref()
+            .map(|users| Participants::summarize(users, &components.message_and_thread.thread));
+        if let (Some(participants), Some(users), Some(presences)) = (
+            components.participants.as_mut(),
             components.users.as_ref(),
-        )
-        .context(CouldNotFinalizeMesagesSnafu)?;
+            components.presences.as_ref(),
+        ) {
+            participants.capture_presence(users, presences);
+        }
+
+        let group_dm_label = components
+            .channel
+            .as_ref()
+            .and_then(|channel| channel.group_dm_label(components.users.as_ref()));
+        if options.title_channel_prefix {
+            let channel_label = group_dm_label.as_ref().or(components
+                .channel
+                .as_ref()
+                .and_then(|channel| channel.name.as_ref().or(channel.id.as_ref())));
+            components.title_suggestion = match (channel_label, components.title_suggestion) {
+                (Some(channel_label), Some(title)) => Some(sanitize_note_title(&format!(
+                    "{} - {}",
+                    channel_label, title
+                ))),
+                (_, title) => title,
+            };
+        }
+
+        if let (NoteTitleStyle::Content, Some(title)) =
+            (options.note_title_style, &components.title_suggestion)
+        {
+            components.file_name = FileName(create_file_name_from_title(title));
+        } else if let Some((label, channel_id)) = group_dm_label.as_ref().zip(
+            components
+                .channel
+                .as_ref()
+                .and_then(|channel| channel.id.as_ref()),
+        ) {
+            // timestamp style names start with the channel id
+            if let Some(rest) = components.file_name.0.strip_prefix(channel_id.as_str()) {
+                components.file_name = FileName(format!("{}{}", label, rest));
+            }
+        }
+        components.save_path =
+            SavePath::new(options.attachment_folder.as_deref(), &components.file_name);
+        components.obsidian_uri = options
+            .vault_name
+            .as_deref()
+            .zip(components.save_path.as_ref())
+            .map(|(vault_name, save_path)| obsidian_uri(vault_name, save_path));
 
         components.channel = if let Some(channel) = components.channel {
             Some(
@@ -86,8 +478,164 @@ impl ObsidianSlackComponents {
         } else {
             components.channel
         };
+
+        if options.user_detail_level != UserDetailLevel::Full {
+            components.trim_users_to(options.user_detail_level);
+        }
+        if let Some(channel) = components.channel.as_mut() {
+            channel.trim_to(options.channel_detail_level);
+        }
+        // last, everything derived from the thread expects it oldest first
+        components
+            .message_and_thread
+            .order_thread(options.thread_order);
+        if let Some(emoji) = options.task_reaction.as_deref() {
+            components.message_and_thread.convert_to_tasks(emoji);
+        }
+        if let Some(max_lines) = options.fold_messages_over_lines {
+            components
+                .message_and_thread
+                .message
+                .iter_mut()
+                .chain(components.message_and_thread.thread.iter_mut())
+                .for_each(|message| message.fold_long_text(max_lines, options.fold_style));
+        }
+        // after folding, a footnote folded away with the text would not show
+        if let Some(channel_link) = components
+            .channel_link
+            .as_deref()
+            .filter(|_| options.permalink_footnotes)
+        {
+            components
+                .message_and_thread
+                .message
+                .iter_mut()
+                .chain(components.message_and_thread.thread.iter_mut())
+                .for_each(|message| message.add_permalink_footnote(channel_link));
+        }
+        if options.normalize_users {
+            components.normalize_users();
+        }
         Ok(components)
     }
+
+    /// Leaves every user and team only in the top level `users` and `teams`, see
+    /// the `normalize_users` option
+    fn normalize_users(&mut self) {
+        self.message_and_thread
+            .message
+            .iter_mut()
+            .chain(self.message_and_thread.thread.iter_mut())
+            .chain(
+                self.channel
+                    .iter_mut()
+                    .flat_map(|channel| channel.latest.iter_mut()),
+            )
+            .for_each(Message::drop_user_info);
+        if let Some(channel) = self.channel.as_mut() {
+            channel.user_info = None;
+        }
+        if let Some(users) = self.users.as_mut() {
+            users.0.values_mut().for_each(|user| user.team_info = None);
+        }
+    }
+
+    /// Fills the users and teams back in where normalized components only have
+    /// their ids, so they read like components saved without the `normalize_users`
+    /// option. Components that were not normalized stay the same
+    pub fn join_users(&mut self) {
+        if let (Some(users), Some(teams)) = (self.users.as_mut(), self.teams.as_ref()) {
+            users.0.values_mut().for_each(|user| {
+                user.team_info = user
+                    .team_id
+                    .as_ref()
+                    .and_then(|team_id| teams.get(team_id))
+                    .cloned();
+            });
+        }
+        let users = match self.users.as_ref() {
+            Some(users) => SharedUsers::new(users),
+            None => return,
+        };
+
+        if let Some(channel) = self.channel.as_mut() {
+            channel.user_info = channel
+                .user
+                .as_deref()
+                .and_then(|user_id| users.get(user_id))
+                .map(|user| User::clone(user));
+        }
+        self.message_and_thread
+            .message
+            .iter_mut()
+            .chain(self.message_and_thread.thread.iter_mut())
+            .chain(
+                self.channel
+                    .iter_mut()
+                    .flat_map(|channel| channel.latest.iter_mut()),
+            )
+            .for_each(|message| message.join_user_info(&users));
+    }
+
+    /// Merges the thread of a note saved before into these components, so a
+    /// refresh keeps messages gone from slack since, e.g. deleted ones, and the
+    /// reaction history. See [`MessageAndThread::merge_message_and_thread`]
+    pub fn merge_saved_note(&mut self, saved: SavedThread) {
+        self.message_and_thread = MessageAndThread::merge_message_and_thread(
+            saved.message_and_thread,
+            std::mem::take(&mut self.message_and_thread),
+        );
+        self.text_statistics = self.message_and_thread.text_statistics();
+    }
+
+    /// Trims every user in the components, see [`crate::users::User::trim_to`]. Runs
+    /// last so everything derived from users, e.g. the participants, is complete
+    fn trim_users_to(&mut self, level: UserDetailLevel) {
+        if let Some(users) = self.users.as_mut() {
+            users.0.values_mut().for_each(|user| user.trim_to(level));
+        }
+        self.message_and_thread
+            .message
+            .iter_mut()
+            .chain(self.message_and_thread.thread.iter_mut())
+            .chain(
+                self.channel
+                    .iter_mut()
+                    .flat_map(|channel| channel.latest.iter_mut()),
+            )
+            .for_each(|message| message.trim_users_to(level));
+        if let Some(user) = self
+            .channel
+            .as_mut()
+            .and_then(|channel| channel.user_info.as_mut())
+        {
+            user.trim_to(level);
+        }
+    }
+}
+
+impl SanitizeText for ObsidianSlackComponents {
+    fn sanitize_text(&mut self) {
+        self.message_and_thread.sanitize_text();
+        if let Some(users) = self.users.as_mut() {
+            users.sanitize_text();
+        }
+        if let Some(channel) = self.channel.as_mut() {
+            channel.sanitize_text();
+        }
+        if let Some(teams) = self.teams.as_mut() {
+            teams.sanitize_text();
+        }
+        if let Some(bots) = self.bots.as_mut() {
+            bots.sanitize_text();
+        }
+        if let Some(mentioned_groups) = self.mentioned_groups.as_mut() {
+            mentioned_groups.sanitize_text();
+        }
+        if let Some(reminders) = self.reminders.as_mut() {
+            reminders.sanitize_text();
+        }
+    }
 }
 
 impl std::fmt::Display for ObsidianSlackComponentsBuilder {
@@ -140,3 +688,107 @@ impl CollectTeams<Error> for ObsidianSlackComponentsBuilder {
             .context(CouldNotGetTeamsFromUsersSnafu)
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Refreshing a saved note merges the thread fetched now into the saved one,
+    //! keeping what slack no longer has, e.g. deleted messages and older reaction counts
+
+    use serde_json::json;
+
+    use crate::{
+        components::{
+            FileName, ObsidianSlackComponents, ObsidianSlackComponentsBuilder, SavedThread,
+        },
+        finalize_options::FinalizeOptions,
+        messages::{MessageAndThread, Messages},
+        render::render_note,
+    };
+
+    fn components(thread: serde_json::Value) -> ObsidianSlackComponents {
+        let thread: Messages = serde_json::from_value(thread).expect("Expected valid messages");
+        ObsidianSlackComponentsBuilder::default()
+            .message_and_thread(MessageAndThread {
+                message: Messages(thread.iter().take(1).cloned().collect()),
+                thread,
+            })
+            .file_name(FileName("C1-1700000000.000100.json".to_string()))
+            .build()
+            .expect("Expected to build the components")
+    }
+
+    #[test]
+    fn merge_saved_note_matches_messages_saved_without_client_msg_id_by_ts() {
+        let saved: SavedThread = serde_json::from_str(&render_note(
+            &components(json!([
+                { "ts": "1700000000.000100", "text": "root" },
+                { "ts": "1700000001.000100", "text": "deleted since" },
+            ])),
+            true,
+        ))
+        .expect("Should read the thread of the note");
+        let mut refreshed = components(json!([
+            { "ts": "1700000000.000100", "client_msg_id": "a1", "text": "root, edited" },
+            { "ts": "1700000002.000100", "client_msg_id": "b2", "text": "new reply" },
+        ]));
+
+        refreshed.merge_saved_note(saved);
+
+        let texts = |messages: &Messages| {
+            messages
+                .iter()
+                .map(|message| message.text.clone().unwrap_or_default())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            vec!["root, edited"],
+            texts(&refreshed.message_and_thread.message)
+        );
+        assert_eq!(
+            vec!["root, edited", "deleted since", "new reply"],
+            texts(&refreshed.message_and_thread.thread)
+        );
+        assert_eq!(
+            Some("a1".to_string()),
+            refreshed.message_and_thread.thread[0].client_msg_id
+        );
+        assert_eq!(6, refreshed.text_statistics.word_count);
+    }
+
+    #[test]
+    fn track_reaction_history_records_the_counts_of_every_refresh() {
+        let options = FinalizeOptions {
+            track_reaction_history: true,
+            ..FinalizeOptions::default()
+        };
+        let fetch = |count: u16, fetched_at_ms: f64| {
+            let thread = json!([{
+                "ts": "1700000000.000100",
+                "text": "root",
+                "reactions": [{ "name": "eyes", "users": [], "count": count }]
+            }]);
+            ObsidianSlackComponents::finalize(components(thread), &options, fetched_at_ms)
+                .expect("Expected to finalize the components")
+        };
+        let saved: SavedThread = serde_json::from_str(&render_note(&fetch(1, 1.7e12), true))
+            .expect("Should read the thread of the note");
+        let mut refreshed = fetch(3, 1.7e12 + 86_400_000.0);
+
+        refreshed.merge_saved_note(saved);
+
+        let history = refreshed.message_and_thread.thread[0]
+            .reaction_history
+            .as_ref()
+            .expect("Expected a reaction history");
+        assert_eq!(
+            vec![
+                (Some("2023-11-14T22:13:20Z"), Some(&1)),
+                (Some("2023-11-15T22:13:20Z"), Some(&3)),
+            ],
+            history
+                .iter()
+                .map(|snapshot| (snapshot.fetched_at.as_deref(), snapshot.counts.get("eyes")))
+                .collect::<Vec<_>>()
+        );
+    }
+}
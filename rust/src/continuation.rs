@@ -0,0 +1,94 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt, Snafu};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not read the continuation token - source: {source}"))]
+    CouldNotParseContinuationToken { source: serde_json::Error },
+
+    #[snafu(display(
+        "The continuation token is for channel {token_channel_id} until {token_latest}, not channel {channel_id} until {latest}"
+    ))]
+    ContinuationTokenDoesNotMatch {
+        token_channel_id: String,
+        token_latest: String,
+        channel_id: String,
+        latest: String,
+    },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// FNV-1a offset basis, the state digest of an export that has not exported
+/// anything yet
+const EMPTY_STATE_DIGEST: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Where a channel export stopped because it hit the `export_time_budget_ms`,
+/// `export_message_budget` or `memory_limit_bytes` option. Handed back in the
+/// `continuation_token` option, the next export picks up from here
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ContinuationToken {
+    pub channel_id: String,
+    /// Start of the rest of the period, as sent to `conversations.history`
+    pub oldest: String,
+    /// End of the period, the same for every part of the export
+    pub latest: String,
+    /// `conversations.history` cursor of the next page, None to start from the
+    /// newest message of the rest of the period
+    pub cursor: Option<String>,
+    /// Threads fetched by every earlier part of the export
+    pub exported_threads: usize,
+    /// Hash of the root ts of every thread exported so far, so the plugin can
+    /// tell that the parts it saved belong together
+    pub state_digest: String,
+    /// ts of the newest root message exported so far, None for tokens from before
+    /// it was kept
+    #[serde(default)]
+    pub newest_ts: Option<String>,
+}
+
+impl ContinuationToken {
+    pub fn parse(token: &str) -> Result<ContinuationToken> {
+        serde_json::from_str(token).context(CouldNotParseContinuationTokenSnafu)
+    }
+
+    /// The token as the string the plugin hands back
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self)
+            .expect("Expected to serialize object with serde, but was unable to. This is a bug")
+    }
+
+    /// Fails unless the token continues an export of `channel_id` until `latest`
+    pub(crate) fn ensure_continues(&self, channel_id: &str, latest: &str) -> Result<()> {
+        ensure!(
+            self.channel_id == channel_id && self.latest == latest,
+            ContinuationTokenDoesNotMatchSnafu {
+                token_channel_id: &self.channel_id,
+                token_latest: &self.latest,
+                channel_id,
+                latest,
+            }
+        );
+        Ok(())
+    }
+}
+
+/// `previous` state digest, or that of an empty export, with the root ts of the
+/// threads exported since
+pub(crate) fn accumulate_state_digest<'a>(
+    previous: Option<&str>,
+    root_ts: impl Iterator<Item = &'a str>,
+) -> String {
+    let start = previous
+        .and_then(|previous| u64::from_str_radix(previous, 16).ok())
+        .unwrap_or(EMPTY_STATE_DIGEST);
+    let digest = root_ts
+        .flat_map(|ts| ts.bytes().chain(std::iter::once(b'\n')))
+        .fold(start, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        });
+    format!("{:016x}", digest)
+}
@@ -0,0 +1,161 @@
+use futures::future::{FutureExt, LocalBoxFuture, Shared};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+/// A response shared by every request with the same id, see [`RecentRequests`]
+pub type SharedResponse = Shared<LocalBoxFuture<'static, Result<String, String>>>;
+
+/// Responses of the requests sent within a window, by request id, so a request
+/// sent again, e.g. by a retry while the first one is still running, is not
+/// processed twice, see [`crate::slack_http_client::RequestUrlParam::request_id`].
+/// Only pending and ok responses are kept, a request that failed is sent again
+#[derive(Default)]
+pub struct RecentRequests {
+    sent: Rc<RefCell<HashMap<String, (f64, SharedResponse)>>>,
+}
+
+impl RecentRequests {
+    /// The response of the request `request_id` sent within `window_ms` before
+    /// `now_ms`, or else the response of `send`, kept for the window unless it
+    /// fails. True when `send` was called
+    pub fn send_once<F>(
+        &self,
+        request_id: &str,
+        now_ms: f64,
+        window_ms: f64,
+        send: F,
+    ) -> (SharedResponse, bool)
+    where
+        F: FnOnce() -> LocalBoxFuture<'static, Result<String, String>>,
+    {
+        self.sent
+            .borrow_mut()
+            .retain(|_, (sent_at_ms, _)| now_ms - *sent_at_ms < window_ms);
+        if let Some((_, response)) = self.sent.borrow().get(request_id) {
+            return (response.clone(), false);
+        }
+        let sent = self.sent.clone();
+        let id = request_id.to_string();
+        let response = send()
+            .map(move |response| {
+                if !is_ok(&response) {
+                    let mut sent = sent.borrow_mut();
+                    // a later request may have taken the id after the window
+                    if sent
+                        .get(&id)
+                        .is_some_and(|(sent_at_ms, _)| *sent_at_ms == now_ms)
+                    {
+                        sent.remove(&id);
+                    }
+                }
+                response
+            })
+            .boxed_local()
+            .shared();
+        self.sent
+            .borrow_mut()
+            .insert(request_id.to_string(), (now_ms, response.clone()));
+        (response, true)
+    }
+}
+
+/// Whether slack answered `"ok": true`
+fn is_ok(response: &Result<String, String>) -> bool {
+    response
+        .as_ref()
+        .ok()
+        .and_then(|body| serde_json::from_str::<serde_json::Value>(body).ok())
+        .and_then(|body| body.get("ok").and_then(serde_json::Value::as_bool))
+        .unwrap_or(false)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of sending a write to slack once, run with `cargo test`. The plugin
+    //! retries calls that time out, which must not add a bookmark twice, but a retry
+    //! of a write that failed must reach slack
+
+    use std::cell::Cell;
+
+    use crate::dedup::RecentRequests;
+    use futures::future::{self, FutureExt, LocalBoxFuture};
+
+    const OK: &str = "{\"ok\":true}";
+
+    /// A send counting its calls in `sends`, answered with `response`
+    fn send<'a>(
+        sends: &'a Cell<usize>,
+        response: Result<&'a str, &'a str>,
+    ) -> impl FnOnce() -> LocalBoxFuture<'static, Result<String, String>> + 'a {
+        move || {
+            sends.set(sends.get() + 1);
+            future::ready(response.map(str::to_string).map_err(str::to_string)).boxed_local()
+        }
+    }
+
+    #[async_std::test]
+    async fn a_request_sent_again_within_the_window_gets_the_first_response() {
+        let recent = RecentRequests::default();
+        let sends = Cell::new(0);
+
+        let (first, sent) = recent.send_once("a", 0.0, 1_000.0, send(&sends, Ok(OK)));
+        assert!(sent);
+        assert_eq!(Ok(OK.to_string()), first.await);
+        let (again, sent) = recent.send_once("a", 999.0, 1_000.0, send(&sends, Ok(OK)));
+        assert!(!sent);
+        assert_eq!(Ok(OK.to_string()), again.await);
+        let (_, sent) = recent.send_once("b", 999.0, 1_000.0, send(&sends, Ok(OK)));
+        assert!(sent);
+        assert_eq!(2, sends.get());
+    }
+
+    #[async_std::test]
+    async fn a_pending_request_is_shared() {
+        let recent = RecentRequests::default();
+        let sends = Cell::new(0);
+
+        let (first, _) = recent.send_once("a", 0.0, 1_000.0, send(&sends, Ok(OK)));
+        let (again, sent) = recent.send_once("a", 1.0, 1_000.0, send(&sends, Ok(OK)));
+
+        assert!(!sent);
+        assert_eq!(first.await, again.await);
+        assert_eq!(1, sends.get());
+    }
+
+    #[async_std::test]
+    async fn a_request_sent_again_after_the_window_is_sent() {
+        let recent = RecentRequests::default();
+        let sends = Cell::new(0);
+
+        recent
+            .send_once("a", 0.0, 1_000.0, send(&sends, Ok(OK)))
+            .0
+            .await
+            .unwrap();
+        let (_, sent) = recent.send_once("a", 1_000.0, 1_000.0, send(&sends, Ok(OK)));
+
+        assert!(sent);
+        assert_eq!(2, sends.get());
+    }
+
+    #[async_std::test]
+    async fn a_request_that_failed_is_sent_again() {
+        let recent = RecentRequests::default();
+        let sends = Cell::new(0);
+
+        let (first, _) = recent.send_once("a", 0.0, 1_000.0, send(&sends, Err("timed out")));
+        assert_eq!(Err("timed out".to_string()), first.await);
+        let (second, sent) = recent.send_once(
+            "a",
+            1.0,
+            1_000.0,
+            send(&sends, Ok("{\"ok\":false,\"error\":\"ratelimited\"}")),
+        );
+        assert!(sent);
+        second.await.unwrap();
+        let (third, sent) = recent.send_once("a", 2.0, 1_000.0, send(&sends, Ok(OK)));
+
+        assert!(sent);
+        assert_eq!(Ok(OK.to_string()), third.await);
+        assert_eq!(3, sends.get());
+    }
+}
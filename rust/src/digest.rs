@@ -0,0 +1,557 @@
+use amplify_derive::Display;
+use do_notation::m;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::HashMap;
+
+use crate::{
+    channels,
+    chronicle::Chronicle,
+    components::FileName,
+    continuation::{self, ContinuationToken},
+    fetch_stats::FetchStats,
+    finalize_options::{FinalizeOptions, NoteTitleStyle},
+    memory::{self, MemoryLimitAction, MemoryUsage, DEFAULT_MEMORY_LIMIT_BYTES},
+    messages::{self, Message, MessageAndThread, Messages, DEFAULT_DO_NOT_ARCHIVE_EMOJI},
+    response::{self, PaginatedResponse, Paginator, ResponseMetadata, SlackEnvelope},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    users::{self, CollectUsers, SharedUsers, Users},
+    utils::{create_file_name_from_title, format_ts_as_date_time, SanitizeText},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("{source}"))]
+    ExportNotAllowed { source: channels::Error },
+
+    #[snafu(display("{source}"))]
+    InvalidContinuationToken { source: continuation::Error },
+
+    #[snafu(display("Could not get the last read message of the channel - source: {source}"))]
+    CouldNotGetLastRead { source: channels::Error },
+
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
+
+    #[snafu(display("{source}"))]
+    CouldNotDeserializeHistoryResponse { source: response::Error },
+
+    #[snafu(display("The history response was not ok. - source: {source}"))]
+    InvalidHistoryResponse { source: response::Error },
+
+    #[snafu(display("Could not parse json from history response string - source: {source}"))]
+    CouldNotParseJsonFromHistoryResponse { source: response::Error },
+
+    #[snafu(display("Could not get thread {thread_ts} from api - source: {source}"))]
+    CouldNotGetThreadFromApi {
+        thread_ts: String,
+        source: messages::Error,
+    },
+
+    #[snafu(display("Could not get users from threads - source: {source}"))]
+    CouldNotCollectUsersFromThreads { source: messages::Error },
+
+    #[snafu(display("Could not get users from api - source: {source}"))]
+    CouldNotGetUsersFromApi { source: users::Error },
+
+    #[snafu(display("Could not finalize digest thread - source: {source}"))]
+    CouldNotFinalizeThread { source: messages::Error },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Reactions listed per thread in a digest
+const TOP_REACTIONS: usize = 3;
+
+/// `since` as sent to `conversations.history`, or with the `only_unread` option
+/// the last read message when it is later
+async fn start_of_period<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+    since: f64,
+) -> Result<String>
+where
+    T: TransportResponse,
+{
+    let options = &client.config.options;
+    if !options.only_unread {
+        return Ok(format!("{:.6}", since));
+    }
+    let last_read = match options.last_read_ts.clone() {
+        Some(last_read) => Some(last_read),
+        None => {
+            channels::get_channel_from_api(client, channel_id)
+                .await
+                .context(CouldNotGetLastReadSnafu)?
+                .last_read
+        }
+    };
+    Ok(last_read
+        .filter(|last_read| {
+            last_read
+                .parse::<f64>()
+                .is_ok_and(|last_read| last_read > since)
+        })
+        .unwrap_or_else(|| format!("{:.6}", since)))
+}
+
+/// Gets the channel's messages from `since` until `until` (unix seconds) and the
+/// replies of their threads, summarized per thread, oldest thread first
+pub async fn get_digest_from_api<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+    since: f64,
+    until: f64,
+) -> Result<Digest>
+where
+    T: TransportResponse,
+{
+    channels::ensure_export_allowed(client, channel_id)
+        .await
+        .context(ExportNotAllowedSnafu)?;
+    let options = &client.config.options;
+    let latest = format!("{:.6}", until);
+    let token = options
+        .continuation_token
+        .as_deref()
+        .map(ContinuationToken::parse)
+        .transpose()
+        .context(InvalidContinuationTokenSnafu)?;
+    if let Some(token) = token.as_ref() {
+        token
+            .ensure_continues(channel_id, &latest)
+            .context(InvalidContinuationTokenSnafu)?;
+    }
+    let oldest = match token.as_ref() {
+        Some(token) => token.oldest.clone(),
+        None => start_of_period(client, channel_id, since).await?,
+    };
+    let limit_bytes = options
+        .memory_limit_bytes
+        .unwrap_or(DEFAULT_MEMORY_LIMIT_BYTES);
+    let used_bytes = MemoryUsage::current().heap_bytes;
+
+    let (mut root_messages, cursor) = get_history_from_api(
+        client,
+        channel_id,
+        &oldest,
+        &latest,
+        token.as_ref().and_then(|token| token.cursor.clone()),
+        used_bytes,
+    )
+    .await?;
+    root_messages.sort_by(|a, b| a.ts.cmp(&b.ts));
+    // like threads saved on their own, every message needs an author, so messages
+    // slack posted itself (e.g. channel joins by integrations) are left out
+    root_messages.retain(|message| message.user.is_some());
+
+    let fitting = memory::threads_within_limit(&root_messages, used_bytes, limit_bytes);
+    let mut warnings = vec![];
+    let mut continue_from = None;
+    if fitting < root_messages.len() {
+        let estimated_bytes = used_bytes
+            + root_messages
+                .iter()
+                .map(memory::estimate_thread_bytes)
+                .sum::<usize>();
+        log::warn!(
+            "rust|get_digest_from_api|estimated memory of {} bytes is over the limit of {} bytes",
+            estimated_bytes,
+            limit_bytes
+        );
+        match options.memory_limit_action {
+            MemoryLimitAction::Warn => warnings.push(format!(
+                "The {} threads need about {} of memory, more than the limit of {}. Export a shorter period or chunk the export",
+                root_messages.len(),
+                memory::format_bytes(estimated_bytes),
+                memory::format_bytes(limit_bytes)
+            )),
+            // paging stopped at the limit already when there is a cursor left
+            MemoryLimitAction::Chunk if cursor.is_some() => {}
+            MemoryLimitAction::Chunk => {
+                continue_from = root_messages[fitting].ts.clone();
+                root_messages.truncate(fitting);
+            }
+        }
+    }
+
+    let newest_ts = root_messages
+        .iter()
+        .filter_map(|message| message.ts.clone())
+        .chain(token.as_ref().and_then(|token| token.newest_ts.clone()))
+        .max();
+    let rest = match (cursor, continue_from.as_ref()) {
+        (Some(cursor), _) => Some((oldest.clone(), Some(cursor))),
+        (None, Some(continue_from)) => Some((continue_from.clone(), None)),
+        (None, None) => None,
+    };
+    let continuation = rest.map(|(rest_oldest, rest_cursor)| {
+        ContinuationToken {
+            channel_id: channel_id.to_string(),
+            oldest: rest_oldest,
+            latest: latest.clone(),
+            cursor: rest_cursor,
+            exported_threads: token.as_ref().map_or(0, |token| token.exported_threads)
+                + root_messages.len(),
+            state_digest: continuation::accumulate_state_digest(
+                token.as_ref().map(|token| token.state_digest.as_str()),
+                root_messages
+                    .iter()
+                    .filter_map(|message| message.ts.as_deref()),
+            ),
+            newest_ts: newest_ts.clone(),
+        }
+        .encode()
+    });
+    let read_up_to = newest_ts.filter(|_| continuation.is_none());
+
+    let threads = join_all(root_messages.into_iter().map(|root_message| async move {
+        match (root_message.reply_count, root_message.ts.as_ref()) {
+            (Some(reply_count), Some(thread_ts)) if reply_count > 0 => {
+                let thread = messages::get_thread_from_api(client, channel_id, thread_ts)
+                    .await
+                    .context(CouldNotGetThreadFromApiSnafu { thread_ts })?;
+                Ok(Messages(
+                    thread
+                        .data
+                        .messages
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|message| message.user.is_some())
+                        .collect(),
+                ))
+            }
+            _ => Ok(Messages(vec![root_message])),
+        }
+    }))
+    .await
+    .into_iter()
+    .collect::<Result<Vec<Messages>>>()?;
+
+    let users = if client.config.feature_flags.get_users {
+        let user_ids = Messages(
+            threads
+                .iter()
+                .flat_map(|thread| thread.iter().cloned())
+                .collect(),
+        )
+        .collect_users()
+        .context(CouldNotCollectUsersFromThreadsSnafu)?;
+        let mut user_ids = user_ids.0;
+        user_ids.sort();
+        user_ids.dedup();
+        Some(
+            users::get_users_from_api(&user_ids, client, &mut Users::default())
+                .await
+                .context(CouldNotGetUsersFromApiSnafu)?,
+        )
+    } else {
+        None
+    };
+
+    Ok(Digest {
+        channel_id: channel_id.to_string(),
+        since: format_ts_as_date_time(&oldest),
+        until: format_ts_as_date_time(&latest),
+        message_count: threads.iter().map(|thread| thread.len()).sum(),
+        file_name: FileName(format!("digest-{}-{}-{}.json", channel_id, oldest, latest)),
+        threads,
+        users,
+        summaries: vec![],
+        index: vec![],
+        fetch_stats: FetchStats::default(),
+        warnings,
+        continue_from,
+        continuation,
+        read_up_to,
+        chronicle: None,
+    })
+}
+
+async fn get_history_from_api<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+    oldest: &str,
+    latest: &str,
+    cursor: Option<String>,
+    used_bytes: usize,
+) -> Result<(Vec<Message>, Option<String>)>
+where
+    T: TransportResponse,
+{
+    let options = &client.config.options;
+    let started_at = js_sys::Date::now();
+    let limit_bytes = options
+        .memory_limit_bytes
+        .unwrap_or(DEFAULT_MEMORY_LIMIT_BYTES);
+    let mut estimated_bytes = used_bytes;
+    let mut messages = vec![];
+    let mut paginator = Paginator::resume(cursor);
+    loop {
+        let result = client
+            .get_conversations_history(channel_id, oldest, latest, paginator.cursor())
+            .into_body()
+            .await;
+        let history_response: HistoryResponse = m! {
+            // mapping error instead of using snafu context because the request's error is a string, not an Error
+            val <- result.map_err(|error| Error::RequestFailed { error });
+            json <- response::parse_response_body(val).context(CouldNotParseJsonFromHistoryResponseSnafu);
+            history_response <- response::defined_from_json(json).context(CouldNotDeserializeHistoryResponseSnafu);
+            valid_response <- client.validate_response(history_response).context(InvalidHistoryResponseSnafu);
+            return valid_response;
+        }?;
+
+        let has_next_page = paginator.advance(&history_response);
+        let page = history_response.data.messages.unwrap_or_default();
+        estimated_bytes += page
+            .iter()
+            .map(memory::estimate_thread_bytes)
+            .sum::<usize>();
+        messages.extend(page);
+
+        let over_budget = options
+            .export_message_budget
+            .is_some_and(|budget| messages.len() >= budget)
+            || options
+                .export_time_budget_ms
+                .is_some_and(|budget| js_sys::Date::now() - started_at >= budget)
+            || (options.memory_limit_action == MemoryLimitAction::Chunk
+                && estimated_bytes > limit_bytes);
+        if !has_next_page || over_budget {
+            return Ok((messages, paginator.into_cursor()));
+        }
+    }
+}
+
+/// A channel's threads over a period, e.g. for a weekly review note
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Display)]
+#[display(Debug)]
+pub struct Digest {
+    pub channel_id: String,
+    /// Start of the period as an RFC 3339 UTC date time
+    pub since: Option<String>,
+    /// End of the period as an RFC 3339 UTC date time
+    pub until: Option<String>,
+    pub message_count: usize,
+    pub summaries: Vec<ThreadSummary>,
+    /// Every message of each thread, a message without replies is a thread of one
+    pub threads: Vec<Messages>,
+    pub users: Option<Users>,
+    pub file_name: FileName,
+    /// One entry per thread, oldest first, to link the threads from a note about
+    /// the channel
+    pub index: Vec<ThreadIndexEntry>,
+    /// Requests made for the result, see [`FetchStats`]
+    pub fetch_stats: FetchStats,
+    /// e.g. that the export is over the `memory_limit_bytes` option
+    pub warnings: Vec<String>,
+    /// ts of the first thread left out to stay under the `memory_limit_bytes`
+    /// option when `memory_limit_action` is `chunk`. Export again with it as
+    /// `since` for the rest of the period
+    pub continue_from: Option<String>,
+    /// Set when the export stopped before the end of the period, see
+    /// [`ContinuationToken`]. Hand it back in the `continuation_token` option,
+    /// with the same channel and `until`, for the next part
+    pub continuation: Option<String>,
+    /// ts of the newest message of the whole export, only on its last part. Hand
+    /// it to [`crate::mark_channel_read`] once every part is saved
+    pub read_up_to: Option<String>,
+    /// The threads grouped by day, only when `group_by_day` is on
+    pub chronicle: Option<Chronicle>,
+}
+
+/// The gist of one thread in a [`Digest`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ThreadSummary {
+    pub thread_ts: Option<String>,
+    pub title_suggestion: Option<String>,
+    pub root_author: Option<String>,
+    pub date: Option<String>,
+    pub message_count: usize,
+    /// Most active first
+    pub participants: Vec<DigestParticipant>,
+    /// Most used first
+    pub top_reactions: Vec<ReactionCount>,
+}
+
+/// A thread of a [`Digest`] as a channel's map of content note links to it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ThreadIndexEntry {
+    pub root_ts: String,
+    pub title_suggestion: Option<String>,
+    pub reply_count: usize,
+    /// The file name [`crate::get_slack_message`] gives the thread's note, following
+    /// the `note_title_style` option. The `title_channel_prefix` option is ignored,
+    /// a digest does not get the channel's name
+    pub file_name: FileName,
+}
+
+impl ThreadIndexEntry {
+    /// None for a thread without a root message ts
+    fn new(
+        channel_id: &str,
+        thread: &Messages,
+        options: &FinalizeOptions,
+    ) -> Option<ThreadIndexEntry> {
+        let root_message = thread.first()?;
+        let root_ts = root_message.ts.clone()?;
+        let title_suggestion = root_message.title_suggestion();
+        let file_name = match (options.note_title_style, title_suggestion.as_deref()) {
+            (NoteTitleStyle::Content, Some(title)) => create_file_name_from_title(title),
+            _ => format!("{}-{}.json", channel_id, root_ts),
+        };
+        Some(ThreadIndexEntry {
+            root_ts,
+            title_suggestion,
+            reply_count: thread.len().saturating_sub(1),
+            file_name: FileName(file_name),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct DigestParticipant {
+    pub id: String,
+    pub name: Option<String>,
+    pub message_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ReactionCount {
+    pub name: String,
+    pub count: u64,
+}
+
+impl ThreadSummary {
+    pub fn summarize(thread: &Messages) -> ThreadSummary {
+        let message_and_thread = MessageAndThread {
+            message: Messages(vec![]),
+            thread: thread.clone(),
+        };
+        let root_message = message_and_thread.root_message();
+
+        let mut participants = HashMap::<&String, DigestParticipant>::new();
+        thread
+            .iter()
+            .filter_map(|message| message.user.as_ref().map(|user_id| (user_id, message)))
+            .for_each(|(user_id, message)| {
+                participants
+                    .entry(user_id)
+                    .or_insert_with(|| DigestParticipant {
+                        id: user_id.to_string(),
+                        name: message.author_name(),
+                        message_count: 0,
+                    })
+                    .message_count += 1;
+            });
+        let mut participants = participants.into_values().collect::<Vec<_>>();
+        participants.sort_by(|a, b| b.message_count.cmp(&a.message_count).then(a.id.cmp(&b.id)));
+
+        let mut reactions = HashMap::<&String, u64>::new();
+        thread
+            .iter()
+            .filter_map(|message| message.reactions.as_ref())
+            .flat_map(|reactions| reactions.iter())
+            .for_each(|reaction| {
+                *reactions.entry(&reaction.name).or_default() += u64::from(reaction.count)
+            });
+        let mut top_reactions = reactions
+            .into_iter()
+            .map(|(name, count)| ReactionCount {
+                name: name.to_string(),
+                count,
+            })
+            .collect::<Vec<ReactionCount>>();
+        top_reactions.sort_by(|a, b| b.count.cmp(&a.count).then(a.name.cmp(&b.name)));
+        top_reactions.truncate(TOP_REACTIONS);
+
+        ThreadSummary {
+            thread_ts: root_message.and_then(|message| message.ts.clone()),
+            title_suggestion: root_message.and_then(Message::title_suggestion),
+            root_author: root_message.and_then(Message::author_name),
+            date: root_message.and_then(Message::date),
+            message_count: thread.len(),
+            participants,
+            top_reactions,
+        }
+    }
+}
+
+impl Digest {
+    /// Enriches the gathered threads as the options ask, fetched at `fetched_at_ms`,
+    /// unix milliseconds
+    pub fn finalize(
+        mut digest: Digest,
+        options: &FinalizeOptions,
+        fetched_at_ms: f64,
+    ) -> Result<Digest> {
+        if !options.disable_text_sanitization {
+            digest
+                .threads
+                .iter_mut()
+                .for_each(|thread| thread.0.iter_mut().for_each(SanitizeText::sanitize_text));
+            if let Some(users) = digest.users.as_mut() {
+                users.sanitize_text();
+            }
+        }
+
+        if options.respect_do_not_archive {
+            let emoji = options
+                .do_not_archive_emoji
+                .as_deref()
+                .unwrap_or(DEFAULT_DO_NOT_ARCHIVE_EMOJI);
+            digest
+                .threads
+                .retain(|thread| !thread.is_marked_do_not_archive(emoji));
+        }
+
+        let users = digest.users.as_ref().map(SharedUsers::new);
+        digest
+            .threads
+            .iter_mut()
+            .try_for_each(|thread| thread.finalize_messages(users.as_ref(), options, fetched_at_ms))
+            .context(CouldNotFinalizeThreadSnafu)?;
+        // threads of only ignored users are left empty
+        digest.threads.retain(|thread| !thread.is_empty());
+        digest.message_count = digest.threads.iter().map(|thread| thread.len()).sum();
+        if let Some(users) = digest.users.as_mut() {
+            users
+                .0
+                .retain(|user_id, _| !options.ignored_users.contains(user_id));
+        }
+        digest.summaries = digest
+            .threads
+            .iter()
+            .map(ThreadSummary::summarize)
+            .collect();
+        digest.index = digest
+            .threads
+            .iter()
+            .filter_map(|thread| ThreadIndexEntry::new(&digest.channel_id, thread, options))
+            .collect();
+        if options.group_by_day {
+            digest.chronicle = Some(Chronicle::build(&digest));
+        }
+        Ok(digest)
+    }
+}
+
+pub type HistoryResponse = SlackEnvelope<HistoryResponseData>;
+
+/// The fields of a `conversations.history` response, see [`HistoryResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HistoryResponseData {
+    pub messages: Option<Vec<Message>>,
+    pub response_metadata: Option<ResponseMetadata>,
+}
+
+impl PaginatedResponse for HistoryResponseData {
+    fn response_metadata(&self) -> Option<&ResponseMetadata> {
+        self.response_metadata.as_ref()
+    }
+}
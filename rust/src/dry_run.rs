@@ -0,0 +1,188 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::FileName,
+    slack_http_client::{SlackApiEndpoints, SlackHttpClientConfigOptions},
+    state_machine::ObsidianSlackStates,
+    users::DEFAULT_USERS_LIST_THRESHOLD,
+};
+
+/// Requests to one endpoint that a run would make
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct PlannedCall {
+    /// e.g. `conversations.replies`
+    pub endpoint: String,
+    pub min_calls: usize,
+    /// None when the number of calls depends on the thread, e.g. one per user
+    pub max_calls: Option<usize>,
+    /// What the calls are for and what their number depends on
+    pub description: String,
+}
+
+impl PlannedCall {
+    fn new(
+        endpoint: SlackApiEndpoints,
+        min_calls: usize,
+        max_calls: Option<usize>,
+        description: &str,
+    ) -> PlannedCall {
+        PlannedCall {
+            endpoint: endpoint.to_string(),
+            min_calls,
+            max_calls,
+            description: description.to_string(),
+        }
+    }
+}
+
+/// What [`crate::get_slack_message`] would do for a url when the `dry_run` option
+/// is on, worked out without calling slack
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct DryRunPlan {
+    pub channel_id: String,
+    pub ts: String,
+    pub thread_ts: Option<String>,
+    pub file_name: FileName,
+    /// The steps that would run, in order
+    pub steps: Vec<String>,
+    pub calls: Vec<PlannedCall>,
+    /// Fewest calls the run can make
+    pub min_calls: usize,
+    /// Most calls the run can make, None if that depends on the thread
+    pub max_calls: Option<usize>,
+}
+
+impl DryRunPlan {
+    pub(crate) fn new(
+        channel_id: &str,
+        ts: &str,
+        thread_ts: Option<&str>,
+        file_name: FileName,
+        states: &[ObsidianSlackStates],
+        options: &SlackHttpClientConfigOptions,
+    ) -> DryRunPlan {
+        let calls = states
+            .iter()
+            .flat_map(|state| DryRunPlan::calls_of(state, options))
+            .collect::<Vec<PlannedCall>>();
+        DryRunPlan {
+            channel_id: channel_id.to_string(),
+            ts: ts.to_string(),
+            thread_ts: thread_ts.map(str::to_string),
+            file_name,
+            steps: states.iter().map(ToString::to_string).collect(),
+            min_calls: calls.iter().map(|call| call.min_calls).sum(),
+            max_calls: calls.iter().map(|call| call.max_calls).sum(),
+            calls,
+        }
+    }
+
+    fn calls_of(
+        state: &ObsidianSlackStates,
+        options: &SlackHttpClientConfigOptions,
+    ) -> Vec<PlannedCall> {
+        match state {
+            ObsidianSlackStates::MessageAndThread => {
+                let mut calls = vec![];
+                if !options.allow_dm_export.unwrap_or(true) {
+                    calls.push(PlannedCall::new(
+                        SlackApiEndpoints::ConversationsInfo,
+                        0,
+                        Some(1),
+                        "whether the conversation is a direct message, unless its id tells",
+                    ));
+                }
+                calls.push(PlannedCall::new(
+                    SlackApiEndpoints::ConversationsReplies,
+                    1,
+                    Some(1),
+                    "the message and its thread",
+                ));
+                calls
+            }
+            ObsidianSlackStates::ChannelInfo => vec![
+                PlannedCall::new(
+                    SlackApiEndpoints::ConversationsInfo,
+                    1,
+                    Some(1),
+                    "the channel",
+                ),
+                PlannedCall::new(
+                    SlackApiEndpoints::ConversationsMembers,
+                    0,
+                    None,
+                    "one per page of members, only for group DMs",
+                ),
+            ],
+            ObsidianSlackStates::UserInfo => {
+                let threshold = options
+                    .users_list_threshold
+                    .unwrap_or(DEFAULT_USERS_LIST_THRESHOLD);
+                vec![
+                    PlannedCall::new(
+                        SlackApiEndpoints::UsersInfo,
+                        1,
+                        None,
+                        &format!(
+                            "one per user in the thread, unless there are at least {} of them",
+                            threshold
+                        ),
+                    ),
+                    PlannedCall::new(
+                        SlackApiEndpoints::UsersList,
+                        0,
+                        None,
+                        &format!(
+                            "one per page of the workspace's users, only when the thread has at least {} users",
+                            threshold
+                        ),
+                    ),
+                ]
+            }
+            ObsidianSlackStates::TeamInfo => vec![PlannedCall::new(
+                SlackApiEndpoints::TeamInfo,
+                1,
+                None,
+                "one per workspace the users belong to",
+            )],
+            ObsidianSlackStates::Usergroups => vec![
+                PlannedCall::new(
+                    SlackApiEndpoints::UsergroupsUsersList,
+                    0,
+                    None,
+                    "one per usergroup mentioned in the thread",
+                ),
+                PlannedCall::new(
+                    SlackApiEndpoints::UsersInfo,
+                    0,
+                    None,
+                    "one per member of the mentioned usergroups not yet fetched",
+                ),
+            ],
+            ObsidianSlackStates::Reminders => vec![PlannedCall::new(
+                SlackApiEndpoints::RemindersList,
+                1,
+                Some(1),
+                "your reminders",
+            )],
+            ObsidianSlackStates::Presence => vec![PlannedCall::new(
+                SlackApiEndpoints::UsersGetPresence,
+                0,
+                None,
+                "one per participant",
+            )],
+            ObsidianSlackStates::BotInfo => vec![PlannedCall::new(
+                SlackApiEndpoints::BotsInfo,
+                0,
+                None,
+                "one per bot that wrote a message",
+            )],
+            ObsidianSlackStates::Start | ObsidianSlackStates::Files | ObsidianSlackStates::End => {
+                vec![]
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use amplify_derive::Display;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use shrinkwraprs::Shrinkwrap;
+
+use crate::{
+    messages::{Message, MessageAndThread},
+    reminders::ThreadReminders,
+};
+
+/// Marks the due date of a task for the obsidian tasks plugin
+const DUE_DATE_SIGNIFIER: &str = "📅";
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// A follow up of the thread, a message that mentions a date or has a reminder set
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct DueTask {
+    /// ts of the message the task is about
+    pub message_ts: Option<String>,
+    /// `YYYY-MM-DD`
+    pub due: String,
+    /// The task as the tasks plugin reads it, e.g. `- [ ] Send the report 📅 2024-06-01`
+    pub line: String,
+}
+
+/// Follow ups in thread order, only when `emit_due_tasks` is on
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct DueTasks(pub Vec<DueTask>);
+
+impl DueTasks {
+    /// A task for the first date each message mentions, and one for each reminder
+    /// due at another date
+    pub fn collect(
+        message_and_thread: &MessageAndThread,
+        reminders: Option<&ThreadReminders>,
+    ) -> DueTasks {
+        let finder = DateFinder::new();
+        let mut tasks = vec![];
+        for message in message_and_thread.thread.iter() {
+            let reminder_dates = reminders
+                .iter()
+                .flat_map(|reminders| reminders.iter())
+                .filter(|reminder| message.ts.as_ref() == Some(&reminder.message_ts))
+                .filter(|reminder| !reminder.complete)
+                .filter_map(|reminder| reminder.date.as_deref())
+                .map(|date| date.chars().take(10).collect::<String>());
+            let mut dates = finder.find(message).into_iter().collect::<Vec<String>>();
+            reminder_dates.for_each(|date| {
+                if !dates.contains(&date) {
+                    dates.push(date);
+                }
+            });
+            tasks.extend(
+                dates
+                    .into_iter()
+                    .filter_map(|due| DueTask::new(message, due)),
+            );
+        }
+        DueTasks(tasks)
+    }
+}
+
+impl DueTask {
+    fn new(message: &Message, due: String) -> Option<DueTask> {
+        let text = message
+            .text
+            .as_deref()?
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())?;
+        Some(DueTask {
+            message_ts: message.ts.clone(),
+            line: format!("- [ ] {} {} {}", text, DUE_DATE_SIGNIFIER, due),
+            due,
+        })
+    }
+}
+
+/// Finds dates written as `2024-06-01`, `June 1` or `Jun 1st, 2024`
+struct DateFinder {
+    iso: Regex,
+    month_day: Regex,
+}
+
+impl DateFinder {
+    fn new() -> DateFinder {
+        DateFinder {
+            iso: Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b")
+                .expect("Expected a valid date pattern, but it was not. This is a bug"),
+            month_day: Regex::new(
+                r"(?i)\b(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?\s+(\d{1,2})(?:st|nd|rd|th)?\b(?:,?\s+(\d{4})\b)?",
+            )
+            .expect("Expected a valid date pattern, but it was not. This is a bug"),
+        }
+    }
+
+    /// The first date the text of `message` mentions. Dates without a year are the
+    /// next one on or after the day the message was sent
+    fn find(&self, message: &Message) -> Option<String> {
+        let text = message.text.as_deref()?;
+        let sent = message.date().and_then(|date| parse_iso_date(&date[..10]));
+
+        let iso = self.iso.captures(text).and_then(|captures| {
+            let found = captures.get(0)?;
+            Some((found.start(), parse_iso_date(found.as_str())?))
+        });
+        let month_day = self.month_day.captures(text).and_then(|captures| {
+            let found = captures.get(0)?;
+            let month = MONTHS
+                .iter()
+                .position(|month| captures[1].eq_ignore_ascii_case(month))?
+                + 1;
+            let day = captures[2].parse::<u32>().ok()?;
+            let year = match captures.get(3) {
+                Some(year) => year.as_str().parse::<u32>().ok()?,
+                None => {
+                    let (sent_year, sent_month, sent_day) = sent?;
+                    if (month as u32, day) < (sent_month, sent_day) {
+                        sent_year + 1
+                    } else {
+                        sent_year
+                    }
+                }
+            };
+            Some((found.start(), valid_date(year, month as u32, day)?))
+        });
+
+        iso.into_iter()
+            .chain(month_day)
+            .min_by_key(|(start, _)| *start)
+            .map(|(_, (year, month, day))| format!("{:04}-{:02}-{:02}", year, month, day))
+    }
+}
+
+fn parse_iso_date(date: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = date.split('-').map(|part| part.parse::<u32>().ok());
+    valid_date(parts.next()??, parts.next()??, parts.next()??)
+}
+
+fn valid_date(year: u32, month: u32, day: u32) -> Option<(u32, u32, u32)> {
+    let in_range = (1..=12).contains(&month) && (1..=31).contains(&day);
+    in_range.then_some((year, month, day))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of finding follow ups for the obsidian tasks plugin, run with `cargo test`
+
+    use crate::{
+        due_dates::{DueTask, DueTasks},
+        messages::{Message, MessageAndThread, Messages},
+        reminders::{ThreadReminder, ThreadReminders},
+    };
+    use rstest::rstest;
+    use serde_json::json;
+
+    /// 2023-11-14T22:13:20Z
+    const SENT_TS: &str = "1700000000.000100";
+
+    fn thread(text: &str) -> MessageAndThread {
+        let message = serde_json::from_value::<Message>(json!({
+            "type": "message",
+            "user": "U0ALICE",
+            "text": text,
+            "ts": SENT_TS,
+        }))
+        .expect("Expected a valid message");
+        MessageAndThread {
+            message: Messages(vec![message.clone()]),
+            thread: Messages(vec![message]),
+        }
+    }
+
+    #[rstest]
+    #[case::iso("Ship the release on 2024-06-01", "2024-06-01")]
+    #[case::month_name("Report due June 1, 2024", "2024-06-01")]
+    #[case::abbreviated("Report due Jun 1st 2024", "2024-06-01")]
+    #[case::later_this_year("Review on Dec 5", "2023-12-05")]
+    #[case::next_year("Budget by March 3rd", "2024-03-03")]
+    #[case::first_of_several("Draft 2024-01-10, final Feb 2", "2024-01-10")]
+    #[case::remind_syntax("/remind me to call Bob on January 15", "2024-01-15")]
+    fn finds_the_first_date_a_message_mentions(#[case] text: &str, #[case] due: &str) {
+        assert_eq!(
+            DueTasks(vec![DueTask {
+                message_ts: Some(SENT_TS.to_string()),
+                due: due.to_string(),
+                line: format!("- [ ] {} 📅 {}", text, due),
+            }]),
+            DueTasks::collect(&thread(text), None)
+        );
+    }
+
+    #[rstest]
+    #[case::no_date("Thanks everyone")]
+    #[case::invalid_date("Build 2024-13-45 failed")]
+    #[case::version_number("Upgraded to 1.2.3")]
+    fn finds_nothing_without_a_date(#[case] text: &str) {
+        assert_eq!(DueTasks::default(), DueTasks::collect(&thread(text), None));
+    }
+
+    #[test]
+    fn adds_the_due_date_of_open_reminders() {
+        let reminder = |id: &str, date: &str, complete: bool| ThreadReminder {
+            id: id.to_string(),
+            message_ts: SENT_TS.to_string(),
+            text: None,
+            date: Some(date.to_string()),
+            recurring: false,
+            complete,
+        };
+        let reminders = ThreadReminders(vec![
+            reminder("Rm1", "2023-11-20T09:00:00Z", false),
+            reminder("Rm2", "2023-11-21T09:00:00Z", true),
+        ]);
+
+        let due_tasks = DueTasks::collect(&thread("Follow up with legal"), Some(&reminders));
+
+        assert_eq!(
+            vec!["- [ ] Follow up with legal 📅 2023-11-20"],
+            due_tasks
+                .iter()
+                .map(|task| task.line.as_str())
+                .collect::<Vec<&str>>()
+        );
+    }
+}
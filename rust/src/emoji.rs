@@ -0,0 +1,165 @@
+use amplify_derive::Display;
+use do_notation::m;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    components::FileName,
+    fetch_stats::FetchStats,
+    response::{self, SlackEnvelope},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
+
+    #[snafu(display("{source}"))]
+    CouldNotDeserializeEmojiResponse { source: response::Error },
+
+    #[snafu(display("The emoji response was not ok. - source: {source}"))]
+    InvalidEmojiResponse { source: response::Error },
+
+    #[snafu(display("Could not parse json from emoji response string - source: {source}"))]
+    CouldNotParseJsonFromEmojiResponse { source: response::Error },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Prefix slack gives the value of an emoji that is another name for an emoji
+const ALIAS_PREFIX: &str = "alias:";
+
+/// Name of the bundle's note
+const EMOJI_PACK_FILE_NAME: &str = "emoji.json";
+
+/// Every custom emoji of the workspace with `emoji.list`
+pub async fn get_emoji_pack_from_api<T>(client: &SlackHttpClient<T>) -> Result<EmojiPack>
+where
+    T: TransportResponse,
+{
+    let result = client.get_emoji_list().into_body().await;
+    let emoji_response: EmojiResponse = m! {
+        // mapping error instead of using snafu context because the request's error is a string, not an Error
+        val <- result.map_err(|error| Error::RequestFailed { error });
+        json <- response::parse_response_body(val).context(CouldNotParseJsonFromEmojiResponseSnafu);
+        emoji_response <- response::defined_from_json(json).context(CouldNotDeserializeEmojiResponseSnafu);
+        valid_response <- client.validate_response(emoji_response).context(InvalidEmojiResponseSnafu);
+        return valid_response;
+    }?;
+
+    Ok(EmojiPack::new(
+        &emoji_response.data.emoji.unwrap_or_default(),
+    ))
+}
+
+/// The workspace's custom emoji as their own bundle, e.g. to archive them or to
+/// show reactions without asking slack
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct EmojiPack {
+    /// Image url of every custom emoji by name, aliases included under their own
+    /// name with the url of the emoji they stand for
+    pub emoji: BTreeMap<String, String>,
+    /// Name of the emoji each alias stands for, e.g. `{"shipit": "squirrel"}`. An
+    /// alias of a standard emoji, e.g. `thumbsup`, has no url in `emoji`
+    pub aliases: BTreeMap<String, String>,
+    pub file_name: FileName,
+    /// Requests made for the result, see [`FetchStats`]
+    pub fetch_stats: FetchStats,
+}
+
+impl EmojiPack {
+    /// Resolves the aliases of `emoji.list`'s name to url or `alias:name` map
+    pub fn new(listed: &HashMap<String, String>) -> EmojiPack {
+        let aliases = listed
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .strip_prefix(ALIAS_PREFIX)
+                    .map(|target| (name.to_string(), target.to_string()))
+            })
+            .collect::<BTreeMap<String, String>>();
+        let emoji = listed
+            .keys()
+            .filter_map(|name| {
+                resolve(listed, name, listed.len()).map(|url| (name.to_string(), url.to_string()))
+            })
+            .collect();
+        EmojiPack {
+            emoji,
+            aliases,
+            file_name: FileName(EMOJI_PACK_FILE_NAME.to_string()),
+            fetch_stats: FetchStats::default(),
+        }
+    }
+}
+
+/// The url `name` ends up at, following at most `hops` aliases so aliases of each
+/// other cannot loop
+fn resolve<'a>(listed: &'a HashMap<String, String>, name: &str, hops: usize) -> Option<&'a str> {
+    let value = listed.get(name)?;
+    match value.strip_prefix(ALIAS_PREFIX) {
+        Some(target) if hops > 0 => resolve(listed, target, hops - 1),
+        Some(_) => None,
+        None => Some(value),
+    }
+}
+
+pub type EmojiResponse = SlackEnvelope<EmojiResponseData>;
+
+/// The fields of a `emoji.list` response, see [`EmojiResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EmojiResponseData {
+    /// Image url, or `alias:name` for another name of an emoji
+    pub emoji: Option<HashMap<String, String>>,
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use serde_json::json;
+
+    use super::get_emoji_pack_from_api;
+    use crate::{slack_http_client::SlackHttpClientConfigOptions, test_support::client};
+
+    #[async_std::test]
+    async fn get_emoji_pack_from_api_resolves_aliases_to_their_urls() {
+        let (client, requested_urls) = client(SlackHttpClientConfigOptions::default(), |_| {
+            Ok(json!({
+                "ok": true,
+                "emoji": {
+                    "squirrel": "https://emoji.slack-edge.com/T1/squirrel/1.png",
+                    "shipit": "alias:squirrel",
+                    "ship_it": "alias:shipit",
+                    "+1_custom": "alias:thumbsup",
+                    "ping": "alias:pong",
+                    "pong": "alias:ping"
+                }
+            }))
+        });
+
+        let emoji_pack = get_emoji_pack_from_api(&client)
+            .await
+            .expect("Should get the emoji");
+
+        let squirrel = "https://emoji.slack-edge.com/T1/squirrel/1.png".to_string();
+        assert_eq!(
+            vec![
+                ("ship_it".to_string(), squirrel.clone()),
+                ("shipit".to_string(), squirrel.clone()),
+                ("squirrel".to_string(), squirrel),
+            ],
+            emoji_pack
+                .emoji
+                .into_iter()
+                .collect::<Vec<(String, String)>>()
+        );
+        assert_eq!(
+            Some(&"thumbsup".to_string()),
+            emoji_pack.aliases.get("+1_custom")
+        );
+        assert_eq!(5, emoji_pack.aliases.len());
+        assert_eq!(1, requested_urls.borrow().len());
+    }
+}
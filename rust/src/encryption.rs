@@ -0,0 +1,179 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt, Snafu};
+
+use crate::{
+    components::{FileName, ObsidianSlackComponents, SavePath},
+    slack_http_client::SlackCredentials,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "An encryption passphrase was given, but this build does not include the encryption feature"
+    ))]
+    EncryptionNotSupported,
+
+    #[snafu(display("The encryption passphrase must not be empty"))]
+    EmptyPassphrase,
+
+    #[snafu(display("Could not serialize components to encrypt - source: {source}"))]
+    CouldNotSerializeComponents { source: serde_json::Error },
+
+    #[snafu(display("Could not read the decrypted components - source: {source}"))]
+    CouldNotParseDecryptedComponents { source: serde_json::Error },
+
+    #[snafu(display("Could not encrypt components: {reason}"))]
+    CouldNotEncrypt { reason: String },
+
+    #[snafu(display("Could not derive the key from the passphrase: {reason}"))]
+    CouldNotDeriveKey { reason: String },
+
+    #[snafu(display(
+        "Could not decrypt components, the passphrase is wrong or the data was changed"
+    ))]
+    CouldNotDecrypt,
+
+    #[snafu(display("The encrypted components are not in the expected format: {reason}"))]
+    InvalidEncryptedData { reason: String },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// What [`crate::get_slack_message`] returns instead of the components when the
+/// `encryption_passphrase` option is set. Only what the plugin needs to save the
+/// note is left readable
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Display)]
+#[display(Debug)]
+pub struct EncryptedComponents {
+    pub file_name: FileName,
+    pub save_path: Option<SavePath>,
+    /// See [`ObsidianSlackComponents::refreshed_credentials`]
+    pub refreshed_credentials: Option<SlackCredentials>,
+    /// The components as json, encrypted with XChaCha20-Poly1305 under a key
+    /// derived from the passphrase with Argon2id, base64 encoded
+    pub encrypted: String,
+}
+
+impl EncryptedComponents {
+    pub fn encrypt(
+        components: &ObsidianSlackComponents,
+        passphrase: &str,
+    ) -> Result<EncryptedComponents> {
+        ensure!(!passphrase.is_empty(), EmptyPassphraseSnafu);
+        let json = serde_json::to_vec(components).context(CouldNotSerializeComponentsSnafu)?;
+        Ok(EncryptedComponents {
+            file_name: components.file_name.clone(),
+            save_path: components.save_path.clone(),
+            refreshed_credentials: components.refreshed_credentials.clone(),
+            encrypted: cipher::encrypt(&json, passphrase)?,
+        })
+    }
+}
+
+/// The components in [`EncryptedComponents::encrypted`]
+pub fn decrypt(encrypted: &str, passphrase: &str) -> Result<ObsidianSlackComponents> {
+    let json = cipher::decrypt(encrypted, passphrase)?;
+    serde_json::from_slice(&json).context(CouldNotParseDecryptedComponentsSnafu)
+}
+
+#[cfg(feature = "encryption")]
+mod cipher {
+    use argon2::Argon2;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use chacha20poly1305::{
+        aead::{Aead, KeyInit},
+        Key, XChaCha20Poly1305, XNonce,
+    };
+
+    use snafu::ensure;
+
+    use super::{
+        CouldNotDecryptSnafu, CouldNotDeriveKeySnafu, CouldNotEncryptSnafu,
+        InvalidEncryptedDataSnafu, Result,
+    };
+
+    /// Start of every encrypted payload, the version of the format
+    const FORMAT_PREFIX: &[u8] = b"OSE1";
+    const SALT_LENGTH: usize = 16;
+    const NONCE_LENGTH: usize = 24;
+
+    fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|err| {
+                CouldNotDeriveKeySnafu {
+                    reason: err.to_string(),
+                }
+                .build()
+            })?;
+        Ok(key)
+    }
+
+    /// `FORMAT_PREFIX | salt | nonce | ciphertext`, base64 encoded
+    pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<String> {
+        let mut salt = [0u8; SALT_LENGTH];
+        let mut nonce = [0u8; NONCE_LENGTH];
+        getrandom::getrandom(&mut salt)
+            .and_then(|_| getrandom::getrandom(&mut nonce))
+            .map_err(|err| {
+                CouldNotEncryptSnafu {
+                    reason: err.to_string(),
+                }
+                .build()
+            })?;
+
+        let ciphertext = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?)
+            .encrypt(XNonce::from_slice(&nonce), plaintext)
+            .map_err(|err| {
+                CouldNotEncryptSnafu {
+                    reason: err.to_string(),
+                }
+                .build()
+            })?;
+
+        let payload = [FORMAT_PREFIX, &salt, &nonce, &ciphertext].concat();
+        Ok(STANDARD.encode(payload))
+    }
+
+    pub fn decrypt(encrypted: &str, passphrase: &str) -> Result<Vec<u8>> {
+        let payload = STANDARD.decode(encrypted.trim()).map_err(|err| {
+            InvalidEncryptedDataSnafu {
+                reason: err.to_string(),
+            }
+            .build()
+        })?;
+        let rest = payload.strip_prefix(FORMAT_PREFIX).ok_or_else(|| {
+            InvalidEncryptedDataSnafu {
+                reason: "unknown format version",
+            }
+            .build()
+        })?;
+        ensure!(
+            rest.len() >= SALT_LENGTH + NONCE_LENGTH,
+            InvalidEncryptedDataSnafu {
+                reason: "too short"
+            }
+        );
+        let (salt, rest) = rest.split_at(SALT_LENGTH);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+
+        XChaCha20Poly1305::new(&derive_key(passphrase, salt)?)
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CouldNotDecryptSnafu.build())
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+mod cipher {
+    use super::{EncryptionNotSupportedSnafu, Result};
+
+    pub fn encrypt(_plaintext: &[u8], _passphrase: &str) -> Result<String> {
+        EncryptionNotSupportedSnafu.fail()
+    }
+
+    pub fn decrypt(_encrypted: &str, _passphrase: &str) -> Result<Vec<u8>> {
+        EncryptionNotSupportedSnafu.fail()
+    }
+}
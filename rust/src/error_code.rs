@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::{
+    bookmarks, channels, continuation, encryption, highlights,
+    response::{self, AUTH_ERRORS},
+    session_cache, slack_http_client, slack_url, state_machine, users,
+};
+
+/// Slack error code for requests rejected by slack's rate limits
+const RATE_LIMIT_ERROR: &str = "ratelimited";
+
+/// Slack error codes of channels the user can not see
+const NOT_IN_CHANNEL_ERRORS: [&str; 2] = ["not_in_channel", "channel_not_found"];
+
+/// Slack error codes of tokens without the permission a request needs
+const MISSING_SCOPE_ERRORS: [&str; 2] = ["missing_scope", "not_allowed_token_type"];
+
+/// Slack error codes of messages that are gone
+const MESSAGE_NOT_FOUND_ERRORS: [&str; 2] = ["thread_not_found", "message_not_found"];
+
+/// Stable code of a top-level error, so the plugin can handle errors without
+/// matching on their (unstable) messages. Every error string returned from wasm
+/// starts with its code in brackets, e.g. `[E_URL_PARSE] ...`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+pub enum ErrorCode {
+    /// The slack url could not be read
+    #[strum(serialize = "E_URL_PARSE")]
+    #[serde(rename = "E_URL_PARSE")]
+    UrlParse,
+    /// Slack no longer accepts the token or cookie
+    #[strum(serialize = "E_AUTH_EXPIRED")]
+    #[serde(rename = "E_AUTH_EXPIRED")]
+    AuthExpired,
+    /// Slack rejected the request because of its rate limits
+    #[strum(serialize = "E_RATE_LIMIT")]
+    #[serde(rename = "E_RATE_LIMIT")]
+    RateLimit,
+    /// The user may not do what was asked, e.g. edit a channel's bookmarks
+    #[strum(serialize = "E_PERMISSION_DENIED")]
+    #[serde(rename = "E_PERMISSION_DENIED")]
+    PermissionDenied,
+    /// Slack answered with any other error
+    #[strum(serialize = "E_SLACK_API")]
+    #[serde(rename = "E_SLACK_API")]
+    SlackApi,
+    /// The arguments or options passed by the plugin are invalid
+    #[strum(serialize = "E_INVALID_INPUT")]
+    #[serde(rename = "E_INVALID_INPUT")]
+    InvalidInput,
+    /// The thread's author marked it as not to be archived, see
+    /// [`crate::messages::Messages::is_marked_do_not_archive`]
+    #[strum(serialize = "E_DO_NOT_ARCHIVE")]
+    #[serde(rename = "E_DO_NOT_ARCHIVE")]
+    DoNotArchive,
+    /// The options forbid saving this, e.g. a direct message when `allow_dm_export`
+    /// is off
+    #[strum(serialize = "E_POLICY")]
+    #[serde(rename = "E_POLICY")]
+    Policy,
+    /// Anything else, usually a failed request or a bug
+    #[strum(serialize = "E_INTERNAL")]
+    #[serde(rename = "E_INTERNAL")]
+    Internal,
+}
+
+impl ErrorCode {
+    /// Code of the first error in the chain of `err` and its sources that has one
+    pub fn of(err: &(dyn snafu::Error + 'static)) -> ErrorCode {
+        std::iter::successors(Some(err), |err| err.source())
+            .find_map(ErrorCode::of_single)
+            .unwrap_or(ErrorCode::Internal)
+    }
+
+    fn of_single(err: &(dyn snafu::Error + 'static)) -> Option<ErrorCode> {
+        if err.is::<slack_url::Error>() {
+            return Some(ErrorCode::UrlParse);
+        }
+        if err.is::<slack_http_client::Error>()
+            || err.is::<highlights::Error>()
+            || err.is::<session_cache::Error>()
+            || err.is::<encryption::Error>()
+            || err.is::<continuation::Error>()
+        {
+            return Some(ErrorCode::InvalidInput);
+        }
+        if let Some(users::Error::InvalidUserId { .. }) = err.downcast_ref::<users::Error>() {
+            return Some(ErrorCode::InvalidInput);
+        }
+        if let Some(channels::Error::ChannelNameNotFound { .. }) =
+            err.downcast_ref::<channels::Error>()
+        {
+            return Some(ErrorCode::InvalidInput);
+        }
+        if let Some(bookmarks::Error::BookmarkPermissionDenied { .. }) =
+            err.downcast_ref::<bookmarks::Error>()
+        {
+            return Some(ErrorCode::PermissionDenied);
+        }
+        if let Some(channels::Error::DirectMessageExportNotAllowed { .. }) =
+            err.downcast_ref::<channels::Error>()
+        {
+            return Some(ErrorCode::Policy);
+        }
+        if let Some(state_machine::Error::ThreadMarkedDoNotArchive { .. }) =
+            err.downcast_ref::<state_machine::Error>()
+        {
+            return Some(ErrorCode::DoNotArchive);
+        }
+        if let Some(response::Error::SlackResponseNotOk { error, .. }) =
+            err.downcast_ref::<response::Error>()
+        {
+            return Some(match error.as_deref() {
+                Some(error) if AUTH_ERRORS.contains(&error) => ErrorCode::AuthExpired,
+                Some(RATE_LIMIT_ERROR) => ErrorCode::RateLimit,
+                _ => ErrorCode::SlackApi,
+            });
+        }
+        match err.downcast_ref::<crate::Error>() {
+            Some(
+                crate::Error::CouldNotParseFeatureFlags { .. }
+                | crate::Error::CouldNotParseOptions { .. }
+                | crate::Error::CouldNotParseClippingUrls { .. }
+                | crate::Error::CouldNotParseComponents { .. }
+                | crate::Error::CouldNotParseOutputFormat { .. }
+                | crate::Error::InvalidLogLevel { .. }
+                | crate::Error::NoFetchFunction
+                | crate::Error::NoClippingUrls,
+            ) => Some(ErrorCode::InvalidInput),
+            _ => None,
+        }
+    }
+
+    /// The code at the start of an error string returned from wasm, if any
+    pub fn from_error_message(message: &str) -> Option<ErrorCode> {
+        message
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .and_then(|(code, _)| code.parse().ok())
+    }
+}
+
+/// What the user can do about an error, shown in the user's language after the
+/// error's summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suggestion {
+    /// Copy a new token and cookie from slack
+    RefreshCredentials,
+    /// Join the channel, or check the link's workspace
+    JoinChannel,
+    /// Use a token with the permission the request needs
+    GrantScope,
+    /// Check whether the message was deleted
+    CheckMessageExists,
+    /// Ask the thread's author to unmark it, or stop respecting the mark
+    AskAuthor,
+    /// Allow saving direct messages
+    AllowDirectMessages,
+}
+
+impl Suggestion {
+    /// Suggestion of the first error in the chain of `err` and its sources that
+    /// has one
+    pub fn of(err: &(dyn snafu::Error + 'static)) -> Option<Suggestion> {
+        std::iter::successors(Some(err), |err| err.source()).find_map(Suggestion::of_single)
+    }
+
+    fn of_single(err: &(dyn snafu::Error + 'static)) -> Option<Suggestion> {
+        if let Some(channels::Error::ChannelNameNotFound { .. }) =
+            err.downcast_ref::<channels::Error>()
+        {
+            return Some(Suggestion::JoinChannel);
+        }
+        if let Some(channels::Error::DirectMessageExportNotAllowed { .. }) =
+            err.downcast_ref::<channels::Error>()
+        {
+            return Some(Suggestion::AllowDirectMessages);
+        }
+        if let Some(state_machine::Error::ThreadMarkedDoNotArchive { .. }) =
+            err.downcast_ref::<state_machine::Error>()
+        {
+            return Some(Suggestion::AskAuthor);
+        }
+        let slack_error = match (
+            err.downcast_ref::<response::Error>(),
+            err.downcast_ref::<bookmarks::Error>(),
+        ) {
+            (Some(response::Error::SlackResponseNotOk { error, .. }), _) => error.as_deref(),
+            (_, Some(bookmarks::Error::BookmarkPermissionDenied { error, .. })) => {
+                Some(error.as_str())
+            }
+            _ => None,
+        }?;
+        match slack_error {
+            error if AUTH_ERRORS.contains(&error) => Some(Suggestion::RefreshCredentials),
+            error if NOT_IN_CHANNEL_ERRORS.contains(&error) => Some(Suggestion::JoinChannel),
+            error if MISSING_SCOPE_ERRORS.contains(&error) => Some(Suggestion::GrantScope),
+            error if MESSAGE_NOT_FOUND_ERRORS.contains(&error) => {
+                Some(Suggestion::CheckMessageExists)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of suggesting what to do about an error, run with `cargo test`
+
+    use crate::{bookmarks, channels, error_code::Suggestion};
+    use rstest::rstest;
+
+    fn denied(error: &str) -> bookmarks::Error {
+        bookmarks::Error::BookmarkPermissionDenied {
+            channel_id: "C1".to_string(),
+            error: error.to_string(),
+        }
+    }
+
+    #[rstest]
+    #[case::invalid_auth("invalid_auth", Some(Suggestion::RefreshCredentials))]
+    #[case::token_revoked("token_revoked", Some(Suggestion::RefreshCredentials))]
+    #[case::not_in_channel("not_in_channel", Some(Suggestion::JoinChannel))]
+    #[case::channel_not_found("channel_not_found", Some(Suggestion::JoinChannel))]
+    #[case::missing_scope("missing_scope", Some(Suggestion::GrantScope))]
+    #[case::thread_not_found("thread_not_found", Some(Suggestion::CheckMessageExists))]
+    #[case::ratelimited("ratelimited", None)]
+    fn suggests_by_slack_error(#[case] error: &str, #[case] expected: Option<Suggestion>) {
+        assert_eq!(expected, Suggestion::of(&denied(error)));
+    }
+
+    #[test]
+    fn suggests_joining_a_channel_that_was_not_found_by_name() {
+        let err = channels::Error::ChannelNameNotFound {
+            name: "general".to_string(),
+        };
+
+        assert_eq!(Some(Suggestion::JoinChannel), Suggestion::of(&err));
+    }
+}
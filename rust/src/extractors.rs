@@ -0,0 +1,114 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use shrinkwraprs::Shrinkwrap;
+use std::collections::BTreeMap;
+
+use crate::messages::{Message, MessageAndThread};
+
+/// Finds items worth listing on top of a note, e.g. decisions, in a message.
+/// Extractors run on every message of the thread when components are finalized
+pub trait Extractor {
+    /// What the extractor finds, the items are listed under this key in
+    /// [`crate::components::ObsidianSlackComponents::extractions`]
+    fn kind(&self) -> &str;
+
+    /// The items in the message, as text
+    fn extract(&self, message: &Message) -> Vec<String>;
+}
+
+/// Takes every line that starts with one of the `prefixes` (ignoring case and
+/// leading whitespace), without the prefix
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct PrefixExtractor {
+    pub kind: String,
+    pub prefixes: Vec<String>,
+}
+
+impl PrefixExtractor {
+    /// `decisions` and `action_items`, used unless the `extractors` option is set
+    pub fn defaults() -> Vec<PrefixExtractor> {
+        vec![
+            PrefixExtractor {
+                kind: "decisions".to_string(),
+                prefixes: vec!["DECISION:".to_string(), ":white_check_mark:".to_string()],
+            },
+            PrefixExtractor {
+                kind: "action_items".to_string(),
+                prefixes: vec!["ACTION:".to_string(), "TODO:".to_string()],
+            },
+        ]
+    }
+}
+
+impl Extractor for PrefixExtractor {
+    fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    fn extract(&self, message: &Message) -> Vec<String> {
+        message
+            .text
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim_start)
+            .filter_map(|line| {
+                self.prefixes
+                    .iter()
+                    .filter(|prefix| !prefix.is_empty())
+                    .find(|prefix| {
+                        line.get(..prefix.len())
+                            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+                    })
+                    .map(|prefix| line[prefix.len()..].trim().to_string())
+            })
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+}
+
+/// One item found by an [`Extractor`]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ExtractedItem {
+    pub text: String,
+    /// ts of the message the item was found in
+    pub message_ts: Option<String>,
+    pub author: Option<String>,
+}
+
+/// Extracted items by [`Extractor::kind`], in thread order
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct Extractions(pub BTreeMap<String, Vec<ExtractedItem>>);
+
+impl Extractions {
+    pub fn extract(
+        message_and_thread: &MessageAndThread,
+        extractors: &[Box<dyn Extractor>],
+    ) -> Extractions {
+        let mut extractions = BTreeMap::<String, Vec<ExtractedItem>>::new();
+        extractors.iter().for_each(|extractor| {
+            let items = message_and_thread
+                .thread
+                .iter()
+                .flat_map(|message| {
+                    extractor
+                        .extract(message)
+                        .into_iter()
+                        .map(move |text| ExtractedItem {
+                            text,
+                            message_ts: message.ts.clone(),
+                            author: message.author_name(),
+                        })
+                })
+                .collect::<Vec<ExtractedItem>>();
+            extractions
+                .entry(extractor.kind().to_string())
+                .or_default()
+                .extend(items);
+        });
+        Extractions(extractions)
+    }
+}
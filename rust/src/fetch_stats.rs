@@ -0,0 +1,56 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Key of the file downloads in [`FetchStats::calls_per_endpoint`], whose urls
+/// end in the name of the file instead of a slack method
+pub const FILES_STATS_KEY: &str = "files";
+
+/// What one call asked of slack, to see why a save was slow and which feature
+/// flags cost the most
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(default)]
+pub struct FetchStats {
+    /// Requests sent per endpoint, e.g. `{"users.info": 3}`, retries included.
+    /// File downloads are counted under [`FILES_STATS_KEY`]
+    pub calls_per_endpoint: BTreeMap<String, usize>,
+    /// Requests per endpoint answered with the response of an identical earlier
+    /// request instead of asking slack again
+    pub cache_hits_per_endpoint: BTreeMap<String, usize>,
+    /// Size of the response bodies in bytes, text counted as utf-8
+    pub bytes_downloaded: usize,
+    /// Requests sent again with refreshed credentials
+    pub retries: usize,
+    /// Time from the start of the call until everything was fetched
+    pub duration_ms: u64,
+}
+
+impl FetchStats {
+    pub fn record_call(&mut self, endpoint: &str) {
+        *self
+            .calls_per_endpoint
+            .entry(endpoint.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn record_cache_hit(&mut self, endpoint: &str) {
+        *self
+            .cache_hits_per_endpoint
+            .entry(endpoint.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn total_calls(&self) -> usize {
+        self.calls_per_endpoint.values().sum()
+    }
+
+    /// A copy with the duration set to the time since `started_at_ms`, a
+    /// `Date.now()` timestamp
+    pub fn with_duration_since(&self, started_at_ms: f64) -> FetchStats {
+        FetchStats {
+            duration_ms: (js_sys::Date::now() - started_at_ms).max(0.0) as u64,
+            ..self.clone()
+        }
+    }
+}
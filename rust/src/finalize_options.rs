@@ -0,0 +1,176 @@
+use amplify_derive::Display;
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    channels::ChannelDetailLevel,
+    extractors::PrefixExtractor,
+    folding::FoldStyle,
+    messages::{IgnoredUserHandling, ThreadOrder},
+    note_links::SavedNote,
+    secret::Secret,
+    users::UserDetailLevel,
+};
+
+/// Optional settings for how a fetched thread, digest or channel becomes a note,
+/// see [`crate::components::ObsidianSlackComponents::finalize`]. The plugin passes
+/// them in the same `options` object as the
+/// [`crate::slack_http_client::SlackHttpClientConfigOptions`], and every field has
+/// a default
+#[derive(Debug, Serialize, Deserialize, Builder, Clone, Default, Display)]
+#[display(Debug)]
+#[serde(default)]
+#[builder(default)]
+pub struct FinalizeOptions {
+    /// Whether the saved note is named after the thread's timestamps or its content
+    pub note_title_style: NoteTitleStyle,
+
+    /// Prefix the title suggestion with the channel's name (or id if the name is
+    /// unknown), requires `get_channel_info` for the name
+    pub title_channel_prefix: bool,
+
+    /// Keywords that, when found in a message, add a tag to the note, e.g.
+    /// `{"outage": "incident"}`
+    pub keyword_tags: HashMap<String, String>,
+
+    /// Emoji that, when a message was reacted with them, add a tag to the note,
+    /// e.g. `{"bookmark": "to-read", "bulb": "idea"}`
+    pub reaction_tags: HashMap<String, String>,
+
+    /// Emoji that marks a reply as done, e.g. `white_check_mark`. When set, every
+    /// reply becomes a markdown checkbox, ticked when reacted with it
+    pub task_reaction: Option<String>,
+
+    /// List the messages that mention a date or have a reminder as tasks for the
+    /// obsidian tasks plugin, see [`crate::due_dates::DueTasks`]
+    pub emit_due_tasks: bool,
+
+    /// Label each message with its language. Needs the `language_detection`
+    /// cargo feature, otherwise no message is labeled
+    pub detect_language: bool,
+
+    /// Keep text exactly as slack sent it instead of normalizing unicode and
+    /// dropping invisible characters, see [`crate::utils::sanitize_text`]
+    pub disable_text_sanitization: bool,
+
+    /// Add each message's time in the author's and the reader's time zone
+    pub render_local_times: bool,
+
+    /// The reader's time zone as seconds east of UTC, defaults to UTC
+    pub reader_tz_offset: Option<i64>,
+
+    /// Name of the vault the note is saved to, needed for
+    /// [`crate::components::ObsidianSlackComponents::obsidian_uri`]
+    pub vault_name: Option<String>,
+
+    /// The vault's attachment folder the note is saved to, defaults to the vault
+    /// root
+    pub attachment_folder: Option<String>,
+
+    /// Lines to pick out of the thread, e.g. decisions. Defaults to
+    /// [`PrefixExtractor::defaults`], an empty list turns extraction off
+    pub extractors: Option<Vec<PrefixExtractor>>,
+
+    /// Keywords to mark as `==highlight==` in the text, matched ignoring case
+    pub highlight_keywords: Vec<String>,
+
+    /// Regexes to mark as `==highlight==` in the text, e.g. `(?i)rollback`
+    pub highlight_patterns: Vec<String>,
+
+    /// User ids whose messages are never saved as they are, e.g. HR bots or
+    /// people who asked not to be archived
+    pub ignored_users: Vec<String>,
+
+    /// Whether the messages of `ignored_users` are left out or anonymized
+    pub ignored_user_handling: IgnoredUserHandling,
+
+    /// Group a channel export by the day each thread started, with statistics for
+    /// each day and a json canvas of the days, see [`crate::chronicle::Chronicle`]
+    pub group_by_day: bool,
+
+    /// Refuse to save threads their author marked as not to be archived, and leave
+    /// them out of digests, see
+    /// [`crate::messages::Messages::is_marked_do_not_archive`]
+    pub respect_do_not_archive: bool,
+
+    /// Emoji that marks a thread as not to be archived, see
+    /// [`crate::messages::DEFAULT_DO_NOT_ARCHIVE_EMOJI`]
+    pub do_not_archive_emoji: Option<String>,
+
+    /// Days without a reply after which a thread is likely finished, see
+    /// [`crate::activity::ThreadActivity`]. Unset leaves the thread's activity out
+    pub finished_after_days: Option<u64>,
+
+    /// Reactions that mark a thread as resolved, and so likely finished, see
+    /// [`crate::activity::DEFAULT_RESOLVED_EMOJI`]
+    pub resolved_emoji: Option<Vec<String>>,
+
+    /// Rewrite links to unfurled github issues and jira tickets as markdown links
+    /// named after them, e.g. `[PROJ-123](https://...)`, see
+    /// [`crate::references::References::link`]
+    pub link_references: bool,
+
+    /// Rewrite every other slack link in the text, `<https://...|label>`, as a
+    /// markdown or bare link, see [`crate::mrkdwn::unwrap_links`]
+    pub unwrap_links: bool,
+
+    /// Record the reaction counts of every message with the time of the fetch.
+    /// Refreshing a saved note keeps the snapshots where the counts changed, see
+    /// [`crate::components::ObsidianSlackComponents::merge_saved_note`]
+    pub track_reaction_history: bool,
+
+    /// How much of every user is kept in the saved note, everything by default
+    pub user_detail_level: UserDetailLevel,
+
+    /// How much of the channel is kept in the saved note, everything by default
+    pub channel_detail_level: ChannelDetailLevel,
+
+    /// Leave out fields without a value instead of writing them as `null`. Off by
+    /// default for tools that expect every field to be present
+    pub strip_nulls: bool,
+
+    /// Encrypt the saved note with this passphrase, see
+    /// [`crate::encryption::EncryptedComponents`]. Needs the `encryption` cargo
+    /// feature, otherwise saving fails rather than writing the note unencrypted
+    pub encryption_passphrase: Option<Secret<String>>,
+
+    /// Report emails, phone numbers and credentials found in the thread, see
+    /// [`crate::pii::PiiReport`]
+    pub detect_pii: bool,
+
+    /// Threads already saved to the vault. Links to them in messages become links
+    /// to their notes, see [`crate::note_links::link_saved_notes`]
+    pub saved_notes: Vec<SavedNote>,
+
+    /// Write every user and team once, in the top level `users` and `teams`, with
+    /// messages, reactions and the channel keeping only their ids. Makes notes of
+    /// long threads much smaller, see
+    /// [`crate::components::ObsidianSlackComponents::join_users`]
+    pub normalize_users: bool,
+
+    /// Order of the messages in the thread, oldest first by default
+    pub thread_order: ThreadOrder,
+
+    /// Fold the text of messages with more lines than this, e.g. pasted logs, so
+    /// they do not take over the note. Nothing is folded by default
+    pub fold_messages_over_lines: Option<usize>,
+
+    /// How long messages are folded, see [`FoldStyle`]
+    pub fold_style: FoldStyle,
+
+    /// End every message with a footnote linking to it in slack, so any part of
+    /// the note can be traced back to where it was said
+    pub permalink_footnotes: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteTitleStyle {
+    /// `<channel id>-<thread ts>-<ts>`, stable across edits of the thread
+    #[default]
+    Timestamp,
+    /// The title suggestion, falling back to the timestamp style if there is none
+    Content,
+}
@@ -0,0 +1,97 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+
+/// How a message longer than the `fold_messages_over_lines` option is folded
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FoldStyle {
+    /// A collapsed obsidian callout, `> [!note]-`
+    #[default]
+    Callout,
+    /// A html `<details>` element, for vaults also read outside of obsidian
+    Details,
+}
+
+/// `text` folded away when it has more than `max_lines` lines, None when it fits
+pub fn fold(text: &str, max_lines: usize, style: FoldStyle) -> Option<String> {
+    let line_count = text.lines().count();
+    if line_count <= max_lines {
+        return None;
+    }
+    let summary = format!("Long message ({} lines)", line_count);
+    Some(match style {
+        FoldStyle::Callout => std::iter::once(format!("> [!note]- {}", summary))
+            .chain(
+                text.lines()
+                    .map(|line| format!("> {}", line).trim_end().to_string()),
+            )
+            .collect::<Vec<String>>()
+            .join("\n"),
+        FoldStyle::Details => format!(
+            "<details>\n<summary>{}</summary>\n\n{}\n\n</details>",
+            summary, text
+        ),
+    })
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of folding long messages, run with `cargo test`
+
+    use crate::{
+        folding::{fold, FoldStyle},
+        messages::Message,
+    };
+    use rstest::rstest;
+    use serde_json::json;
+
+    const LOG: &str = "line 1\nline 2\n\nline 4";
+
+    #[rstest]
+    #[case::callout(
+        FoldStyle::Callout,
+        "> [!note]- Long message (4 lines)\n> line 1\n> line 2\n>\n> line 4"
+    )]
+    #[case::details(
+    FoldStyle::Details,
+    "<details>\n<summary>Long message (4 lines)</summary>\n\nline 1\nline 2\n\nline 4\n\n</details>"
+)]
+    fn folds_text_over_the_threshold(#[case] style: FoldStyle, #[case] expected: &str) {
+        assert_eq!(Some(expected.to_string()), fold(LOG, 3, style));
+    }
+
+    #[rstest]
+    #[case::under(5)]
+    #[case::at(4)]
+    fn leaves_text_within_the_threshold(#[case] max_lines: usize) {
+        assert_eq!(None, fold(LOG, max_lines, FoldStyle::Callout));
+    }
+
+    #[test]
+    fn folding_a_message_keeps_its_metadata() {
+        let mut message = serde_json::from_value::<Message>(json!({
+            "type": "message",
+            "user": "U0ALICE",
+            "text": LOG,
+            "ts": "1672531200.000100",
+            "thread_ts": "1672531200.000100",
+            "reactions": [{ "name": "eyes", "users": ["U0BOB"], "count": 1 }]
+        }))
+        .expect("Expected a valid message");
+        let unfolded = message.clone();
+
+        message.fold_long_text(3, FoldStyle::Callout);
+
+        assert_eq!(Some(4), message.folded_line_count);
+        assert_eq!(fold(LOG, 3, FoldStyle::Callout), message.text);
+        assert_eq!(
+            Message {
+                text: unfolded.text.clone(),
+                folded_line_count: None,
+                ..message
+            },
+            unfolded
+        );
+    }
+}
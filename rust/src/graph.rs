@@ -0,0 +1,138 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{messages::MessageAndThread, users::User};
+
+/// A user taking part in the thread
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct GraphNode {
+    /// The user id
+    pub id: String,
+    /// Display name, only when users were retrieved
+    pub name: Option<String>,
+    /// Messages the user wrote in the thread
+    pub message_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphEdgeKind {
+    /// `from` wrote the message after one by `to`
+    Reply,
+    /// `from` reacted to a message by `to`
+    Reaction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: GraphEdgeKind,
+    /// How often it happened in the thread
+    pub weight: usize,
+}
+
+/// Who replied after whom and who reacted to whom in the thread. Users interacting
+/// with themselves are left out
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ParticipationGraph {
+    /// In order of first appearance in the thread
+    pub nodes: Vec<GraphNode>,
+    /// Sorted by `from`, `to` and `kind`
+    pub edges: Vec<GraphEdge>,
+}
+
+impl ParticipationGraph {
+    pub fn build(message_and_thread: &MessageAndThread) -> ParticipationGraph {
+        let mut nodes: Vec<GraphNode> = vec![];
+        let mut weights = BTreeMap::<(String, String, GraphEdgeKind), usize>::new();
+        let mut previous_author: Option<&str> = None;
+
+        for message in message_and_thread.thread.iter() {
+            let author = message.user.as_deref();
+            if let Some(author) = author {
+                node(&mut nodes, author, message.user_info.as_deref()).message_count += 1;
+                if let Some(previous_author) =
+                    previous_author.filter(|&previous| previous != author)
+                {
+                    *weights
+                        .entry((
+                            author.to_string(),
+                            previous_author.to_string(),
+                            GraphEdgeKind::Reply,
+                        ))
+                        .or_default() += 1;
+                }
+            }
+
+            for reaction in message
+                .reactions
+                .iter()
+                .flat_map(|reactions| reactions.iter())
+            {
+                for (index, user_id) in reaction.users.iter().enumerate() {
+                    let user_info = reaction
+                        .users_info
+                        .as_ref()
+                        .and_then(|users_info| users_info.get(index))
+                        .map(AsRef::as_ref)
+                        .filter(|user| &user.id == user_id);
+                    node(&mut nodes, user_id, user_info);
+                    if let Some(author) = author.filter(|&author| author != user_id) {
+                        *weights
+                            .entry((
+                                user_id.to_string(),
+                                author.to_string(),
+                                GraphEdgeKind::Reaction,
+                            ))
+                            .or_default() += 1;
+                    }
+                }
+            }
+
+            previous_author = author.or(previous_author);
+        }
+
+        ParticipationGraph {
+            nodes,
+            edges: weights
+                .into_iter()
+                .map(|((from, to, kind), weight)| GraphEdge {
+                    from,
+                    to,
+                    kind,
+                    weight,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The node of `user_id`, added when it is not there yet
+fn node<'a>(
+    nodes: &'a mut Vec<GraphNode>,
+    user_id: &str,
+    user: Option<&User>,
+) -> &'a mut GraphNode {
+    let index = match nodes.iter().position(|node| node.id == user_id) {
+        Some(index) => index,
+        None => {
+            nodes.push(GraphNode {
+                id: user_id.to_string(),
+                name: None,
+                message_count: 0,
+            });
+            nodes.len() - 1
+        }
+    };
+    let node = &mut nodes[index];
+    if node.name.is_none() {
+        node.name = user.and_then(User::display_name);
+    }
+    node
+}
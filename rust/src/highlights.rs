@@ -0,0 +1,148 @@
+use amplify_derive::Display;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use shrinkwraprs::Shrinkwrap;
+use snafu::{ResultExt, Snafu};
+
+use crate::messages::{Message, MessageAndThread};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Invalid highlight pattern {pattern} - source: {source}"))]
+    InvalidHighlightPattern {
+        pattern: String,
+        source: regex::Error,
+    },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Marks text matching any of its rules as `==highlight==`. Keywords match
+/// ignoring case, patterns are regexes
+#[derive(Debug, Clone, Default)]
+pub struct Highlighter {
+    rules: Vec<(String, Regex)>,
+}
+
+impl Highlighter {
+    pub fn new(keywords: &[String], patterns: &[String]) -> Result<Highlighter> {
+        let keyword_rules = keywords
+            .iter()
+            .filter(|keyword| !keyword.is_empty())
+            .map(|keyword| {
+                let regex = RegexBuilder::new(&regex::escape(keyword))
+                    .case_insensitive(true)
+                    .build()
+                    .context(InvalidHighlightPatternSnafu { pattern: keyword })?;
+                Ok((keyword.clone(), regex))
+            });
+        let pattern_rules = patterns.iter().map(|pattern| {
+            let regex = Regex::new(pattern).context(InvalidHighlightPatternSnafu { pattern })?;
+            Ok((pattern.clone(), regex))
+        });
+        Ok(Highlighter {
+            rules: keyword_rules.chain(pattern_rules).collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Non-overlapping matches of every rule as `(start, end, rule)`, in text
+    /// order. When matches overlap, the one starting first wins
+    fn find_matches<'a>(&'a self, text: &str) -> Vec<(usize, usize, &'a str)> {
+        let mut matches = self
+            .rules
+            .iter()
+            .flat_map(|(rule, regex)| {
+                regex
+                    .find_iter(text)
+                    .filter(|found| !found.as_str().is_empty())
+                    .map(move |found| (found.start(), found.end(), rule.as_str()))
+            })
+            .collect::<Vec<(usize, usize, &str)>>();
+        matches.sort_by_key(|(start, end, _)| (*start, std::cmp::Reverse(*end)));
+        let mut last_end = 0;
+        matches.retain(|(start, end, _)| {
+            let keep = *start >= last_end;
+            if keep {
+                last_end = *end;
+            }
+            keep
+        });
+        matches
+    }
+
+    /// The text with every match wrapped in `==`, None if nothing matched
+    pub fn highlight(&self, text: &str) -> Option<String> {
+        let matches = self.find_matches(text);
+        if matches.is_empty() {
+            return None;
+        }
+        let mut highlighted = String::with_capacity(text.len() + matches.len() * 4);
+        let mut last_end = 0;
+        matches.iter().for_each(|(start, end, _)| {
+            highlighted.push_str(&text[last_end..*start]);
+            highlighted.push_str("==");
+            highlighted.push_str(&text[*start..*end]);
+            highlighted.push_str("==");
+            last_end = *end;
+        });
+        highlighted.push_str(&text[last_end..]);
+        Some(highlighted)
+    }
+}
+
+/// One highlighted match, see [`Highlighter`]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct HighlightMatch {
+    /// The keyword or pattern that matched
+    pub rule: String,
+    /// The matched text
+    pub text: String,
+    /// The whole line the match is in, to list it without the rest of the message
+    pub line: String,
+    /// ts of the message the match was found in
+    pub message_ts: Option<String>,
+    pub author: Option<String>,
+}
+
+/// Every highlighted match in the thread, in thread order
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct Highlights(pub Vec<HighlightMatch>);
+
+impl Highlights {
+    pub fn collect(message_and_thread: &MessageAndThread, highlighter: &Highlighter) -> Highlights {
+        Highlights(
+            message_and_thread
+                .thread
+                .iter()
+                .flat_map(|message| Highlights::collect_from_message(message, highlighter))
+                .collect(),
+        )
+    }
+
+    fn collect_from_message(message: &Message, highlighter: &Highlighter) -> Vec<HighlightMatch> {
+        message
+            .text
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .flat_map(|line| {
+                highlighter
+                    .find_matches(line)
+                    .into_iter()
+                    .map(move |(start, end, rule)| HighlightMatch {
+                        rule: rule.to_string(),
+                        text: line[start..end].to_string(),
+                        line: line.to_string(),
+                        message_ts: message.ts.clone(),
+                        author: message.author_name(),
+                    })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,91 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::users::{User, Users};
+
+/// The huddle a huddle message is about, as slack returns it in the message's
+/// `room`
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct HuddleRoom {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    /// Unix seconds
+    pub date_start: Option<i64>,
+    /// Unix seconds, 0 or None while the huddle is going on
+    pub date_end: Option<i64>,
+    pub has_ended: Option<bool>,
+    /// Who is in the huddle right now
+    pub participants: Option<Vec<String>>,
+    /// Everyone who joined at some point
+    pub participant_history: Option<Vec<String>>,
+}
+
+impl HuddleRoom {
+    /// Everyone who joined the huddle at some point
+    pub fn participant_ids(&self) -> &[String] {
+        self.participant_history
+            .as_ref()
+            .filter(|history| !history.is_empty())
+            .or(self.participants.as_ref())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn duration_minutes(&self) -> Option<i64> {
+        match (self.date_start, self.date_end) {
+            (Some(start), Some(end)) if end >= start && end > 0 => {
+                Some(((end - start) as f64 / 60.0).round() as i64)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct HuddleParticipant {
+    pub id: String,
+    /// None when users were not retrieved
+    pub name: Option<String>,
+}
+
+/// What a huddle message says, worked out from its [`HuddleRoom`]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct HuddleSummary {
+    /// None while the huddle is going on
+    pub duration_minutes: Option<i64>,
+    pub participants: Vec<HuddleParticipant>,
+    /// e.g. `Huddle: 32 min, 4 participants`, the message's text when slack sent
+    /// none
+    pub line: String,
+}
+
+impl HuddleSummary {
+    pub fn new(room: &HuddleRoom, users: Option<&Users>) -> HuddleSummary {
+        let participants = room
+            .participant_ids()
+            .iter()
+            .map(|id| HuddleParticipant {
+                id: id.to_string(),
+                name: users
+                    .and_then(|users| users.get(id))
+                    .and_then(User::display_name),
+            })
+            .collect::<Vec<HuddleParticipant>>();
+        let duration_minutes = room.duration_minutes();
+        let participant_count = match participants.len() {
+            1 => "1 participant".to_string(),
+            count => format!("{} participants", count),
+        };
+        let line = match duration_minutes {
+            Some(minutes) => format!("Huddle: {} min, {}", minutes, participant_count),
+            None => format!("Huddle: {}", participant_count),
+        };
+        HuddleSummary {
+            duration_minutes,
+            participants,
+            line,
+        }
+    }
+}
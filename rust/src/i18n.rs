@@ -0,0 +1,276 @@
+use crate::error_code::{ErrorCode, Suggestion};
+
+/// Languages user-facing messages are translated to. Anything else falls back
+/// to English
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+    Japanese,
+}
+
+impl Language {
+    /// Language of a locale such as `de-DE` or `fr`, ignoring the region
+    pub fn from_locale(locale: &str) -> Language {
+        let language = locale
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "de" => Language::German,
+            "fr" => Language::French,
+            "es" => Language::Spanish,
+            "ja" => Language::Japanese,
+            _ => Language::English,
+        }
+    }
+}
+
+/// One sentence telling the user what went wrong, shown before the technical
+/// details of the error
+pub fn error_summary(code: ErrorCode, language: Language) -> &'static str {
+    match (language, code) {
+        (Language::English, ErrorCode::UrlParse) => {
+            "The Slack link could not be read. Copy the link of a message in Slack and try again."
+        }
+        (Language::English, ErrorCode::AuthExpired) => {
+            "Slack no longer accepts your token or cookie. Update them in the plugin settings."
+        }
+        (Language::English, ErrorCode::RateLimit) => {
+            "Slack is limiting requests right now. Wait a minute and try again."
+        }
+        (Language::English, ErrorCode::PermissionDenied) => {
+            "You do not have permission to do this in Slack."
+        }
+        (Language::English, ErrorCode::SlackApi) => "Slack returned an error.",
+        (Language::English, ErrorCode::InvalidInput) => {
+            "The plugin's settings or arguments are invalid."
+        }
+        (Language::English, ErrorCode::DoNotArchive) => {
+            "The author of this thread asked for it not to be archived."
+        }
+        (Language::English, ErrorCode::Policy) => {
+            "Your settings do not allow saving direct messages."
+        }
+        (Language::English, ErrorCode::Internal) => "There was a problem getting slack messages.",
+
+        (Language::German, ErrorCode::UrlParse) => {
+            "Der Slack-Link konnte nicht gelesen werden. Kopiere den Link einer Nachricht in Slack und versuche es erneut."
+        }
+        (Language::German, ErrorCode::AuthExpired) => {
+            "Slack akzeptiert dein Token oder Cookie nicht mehr. Aktualisiere sie in den Plugin-Einstellungen."
+        }
+        (Language::German, ErrorCode::RateLimit) => {
+            "Slack begrenzt gerade die Anfragen. Warte eine Minute und versuche es erneut."
+        }
+        (Language::German, ErrorCode::PermissionDenied) => {
+            "Du hast in Slack keine Berechtigung dafür."
+        }
+        (Language::German, ErrorCode::SlackApi) => "Slack hat einen Fehler zurückgegeben.",
+        (Language::German, ErrorCode::InvalidInput) => {
+            "Die Einstellungen oder Argumente des Plugins sind ungültig."
+        }
+        (Language::German, ErrorCode::DoNotArchive) => {
+            "Der Autor dieses Threads möchte nicht, dass er archiviert wird."
+        }
+        (Language::German, ErrorCode::Policy) => {
+            "Deine Einstellungen erlauben es nicht, Direktnachrichten zu speichern."
+        }
+        (Language::German, ErrorCode::Internal) => {
+            "Beim Abrufen der Slack-Nachrichten ist ein Problem aufgetreten."
+        }
+
+        (Language::French, ErrorCode::UrlParse) => {
+            "Le lien Slack n'a pas pu être lu. Copiez le lien d'un message dans Slack et réessayez."
+        }
+        (Language::French, ErrorCode::AuthExpired) => {
+            "Slack n'accepte plus votre jeton ou cookie. Mettez-les à jour dans les paramètres du plugin."
+        }
+        (Language::French, ErrorCode::RateLimit) => {
+            "Slack limite les requêtes en ce moment. Patientez une minute et réessayez."
+        }
+        (Language::French, ErrorCode::PermissionDenied) => {
+            "Vous n'avez pas l'autorisation de faire cela dans Slack."
+        }
+        (Language::French, ErrorCode::SlackApi) => "Slack a renvoyé une erreur.",
+        (Language::French, ErrorCode::InvalidInput) => {
+            "Les paramètres ou arguments du plugin ne sont pas valides."
+        }
+        (Language::French, ErrorCode::DoNotArchive) => {
+            "L'auteur de ce fil a demandé qu'il ne soit pas archivé."
+        }
+        (Language::French, ErrorCode::Policy) => {
+            "Vos paramètres ne permettent pas d'enregistrer les messages directs."
+        }
+        (Language::French, ErrorCode::Internal) => {
+            "Un problème est survenu lors de la récupération des messages Slack."
+        }
+
+        (Language::Spanish, ErrorCode::UrlParse) => {
+            "No se pudo leer el enlace de Slack. Copia el enlace de un mensaje en Slack y vuelve a intentarlo."
+        }
+        (Language::Spanish, ErrorCode::AuthExpired) => {
+            "Slack ya no acepta tu token o cookie. Actualízalos en la configuración del plugin."
+        }
+        (Language::Spanish, ErrorCode::RateLimit) => {
+            "Slack está limitando las solicitudes en este momento. Espera un minuto y vuelve a intentarlo."
+        }
+        (Language::Spanish, ErrorCode::PermissionDenied) => {
+            "No tienes permiso para hacer esto en Slack."
+        }
+        (Language::Spanish, ErrorCode::SlackApi) => "Slack devolvió un error.",
+        (Language::Spanish, ErrorCode::InvalidInput) => {
+            "La configuración o los argumentos del plugin no son válidos."
+        }
+        (Language::Spanish, ErrorCode::DoNotArchive) => {
+            "El autor de este hilo pidió que no se archive."
+        }
+        (Language::Spanish, ErrorCode::Policy) => {
+            "Tu configuración no permite guardar mensajes directos."
+        }
+        (Language::Spanish, ErrorCode::Internal) => {
+            "Hubo un problema al obtener los mensajes de Slack."
+        }
+
+        (Language::Japanese, ErrorCode::UrlParse) => {
+            "Slack のリンクを読み取れませんでした。Slack でメッセージのリンクをコピーして、もう一度お試しください。"
+        }
+        (Language::Japanese, ErrorCode::AuthExpired) => {
+            "Slack がトークンまたは Cookie を受け付けなくなりました。プラグインの設定で更新してください。"
+        }
+        (Language::Japanese, ErrorCode::RateLimit) => {
+            "Slack がリクエストを制限しています。1 分ほど待ってから、もう一度お試しください。"
+        }
+        (Language::Japanese, ErrorCode::PermissionDenied) => {
+            "Slack でこの操作を行う権限がありません。"
+        }
+        (Language::Japanese, ErrorCode::SlackApi) => "Slack がエラーを返しました。",
+        (Language::Japanese, ErrorCode::InvalidInput) => {
+            "プラグインの設定または引数が無効です。"
+        }
+        (Language::Japanese, ErrorCode::DoNotArchive) => {
+            "このスレッドの作成者はアーカイブしないよう求めています。"
+        }
+        (Language::Japanese, ErrorCode::Policy) => {
+            "設定により、ダイレクトメッセージは保存できません。"
+        }
+        (Language::Japanese, ErrorCode::Internal) => {
+            "Slack メッセージの取得中に問題が発生しました。"
+        }
+    }
+}
+
+/// One sentence telling the user what to try, shown after the error's summary
+pub fn suggestion_text(suggestion: Suggestion, language: Language) -> &'static str {
+    match (language, suggestion) {
+        (Language::English, Suggestion::RefreshCredentials) => {
+            "Copy a fresh xoxc token and xoxd cookie from Slack in your browser."
+        }
+        (Language::English, Suggestion::JoinChannel) => {
+            "Are you a member of this channel? Join it in Slack, or check that the link is from this workspace."
+        }
+        (Language::English, Suggestion::GrantScope) => {
+            "Your token lacks a permission this needs. Use a token copied from Slack in your browser."
+        }
+        (Language::English, Suggestion::CheckMessageExists) => {
+            "The message may have been deleted. Open the link in Slack to check."
+        }
+        (Language::English, Suggestion::AskAuthor) => {
+            "Ask the author to remove their reaction, or stop respecting it in the plugin settings."
+        }
+        (Language::English, Suggestion::AllowDirectMessages) => {
+            "Allow saving direct messages in the plugin settings."
+        }
+
+        (Language::German, Suggestion::RefreshCredentials) => {
+            "Kopiere ein neues xoxc-Token und xoxd-Cookie aus Slack in deinem Browser."
+        }
+        (Language::German, Suggestion::JoinChannel) => {
+            "Bist du Mitglied dieses Kanals? Tritt ihm in Slack bei oder prüfe, ob der Link aus diesem Workspace stammt."
+        }
+        (Language::German, Suggestion::GrantScope) => {
+            "Deinem Token fehlt eine nötige Berechtigung. Verwende ein Token aus Slack in deinem Browser."
+        }
+        (Language::German, Suggestion::CheckMessageExists) => {
+            "Die Nachricht wurde vielleicht gelöscht. Öffne den Link in Slack, um das zu prüfen."
+        }
+        (Language::German, Suggestion::AskAuthor) => {
+            "Bitte den Autor, seine Reaktion zu entfernen, oder beachte sie in den Plugin-Einstellungen nicht mehr."
+        }
+        (Language::German, Suggestion::AllowDirectMessages) => {
+            "Erlaube das Speichern von Direktnachrichten in den Plugin-Einstellungen."
+        }
+
+        (Language::French, Suggestion::RefreshCredentials) => {
+            "Copiez un nouveau jeton xoxc et un nouveau cookie xoxd depuis Slack dans votre navigateur."
+        }
+        (Language::French, Suggestion::JoinChannel) => {
+            "Êtes-vous membre de ce canal ? Rejoignez-le dans Slack ou vérifiez que le lien provient de cet espace de travail."
+        }
+        (Language::French, Suggestion::GrantScope) => {
+            "Il manque à votre jeton une autorisation nécessaire. Utilisez un jeton copié depuis Slack dans votre navigateur."
+        }
+        (Language::French, Suggestion::CheckMessageExists) => {
+            "Le message a peut-être été supprimé. Ouvrez le lien dans Slack pour vérifier."
+        }
+        (Language::French, Suggestion::AskAuthor) => {
+            "Demandez à l'auteur de retirer sa réaction, ou ne la respectez plus dans les paramètres du plugin."
+        }
+        (Language::French, Suggestion::AllowDirectMessages) => {
+            "Autorisez l'enregistrement des messages directs dans les paramètres du plugin."
+        }
+
+        (Language::Spanish, Suggestion::RefreshCredentials) => {
+            "Copia un token xoxc y una cookie xoxd nuevos desde Slack en tu navegador."
+        }
+        (Language::Spanish, Suggestion::JoinChannel) => {
+            "¿Eres miembro de este canal? Únete en Slack o comprueba que el enlace sea de este espacio de trabajo."
+        }
+        (Language::Spanish, Suggestion::GrantScope) => {
+            "A tu token le falta un permiso necesario. Usa un token copiado desde Slack en tu navegador."
+        }
+        (Language::Spanish, Suggestion::CheckMessageExists) => {
+            "Es posible que el mensaje se haya eliminado. Abre el enlace en Slack para comprobarlo."
+        }
+        (Language::Spanish, Suggestion::AskAuthor) => {
+            "Pide al autor que quite su reacción, o deja de respetarla en la configuración del plugin."
+        }
+        (Language::Spanish, Suggestion::AllowDirectMessages) => {
+            "Permite guardar mensajes directos en la configuración del plugin."
+        }
+
+        (Language::Japanese, Suggestion::RefreshCredentials) => {
+            "ブラウザの Slack から新しい xoxc トークンと xoxd Cookie をコピーしてください。"
+        }
+        (Language::Japanese, Suggestion::JoinChannel) => {
+            "このチャンネルのメンバーですか？Slack で参加するか、リンクがこのワークスペースのものか確認してください。"
+        }
+        (Language::Japanese, Suggestion::GrantScope) => {
+            "トークンに必要な権限がありません。ブラウザの Slack からコピーしたトークンを使ってください。"
+        }
+        (Language::Japanese, Suggestion::CheckMessageExists) => {
+            "メッセージが削除された可能性があります。Slack でリンクを開いて確認してください。"
+        }
+        (Language::Japanese, Suggestion::AskAuthor) => {
+            "作成者にリアクションを外してもらうか、プラグイン設定でこの指定を無視してください。"
+        }
+        (Language::Japanese, Suggestion::AllowDirectMessages) => {
+            "プラグイン設定でダイレクトメッセージの保存を許可してください。"
+        }
+    }
+}
+
+/// Label in front of the error's technical details, which stay in English
+pub fn error_details_label(language: Language) -> &'static str {
+    match language {
+        Language::English => "Error message",
+        Language::German => "Fehlermeldung",
+        Language::French => "Message d'erreur",
+        Language::Spanish => "Mensaje de error",
+        Language::Japanese => "エラーメッセージ",
+    }
+}
@@ -0,0 +1,149 @@
+const JPEG_START: [u8; 2] = [0xff, 0xd8];
+/// Start of the compressed image, after which a jpeg has no more metadata
+const JPEG_START_OF_SCAN: u8 = 0xda;
+/// `APP1`, Exif and XMP, where cameras and phones put the location
+const JPEG_APP1: u8 = 0xe1;
+/// `APP13`, Photoshop's IPTC, which can have the location too
+const JPEG_APP13: u8 = 0xed;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+/// Chunks with Exif or text, which XMP is stored as
+const PNG_METADATA_CHUNKS: [&[u8; 4]; 4] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt"];
+
+/// `data` without its Exif, XMP and IPTC metadata, e.g. where a photo was
+/// taken, when it is a jpeg or png. Other files, and images that can not be
+/// read, are returned as they are
+pub fn strip_metadata(data: &[u8]) -> Vec<u8> {
+    let stripped = if data.starts_with(&JPEG_START) {
+        strip_jpeg_metadata(data)
+    } else if data.starts_with(&PNG_SIGNATURE) {
+        strip_png_metadata(data)
+    } else {
+        return data.to_vec();
+    };
+    stripped.unwrap_or_else(|| {
+        log::warn!("rust|strip_metadata|could not read the image, kept it as it is");
+        data.to_vec()
+    })
+}
+
+fn strip_jpeg_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let mut stripped = JPEG_START.to_vec();
+    let mut rest = &data[JPEG_START.len()..];
+    loop {
+        let marker = match rest {
+            [0xff, marker, ..] => *marker,
+            _ => return None,
+        };
+        // a fill byte before the marker
+        if marker == 0xff {
+            stripped.push(0xff);
+            rest = &rest[1..];
+            continue;
+        }
+        // markers without a length, e.g. restart markers
+        if marker == 0x01 || (0xd0..=0xd9).contains(&marker) {
+            stripped.extend_from_slice(&rest[..2]);
+            rest = &rest[2..];
+            continue;
+        }
+        if marker == JPEG_START_OF_SCAN {
+            stripped.extend_from_slice(rest);
+            return Some(stripped);
+        }
+        let length = u16::from_be_bytes([*rest.get(2)?, *rest.get(3)?]) as usize;
+        let segment = rest.get(..2 + length)?;
+        if marker != JPEG_APP1 && marker != JPEG_APP13 {
+            stripped.extend_from_slice(segment);
+        }
+        rest = &rest[segment.len()..];
+    }
+}
+
+fn strip_png_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let mut stripped = PNG_SIGNATURE.to_vec();
+    let mut rest = &data[PNG_SIGNATURE.len()..];
+    while !rest.is_empty() {
+        let length = u32::from_be_bytes([rest[0], *rest.get(1)?, *rest.get(2)?, *rest.get(3)?]);
+        // length, type, data and crc
+        let chunk = rest.get(..(length as usize).checked_add(12)?)?;
+        if !PNG_METADATA_CHUNKS.iter().any(|kind| &chunk[4..8] == *kind) {
+            stripped.extend_from_slice(chunk);
+        }
+        rest = &rest[chunk.len()..];
+    }
+    Some(stripped)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of removing the metadata of images, run with `cargo test`. Photos
+    //! shared in slack often carry where they were taken, which must not end up in
+    //! a synced vault
+
+    use crate::image_metadata::strip_metadata;
+
+    /// A segment of a jpeg, its length counts itself but not the marker
+    fn jpeg_segment(marker: u8, data: &[u8]) -> Vec<u8> {
+        let length = (data.len() + 2) as u16;
+        [&[0xff, marker][..], &length.to_be_bytes(), data].concat()
+    }
+
+    /// A chunk of a png, with a made up crc, which is not checked
+    fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        [
+            &(data.len() as u32).to_be_bytes()[..],
+            kind,
+            data,
+            &[0, 0, 0, 0],
+        ]
+        .concat()
+    }
+
+    const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+    #[test]
+    fn strips_exif_from_jpegs() {
+        let start = vec![0xff, 0xd8];
+        let jfif = jpeg_segment(0xe0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        let exif = jpeg_segment(0xe1, b"Exif\0\0GPSLatitude 52.52");
+        let quantization = jpeg_segment(0xdb, &[0; 65]);
+        let scan = [
+            jpeg_segment(0xda, &[1, 1, 0, 0, 0x3f, 0]),
+            vec![0x12, 0xff, 0x00, 0x34, 0xff, 0xd9],
+        ]
+        .concat();
+
+        let stripped = strip_metadata(&[&start[..], &jfif, &exif, &quantization, &scan].concat());
+
+        assert_eq!([start, jfif, quantization, scan].concat(), stripped);
+    }
+
+    #[test]
+    fn strips_exif_and_text_from_pngs() {
+        let header = png_chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]);
+        let exif = png_chunk(b"eXIf", b"MM\0*GPSLatitude 52.52");
+        let xmp = png_chunk(b"iTXt", b"XML:com.adobe.xmp\0\0\0\0\0<exif:GPSLatitude/>");
+        let data = png_chunk(b"IDAT", &[0x78, 0x9c, 0x63, 0, 1]);
+        let end = png_chunk(b"IEND", &[]);
+
+        let stripped =
+            strip_metadata(&[&PNG_SIGNATURE[..], &header, &exif, &xmp, &data, &end].concat());
+
+        assert_eq!(
+            [&PNG_SIGNATURE[..], &header, &data, &end].concat(),
+            stripped
+        );
+    }
+
+    #[test]
+    fn keeps_other_files_and_broken_images_as_they_are() {
+        let text = b"not an image".to_vec();
+        let truncated_jpeg = [&[0xff, 0xd8][..], &jpeg_segment(0xe1, b"Exif\0\0")[..5]].concat();
+        let truncated_png = [&PNG_SIGNATURE[..], &png_chunk(b"eXIf", b"MM\0*")[..6]].concat();
+
+        assert_eq!(text, strip_metadata(&text));
+        assert_eq!(truncated_jpeg, strip_metadata(&truncated_jpeg));
+        assert_eq!(truncated_png, strip_metadata(&truncated_png));
+    }
+}
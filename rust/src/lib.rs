@@ -5,35 +5,93 @@
 //! This is possible by using Slack's web interface's 'xoxc' token and
 //! corresponding 'xoxd' cookie.
 
+pub mod activity;
+pub mod attachments;
+pub mod bookmarks;
+pub mod bots;
+pub mod build_info;
 pub mod channels;
+pub mod chronicle;
+pub mod clippings;
 pub mod components;
+pub mod continuation;
+pub mod dedup;
+pub mod digest;
+pub mod dry_run;
+pub mod due_dates;
+pub mod emoji;
+pub mod encryption;
+pub mod error_code;
+pub mod extractors;
+pub mod fetch_stats;
+pub mod finalize_options;
+pub mod folding;
+pub mod graph;
+pub mod highlights;
+pub mod huddles;
+mod i18n;
+pub mod image_metadata;
+mod logging;
+pub mod memory;
 pub mod messages;
+pub mod mrkdwn;
+pub mod note_links;
+pub mod org;
+pub mod panics;
+pub mod pii;
+pub mod preview;
+pub mod rate_limit;
+pub mod references;
+pub mod reminders;
+pub mod render;
 mod response;
+pub mod rich_text;
+pub mod secret;
+pub mod session_cache;
 pub mod slack_http_client;
-mod slack_url;
+pub mod slack_url;
 mod state_machine;
 pub mod team;
+#[cfg(test)]
+mod test_support;
+pub mod usergroups;
 pub mod users;
 mod utils;
 
 use crate::{
-    slack_http_client::{get_api_base, SlackHttpClient, SlackHttpClientConfig},
+    slack_http_client::{
+        resolve_api_base, ApiBudget, RequestUrlParam, SlackHttpClient, SlackHttpClientConfig,
+        SlackHttpClientConfigFeatureFlags, SlackHttpClientConfigOptions, TransportResponse,
+    },
+    users::Users,
     utils::create_file_name,
 };
 
-use components::{FileName, ObsidianSlackComponents, ObsidianSlackComponentsBuilder};
+use channels::ChannelInfo;
+use clippings::ObsidianSlackClippings;
+use components::{FileName, ObsidianSlackComponents, ObsidianSlackComponentsBuilder, SavedThread};
+use digest::Digest;
+use dry_run::DryRunPlan;
+use encryption::EncryptedComponents;
+use error_code::ErrorCode;
+use fetch_stats::FetchStats;
+use finalize_options::FinalizeOptions;
+use logging::LogLevelOverride;
 
 use do_notation::m;
 use js_sys::Promise;
 
-use slack_url::SlackUrl;
+use slack_url::{ChannelReference, SlackUrl};
 use snafu::{ResultExt, Snafu};
-use state_machine::{
-    ObsidianSlackStateMachine, ObsidianSlackStateMachineInput, ObsidianSlackStates,
-};
-use std::str::FromStr;
+use state_machine::{ObsidianSlackStateMachine, ObsidianSlackStateMachineInput};
+use std::{cell::RefCell, rc::Rc};
 
-use utils::{curry_request_func, set_panic_hook, top_level_fail};
+use utils::{
+    cache_session_requests, dedupe_writes, fetch_request_func, limit_api_calls,
+    locale_from_options, log_level_from_options, memoize_requests, pace_requests,
+    prepare_request_func, set_panic_hook, to_js_value, top_level_fail, PreparedRequestFunc,
+    SanitizeText,
+};
 use wasm_bindgen::prelude::*;
 
 #[derive(Debug, Snafu)]
@@ -46,6 +104,12 @@ pub enum Error {
         source: serde_wasm_bindgen::Error,
     },
 
+    // the options are left out, they can hold the encryption passphrase
+    #[snafu(display(
+        "Could not parse options js value to an options rust object - source: {source}"
+    ))]
+    CouldNotParseOptions { source: serde_wasm_bindgen::Error },
+
     #[snafu(display("Could not create slack http client config - source: {source}"))]
     ErrorCreatingSlackHttpClientConfig { source: slack_http_client::Error },
 
@@ -61,28 +125,158 @@ pub enum Error {
 
     #[snafu(display("There was a problem finalizing components to save - source {source}"))]
     CouldNotFinalizeComponents { source: components::Error },
+
+    #[snafu(display(
+        "Could not parse permalinks js value to a list of urls: {urls} - source: {source}"
+    ))]
+    CouldNotParseClippingUrls {
+        urls: String,
+        source: serde_wasm_bindgen::Error,
+    },
+
+    #[snafu(display("At least one permalink is needed to export clippings"))]
+    NoClippingUrls,
+
+    #[snafu(display("There was a problem getting clippings - source: {source}"))]
+    CouldNotGetClippings { source: clippings::Error },
+
+    #[snafu(display("There was a problem finalizing clippings to save - source: {source}"))]
+    CouldNotFinalizeClippings { source: clippings::Error },
+
+    #[snafu(display("Could not create slack channel url - source: {source}"))]
+    ErrorCreatingSlackChannelUrl { source: slack_url::Error },
+
+    #[snafu(display("Could not find the channel - source: {source}"))]
+    CouldNotResolveChannel { source: channels::Error },
+
+    #[snafu(display("There was a problem building the digest - source: {source}"))]
+    CouldNotBuildDigest { source: digest::Error },
+
+    #[snafu(display("There was a problem finalizing the digest to save - source: {source}"))]
+    CouldNotFinalizeDigest { source: digest::Error },
+
+    #[snafu(display("Could not bookmark the note in slack - source: {source}"))]
+    CouldNotAddBookmark { source: bookmarks::Error },
+
+    #[snafu(display("Could not mark the channel as read in slack - source: {source}"))]
+    CouldNotMarkChannelRead { source: channels::Error },
+
+    #[snafu(display("Could not set the reminder in slack - source: {source}"))]
+    CouldNotAddReminder { source: reminders::Error },
+
+    #[snafu(display("Could not get the channel's info - source: {source}"))]
+    CouldNotGetChannelInfo { source: channels::Error },
+
+    #[snafu(display("There was a problem finalizing the channel's info - source: {source}"))]
+    CouldNotFinalizeChannelInfo { source: channels::Error },
+
+    #[snafu(display("Could not get the user's info - source: {source}"))]
+    CouldNotGetUserInfo { source: users::Error },
+
+    #[snafu(display("Could not list your conversations - source: {source}"))]
+    CouldNotListConversations { source: channels::Error },
+
+    #[snafu(display("Could not export the workspace's emoji - source: {source}"))]
+    CouldNotExportEmoji { source: emoji::Error },
+
+    #[snafu(display("Could not preview the message - source: {source}"))]
+    CouldNotPreviewMessage { source: preview::Error },
+
+    #[snafu(display("Could not import the cache - source: {source}"))]
+    CouldNotImportCache { source: session_cache::Error },
+
+    #[snafu(display("Could not encrypt the components - source: {source}"))]
+    CouldNotEncryptComponents { source: encryption::Error },
+
+    #[snafu(display("Could not decrypt the components - source: {source}"))]
+    CouldNotDecryptComponents { source: encryption::Error },
+
+    #[snafu(display(
+        "Could not parse the saved note js value to a saved thread rust object: {saved} - source: {source}"
+    ))]
+    CouldNotParseSavedNote {
+        saved: String,
+        source: serde_wasm_bindgen::Error,
+    },
+
+    #[snafu(display(
+        "Could not parse components js value to a components rust object: {components} - source: {source}"
+    ))]
+    CouldNotParseComponents {
+        components: String,
+        source: serde_wasm_bindgen::Error,
+    },
+
+    #[snafu(display(
+        "Could not parse output format js value to an output format: {output_format} - source: {source}"
+    ))]
+    CouldNotParseOutputFormat {
+        output_format: String,
+        source: serde_wasm_bindgen::Error,
+    },
+
+    #[snafu(display("No fetch function was given and there is no global fetch"))]
+    NoFetchFunction,
+
+    #[snafu(display("There was a problem gathering the thread - source: {source}"))]
+    CouldNotGatherComponents { source: state_machine::Error },
+
+    #[snafu(display("Could not change the log level - source: {source}"))]
+    InvalidLogLevel { source: logging::Error },
 }
 
+/// Version, commit, cargo features and note format version of this build, see
+/// [`build_info::BuildInfo`]
 #[wasm_bindgen]
-pub fn init_wasm(log_level: Option<String>) {
+pub fn get_build_info() -> JsValue {
+    to_js_value(&build_info::BuildInfo::current(), false)
+}
+
+/// Sets up logging at `log_level`, `info` by default. Calling it again, e.g. when
+/// the plugin is reloaded, only changes the level. Throws for an unknown level
+#[wasm_bindgen]
+pub fn init_wasm(log_level: Option<String>) -> Result<(), JsValue> {
     set_panic_hook();
 
-    let level_string = match log_level {
-        Some(level) => level,
-        None => log::Level::Info.to_string(),
-    };
+    logging::init(log_level.as_deref().unwrap_or(log::Level::Info.as_str()))
+        .context(InvalidLogLevelSnafu)
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
 
-    let level = match log::Level::from_str(&level_string) {
-        Ok(level) => level,
-        Err(err) => {
-            panic!("rust|init| unable to prase provided log level|err={}", err)
-        }
-    };
+/// Changes the level of everything logged from now on, e.g. `debug` while
+/// reproducing a bug. Throws for an unknown level
+#[wasm_bindgen]
+pub fn set_log_level(log_level: String) -> Result<(), JsValue> {
+    logging::set_level(&log_level)
+        .context(InvalidLogLevelSnafu)
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
 
-    match console_log::init_with_level(level) {
-        Ok(_) => (),
-        Err(err) => panic!("rust|init| unable to init with level|err={}", err),
-    };
+/// The bug a call ran into, when it failed with a `RuntimeError` instead of an
+/// error message. Panics abort calls in wasm, so a call that panicked leaves its
+/// panic here, formatted like any other error for bug reports. `null` when there
+/// was no panic since the last time. The plugin calls it when a call throws, and
+/// should reload the module afterwards, an aborted call does not clean up
+#[wasm_bindgen]
+pub fn take_internal_bug(locale: Option<String>) -> JsValue {
+    panics::take_last_panic().map_or(JsValue::NULL, |bug| top_level_fail(&bug, locale.as_deref()))
+}
+
+/// The image without its Exif, XMP and IPTC metadata, e.g. where a photo was
+/// taken, for attachments the plugin downloads itself. Files that are not jpeg
+/// or png images are returned as they are
+#[wasm_bindgen]
+pub fn strip_image_metadata(data: &[u8]) -> Vec<u8> {
+    image_metadata::strip_metadata(data)
+}
+
+/// Sends every log record to `log_sink` as well, called with its level, e.g.
+/// `INFO`, target and message, so the plugin can keep a debug log for support.
+/// With `log_to_console` false records only go to `log_sink`. Passing no
+/// `log_sink` logs to the console again
+#[wasm_bindgen]
+pub fn set_log_sink(log_sink: Option<js_sys::Function>, log_to_console: Option<bool>) {
+    logging::set_sink(log_sink, log_to_console.unwrap_or(true));
 }
 
 /// Interface to get slack messages and save to your obsidian vault
@@ -103,13 +297,24 @@ pub fn init_wasm(log_level: Option<String>) {
 /// web interface/web app by right clicking any message/thread and copying it's
 /// link
 ///
+/// The `refresh_credentials_func` is optional. When given, it is called whenever
+/// Slack rejects the `api_token` or `cookie` and must return (a promise of) an
+/// object with a new `token` and `cookie`. The failed request is retried once with
+/// them, and the new values are returned in `refreshed_credentials` so the plugin
+/// can store them
+///
+/// The `options` are optional settings for the http client, see
+/// [`SlackHttpClientConfigOptions`], and for the note made of what it fetched, see
+/// [`FinalizeOptions`], in one object. `undefined` or `null` uses the defaults
+///
 /// The `vault` is the Obisidian vault to save the messages to. See:
 /// https://marcus.se.net/obsidian-plugin-docs/vault
 ///
 /// Panics:
-/// The function is designed to catch all errors and display an alert in Obsidian
-/// with the error. If there is a panic, then there is a programming bug that needs
-/// to be addressed
+/// Errors are returned as a message for Obsidian to show. A panic is a bug, and
+/// in wasm it aborts the call with a `RuntimeError` instead of returning. The
+/// plugin then gets the message of the bug from [`take_internal_bug`] and should
+/// be reloaded, the module may be left in a broken state
 #[wasm_bindgen]
 pub async fn get_slack_message(
     api_token: String,
@@ -117,51 +322,1013 @@ pub async fn get_slack_message(
     url: String,
     feature_flags: JsValue,
     request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
 ) -> JsValue {
-    let make_request = curry_request_func(js_sys::Function::from(request_func));
-    let feature_flags_string = format!("{:#?}", feature_flags);
+    let locale = locale_from_options(&options);
+    let _log_level_override = match log_level_from_options(&options)
+        .map(|level| LogLevelOverride::new(&level))
+        .transpose()
+        .context(InvalidLogLevelSnafu)
+    {
+        Ok(log_level_override) => log_level_override,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+    let started_at = js_sys::Date::now();
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        refreshed_credentials,
+    } = prepare_request_func(request_func, refresh_credentials_func);
 
-    let (client, slack_url) = match m! {
-        feature_flags <- serde_wasm_bindgen::from_value(feature_flags).context(CouldNotParseFeatureFlagsSnafu {feature_flags: feature_flags_string});
-        config <- SlackHttpClientConfig::new(
-                get_api_base(),
-                api_token.to_string(),
-                cookie.to_string(),
-                feature_flags,
-            ).context(ErrorCreatingSlackHttpClientConfigSnafu);
+    let client_fetch_stats = fetch_stats.clone();
+    let (client, slack_url, finalize_options) = match m! {
+        feature_flags <- parse_feature_flags(feature_flags);
         slack_url <- SlackUrl::new(&url).context(ErrorCreatingSlackUrlSnafu);
-        let client = SlackHttpClient::<Promise>::new(config, make_request);
-        return (client, slack_url);
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, slack_url.host(), feature_flags, options, make_request, client_fetch_stats);
+        return (client, slack_url, finalize_options);
     } {
         Ok(x) => x,
-        Err(err) => return top_level_fail(&err),
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
     };
 
+    if client.config.options.dry_run {
+        return ObsidianSlackStateMachine::plan(
+            &client.config.feature_flags,
+            &client.config.options,
+        )
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |states| {
+                let plan = DryRunPlan::new(
+                    &slack_url.channel_id,
+                    &slack_url.ts,
+                    slack_url.thread_ts.as_deref(),
+                    FileName(create_file_name(&slack_url)),
+                    &states,
+                    &client.config.options,
+                );
+                serde_wasm_bindgen::to_value(&plan).expect(
+                    "Expected to serialize object with serde, but was unable to. This is a bug",
+                )
+            },
+        );
+    }
+
     let mut state_machine_inputs = ObsidianSlackStateMachineInput::<Promise> {
         client,
         slack_url,
         components: ObsidianSlackComponentsBuilder::default(),
+        users_cache: Users::default(),
+        finalize_options,
+    };
+    // only catches where panics unwind, e.g. in native tests, see `take_internal_bug`
+    match panics::catch_panic(ObsidianSlackStateMachine::run(&mut state_machine_inputs)).await {
+        Ok(Ok(())) => (),
+        Ok(Err(err)) => return top_level_fail(&err, locale.as_deref()),
+        Err(bug) => return top_level_fail(&bug, locale.as_deref()),
+    }
+
+    let ObsidianSlackStateMachineInput {
+        components: mut components_builder,
+        client,
+        slack_url,
+        finalize_options,
+        ..
+    } = state_machine_inputs;
+
+    let strip_nulls = finalize_options.strip_nulls;
+    m! {
+        let file_name = create_file_name(&slack_url);
+        let refreshed_credentials = refreshed_credentials.borrow().clone();
+        let fetch_stats = fetch_stats.borrow().with_duration_since(started_at);
+        components <- components_builder
+            .file_name(FileName(file_name))
+            .channel_link(slack_url.channel_link())
+            .refreshed_credentials(refreshed_credentials)
+            .warnings(client.warnings())
+            .fetch_stats(fetch_stats)
+            .build()
+            .context(CouldNotBuildComponentsTogetherSnafu);
+        components <- ObsidianSlackComponents::finalize(components, &finalize_options, js_sys::Date::now()).context(CouldNotFinalizeComponentsSnafu);
+        result <- match &finalize_options.encryption_passphrase {
+            Some(passphrase) => EncryptedComponents::encrypt(&components, passphrase.expose())
+                .context(CouldNotEncryptComponentsSnafu)
+                .map(|encrypted| to_js_value(&encrypted, strip_nulls)),
+            None => Ok(to_js_value(&components, strip_nulls)),
+        };
+        return result;
+    }
+    .unwrap_or_else(|err| top_level_fail(&err, locale.as_deref()))
+}
+
+/// The components of the thread at `slack_url`, gathered with `client` the way
+/// [`get_slack_message`] gathers them and finalized with `finalize_options`, for
+/// running without js, e.g. in the `obsidian-slack` cli, saved at `now_ms`, unix
+/// milliseconds
+pub async fn export_message<T>(
+    client: SlackHttpClient<T>,
+    slack_url: SlackUrl,
+    finalize_options: FinalizeOptions,
+    now_ms: f64,
+) -> Result<ObsidianSlackComponents, Error>
+where
+    T: TransportResponse,
+{
+    let mut state_machine_inputs = ObsidianSlackStateMachineInput {
+        client,
+        slack_url,
+        components: ObsidianSlackComponentsBuilder::default(),
+        users_cache: Users::default(),
+        finalize_options,
+    };
+    ObsidianSlackStateMachine::run(&mut state_machine_inputs)
+        .await
+        .context(CouldNotGatherComponentsSnafu)?;
+
+    let ObsidianSlackStateMachineInput {
+        components: mut components_builder,
+        client,
+        slack_url,
+        finalize_options,
+        ..
+    } = state_machine_inputs;
+    let components = components_builder
+        .file_name(FileName(create_file_name(&slack_url)))
+        .channel_link(slack_url.channel_link())
+        .warnings(client.warnings())
+        .build()
+        .context(CouldNotBuildComponentsTogetherSnafu)?;
+    ObsidianSlackComponents::finalize(components, &finalize_options, now_ms)
+        .context(CouldNotFinalizeComponentsSnafu)
+}
+
+/// [`get_slack_message`] for node, e.g. to archive threads in ci, where there is
+/// no obsidian `request` function. Requests are sent with `fetch`, node's global
+/// `fetch` when it is undefined. Credentials can not be refreshed
+#[wasm_bindgen]
+pub async fn get_slack_message_node(
+    api_token: String,
+    cookie: String,
+    url: String,
+    feature_flags: JsValue,
+    options: JsValue,
+    fetch: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let fetch = if fetch.is_function() {
+        fetch
+    } else {
+        js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("fetch"))
+            .unwrap_or(JsValue::UNDEFINED)
+    };
+    if !fetch.is_function() {
+        return top_level_fail(&Error::NoFetchFunction, locale.as_deref());
+    }
+
+    let request_func = fetch_request_func(js_sys::Function::from(fetch));
+    get_slack_message(
+        api_token,
+        cookie,
+        url,
+        feature_flags,
+        request_func.as_ref().clone(),
+        options,
+        JsValue::UNDEFINED,
+    )
+    .await
+}
+
+/// Exports individual messages, possibly from different channels, as one bundle of
+/// clippings for curating highlights into a single note
+///
+/// The `urls` are an array of message permalinks. Only the linked messages are
+/// saved, not their threads. Of the `feature_flags`, only `get_users` applies.
+/// Everything else is the same as for [`get_slack_message`]
+#[wasm_bindgen]
+pub async fn get_slack_clippings(
+    api_token: String,
+    cookie: String,
+    urls: JsValue,
+    feature_flags: JsValue,
+    request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let started_at = js_sys::Date::now();
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, refresh_credentials_func);
+    let urls_string = format!("{:#?}", urls);
+
+    let client_fetch_stats = fetch_stats.clone();
+    let (client, slack_urls, finalize_options) = match m! {
+        feature_flags <- parse_feature_flags(feature_flags);
+        urls <- serde_wasm_bindgen::from_value::<Vec<String>>(urls).context(CouldNotParseClippingUrlsSnafu {urls: urls_string});
+        slack_urls <- urls
+            .into_iter()
+            .map(|url| SlackUrl::new(&url).map(|slack_url| (url, slack_url)))
+            .collect::<Result<Vec<(String, SlackUrl)>, slack_url::Error>>()
+            .context(ErrorCreatingSlackUrlSnafu);
+        first_host <- slack_urls.first().map_or(NoClippingUrlsSnafu.fail(), |(_, slack_url)| Ok(slack_url.host().map(str::to_string)));
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, first_host.as_deref(), feature_flags, options, make_request, client_fetch_stats);
+        return (client, slack_urls, finalize_options);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    let mut clippings = match clippings::get_clippings_from_api(&client, &slack_urls)
+        .await
+        .context(CouldNotGetClippingsSnafu)
+    {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    clippings.fetch_stats = fetch_stats.borrow().with_duration_since(started_at);
+
+    ObsidianSlackClippings::finalize(clippings, &finalize_options, js_sys::Date::now())
+        .context(CouldNotFinalizeClippingsSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |buffer| to_js_value(&buffer, finalize_options.strip_nulls),
+        )
+}
+
+/// Builds a digest of a channel's threads over a period, e.g. for a daily or
+/// weekly review note
+///
+/// The `channel_url` is a link to the channel, e.g.
+/// `https://workspace.slack.com/archives/C0000000000`, its bare id or its name,
+/// e.g. `#general`, see [`ChannelReference`]. `since` and `until` are unix
+/// timestamps in seconds. Of the `feature_flags`, only `get_users`
+/// applies. Everything else is the same as for [`get_slack_message`]
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub async fn build_digest(
+    api_token: String,
+    cookie: String,
+    channel_url: String,
+    since: f64,
+    until: f64,
+    feature_flags: JsValue,
+    request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let started_at = js_sys::Date::now();
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, refresh_credentials_func);
+
+    let client_fetch_stats = fetch_stats.clone();
+    let (client, channel_reference, finalize_options) = match m! {
+        feature_flags <- parse_feature_flags(feature_flags);
+        channel_reference <- ChannelReference::new(&channel_url).context(ErrorCreatingSlackChannelUrlSnafu);
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, channel_reference.host(), feature_flags, options, make_request, client_fetch_stats);
+        return (client, channel_reference, finalize_options);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    let channel_id = match channels::resolve_channel_id(&client, &channel_reference)
+        .await
+        .context(CouldNotResolveChannelSnafu)
+    {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    let mut digest = match digest::get_digest_from_api(&client, &channel_id, since, until)
+        .await
+        .context(CouldNotBuildDigestSnafu)
+    {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    digest.fetch_stats = fetch_stats.borrow().with_duration_since(started_at);
+
+    Digest::finalize(digest, &finalize_options, js_sys::Date::now())
+        .context(CouldNotFinalizeDigestSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |buffer| to_js_value(&buffer, finalize_options.strip_nulls),
+        )
+}
+
+/// Bookmarks a saved note in the slack channel of `url`, so the channel links
+/// back to the vault. The `link` is usually the note's `obsidian://` uri
+///
+/// Does nothing and returns `null` unless the `bookmark_note_in_channel` option
+/// is on, so the plugin can call it after every save. Otherwise returns the added
+/// bookmark, or an error message, e.g. when the user may not edit the channel's
+/// bookmarks
+///
+/// The `api_token`, `cookie`, `url`, `request_func` and `options` are the same as
+/// for [`get_slack_message`]
+#[wasm_bindgen]
+pub async fn add_slack_bookmark(
+    api_token: String,
+    cookie: String,
+    url: String,
+    title: String,
+    link: String,
+    request_func: JsValue,
+    options: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, JsValue::UNDEFINED);
+    let (client, slack_url) = match m! {
+        slack_url <- SlackUrl::new(&url).context(ErrorCreatingSlackUrlSnafu);
+        client <- create_client(api_token, cookie, slack_url.host(), SlackHttpClientConfigFeatureFlags::default(), options, make_request, fetch_stats);
+        return (client, slack_url);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    if !client.config.options.bookmark_note_in_channel {
+        return JsValue::NULL;
+    }
+
+    bookmarks::add_bookmark_to_api(&client, &slack_url.channel_id, &title, &link)
+        .await
+        .context(CouldNotAddBookmarkSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |bookmark| {
+                serde_wasm_bindgen::to_value(&bookmark).expect(
+                    "Expected to serialize object with serde, but was unable to. This is a bug",
+                )
+            },
+        )
+}
+
+/// Marks the slack channel of `channel_url` as read up to the message at `ts`,
+/// usually the `read_up_to` of the [`Digest`] of the channel's export, so slack's
+/// unread state matches what was saved to the vault
+///
+/// Does nothing and returns `null` unless the `mark_read_after_export` option is
+/// on, so the plugin can call it after every export. Otherwise returns `true`, or
+/// an error message
+///
+/// The `api_token`, `cookie`, `request_func` and `options` are the same as for
+/// [`build_digest`]
+#[wasm_bindgen]
+pub async fn mark_channel_read(
+    api_token: String,
+    cookie: String,
+    channel_url: String,
+    ts: String,
+    request_func: JsValue,
+    options: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, JsValue::UNDEFINED);
+    let (client, channel_reference) = match m! {
+        channel_reference <- ChannelReference::new(&channel_url).context(ErrorCreatingSlackChannelUrlSnafu);
+        client <- create_client(api_token, cookie, channel_reference.host(), SlackHttpClientConfigFeatureFlags::default(), options, make_request, fetch_stats);
+        return (client, channel_reference);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    if !client.config.options.mark_read_after_export {
+        return JsValue::NULL;
+    }
+
+    let channel_id = match channels::resolve_channel_id(&client, &channel_reference)
+        .await
+        .context(CouldNotResolveChannelSnafu)
+    {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    channels::mark_read_in_api(&client, &channel_id, &ts)
+        .await
+        .context(CouldNotMarkChannelReadSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |_| JsValue::TRUE,
+        )
+}
+
+/// Sets a slack reminder, e.g. to follow up on a thread that was archived with
+/// `text` linking to its note. `ts` is when it is due, a unix timestamp in
+/// seconds or how slack writes it, e.g. `in 2 days`
+///
+/// Does nothing and returns `null` unless the `allow_add_reminder` option is on.
+/// Otherwise returns the added [`reminders::Reminder`], or an error message
+///
+/// The `api_token`, `cookie`, `request_func`, `options` and
+/// `refresh_credentials_func` are the same as for [`get_slack_message`]
+#[wasm_bindgen]
+pub async fn add_reminder(
+    api_token: String,
+    cookie: String,
+    text: String,
+    ts: String,
+    request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, refresh_credentials_func);
+    let (client, finalize_options) = match m! {
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, None, SlackHttpClientConfigFeatureFlags::default(), options, make_request, fetch_stats);
+        return (client, finalize_options);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
     };
-    let mut current_state = ObsidianSlackStates::Start;
-    while current_state != ObsidianSlackStates::End {
-        current_state =
-            match ObsidianSlackStateMachine::transition(current_state, &mut state_machine_inputs)
-                .await
-            {
-                Ok(x) => x,
-                Err(err) => return top_level_fail(&err),
-            };
+
+    if !client.config.options.allow_add_reminder {
+        return JsValue::NULL;
     }
 
+    reminders::add_reminder_to_api(&client, &text, &ts)
+        .await
+        .context(CouldNotAddReminderSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |reminder| to_js_value(&reminder, finalize_options.strip_nulls),
+        )
+}
+
+/// A short preview of the thread `url` points to, e.g. to show on hover before
+/// saving it, see [`preview::MessagePreview`]. Only the thread and its root
+/// message's author are requested
+///
+/// The `api_token`, `cookie`, `url`, `request_func`, `options` and
+/// `refresh_credentials_func` are the same as for [`get_slack_message`]
+#[wasm_bindgen]
+pub async fn preview_message(
+    api_token: String,
+    cookie: String,
+    url: String,
+    request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, refresh_credentials_func);
+    let (client, slack_url, finalize_options) = match m! {
+        slack_url <- SlackUrl::new(&url).context(ErrorCreatingSlackUrlSnafu);
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, slack_url.host(), SlackHttpClientConfigFeatureFlags::default(), options, make_request, fetch_stats);
+        return (client, slack_url, finalize_options);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    preview::get_message_preview_from_api(&client, &url, &slack_url, &finalize_options)
+        .await
+        .context(CouldNotPreviewMessageSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |preview| to_js_value(&preview, finalize_options.strip_nulls),
+        )
+}
+
+/// Gets a channel without any of its messages, e.g. for an index note of the
+/// channel, see [`ChannelInfo`]
+///
+/// The `channel_url_or_id` is a link to the channel, its bare id or its name, as
+/// for [`build_digest`]. The `api_token`, `cookie`, `request_func`,
+/// `options` and `refresh_credentials_func` are the same as for
+/// [`get_slack_message`]
+#[wasm_bindgen]
+pub async fn get_channel_info(
+    api_token: String,
+    cookie: String,
+    channel_url_or_id: String,
+    request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let started_at = js_sys::Date::now();
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, refresh_credentials_func);
+
+    let client_fetch_stats = fetch_stats.clone();
+    let (client, channel_reference, finalize_options) = match m! {
+        channel_reference <- ChannelReference::new(&channel_url_or_id).context(ErrorCreatingSlackChannelUrlSnafu);
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, channel_reference.host(), SlackHttpClientConfigFeatureFlags::default(), options, make_request, client_fetch_stats);
+        return (client, channel_reference, finalize_options);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    let channel_id = match channels::resolve_channel_id(&client, &channel_reference)
+        .await
+        .context(CouldNotResolveChannelSnafu)
+    {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    let mut info = match channels::get_channel_info_from_api(&client, &channel_id)
+        .await
+        .context(CouldNotGetChannelInfoSnafu)
+    {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    info.fetch_stats = fetch_stats.borrow().with_duration_since(started_at);
+
+    ChannelInfo::finalize(info, &finalize_options)
+        .context(CouldNotFinalizeChannelInfoSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |buffer| to_js_value(&buffer, finalize_options.strip_nulls),
+        )
+}
+
+/// Gets a single user with their workspace as `team_info`, e.g. to create a note
+/// about a person
+///
+/// The `user_id_or_mention` is the user's id, e.g. `U0000000000`, or a mention
+/// as slack writes it, e.g. `<@U0000000000>`. The `api_token`, `cookie`,
+/// `request_func`, `options` and `refresh_credentials_func` are the same as for
+/// [`get_slack_message`]
+#[wasm_bindgen]
+pub async fn get_user_info(
+    api_token: String,
+    cookie: String,
+    user_id_or_mention: String,
+    request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, refresh_credentials_func);
+
+    let (client, user_id, options) = match m! {
+        user_id <- users::parse_user_id(&user_id_or_mention).context(CouldNotGetUserInfoSnafu);
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, None, SlackHttpClientConfigFeatureFlags::default(), options, make_request, fetch_stats);
+        return (client, user_id, finalize_options);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    users::get_user_info_from_api(&user_id, &client)
+        .await
+        .context(CouldNotGetUserInfoSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |mut user| {
+                if !options.disable_text_sanitization {
+                    user.sanitize_text();
+                }
+                user.trim_to(options.user_detail_level);
+                to_js_value(&user, options.strip_nulls)
+            },
+        )
+}
+
+/// Lists the conversations you are a member of as `id` and `name` pairs, e.g. for
+/// a channel picker instead of pasting links, see [`channels::ConversationEntry`]
+///
+/// The `types` are slack's conversation types, comma separated, e.g.
+/// `public_channel,private_channel,mpim,im`. `undefined` lists public and private
+/// channels. At most `limit` conversations are listed, `undefined` lists them all.
+/// The `api_token`, `cookie`, `request_func`, `options` and
+/// `refresh_credentials_func` are the same as for [`get_slack_message`]
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub async fn list_conversations(
+    api_token: String,
+    cookie: String,
+    types: Option<String>,
+    limit: Option<usize>,
+    request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, refresh_credentials_func);
+
+    let (client, finalize_options) = match m! {
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, None, SlackHttpClientConfigFeatureFlags::default(), options, make_request, fetch_stats);
+        return (client, finalize_options);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    let types = types
+        .as_deref()
+        .unwrap_or(channels::DEFAULT_CONVERSATION_TYPES);
+    channels::list_conversations_from_api(&client, types, limit)
+        .await
+        .context(CouldNotListConversationsSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |conversations| to_js_value(&conversations, finalize_options.strip_nulls),
+        )
+}
+
+/// Exports the workspace's custom emoji as their own bundle, see
+/// [`emoji::EmojiPack`]
+///
+/// The `api_token`, `cookie`, `request_func`, `options` and
+/// `refresh_credentials_func` are the same as for [`get_slack_message`]
+#[wasm_bindgen]
+pub async fn export_emoji(
+    api_token: String,
+    cookie: String,
+    request_func: JsValue,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let locale = locale_from_options(&options);
+    let started_at = js_sys::Date::now();
+    let PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        ..
+    } = prepare_request_func(request_func, refresh_credentials_func);
+
+    let client_fetch_stats = fetch_stats.clone();
+    let (client, finalize_options) = match m! {
+        finalize_options <- parse_finalize_options(&options);
+        client <- create_client(api_token, cookie, None, SlackHttpClientConfigFeatureFlags::default(), options, make_request, client_fetch_stats);
+        return (client, finalize_options);
+    } {
+        Ok(x) => x,
+        Err(err) => return top_level_fail(&err, locale.as_deref()),
+    };
+
+    emoji::get_emoji_pack_from_api(&client)
+        .await
+        .context(CouldNotExportEmojiSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, locale.as_deref()),
+            |mut emoji_pack| {
+                emoji_pack.fetch_stats = fetch_stats.borrow().with_duration_since(started_at);
+                to_js_value(&emoji_pack, finalize_options.strip_nulls)
+            },
+        )
+}
+
+/// The code of an error string returned by any other function, e.g.
+/// `E_AUTH_EXPIRED`, see [`ErrorCode`]. `undefined` if `message` has none
+#[wasm_bindgen]
+pub fn get_error_code(message: String) -> Option<String> {
+    ErrorCode::from_error_message(&message).map(|code| code.to_string())
+}
+
+/// Empties the cache kept by the `session_cache` option, e.g. after switching
+/// workspaces or when a renamed channel still shows its old name
+#[wasm_bindgen]
+pub fn clear_cache() {
+    session_cache::clear();
+}
+
+/// Number of users, teams and channels in the cache kept by the `session_cache`
+/// option and how often it was used, see [`session_cache::SessionCacheStats`]
+#[wasm_bindgen]
+pub fn cache_stats() -> JsValue {
+    serde_wasm_bindgen::to_value(&session_cache::stats())
+        .expect("Expected to serialize object with serde, but was unable to. This is a bug")
+}
+
+/// Size of the wasm memory in bytes, see [`memory::MemoryUsage`]. It only grows, so
+/// the plugin can compare it before and after a large export
+#[wasm_bindgen]
+pub fn memory_usage() -> JsValue {
+    serde_wasm_bindgen::to_value(&memory::MemoryUsage::current())
+        .expect("Expected to serialize object with serde, but was unable to. This is a bug")
+}
+
+/// The text of the note to save for `components`, as returned by
+/// [`get_slack_message`], in the [`render::OutputFormat`] given as
+/// `output_format`, json when undefined. Notes keep rendering the same until
+/// [`render::NOTE_FORMAT_VERSION`] changes
+#[wasm_bindgen]
+pub fn render_note(components: JsValue, output_format: JsValue, strip_nulls: bool) -> JsValue {
+    let components_string = format!("{:#?}", components);
+    let output_format_string = format!("{:#?}", output_format);
+    m! {
+        components <- serde_wasm_bindgen::from_value::<ObsidianSlackComponents>(components)
+            .context(CouldNotParseComponentsSnafu {
+                components: components_string,
+            });
+        output_format <- serde_wasm_bindgen::from_value::<Option<render::OutputFormat>>(output_format)
+            .context(CouldNotParseOutputFormatSnafu {
+                output_format: output_format_string,
+            });
+        return render::render(&components, output_format.unwrap_or_default(), strip_nulls);
+    }
+    .map_or_else(|err| top_level_fail(&err, None), JsValue::from)
+}
+
+/// The `components` returned by [`get_slack_message`] with the `normalize_users`
+/// option, with the users and teams filled back in, see
+/// [`ObsidianSlackComponents::join_users`]
+#[wasm_bindgen]
+pub fn join_users(components: JsValue, strip_nulls: bool) -> JsValue {
+    let components_string = format!("{:#?}", components);
+    serde_wasm_bindgen::from_value::<ObsidianSlackComponents>(components)
+        .context(CouldNotParseComponentsSnafu {
+            components: components_string,
+        })
+        .map_or_else(
+            |err| top_level_fail(&err, None),
+            |mut components| {
+                components.join_users();
+                to_js_value(&components, strip_nulls)
+            },
+        )
+}
+
+/// The `components` returned by [`get_slack_message`] for a refresh of a note,
+/// with the thread of the note as saved before merged in. `saved` is the note
+/// parsed from json, see [`ObsidianSlackComponents::merge_saved_note`]
+#[wasm_bindgen]
+pub fn merge_saved_note(saved: JsValue, components: JsValue, strip_nulls: bool) -> JsValue {
+    let saved_string = format!("{:#?}", saved);
+    let components_string = format!("{:#?}", components);
     m! {
-        let file_name = create_file_name(&state_machine_inputs.slack_url);
-        let mut components_builder = state_machine_inputs.components;
-        components <- components_builder.file_name(FileName(file_name)).build().context(CouldNotBuildComponentsTogetherSnafu);
-        components <- ObsidianSlackComponents::finalize(components).context(CouldNotFinalizeComponentsSnafu);
-        return components;
+        saved <- serde_wasm_bindgen::from_value::<SavedThread>(saved)
+            .context(CouldNotParseSavedNoteSnafu {
+                saved: saved_string,
+            });
+        components <- serde_wasm_bindgen::from_value::<ObsidianSlackComponents>(components)
+            .context(CouldNotParseComponentsSnafu {
+                components: components_string,
+            });
+        return (saved, components);
     }
     .map_or_else(
-        |err| top_level_fail(&err),
-        |buffer| serde_wasm_bindgen::to_value(&buffer).expect("Expected to serialize object with serde, but was unable to. This is a bug"),
+        |err| top_level_fail(&err, None),
+        |(saved, mut components)| {
+            components.merge_saved_note(saved);
+            to_js_value(&components, strip_nulls)
+        },
+    )
+}
+
+/// The components in the `encrypted` field of what [`get_slack_message`] returns
+/// when the `encryption_passphrase` option is set, see [`EncryptedComponents`].
+/// Returns an error message if the passphrase is wrong or the build does not
+/// include the `encryption` feature
+#[wasm_bindgen]
+pub fn decrypt_components(encrypted: String, passphrase: String) -> JsValue {
+    encryption::decrypt(&encrypted, &passphrase)
+        .context(CouldNotDecryptComponentsSnafu)
+        .map_or_else(
+            |err| top_level_fail(&err, None),
+            |components| to_js_value(&components, false),
+        )
+}
+
+/// The cache kept by the `session_cache` option as a json string, for the plugin
+/// to save in its data and hand to [`import_cache`] after obsidian restarts
+#[wasm_bindgen]
+pub fn export_cache() -> String {
+    serde_json::to_string(&session_cache::export())
+        .expect("Expected to serialize object with serde, but was unable to. This is a bug")
+}
+
+/// Adds the entries of a cache exported by [`export_cache`] to the session cache,
+/// dropping those older than `ttl_seconds`, a week by default. Returns the number
+/// of entries added, or an error message if `json` is not an exported cache
+#[wasm_bindgen]
+pub fn import_cache(json: String, ttl_seconds: Option<f64>) -> JsValue {
+    session_cache::import(
+        &json,
+        ttl_seconds.unwrap_or(session_cache::DEFAULT_CACHE_TTL_SECONDS),
     )
+    .context(CouldNotImportCacheSnafu)
+    .map_or_else(
+        |err| top_level_fail(&err, None),
+        |imported| JsValue::from_f64(imported as f64),
+    )
+}
+
+/// The `feature_flags` js value of an export
+fn parse_feature_flags(feature_flags: JsValue) -> Result<SlackHttpClientConfigFeatureFlags, Error> {
+    let feature_flags_string = format!("{:#?}", feature_flags);
+    serde_wasm_bindgen::from_value(feature_flags).context(CouldNotParseFeatureFlagsSnafu {
+        feature_flags: feature_flags_string,
+    })
+}
+
+/// The [`FinalizeOptions`] of the plugin's `options`, which [`create_client`] parses
+/// again for the client's own settings. `undefined` or `null` uses the defaults
+fn parse_finalize_options(options: &JsValue) -> Result<FinalizeOptions, Error> {
+    serde_wasm_bindgen::from_value::<Option<FinalizeOptions>>(options.clone())
+        .context(CouldNotParseOptionsSnafu)
+        .map(Option::unwrap_or_default)
+}
+
+/// Builds the client for the workspace at `slack_url_host`, see [`resolve_api_base`].
+/// Repeated requests are answered from memory and counted in `fetch_stats`, see
+/// [`memoize_requests`] and [`cache_session_requests`]
+fn create_client(
+    api_token: String,
+    cookie: String,
+    slack_url_host: Option<&str>,
+    feature_flags: SlackHttpClientConfigFeatureFlags,
+    options: JsValue,
+    make_request: Box<dyn Fn(RequestUrlParam) -> Promise>,
+    fetch_stats: Rc<RefCell<FetchStats>>,
+) -> Result<SlackHttpClient<Promise>, Error> {
+    m! {
+        options <- serde_wasm_bindgen::from_value::<Option<SlackHttpClientConfigOptions>>(options).context(CouldNotParseOptionsSnafu);
+        let options = options.unwrap_or_default();
+        api_base <- resolve_api_base(options.api_base.as_deref(), slack_url_host).context(ErrorCreatingSlackHttpClientConfigSnafu);
+        config <- SlackHttpClientConfig::new(
+                api_base,
+                api_token.to_string(),
+                cookie.to_string(),
+                feature_flags,
+                options,
+            ).context(ErrorCreatingSlackHttpClientConfigSnafu);
+        let api_budget = Rc::new(ApiBudget::new(config.options.max_api_calls));
+        let make_request = pace_requests(make_request, &config.options);
+        let make_request = limit_api_calls(make_request, api_budget.clone());
+        let make_request = if config.options.session_cache {
+            cache_session_requests(make_request, fetch_stats.clone())
+        } else {
+            make_request
+        };
+        let make_request = memoize_requests(make_request, fetch_stats.clone());
+        let make_request =
+            dedupe_writes(make_request, config.options.write_dedup_window_ms, fetch_stats);
+        return SlackHttpClient::<Promise>::new(config, make_request, api_budget);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::export_message;
+    use crate::{
+        finalize_options::FinalizeOptions,
+        messages::IgnoredUserHandling,
+        slack_http_client::{SlackHttpClientConfigFeatureFlags, SlackHttpClientConfigOptions},
+        slack_url::SlackUrl,
+        test_support::{client, client_with},
+    };
+
+    #[async_std::test]
+    async fn export_message_gathers_and_finalizes_the_thread() {
+        let options = FinalizeOptions {
+            finished_after_days: Some(7),
+            ..FinalizeOptions::default()
+        };
+        let (client, requested_urls) = client(SlackHttpClientConfigOptions::default(), |url| {
+            Ok(if url.contains("users.") {
+                json!({
+                    "ok": true,
+                    "user": { "id": "U1", "name": "one" },
+                    "members": [{ "id": "U1", "name": "one" }]
+                })
+            } else {
+                json!({
+                    "ok": true,
+                    "channel": { "id": "C1", "is_channel": true },
+                    "messages": [{ "ts": "1700000000.000100", "user": "U1", "text": "root" }]
+                })
+            })
+        });
+        let slack_url = SlackUrl::new("https://workspace.slack.com/archives/C1/p1700000000000100")
+            .expect("Should parse the link");
+
+        let components = export_message(client, slack_url, options, 1_700_200_000_000.0)
+            .await
+            .expect("Should export the thread");
+
+        assert_eq!("C1-1700000000.000100.json", components.file_name.0);
+        assert_eq!(
+            Some("one".to_string()),
+            components.users.as_ref().unwrap()["U1"].name
+        );
+        assert_eq!(
+            Some(2),
+            components
+                .activity
+                .as_ref()
+                .and_then(|activity| activity.days_since_last_activity)
+        );
+        assert!(requested_urls
+            .borrow()
+            .iter()
+            .any(|url| url.contains("conversations.replies")));
+    }
+
+    #[rstest]
+    #[case::exclude(IgnoredUserHandling::Exclude, 1)]
+    #[case::anonymize(IgnoredUserHandling::Anonymize, 2)]
+    #[async_std::test]
+    async fn export_message_leaves_an_ignored_user_out_of_a_dm(
+        #[case] ignored_user_handling: IgnoredUserHandling,
+        #[case] message_count: usize,
+    ) {
+        let options = FinalizeOptions {
+            ignored_users: vec!["U2".to_string()],
+            ignored_user_handling,
+            ..FinalizeOptions::default()
+        };
+        let feature_flags = SlackHttpClientConfigFeatureFlags {
+            get_users: true,
+            get_channel_info: true,
+            get_team_info: false,
+            get_file_data: false,
+        };
+        let (client, requested_urls) = client_with(
+            feature_flags,
+            SlackHttpClientConfigOptions::default(),
+            |url| {
+                Ok(if url.contains("users.") {
+                    json!({
+                        "ok": true,
+                        "user": { "id": "U1", "name": "one" },
+                        "members": [{ "id": "U1", "name": "one" }]
+                    })
+                } else {
+                    json!({
+                        "ok": true,
+                        "channel": { "id": "D1", "is_im": true, "user": "U2" },
+                        "messages": [
+                            { "ts": "1700000000.000100", "user": "U1", "text": "root" },
+                            { "ts": "1700000001.000100", "user": "U2", "text": "reply" }
+                        ]
+                    })
+                })
+            },
+        );
+        let slack_url = SlackUrl::new("https://workspace.slack.com/archives/D1/p1700000000000100")
+            .expect("Should parse the link");
+
+        let components = export_message(client, slack_url, options, 1_700_200_000_000.0)
+            .await
+            .expect("Should export the DM");
+
+        let channel = components.channel.as_ref().expect("Expected the DM");
+        assert_eq!(None, channel.user);
+        assert_eq!(None, channel.user_info);
+        assert_eq!(message_count, components.message_and_thread.thread.len());
+        assert!(components
+            .message_and_thread
+            .thread
+            .iter()
+            .all(|message| message.user.as_deref() != Some("U2")));
+        assert!(!components.users.as_ref().unwrap().contains_key("U2"));
+        assert!(!requested_urls.borrow().iter().any(|url| url.contains("U2")));
+    }
 }
@@ -0,0 +1,106 @@
+use js_sys::Function;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use snafu::{OptionExt, Snafu};
+use std::{
+    cell::{Cell, RefCell},
+    str::FromStr,
+};
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "Provided log level was invalid, expected one of error, warn, info, debug or trace: {level}"
+    ))]
+    InvalidLogLevel { level: String },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+static LOGGER: ObsidianSlackLogger = ObsidianSlackLogger;
+
+thread_local! {
+    /// Called with the level, target and message of each record, see [`set_sink`]
+    static SINK: RefCell<Option<Function>> = const { RefCell::new(None) };
+    static LOG_TO_CONSOLE: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Writes records to the browser console, where `console_log` would, and to the
+/// plugin's log sink when it set one
+struct ObsidianSlackLogger;
+
+impl Log for ObsidianSlackLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if LOG_TO_CONSOLE.with(Cell::get) {
+            console_log::log(record);
+        }
+        SINK.with(|sink| {
+            if let Some(sink) = sink.borrow().as_ref() {
+                // a failing sink can not be logged without calling it again
+                let _ = sink.call3(
+                    &JsValue::NULL,
+                    &JsValue::from_str(record.level().as_str()),
+                    &JsValue::from_str(record.target()),
+                    &JsValue::from_str(&record.args().to_string()),
+                );
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the logger at `level`. Installing it again, e.g. when the plugin is
+/// reloaded, only changes the level
+pub(crate) fn init(level: &str) -> Result<()> {
+    let level = parse_level(level)?;
+    // the logger can only be set once, later calls keep the one already set
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(level.to_level_filter());
+    Ok(())
+}
+
+pub(crate) fn set_level(level: &str) -> Result<()> {
+    log::set_max_level(parse_level(level)?.to_level_filter());
+    Ok(())
+}
+
+fn parse_level(level: &str) -> Result<Level> {
+    Level::from_str(level)
+        .ok()
+        .context(InvalidLogLevelSnafu { level })
+}
+
+/// Logs at another level until dropped, then at the level from before. Meant to
+/// debug a single call, calls running at the same time log at this level too
+pub(crate) struct LogLevelOverride {
+    previous: LevelFilter,
+}
+
+impl LogLevelOverride {
+    pub(crate) fn new(level: &str) -> Result<LogLevelOverride> {
+        let previous = log::max_level();
+        set_level(level)?;
+        Ok(LogLevelOverride { previous })
+    }
+}
+
+impl Drop for LogLevelOverride {
+    fn drop(&mut self) {
+        log::set_max_level(self.previous);
+    }
+}
+
+/// Sends records to `sink` too, or only to it when `log_to_console` is false.
+/// Without a sink records go to the console whatever `log_to_console` is
+pub(crate) fn set_sink(sink: Option<Function>, log_to_console: bool) {
+    LOG_TO_CONSOLE.with(|console| console.set(log_to_console || sink.is_none()));
+    SINK.with(|current| *current.borrow_mut() = sink);
+}
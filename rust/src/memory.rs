@@ -0,0 +1,88 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::messages::Message;
+
+/// Size of a page of wasm memory
+#[cfg(target_arch = "wasm32")]
+const WASM_PAGE_BYTES: usize = 64 * 1024;
+
+/// Memory a channel export may use before the `memory_limit_action` kicks in, when
+/// the `memory_limit_bytes` option is not set
+pub const DEFAULT_MEMORY_LIMIT_BYTES: usize = 512 * 1024 * 1024;
+
+/// What a channel export does when its messages would not fit in the memory limit
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryLimitAction {
+    /// Exports every thread and adds a warning to the result
+    #[default]
+    Warn,
+    /// Exports only the threads that fit and tells where to continue, see
+    /// [`crate::digest::Digest::continue_from`]
+    Chunk,
+}
+
+/// What [`crate::memory_usage`] returns
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct MemoryUsage {
+    /// Size of the wasm memory, which never shrinks
+    pub heap_bytes: usize,
+}
+
+impl MemoryUsage {
+    pub fn current() -> MemoryUsage {
+        MemoryUsage {
+            heap_bytes: heap_bytes(),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn heap_bytes() -> usize {
+    core::arch::wasm32::memory_size(0) * WASM_PAGE_BYTES
+}
+
+/// Outside of wasm there is no linear memory to measure
+#[cfg(not(target_arch = "wasm32"))]
+fn heap_bytes() -> usize {
+    0
+}
+
+/// Rough memory the thread of `root_message` takes once fetched and finalized:
+/// its json size for the root and each reply, times two for the copies made
+/// while finalizing
+pub fn estimate_thread_bytes(root_message: &Message) -> usize {
+    let message_bytes = serde_json::to_vec(root_message)
+        .map(|json| json.len())
+        .unwrap_or_default();
+    let messages = 1 + usize::from(root_message.reply_count.unwrap_or_default());
+    message_bytes * messages * 2
+}
+
+/// Number of `root_messages`, from the first, whose threads fit in `limit_bytes`
+/// next to the memory already in use. At least one, so an export always makes
+/// progress
+pub fn threads_within_limit(
+    root_messages: &[Message],
+    used_bytes: usize,
+    limit_bytes: usize,
+) -> usize {
+    let mut estimated_bytes = used_bytes;
+    root_messages
+        .iter()
+        .take_while(|root_message| {
+            estimated_bytes += estimate_thread_bytes(root_message);
+            estimated_bytes <= limit_bytes
+        })
+        .count()
+        .max(1)
+        .min(root_messages.len())
+}
+
+/// `bytes` in MiB with one decimal, e.g. `12.5 MiB`
+pub(crate) fn format_bytes(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
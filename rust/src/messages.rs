@@ -1,27 +1,42 @@
 use amplify_derive::Display;
+use base64::{engine::general_purpose::STANDARD, Engine};
 use do_notation::m;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use shrinkwraprs::Shrinkwrap;
-use snafu::{ResultExt, Snafu};
+use snafu::{OptionExt, ResultExt, Snafu};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    convert::TryFrom,
     iter::FromIterator,
+    sync::Arc,
 };
 
 use crate::{
-    response::{self, convert_result_string_to_object, SlackResponseValidator},
-    slack_http_client::SlackHttpClient,
+    bots::Bot,
+    finalize_options::FinalizeOptions,
+    folding::{self, FoldStyle},
+    huddles::{HuddleRoom, HuddleSummary},
+    image_metadata,
+    note_links::link_saved_notes,
+    response::{self, PaginatedResponse, Paginator, ResponseMetadata, SlackEnvelope},
+    rich_text::{self, Block},
+    slack_http_client::{SlackHttpClient, TransportResponse, RESPONSE_TOO_LARGE_ERROR},
     slack_url::SlackUrl,
-    users::{CollectUsers, User, UserIds, Users},
+    usergroups::UsergroupMention,
+    users::{CollectUsers, SharedUsers, User, UserDetailLevel, UserIds, Users},
+    utils::{
+        contains_word, extract_hashtags, format_ts_as_date_time, format_ts_as_local_date_time,
+        sanitize_note_title, sanitize_optional_text, sanitize_text, SanitizeText,
+    },
 };
 
 #[derive(Debug, Snafu)]
 pub enum Error {
-    #[snafu(display("When mapping user ids from response to retrieved user info, user id was not in user map. user_id: {user_id} - user_map: {user_map}"))]
-    UserIdNotFoundInUserMap { user_id: String, user_map: String },
+    #[snafu(display("When mapping user ids from response to retrieved user info, user id was not in user map. user_id: {user_id} - users in map: {user_count}"))]
+    UserIdNotFoundInUserMap { user_id: String, user_count: usize },
 
-    #[snafu(display("Awaiting a JsFuture returned an error: {error}"))]
-    WasmErrorFromJsFuture { error: String },
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
 
     #[snafu(display("The message response was not ok. - source: {source}"))]
     InvalidMessageResponse { source: response::Error },
@@ -46,12 +61,29 @@ pub enum Error {
     MessagesNotFoundInMessageResponse { message_response: String },
 
     #[snafu(display("{source}"))]
-    SerdeWasmBindgenCouldNotParseMessageResponse { source: response::Error },
+    CouldNotDeserializeMessageResponse { source: response::Error },
 
     #[snafu(display("{file_url}"))]
     FileDataWasNotString { file_url: String },
+
+    #[snafu(display(
+        "File {name} is {size} bytes, more than the {max_size} bytes of max_file_size_bytes"
+    ))]
+    FileTooLarge {
+        name: String,
+        size: i64,
+        max_size: u64,
+    },
+
+    #[snafu(display(
+        "Stopped downloading file {name} at more than the {max_size} bytes of max_file_size_bytes"
+    ))]
+    DownloadTooLarge { name: String, max_size: u64 },
 }
 
+/// Largest file downloaded by default, see the `max_file_size_bytes` option
+pub const DEFAULT_MAX_FILE_SIZE_BYTES: u64 = 50 * 1024 * 1024;
+
 type Result<T, E = Error> = std::result::Result<T, E>;
 
 pub async fn get_messages_from_api<T>(
@@ -59,42 +91,67 @@ pub async fn get_messages_from_api<T>(
     slack_url: &SlackUrl,
 ) -> Result<MessageAndThread>
 where
-    wasm_bindgen_futures::JsFuture: std::convert::From<T>,
+    T: TransportResponse,
 {
     let thread_ts = slack_url.thread_ts.as_ref().unwrap_or(&slack_url.ts);
-
-    let awaited_val = wasm_bindgen_futures::JsFuture::from(
-        client.get_conversations_replies(&slack_url.channel_id, thread_ts),
-    )
-    .await
-    // mapping error instead of using snafu context because jsvalue is not an Error from parse method
-    .map_err(|err| Error::WasmErrorFromJsFuture {
-        error: format!("{:#?}", err),
-    })?;
-
-    let response = m! {
-        js_obj <- convert_result_string_to_object(awaited_val).context(CouldNotParseJsonFromMessageResponseSnafu);
-        message_response <- response::defined_from_js_object(js_obj).context(SerdeWasmBindgenCouldNotParseMessageResponseSnafu);
-        valid_response <- MessageResponse::validate_response(message_response).context(InvalidMessageResponseSnafu);
-        return valid_response;
-    }?;
-
-    let copy = MessageResponse::copy_from_existing_given_seed_ts(&response, &slack_url.ts);
+    let response = get_thread_from_api(client, &slack_url.channel_id, thread_ts).await?;
 
     Ok(MessageAndThread {
-        message: Messages(
-            copy.messages
-                .expect("Expected messsages but found None, this is a bug"),
-        ),
+        message: Messages(response.data.seed_messages(&slack_url.ts)),
         thread: Messages(
             response
+                .data
                 .messages
                 .expect("Expected messsages but found None, this is a bug"),
         ),
     })
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
+/// Gets every message of the thread started by `thread_ts`, paging through the
+/// replies of long threads. The messages of every page end up in the first one
+pub(crate) async fn get_thread_from_api<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+    thread_ts: &str,
+) -> Result<MessageResponse>
+where
+    T: TransportResponse,
+{
+    let mut thread: Option<MessageResponse> = None;
+    let mut paginator = Paginator::default();
+    loop {
+        let awaited_val = client
+            .get_conversations_replies(channel_id, thread_ts, paginator.cursor())
+            .into_body()
+            .await
+            // mapping error instead of using snafu context because the request's error is a string, not an Error
+            .map_err(|error| Error::RequestFailed { error })?;
+
+        let page: MessageResponse = m! {
+            json <- response::parse_response_body(awaited_val).context(CouldNotParseJsonFromMessageResponseSnafu);
+            message_response <- response::defined_from_json(json).context(CouldNotDeserializeMessageResponseSnafu);
+            valid_response <- client.validate_response(message_response).context(InvalidMessageResponseSnafu);
+            return valid_response;
+        }?;
+
+        let has_next_page = paginator.advance(&page);
+        match thread.as_mut() {
+            Some(thread) => thread
+                .data
+                .messages
+                .get_or_insert_with(Vec::new)
+                .extend(page.data.messages.unwrap_or_default()),
+            None => thread = Some(page),
+        }
+        if !has_next_page {
+            return Ok(
+                thread.expect("Expected the first page of the thread, but got None. This is a bug")
+            );
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Display, PartialEq, Eq)]
 #[display(Debug)]
 pub struct MessageAndThread {
     pub message: Messages,
@@ -132,14 +189,184 @@ impl CollectUsers<Error> for MessageAndThread {
 }
 
 impl MessageAndThread {
+    /// Finalizes the messages in place, fetched at `fetched_at_ms`, unix
+    /// milliseconds. The root message and its replies share one copy of each user,
+    /// see [`SharedUsers`]
     pub fn finalize_message_and_thread(
-        mut message_and_thread: MessageAndThread,
+        &mut self,
         users: Option<&Users>,
-    ) -> Result<MessageAndThread> {
-        message_and_thread.message =
-            Messages::finalize_messages(message_and_thread.message, users)?;
-        message_and_thread.thread = Messages::finalize_messages(message_and_thread.thread, users)?;
-        Ok(message_and_thread)
+        options: &FinalizeOptions,
+        fetched_at_ms: f64,
+    ) -> Result<()> {
+        let users = users.map(SharedUsers::new);
+        self.message
+            .finalize_messages(users.as_ref(), options, fetched_at_ms)?;
+        self.thread
+            .finalize_messages(users.as_ref(), options, fetched_at_ms)
+    }
+
+    /// Merges a newer fetch of the same thread into an existing one, see
+    /// [`Messages::merge_messages`]
+    pub fn merge_message_and_thread(
+        existing: MessageAndThread,
+        newer: MessageAndThread,
+    ) -> MessageAndThread {
+        MessageAndThread {
+            message: Messages::merge_messages(existing.message, newer.message),
+            thread: Messages::merge_messages(existing.thread, newer.thread),
+        }
+    }
+
+    pub fn text_statistics(&self) -> TextStatistics {
+        let (word_count, character_count) = self
+            .thread
+            .iter()
+            .filter_map(|message| message.text.as_ref())
+            .fold((0, 0), |(words, characters), text| {
+                (
+                    words + text.split_whitespace().count(),
+                    characters + text.chars().count(),
+                )
+            });
+        TextStatistics {
+            word_count,
+            character_count,
+            reading_time_minutes: word_count.div_ceil(WORDS_READ_PER_MINUTE),
+        }
+    }
+
+    pub fn sanitize_text(&mut self) {
+        self.message
+            .iter_mut()
+            .chain(self.thread.iter_mut())
+            .for_each(SanitizeText::sanitize_text);
+    }
+
+    /// Tags for the thread: `#hashtags` written in any message, the tags of any
+    /// `keyword_tags` keyword that appears in a message and the tags of any
+    /// `reaction_tags` emoji a message was reacted with
+    pub fn collect_tags(
+        &self,
+        keyword_tags: &HashMap<String, String>,
+        reaction_tags: &HashMap<String, String>,
+    ) -> Tags {
+        let reaction_tags = reaction_tags
+            .iter()
+            .map(|(emoji, tag)| (emoji.trim_matches(':'), tag))
+            .collect::<HashMap<&str, &String>>();
+        let tags_from_reactions = self
+            .thread
+            .iter()
+            .filter_map(|message| message.reactions.as_ref())
+            .flat_map(|reactions| reactions.iter())
+            // reactions with a skin tone are named e.g. `thumbsup::skin-tone-2`
+            .filter_map(|reaction| reaction.name.split("::").next())
+            .filter_map(|name| reaction_tags.get(name))
+            .map(|tag| tag.trim_start_matches('#').to_string());
+        self.thread
+            .iter()
+            .filter_map(|message| message.text.as_ref())
+            .flat_map(|text| {
+                let mut tags = extract_hashtags(text);
+                tags.extend(
+                    keyword_tags
+                        .iter()
+                        .filter(|(keyword, _)| contains_word(text, keyword))
+                        .map(|(_, tag)| tag.trim_start_matches('#').to_string()),
+                );
+                tags
+            })
+            .chain(tags_from_reactions)
+            .filter(|tag| !tag.is_empty())
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Usergroups mentioned anywhere in the thread, once per usergroup
+    pub fn collect_usergroup_mentions(&self) -> Vec<UsergroupMention> {
+        let mut mentions: Vec<UsergroupMention> = vec![];
+        self.thread
+            .iter()
+            .filter_map(|message| message.text.as_ref())
+            .flat_map(|text| UsergroupMention::extract(text))
+            .for_each(|mention| {
+                match mentions
+                    .iter_mut()
+                    .find(|existing| existing.id == mention.id)
+                {
+                    Some(existing) => {
+                        if existing.handle.is_none() {
+                            existing.handle = mention.handle;
+                        }
+                    }
+                    None => mentions.push(mention),
+                }
+            });
+        mentions
+    }
+
+    /// The message that started the thread. Slack returns it first, but it is
+    /// looked up by ts in case it isn't
+    pub fn root_message(&self) -> Option<&Message> {
+        self.thread
+            .iter()
+            .find(|message| message.thread_ts.is_none() || message.thread_ts == message.ts)
+            .or_else(|| self.thread.first())
+    }
+
+    /// Turns the replies of a task thread into checkboxes, ticked for those
+    /// reacted with `emoji`. The root message stays as it is, it is usually what
+    /// the tasks are about
+    pub fn convert_to_tasks(&mut self, emoji: &str) {
+        let emoji = emoji.trim_matches(':');
+        self.thread
+            .iter_mut()
+            .filter(|message| message.thread_ts.is_some() && message.thread_ts != message.ts)
+            .for_each(|message| message.convert_to_task(emoji));
+    }
+
+    /// Puts the thread, fetched oldest first, in `order`
+    pub fn order_thread(&mut self, order: ThreadOrder) {
+        match order {
+            ThreadOrder::OldestFirst => {}
+            ThreadOrder::NewestFirst => self.thread.reverse(),
+            ThreadOrder::RootThenNewest => {
+                let root_index = self.root_message().and_then(|root| {
+                    self.thread
+                        .iter()
+                        .position(|message| std::ptr::eq(message, root))
+                });
+                let last_index = self.thread.len().saturating_sub(1);
+                self.thread.reverse();
+                if let Some(root_index) = root_index {
+                    let root = self.thread.remove(last_index - root_index);
+                    self.thread.insert(0, root);
+                }
+            }
+        }
+    }
+
+    /// Drops the thread messages the filters reject. The root message and the
+    /// linked message are always kept, but lose their reactions like every other
+    /// message when `exclude_reactions` is set
+    pub fn filter(mut self, filters: &MessageFilters) -> MessageAndThread {
+        let kept_ts = self
+            .message
+            .iter()
+            .chain(self.root_message())
+            .filter_map(|message| message.ts.clone())
+            .collect::<HashSet<String>>();
+        self.thread.retain(|message| {
+            message.ts.as_ref().is_some_and(|ts| kept_ts.contains(ts)) || filters.keeps(message)
+        });
+        if filters.exclude_reactions {
+            self.message
+                .iter_mut()
+                .chain(self.thread.iter_mut())
+                .for_each(|message| message.reactions = None);
+        }
+        self
     }
 
     pub fn collect_file_links(&self) -> FileLinks {
@@ -154,42 +381,243 @@ impl MessageAndThread {
             .flatten()
             .collect()
     }
+
+    /// Like [`MessageAndThread::collect_file_links`], but files larger than
+    /// `max_size` bytes are skipped, so the plugin never downloads them
+    pub fn collect_file_links_up_to(&self, max_size: u64) -> (FileLinks, SkippedFiles) {
+        let mut file_links = FileLinks(HashMap::new());
+        let mut skipped_files = SkippedFiles::default();
+        for file in self
+            .thread
+            .iter()
+            .filter_map(|message| message.files.as_ref())
+            .flat_map(|files| files.iter())
+        {
+            match file.check_size(max_size) {
+                Ok(()) => {
+                    file_links
+                        .0
+                        .insert(file.link_name(), file.url_private.clone());
+                }
+                Err(err) => {
+                    log::warn!("rust|collect_file_links_up_to|skipped|{}", err);
+                    skipped_files.0.insert(file.link_name(), err.to_string());
+                }
+            }
+        }
+        (file_links, skipped_files)
+    }
+}
+
+/// Rules for dropping noisy messages from a thread, applied right after it is
+/// fetched so the authors of dropped messages are never requested
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(default)]
+pub struct MessageFilters {
+    /// Drop messages posted by bots and integrations
+    pub exclude_bots: bool,
+    /// Drop messages with any of these subtypes, e.g. `channel_join`
+    pub exclude_subtypes: Vec<String>,
+    /// Keep only messages from these user ids, every user when empty
+    pub only_users: Vec<String>,
+    /// Drop messages with fewer characters of text, ignoring surrounding whitespace
+    pub min_text_length: Option<usize>,
+    /// Drop the reactions of every message
+    pub exclude_reactions: bool,
+}
+
+impl MessageFilters {
+    pub fn is_empty(&self) -> bool {
+        self == &MessageFilters::default()
+    }
+
+    /// Filters that reject what either `self` or `other` rejects
+    pub fn merge(mut self, other: &MessageFilters) -> MessageFilters {
+        self.exclude_bots |= other.exclude_bots;
+        self.exclude_subtypes
+            .extend(other.exclude_subtypes.iter().cloned());
+        self.only_users.extend(other.only_users.iter().cloned());
+        self.min_text_length = self.min_text_length.max(other.min_text_length);
+        self.exclude_reactions |= other.exclude_reactions;
+        self
+    }
+
+    pub fn keeps(&self, message: &Message) -> bool {
+        !(self.exclude_bots && message.is_from_bot())
+            && message
+                .subtype
+                .as_ref()
+                .is_none_or(|subtype| !self.exclude_subtypes.contains(subtype))
+            && (self.only_users.is_empty()
+                || message
+                    .user
+                    .as_ref()
+                    .is_some_and(|user| self.only_users.contains(user)))
+            && self.min_text_length.is_none_or(|min_text_length| {
+                message
+                    .text
+                    .as_deref()
+                    .unwrap_or_default()
+                    .trim()
+                    .chars()
+                    .count()
+                    >= min_text_length
+            })
+    }
+}
+
+/// Reaction with which the author of a thread marks it as not to be archived,
+/// unless overridden by `do_not_archive_emoji`
+pub const DEFAULT_DO_NOT_ARCHIVE_EMOJI: &str = "no_entry_sign";
+
+/// Text of messages that were deleted, see [`Message::is_deleted`]
+pub const DELETED_MESSAGE_TEXT: &str = "_Message deleted_";
+
+/// Warning added to the components when deleted messages were kept as
+/// placeholders, followed by their count, e.g. `deleted_messages=2`
+pub const DELETED_MESSAGES_WARNING: &str = "deleted_messages";
+
+/// Replaces mentions of ignored users, see [`Message::hide_users`]
+const ANONYMOUS_MENTION: &str = "@someone";
+
+/// Subtypes of messages slack posts about the channel rather than the
+/// conversation, e.g. someone joining it
+const CHANNEL_EVENT_SUBTYPES: [&str; 8] = [
+    "channel_join",
+    "channel_leave",
+    "group_join",
+    "group_leave",
+    "bot_add",
+    "bot_remove",
+    "pinned_item",
+    "unpinned_item",
+];
+
+/// Predefined [`MessageFilters`], selectable by name through the options
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterProfile {
+    /// Keeps the thread exactly as it is in slack
+    Faithful,
+    /// Drops channel events such as joins and leaves, and bot messages
+    #[default]
+    Clean,
+    /// Like `Clean`, and drops reactions as well
+    Minimal,
+}
+
+/// What happens to the messages of the users in the `ignored_users` option, e.g.
+/// people who asked not to be archived
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum IgnoredUserHandling {
+    /// Leaves their messages out of the thread
+    #[default]
+    Exclude,
+    /// Keeps their messages without their author
+    Anonymize,
+}
+
+/// Order of the messages in the thread of a saved note, see
+/// [`MessageAndThread::order_thread`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadOrder {
+    /// As in slack, the root message first
+    #[default]
+    OldestFirst,
+    /// The latest reply first, the root message last
+    NewestFirst,
+    /// The root message first, then the replies latest first, for notes that
+    /// follow the latest update of a thread
+    RootThenNewest,
+}
+
+impl FilterProfile {
+    pub fn filters(&self) -> MessageFilters {
+        let clean = || MessageFilters {
+            exclude_bots: true,
+            exclude_subtypes: CHANNEL_EVENT_SUBTYPES
+                .iter()
+                .map(|subtype| subtype.to_string())
+                .collect(),
+            ..Default::default()
+        };
+        match self {
+            FilterProfile::Faithful => MessageFilters::default(),
+            FilterProfile::Clean => clean(),
+            FilterProfile::Minimal => MessageFilters {
+                exclude_reactions: true,
+                ..clean()
+            },
+        }
+    }
 }
 
+/// Average adult silent reading speed, used to estimate reading time
+const WORDS_READ_PER_MINUTE: usize = 200;
+
+/// Size of the thread's text, to help decide whether it is worth summarizing
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct TextStatistics {
+    pub word_count: usize,
+    pub character_count: usize,
+    /// Rounded up, so any text at all takes at least a minute
+    pub reading_time_minutes: usize,
+}
+
+/// Obsidian tags, without the leading `#`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct Tags(pub Vec<String>);
+
+impl FromIterator<String> for Tags {
+    fn from_iter<T: IntoIterator<Item = String>>(iter: T) -> Self {
+        Tags(iter.into_iter().collect())
+    }
+}
+
+pub type MessageResponse = SlackEnvelope<MessageResponseData>;
+
+/// The fields of a `conversations.replies` response, see [`MessageResponse`]
 #[derive(Debug, Serialize, Deserialize, Clone, Display)]
 #[display(Debug)]
-pub struct MessageResponse {
+pub struct MessageResponseData {
     pub messages: Option<Vec<Message>>,
-    pub ok: Option<bool>,
-    pub error: Option<String>,
+    pub response_metadata: Option<ResponseMetadata>,
 }
 
-impl MessageResponse {
-    fn copy_from_existing_given_seed_ts(&self, seed_ts: &str) -> MessageResponse {
-        let mut copy = self.to_owned();
-        copy.messages =
-            Some(
-                copy.messages
-                    .expect("Expected messages to work on, got None. This is a bug")
-                    .into_iter()
-                    .filter(|message| {
-                        message.ts.as_ref().expect(
-                            "Expected message to have a timestamp, but got None. This is a bug",
-                        ) == seed_ts
-                    })
-                    .collect(),
-            );
-        copy
+impl MessageResponseData {
+    /// The messages sent at `seed_ts`, copied without the rest of the response
+    fn seed_messages(&self, seed_ts: &str) -> Vec<Message> {
+        self.messages
+            .as_ref()
+            .expect("Expected messages to work on, got None. This is a bug")
+            .iter()
+            .filter(|message| {
+                message
+                    .ts
+                    .as_ref()
+                    .expect("Expected message to have a timestamp, but got None. This is a bug")
+                    == seed_ts
+            })
+            .cloned()
+            .collect()
     }
 }
 
-impl SlackResponseValidator for MessageResponse {
-    fn ok(&self) -> Option<bool> {
-        self.ok
+impl PaginatedResponse for MessageResponseData {
+    fn response_metadata(&self) -> Option<&ResponseMetadata> {
+        self.response_metadata.as_ref()
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Display, Shrinkwrap, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Display, Shrinkwrap, PartialEq, Eq)]
 #[display(Debug)]
 #[shrinkwrap(mutable)]
 pub struct Messages(pub Vec<Message>);
@@ -199,20 +627,26 @@ impl CollectUsers<Error> for Messages {
         Ok(self
             .iter()
             .map(|message| -> Result<Vec<String>> {
-                let message_user = message.user.as_ref().map_or(
-                    UserIdWasNoneInMessageSnafu {
-                        container: message.to_owned(),
-                    }
-                    .fail(),
-                    |user| Ok(user.to_string()),
-                )?;
+                let message_user =
+                    message
+                        .user
+                        .clone()
+                        .with_context(|| UserIdWasNoneInMessageSnafu {
+                            container: message.to_owned(),
+                        })?;
                 let mut reactions_users = message.reactions.as_ref().map_or(vec![], |reactions| {
                     reactions
                         .iter()
-                        .flat_map(|reaction| reaction.users.clone())
+                        .flat_map(|reaction| reaction.users.iter().cloned())
                         .collect::<Vec<String>>()
                 });
                 reactions_users.push(message_user);
+                reactions_users.extend(
+                    message
+                        .room
+                        .iter()
+                        .flat_map(|room| room.participant_ids().iter().cloned()),
+                );
                 Ok(reactions_users)
             })
             .collect::<Result<Vec<Vec<String>>>>()?
@@ -223,18 +657,108 @@ impl CollectUsers<Error> for Messages {
 }
 
 impl Messages {
-    fn finalize_messages(mut messages: Messages, users: Option<&Users>) -> Result<Messages> {
-        messages = Messages(
-            messages
-                .0
-                .into_iter()
-                .map(|mut message| {
-                    message = Message::finalize_message(message, users)?;
-                    Ok(message)
+    /// Whether the author of the thread's first message marked the thread as not
+    /// to be archived, by reacting to that message with `emoji` or by writing it
+    /// (as `:emoji:`) in any of their messages
+    pub fn is_marked_do_not_archive(&self, emoji: &str) -> bool {
+        let root_message = match self.first() {
+            Some(root_message) => root_message,
+            None => return false,
+        };
+        let author = match root_message.user.as_ref() {
+            Some(author) => author,
+            None => return false,
+        };
+        let emoji = emoji.trim_matches(':');
+        let written_marker = format!(":{}:", emoji);
+        let reacted = root_message
+            .reactions
+            .iter()
+            .flat_map(|reactions| reactions.iter())
+            .filter(|reaction| reaction.name.split("::").next() == Some(emoji))
+            .any(|reaction| reaction.users.contains(author));
+        reacted
+            || self.iter().any(|message| {
+                message.user.as_ref() == Some(author)
+                    && message
+                        .text
+                        .as_deref()
+                        .is_some_and(|text| text.contains(&written_marker))
+            })
+    }
+
+    pub(crate) fn finalize_messages(
+        &mut self,
+        users: Option<&SharedUsers>,
+        options: &FinalizeOptions,
+        fetched_at_ms: f64,
+    ) -> Result<()> {
+        let ignored_users = &options.ignored_users;
+        if options.ignored_user_handling != IgnoredUserHandling::Anonymize {
+            self.0
+                .retain(|message| !message.is_by_any_of(ignored_users));
+        }
+        // first, the users of ignored users are not fetched
+        self.iter_mut().try_for_each(|message| {
+            if !ignored_users.is_empty() {
+                message.hide_users(ignored_users);
+            }
+            message.finalize_message(users, options, fetched_at_ms)
+        })
+    }
+
+    /// Merges `newer` into `existing`. Two messages are the same when their
+    /// `client_msg_id` or their ts match: edits keep the `client_msg_id`, but not
+    /// every message has one (e.g. bot messages, or ones saved before it was kept).
+    /// Messages present in both are replaced by the newer version in their existing
+    /// position and messages only in `newer` are appended, so merging the same fetch
+    /// twice changes nothing. A message with neither is appended only if no equal
+    /// message is there yet. Replaced messages keep their reaction history, see
+    /// [`Message::with_reaction_history_of`]
+    pub fn merge_messages(existing: Messages, newer: Messages) -> Messages {
+        let index_by = |key: fn(&Message) -> Option<&str>| {
+            newer
+                .iter()
+                .enumerate()
+                .filter_map(|(index, message)| key(message).map(|key| (key, index)))
+                .fold(HashMap::new(), |mut indexes, (key, index)| {
+                    indexes.entry(key).or_insert(index);
+                    indexes
                 })
-                .collect::<Result<Vec<Message>>>()?,
-        );
-        Ok(messages)
+        };
+        let by_client_msg_id = index_by(|message| message.client_msg_id.as_deref());
+        let by_ts = index_by(|message| message.ts.as_deref());
+
+        let mut is_merged = vec![false; newer.len()];
+        let mut merged = existing
+            .0
+            .into_iter()
+            .map(|message| {
+                let newer_index = message
+                    .client_msg_id
+                    .as_deref()
+                    .and_then(|id| by_client_msg_id.get(id))
+                    .into_iter()
+                    .chain(message.ts.as_deref().and_then(|ts| by_ts.get(ts)))
+                    .copied()
+                    .find(|&index| !is_merged[index]);
+                match newer_index {
+                    Some(index) => {
+                        is_merged[index] = true;
+                        newer[index].clone().with_reaction_history_of(message)
+                    }
+                    None => message,
+                }
+            })
+            .collect::<Vec<Message>>();
+
+        for (message, is_merged) in newer.0.into_iter().zip(is_merged) {
+            let has_identity = message.client_msg_id.is_some() || message.ts.is_some();
+            if !is_merged && (has_identity || !merged.contains(&message)) {
+                merged.push(message);
+            }
+        }
+        Messages(merged)
     }
 }
 
@@ -242,52 +766,423 @@ impl Messages {
 #[display(Debug)]
 pub struct Message {
     pub r#type: Option<String>,
+    /// e.g. `bot_message` or `channel_join`, None for plain messages
+    pub subtype: Option<String>,
+    /// True for messages slack only keeps as a placeholder, e.g. a deleted message
+    /// that was replied to
+    pub hidden: Option<bool>,
+    pub bot_id: Option<String>,
+    pub client_msg_id: Option<String>,
     pub user: Option<String>,
-    pub user_info: Option<User>,
+    pub user_info: Option<Arc<User>>,
     pub text: Option<String>,
     pub thread_ts: Option<String>,
     pub reply_count: Option<u16>,
     pub ts: Option<String>,
     pub reactions: Option<Reactions>,
     pub files: Option<Files>,
+    /// ISO 639-3 code of the text's language, only set when `detect_language` is
+    /// on and the detection is reliable
+    pub lang: Option<String>,
+    /// The ts in the author's time zone, when `render_local_times` is on and users
+    /// were retrieved
+    pub author_local_time: Option<String>,
+    /// The ts in the reader's time zone, when `render_local_times` is on
+    pub reader_local_time: Option<String>,
+    /// The text with `==highlight==` marks, only when a highlight keyword or
+    /// pattern matched, see [`crate::highlights::Highlighter`]
+    pub highlighted_text: Option<String>,
+    /// Link previews and integration content, e.g. github unfurls
+    pub attachments: Option<Vec<Attachment>>,
+    /// The huddle of a huddle message
+    pub room: Option<HuddleRoom>,
+    /// The huddle's length and participants, for huddle messages
+    pub huddle: Option<HuddleSummary>,
+    /// Reaction counts at each fetch they changed, oldest first, only when
+    /// `track_reaction_history` is on
+    pub reaction_history: Option<Vec<ReactionSnapshot>>,
+    /// True for messages sent with scheduled send rather than live, when slack
+    /// tells. Set as well when any other hint of it is found, see
+    /// [`Message::was_scheduled`]
+    pub is_scheduled: Option<bool>,
+    /// Id of the scheduled message it was sent from, as returned by
+    /// `chat.scheduleMessage`
+    pub scheduled_message_id: Option<String>,
+    /// Name and icon of the bot that wrote the message, only when
+    /// `include_bot_profiles` is on
+    pub bot_info: Option<Bot>,
+    /// Layout blocks of the message, the rich text ones hold its formatting
+    pub blocks: Option<Vec<Block>>,
+    /// The message as markdown, rendered from its rich text blocks. None when it
+    /// has none, `text` is all there is then
+    pub markdown: Option<String>,
+    /// Lines of the text before it was folded away, only for messages longer than
+    /// the `fold_messages_over_lines` option
+    pub folded_line_count: Option<usize>,
+    /// Link to the message in slack, only when `permalink_footnotes` is on
+    pub permalink: Option<String>,
+    /// Whether the task of the message is done, only for replies when
+    /// `task_reaction` is set
+    pub task_done: Option<bool>,
+}
+
+/// The reaction counts of a message when it was fetched
+#[derive(Debug, Serialize, Deserialize, Clone, Display, PartialEq, Eq)]
+#[display(Debug)]
+pub struct ReactionSnapshot {
+    /// RFC 3339 UTC date time of the fetch
+    pub fetched_at: Option<String>,
+    /// Count per reaction name
+    pub counts: BTreeMap<String, u16>,
+}
+
+impl ReactionSnapshot {
+    fn new(reactions: Option<&Reactions>, fetched_at_ms: f64) -> ReactionSnapshot {
+        ReactionSnapshot {
+            fetched_at: format_ts_as_date_time(&((fetched_at_ms / 1000.0) as i64).to_string()),
+            counts: reactions
+                .iter()
+                .flat_map(|reactions| reactions.iter())
+                .map(|reaction| (reaction.name.clone(), reaction.count))
+                .collect(),
+        }
+    }
+}
+
+/// Content slack attached to a message, such as the preview of a link
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Display, PartialEq, Eq)]
+#[display(Debug)]
+pub struct Attachment {
+    /// e.g. `GitHub` or `Jira`
+    pub service_name: Option<String>,
+    pub title: Option<String>,
+    pub title_link: Option<String>,
+    pub from_url: Option<String>,
+    pub original_url: Option<String>,
+    pub text: Option<String>,
+    pub fallback: Option<String>,
+}
+
+impl Attachment {
+    /// The links the attachment is about, most specific first
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        vec![
+            self.title_link.as_deref(),
+            self.from_url.as_deref(),
+            self.original_url.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+impl SanitizeText for Message {
+    fn sanitize_text(&mut self) {
+        sanitize_optional_text(&mut self.text);
+        if let Some(files) = self.files.as_mut() {
+            files.0.iter_mut().for_each(SanitizeText::sanitize_text);
+        }
+    }
+}
+
+#[cfg(feature = "language_detection")]
+fn detect_language(text: &str) -> Option<String> {
+    whatlang::detect(text)
+        .filter(whatlang::Info::is_reliable)
+        .map(|info| info.lang().code().to_string())
+}
+
+#[cfg(not(feature = "language_detection"))]
+fn detect_language(_text: &str) -> Option<String> {
+    None
 }
 
 impl Message {
-    fn finalize_message(mut message: Message, users: Option<&Users>) -> Result<Message> {
-        if let Some(users) = users {
-            let user_id = message
-                .user
-                .as_ref()
-                .expect("expected a user id, got None. This should never happen");
-            message = if let Some(user) = users.get(user_id) {
-                Ok({
-                    message.user_info = Some(user.to_owned());
-                    message
-                })
-            } else {
-                UserIdNotFoundInUserMapSnafu {
-                    user_id,
-                    user_map: format!("{:#?}", users),
-                }
-                .fail()
-            }?;
+    /// Trims the author and the users who reacted, see [`User::trim_to`]
+    pub fn trim_users_to(&mut self, level: UserDetailLevel) {
+        self.user_info
+            .iter_mut()
+            .chain(
+                self.reactions
+                    .iter_mut()
+                    .flat_map(|reactions| reactions.0.iter_mut())
+                    .flat_map(|reaction| reaction.users_info.iter_mut().flatten()),
+            )
+            .for_each(|user| Arc::make_mut(user).trim_to(level));
+    }
 
-            message.reactions = if let Some(reactions) = message.reactions {
-                Some(
-                    reactions
-                        .0
-                        .into_iter()
-                        .map(|reaction| Reaction::finalize_reaction(reaction, users))
-                        .collect::<Result<Reactions>>()?,
+    /// Drops the author and the users who reacted, leaving only their ids
+    pub(crate) fn drop_user_info(&mut self) {
+        self.user_info = None;
+        self.reactions
+            .iter_mut()
+            .flat_map(|reactions| reactions.0.iter_mut())
+            .for_each(|reaction| reaction.users_info = None);
+    }
+
+    /// Fills in the author and the users who reacted by their ids, the opposite of
+    /// [`Message::drop_user_info`]. Users not in `users` are left out
+    pub(crate) fn join_user_info(&mut self, users: &SharedUsers) {
+        self.user_info = self
+            .user
+            .as_deref()
+            .and_then(|user_id| users.get(user_id))
+            .cloned();
+        self.reactions
+            .iter_mut()
+            .flat_map(|reactions| reactions.0.iter_mut())
+            .for_each(|reaction| {
+                reaction.users_info = Some(
+                    reaction
+                        .users
+                        .iter()
+                        .filter_map(|user_id| users.get(user_id))
+                        .cloned()
+                        .collect(),
                 )
-            } else {
-                message.reactions
-            };
-            Ok(message)
-        } else {
-            Ok(message)
+            });
+    }
+
+    fn is_by_any_of(&self, user_ids: &[String]) -> bool {
+        self.user
+            .as_ref()
+            .is_some_and(|user_id| user_ids.contains(user_id))
+    }
+
+    /// Removes every trace of the users: the message's author if it is one of them,
+    /// their reactions and their mentions in the text
+    pub fn hide_users(&mut self, user_ids: &[String]) {
+        if self.is_by_any_of(user_ids) {
+            self.user = None;
+            self.user_info = None;
+            self.author_local_time = None;
+        }
+
+        if let Some(reactions) = self.reactions.take() {
+            let reactions = reactions
+                .0
+                .into_iter()
+                .filter_map(|reaction| reaction.without_users(user_ids))
+                .collect::<Reactions>();
+            self.reactions = Some(reactions).filter(|reactions| !reactions.is_empty());
+        }
+
+        if let Some(room) = self.room.as_mut() {
+            room.participants
+                .iter_mut()
+                .chain(room.participant_history.iter_mut())
+                .for_each(|participants| participants.retain(|id| !user_ids.contains(id)));
+        }
+        if let Some(huddle) = self.huddle.as_mut() {
+            huddle
+                .participants
+                .retain(|participant| !user_ids.contains(&participant.id));
+        }
+
+        if let Some(text) = self.text.as_mut() {
+            user_ids.iter().for_each(|user_id| {
+                *text = text.replace(&format!("<@{}>", user_id), ANONYMOUS_MENTION);
+            });
         }
     }
+
+    /// Whether a bot or an integration posted the message
+    pub fn is_from_bot(&self) -> bool {
+        self.bot_id.is_some() || self.subtype.as_deref() == Some("bot_message")
+    }
+
+    /// First non-empty line of the text, sanitized so it is usable as a note title
+    pub fn title_suggestion(&self) -> Option<String> {
+        self.text
+            .as_ref()?
+            .lines()
+            .map(sanitize_note_title)
+            .find(|line| !line.is_empty())
+    }
+
+    /// Display name of the author, falling back to the user id when user info was
+    /// not retrieved. Bots go by the name slack shows for them, or their bot id
+    pub fn author_name(&self) -> Option<String> {
+        self.user_info
+            .as_deref()
+            .and_then(User::display_name)
+            .or_else(|| self.bot_info.as_ref().map(Bot::display_name))
+            .or_else(|| self.user.clone())
+            .or_else(|| self.bot_id.clone())
+    }
+
+    /// Folds the text, and the markdown of the rich text, of a message longer than
+    /// `max_lines`. The number of lines before folding is kept in
+    /// [`Message::folded_line_count`]
+    pub fn fold_long_text(&mut self, max_lines: usize, style: FoldStyle) {
+        self.folded_line_count = self
+            .text
+            .iter()
+            .chain(self.markdown.iter())
+            .map(|text| text.lines().count())
+            .filter(|line_count| *line_count > max_lines)
+            .max();
+        for text in self.text.iter_mut().chain(self.markdown.iter_mut()) {
+            if let Some(folded) = folding::fold(text, max_lines, style) {
+                *text = folded;
+            }
+        }
+    }
+
+    fn convert_to_task(&mut self, emoji: &str) {
+        let done = self
+            .reactions
+            .iter()
+            .flat_map(|reactions| reactions.iter())
+            .any(|reaction| reaction.name == emoji);
+        let checkbox = if done { "- [x]" } else { "- [ ]" };
+        for text in self.text.iter_mut().chain(self.markdown.iter_mut()) {
+            // continuation lines are indented to stay within the list item
+            *text = format!("{} {}", checkbox, text.replace('\n', "\n  "));
+        }
+        self.task_done = Some(done);
+    }
+
+    /// Link to the message in the channel of `channel_link`, see
+    /// [`crate::slack_url::SlackUrl::channel_link`]. Replies link into their thread
+    pub fn permalink_in(&self, channel_link: &str) -> Option<String> {
+        let ts = self.ts.as_deref()?;
+        let link = format!("{}/p{}", channel_link, ts.replace('.', ""));
+        Some(
+            match self
+                .thread_ts
+                .as_deref()
+                .filter(|thread_ts| *thread_ts != ts)
+            {
+                Some(thread_ts) => format!("{}?thread_ts={}", link, thread_ts),
+                None => link,
+            },
+        )
+    }
+
+    /// Ends the text, and the markdown of the rich text, with a footnote linking
+    /// to the message in slack. The footnote is named after the ts, so it is
+    /// unique within the note
+    pub fn add_permalink_footnote(&mut self, channel_link: &str) {
+        let (permalink, ts) = match (self.permalink_in(channel_link), self.ts.as_deref()) {
+            (Some(permalink), Some(ts)) => (permalink, ts.replace('.', "")),
+            _ => return,
+        };
+        for text in self.text.iter_mut().chain(self.markdown.iter_mut()) {
+            text.push_str(&format!(" [^{}]\n\n[^{}]: {}", ts, ts, permalink));
+        }
+        self.permalink = Some(permalink);
+    }
+
+    /// Whether the message was deleted and slack only kept a placeholder of it,
+    /// a `tombstone` or a hidden message
+    pub fn is_deleted(&self) -> bool {
+        self.subtype.as_deref() == Some("tombstone") || self.hidden == Some(true)
+    }
+
+    /// Whether slack left any hint that the message was sent with scheduled send
+    pub fn was_scheduled(&self) -> bool {
+        self.is_scheduled == Some(true) || self.scheduled_message_id.is_some()
+    }
+
+    /// The ts as an RFC 3339 UTC date time
+    pub fn date(&self) -> Option<String> {
+        self.ts.as_deref().and_then(format_ts_as_date_time)
+    }
+
+    /// This message with the reaction history of an earlier fetch of it in front of
+    /// its own. Snapshots already in the history, or with the same counts as the
+    /// latest one, are left out so only changes are recorded
+    pub fn with_reaction_history_of(mut self, existing: Message) -> Message {
+        let mut history = existing.reaction_history.unwrap_or_default();
+        for snapshot in self.reaction_history.take().unwrap_or_default() {
+            let changed = history
+                .last()
+                .is_none_or(|latest| latest.counts != snapshot.counts);
+            if changed && !history.contains(&snapshot) {
+                history.push(snapshot);
+            }
+        }
+        self.reaction_history = Some(history).filter(|history| !history.is_empty());
+        self
+    }
+
+    pub(crate) fn finalize_message(
+        &mut self,
+        users: Option<&SharedUsers>,
+        options: &FinalizeOptions,
+        fetched_at_ms: f64,
+    ) -> Result<()> {
+        if !options.saved_notes.is_empty() {
+            self.text = self
+                .text
+                .as_deref()
+                .map(|text| link_saved_notes(text, &options.saved_notes));
+        }
+
+        if self.is_deleted() {
+            self.text = Some(DELETED_MESSAGE_TEXT.to_string());
+            self.blocks = None;
+            self.attachments = None;
+        }
+
+        if let Some(room) = self.room.as_ref() {
+            let huddle = HuddleSummary::new(room, users.map(SharedUsers::users));
+            if self.text.as_deref().unwrap_or_default().trim().is_empty() {
+                self.text = Some(huddle.line.clone());
+            }
+            self.huddle = Some(huddle);
+        }
+
+        self.markdown = self.blocks.as_deref().and_then(rich_text::to_markdown);
+
+        if self.was_scheduled() {
+            self.is_scheduled = Some(true);
+        }
+
+        if options.track_reaction_history {
+            self.reaction_history = Some(vec![ReactionSnapshot::new(
+                self.reactions.as_ref(),
+                fetched_at_ms,
+            )]);
+        }
+
+        if options.detect_language {
+            self.lang = self.text.as_deref().and_then(detect_language);
+        }
+
+        if options.render_local_times {
+            if let Some(ts) = self.ts.as_deref() {
+                self.author_local_time = users
+                    .zip(self.user.as_ref())
+                    .and_then(|(users, user_id)| users.get(user_id))
+                    .and_then(|user| user.tz_offset)
+                    .and_then(|tz_offset| format_ts_as_local_date_time(ts, tz_offset));
+                self.reader_local_time =
+                    format_ts_as_local_date_time(ts, options.reader_tz_offset.unwrap_or_default());
+            }
+        }
+
+        if let Some(users) = users {
+            // messages of ignored users may be kept without their author
+            if let Some(user_id) = self.user.as_ref() {
+                self.user_info = Some(Arc::clone(users.get(user_id).with_context(|| {
+                    UserIdNotFoundInUserMapSnafu {
+                        user_id,
+                        user_count: users.users().len(),
+                    }
+                })?));
+            }
+
+            if let Some(reactions) = self.reactions.as_mut() {
+                reactions
+                    .0
+                    .iter_mut()
+                    .try_for_each(|reaction| reaction.finalize_reaction(users))?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display, Shrinkwrap)]
@@ -305,29 +1200,39 @@ impl FromIterator<Reaction> for Reactions {
 pub struct Reaction {
     pub name: String,
     pub users: Vec<String>,
-    pub users_info: Option<Vec<User>>,
+    pub users_info: Option<Vec<Arc<User>>>,
     pub count: u16,
 }
 
 impl Reaction {
-    fn finalize_reaction(mut reaction: Reaction, users: &Users) -> Result<Reaction> {
-        reaction.users_info = Some({
-            reaction
-                .users
+    /// None when only these users reacted
+    fn without_users(mut self, user_ids: &[String]) -> Option<Reaction> {
+        let reacted = self.users.len();
+        self.users.retain(|user_id| !user_ids.contains(user_id));
+        if let Some(users_info) = self.users_info.as_mut() {
+            users_info.retain(|user| !user_ids.contains(&user.id));
+        }
+        let removed = u16::try_from(reacted - self.users.len()).unwrap_or(u16::MAX);
+        self.count = self.count.saturating_sub(removed);
+        Some(self).filter(|reaction| reaction.count > 0)
+    }
+
+    fn finalize_reaction(&mut self, users: &SharedUsers) -> Result<()> {
+        self.users_info = Some(
+            self.users
                 .iter()
                 .map(|user_id| {
-                    users.get(user_id).map_or(
-                        UserIdNotFoundInUserMapSnafu {
+                    users
+                        .get(user_id)
+                        .cloned()
+                        .with_context(|| UserIdNotFoundInUserMapSnafu {
                             user_id,
-                            user_map: format!("{:#?}", users),
-                        }
-                        .fail(),
-                        |user| Ok(user.clone()),
-                    )
+                            user_count: users.users().len(),
+                        })
                 })
-                .collect::<Result<Vec<User>>>()?
-        });
-        Ok(reaction)
+                .collect::<Result<Vec<Arc<User>>>>()?,
+        );
+        Ok(())
     }
 }
 
@@ -344,19 +1249,42 @@ impl FromIterator<File> for Files {
 impl Files {
     pub fn collect_file_links(&self) -> FileLinks {
         self.iter()
-            .map(|file| {
-                let user_team = file.user_team.clone();
-                let file_id = file.id.clone();
-                let title = file.title.clone();
-                (
-                    format!("{user_team}-{file_id}-{title}"),
-                    file.url_private.clone(),
-                )
-            })
+            .map(|file| (file.link_name(), file.url_private.clone()))
             .collect()
     }
 }
 
+impl File {
+    /// Name the file is saved under, the key of [`FileLinks`]
+    pub fn link_name(&self) -> String {
+        format!("{}-{}-{}", self.user_team, self.id, self.title)
+    }
+
+    /// Fails with [`Error::FileTooLarge`] if slack says the file is larger than
+    /// `max_size` bytes
+    pub fn check_size(&self, max_size: u64) -> Result<()> {
+        match u64::try_from(self.size) {
+            Ok(size) if size > max_size => FileTooLargeSnafu {
+                name: self.name.clone(),
+                size: self.size,
+                max_size,
+            }
+            .fail(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl SanitizeText for File {
+    fn sanitize_text(&mut self) {
+        self.name = sanitize_text(&self.name);
+        self.title = sanitize_text(&self.title);
+    }
+}
+
+/// Url of each file, by the name it is saved under. A file deduped by the
+/// `dedupe_file_data` option has the name of the saved one with its content
+/// instead
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display, Shrinkwrap)]
 #[display(Debug)]
 pub struct FileLinks(pub HashMap<String, String>);
@@ -367,6 +1295,102 @@ impl FromIterator<(String, String)> for FileLinks {
     }
 }
 
+/// Contents of the files of [`FileLinks`], by the same names
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct FilesData(pub BTreeMap<String, FileData>);
+
+impl FilesData {
+    /// Removes the metadata of the images, see [`image_metadata::strip_metadata`]
+    pub fn strip_image_metadata(mut self) -> FilesData {
+        self.0
+            .values_mut()
+            .for_each(|data| data.0 = image_metadata::strip_metadata(&data.0));
+        self
+    }
+}
+
+/// Bytes of a file, base64 encoded in json
+#[derive(Clone, Debug, PartialEq, Eq, Shrinkwrap)]
+pub struct FileData(pub Vec<u8>);
+
+impl Serialize for FileData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for FileData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD
+            .decode(encoded)
+            .map(FileData)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Downloads the files of `file_links`. A file that could not be downloaded is
+/// skipped, so the rest of the thread is still saved. Slack's size of a file can
+/// be off, so a download is stopped and skipped too once it is larger than
+/// `max_size` bytes, see [`crate::slack_http_client::RequestUrlParam::max_response_bytes`]
+pub async fn get_files_data_from_api<T>(
+    client: &SlackHttpClient<T>,
+    file_links: &FileLinks,
+    max_size: u64,
+) -> (FilesData, SkippedFiles)
+where
+    T: TransportResponse,
+{
+    let mut files_data = FilesData::default();
+    let mut skipped_files = SkippedFiles::default();
+    for (name, url) in file_links.iter() {
+        let data = match client.get_file(url, max_size).into_bytes().await {
+            Ok(data) => data,
+            Err(error) => {
+                let err = if error.contains(RESPONSE_TOO_LARGE_ERROR) {
+                    DownloadTooLargeSnafu {
+                        name: name.clone(),
+                        max_size,
+                    }
+                    .build()
+                } else {
+                    Error::RequestFailed { error }
+                };
+                log::warn!("rust|get_files_data_from_api|skipped|{}", err);
+                skipped_files.0.insert(name.clone(), err.to_string());
+                continue;
+            }
+        };
+        // a request_func that does not check max_response_bytes read it all
+        if data.len() as u64 > max_size {
+            let err = FileTooLargeSnafu {
+                name: name.clone(),
+                size: data.len() as i64,
+                max_size,
+            }
+            .build();
+            log::warn!("rust|get_files_data_from_api|skipped|{}", err);
+            skipped_files.0.insert(name.clone(), err.to_string());
+        } else {
+            files_data.0.insert(name.clone(), FileData(data));
+        }
+    }
+    (files_data, skipped_files)
+}
+
+/// Files left out of [`FileLinks`], by the name they would be saved under, with
+/// why they were skipped
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct SkippedFiles(pub BTreeMap<String, String>);
+
+impl FromIterator<(String, String)> for SkippedFiles {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        SkippedFiles(iter.into_iter().collect())
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
 #[display(Debug)]
 pub struct File {
@@ -382,3 +1406,320 @@ pub struct File {
     pub permalink: String,
     pub permalink_public: String,
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! The plugin downloads every file in `file_links` into the vault. Word
+    //! counts are of the text as slack sends it, markup included
+
+    use std::{cell::RefCell, rc::Rc};
+
+    use futures::future::{self, FutureExt, LocalBoxFuture};
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use super::{
+        get_files_data_from_api, get_messages_from_api, Error, File, Files, FilesData, Message,
+        MessageAndThread, Messages, TextStatistics,
+    };
+    use crate::{
+        slack_http_client::{
+            RequestUrlParam, ResponseType, SlackHttpClient, SlackHttpClientConfigFeatureFlags,
+            SlackHttpClientConfigOptions, RESPONSE_TOO_LARGE_ERROR,
+        },
+        slack_url::SlackUrl,
+        test_support::{self, client},
+    };
+
+    type Response = LocalBoxFuture<'static, Result<Vec<u8>, String>>;
+
+    /// Start of a png: its signature, which is not utf-8, and its header
+    const PNG: [u8; 24] = [
+        0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0, 0, 0, 0x0d, b'I', b'H', b'D', b'R', 0,
+        0, 0, 1, 0, 0, 0, 1,
+    ];
+
+    fn file(id: &str, size: i64) -> File {
+        File {
+            id: id.to_string(),
+            name: format!("{}.png", id),
+            title: id.to_string(),
+            user_team: "T1".to_string(),
+            mimetype: "image/png".to_string(),
+            filetype: "png".to_string(),
+            size,
+            url_private: format!("https://files.slack.com/{}.png", id),
+            url_private_download: format!("https://files.slack.com/download/{}.png", id),
+            permalink: String::new(),
+            permalink_public: String::new(),
+        }
+    }
+
+    fn thread(files: Vec<File>) -> MessageAndThread {
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "type": "message",
+            "ts": "1700000000.000100",
+            "text": "screenshots",
+        }))
+        .expect("Should parse message");
+        MessageAndThread {
+            message: Messages(vec![message.clone()]),
+            thread: Messages(vec![Message {
+                files: Some(Files(files)),
+                ..message
+            }]),
+        }
+    }
+
+    #[test]
+    fn files_larger_than_the_limit_are_skipped_instead_of_linked() {
+        let thread = thread(vec![file("F1", 1_000), file("F2", 1_001)]);
+
+        let (file_links, skipped_files) = thread.collect_file_links_up_to(1_000);
+
+        assert_eq!(
+            vec!["T1-F1-F1"],
+            file_links.keys().map(String::as_str).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["T1-F2-F2"],
+            skipped_files.keys().map(String::as_str).collect::<Vec<_>>()
+        );
+        assert!(skipped_files["T1-F2-F2"].contains("1001 bytes"));
+    }
+
+    #[test]
+    fn check_size_fails_with_a_typed_error() {
+        let err = file("F1", 2_000).check_size(1_000).unwrap_err();
+
+        assert!(matches!(
+            err,
+            Error::FileTooLarge {
+                size: 2_000,
+                max_size: 1_000,
+                ..
+            }
+        ));
+        assert!(file("F1", 1_000).check_size(1_000).is_ok());
+    }
+
+    /// A client answering every request with `body`, and the requests it sent.
+    /// Like the fetch transport it rejects a body over the request's
+    /// `max_response_bytes`
+    fn file_client(
+        body: Vec<u8>,
+    ) -> (SlackHttpClient<Response>, Rc<RefCell<Vec<RequestUrlParam>>>) {
+        client_checking_size(body, true)
+    }
+
+    fn client_checking_size(
+        body: Vec<u8>,
+        checks_size: bool,
+    ) -> (SlackHttpClient<Response>, Rc<RefCell<Vec<RequestUrlParam>>>) {
+        let requests = Rc::new(RefCell::new(vec![]));
+        let client_requests = requests.clone();
+        let request_func = move |params: RequestUrlParam| -> Response {
+            let too_large = params
+                .max_response_bytes()
+                .is_some_and(|max_bytes| checks_size && body.len() as u64 > max_bytes);
+            client_requests.borrow_mut().push(params);
+            if too_large {
+                let error = format!("{}: {} bytes", RESPONSE_TOO_LARGE_ERROR, body.len());
+                return future::ready(Err(error)).boxed_local();
+            }
+            future::ready(Ok(body.clone())).boxed_local()
+        };
+        (client_sending(request_func), requests)
+    }
+
+    /// A client downloading files with `request_func`
+    fn client_sending(
+        request_func: impl Fn(RequestUrlParam) -> Response + 'static,
+    ) -> SlackHttpClient<Response> {
+        let feature_flags = SlackHttpClientConfigFeatureFlags {
+            get_users: false,
+            get_channel_info: false,
+            get_team_info: false,
+            get_file_data: true,
+        };
+        test_support::client_sending(
+            feature_flags,
+            SlackHttpClientConfigOptions::default(),
+            request_func,
+        )
+    }
+
+    #[async_std::test]
+    async fn png_files_survive_the_download_and_json() {
+        let (client, requests) = file_client(PNG.to_vec());
+        let file_links = Files(vec![file("F1", PNG.len() as i64)]).collect_file_links();
+
+        let (files_data, skipped_files) =
+            get_files_data_from_api(&client, &file_links, 1_000).await;
+
+        assert!(skipped_files.is_empty());
+        let json = serde_json::to_value(&files_data).unwrap();
+        assert_eq!(
+            "iVBORw0KGgoAAAANSUhEUgAAAAEAAAAB",
+            json["T1-F1-F1"].as_str().unwrap()
+        );
+        let round_tripped: FilesData = serde_json::from_value(json).unwrap();
+        assert_eq!(PNG.to_vec(), round_tripped["T1-F1-F1"].0);
+        let requests = requests.borrow();
+        assert_eq!("https://files.slack.com/F1.png", requests[0].url());
+        assert_eq!(ResponseType::ArrayBuffer, requests[0].response_type());
+        assert_eq!(Some(1_000), requests[0].max_response_bytes());
+    }
+
+    #[async_std::test]
+    async fn downloads_larger_than_the_limit_are_stopped_and_skipped() {
+        let (client, requests) = file_client(PNG.to_vec());
+        // slack's size is off, the download is larger
+        let file_links = Files(vec![file("F1", 1)]).collect_file_links();
+
+        let (files_data, skipped_files) = get_files_data_from_api(&client, &file_links, 10).await;
+
+        assert!(files_data.is_empty());
+        assert_eq!(Some(10), requests.borrow()[0].max_response_bytes());
+        assert_eq!(
+            "Stopped downloading file T1-F1-F1 at more than the 10 bytes of max_file_size_bytes",
+            skipped_files["T1-F1-F1"]
+        );
+    }
+
+    #[async_std::test]
+    async fn files_that_could_not_be_downloaded_are_skipped() {
+        let requests = Rc::new(RefCell::new(0));
+        let client_requests = requests.clone();
+        let client = client_sending(move |_| {
+            *client_requests.borrow_mut() += 1;
+            if *client_requests.borrow() == 1 {
+                future::ready(Err("Request failed, status 404".to_string())).boxed_local()
+            } else {
+                future::ready(Ok(PNG.to_vec())).boxed_local()
+            }
+        });
+        let file_links = Files(vec![file("F1", 1), file("F2", 1)]).collect_file_links();
+
+        let (files_data, skipped_files) =
+            get_files_data_from_api(&client, &file_links, 1_000).await;
+
+        assert_eq!(2, *requests.borrow());
+        assert_eq!(1, files_data.len());
+        assert_eq!(1, skipped_files.len());
+        let (skipped, reason) = skipped_files.iter().next().unwrap();
+        assert!(!files_data.contains_key(skipped));
+        assert_eq!(
+            "The request to slack failed: Request failed, status 404",
+            reason
+        );
+    }
+
+    #[async_std::test]
+    async fn downloads_larger_than_the_limit_are_skipped_when_read_whole() {
+        let (client, _) = client_checking_size(PNG.to_vec(), false);
+        let file_links = Files(vec![file("F1", 1)]).collect_file_links();
+
+        let (files_data, skipped_files) = get_files_data_from_api(&client, &file_links, 10).await;
+
+        assert!(files_data.is_empty());
+        assert!(skipped_files["T1-F1-F1"].contains("24 bytes"));
+    }
+
+    #[async_std::test]
+    async fn get_messages_from_api_pages_through_long_threads() {
+        let (client, requested_urls) = client(SlackHttpClientConfigOptions::default(), |url| {
+            Ok(if url.contains("cursor=page2") {
+                json!({
+                    "ok": true,
+                    "messages": [{ "ts": "1700000200.000100", "user": "U2", "text": "last" }],
+                    "response_metadata": { "next_cursor": "" }
+                })
+            } else {
+                json!({
+                    "ok": true,
+                    "messages": [
+                        { "ts": "1700000000.000100", "user": "U1", "text": "root" },
+                        { "ts": "1700000100.000100", "user": "U2", "text": "first" }
+                    ],
+                    "response_metadata": { "next_cursor": "page2" }
+                })
+            })
+        });
+        let slack_url = SlackUrl::new("https://workspace.slack.com/archives/C1/p1700000000000100")
+            .expect("Should parse the link");
+
+        let message_and_thread = get_messages_from_api(&client, &slack_url)
+            .await
+            .expect("Should get the thread");
+
+        assert_eq!(
+            vec!["root", "first", "last"],
+            message_and_thread
+                .thread
+                .iter()
+                .filter_map(|message| message.text.as_deref())
+                .collect::<Vec<&str>>()
+        );
+        assert_eq!(1, message_and_thread.message.len());
+        assert_eq!(2, requested_urls.borrow().len());
+    }
+
+    fn thread_of(messages: Value) -> MessageAndThread {
+        serde_json::from_value(json!({ "message": [], "thread": messages }))
+            .expect("Expected a valid thread")
+    }
+
+    fn statistics(
+        word_count: usize,
+        character_count: usize,
+        reading_time_minutes: usize,
+    ) -> TextStatistics {
+        TextStatistics {
+            word_count,
+            character_count,
+            reading_time_minutes,
+        }
+    }
+
+    #[rstest]
+    #[case::no_messages(json!([]), statistics(0, 0, 0))]
+    #[case::empty_text(json!([{ "type": "message", "text": "" }]), statistics(0, 0, 0))]
+    #[case::no_text(json!([{ "type": "message" }]), statistics(0, 0, 0))]
+    #[case::mrkdwn_and_mention(
+        json!([{ "type": "message", "text": "Hi <@U123ABC>, see *bold* _it_" }]),
+        statistics(5, 30, 1)
+    )]
+    #[case::link_and_whitespace(
+        json!([{ "type": "message", "text": "  <https://example.com|a link>  " }]),
+        statistics(2, 32, 1)
+    )]
+    #[case::every_message(
+        json!([
+            { "type": "message", "text": "Hi <@U123ABC>, see *bold* _it_" },
+            { "type": "message", "text": "" },
+            { "type": "message", "text": "  <https://example.com|a link>  " },
+        ]),
+        statistics(7, 62, 1)
+    )]
+    fn text_statistics_count_the_text_of_the_thread(
+        #[case] messages: Value,
+        #[case] expected: TextStatistics,
+    ) {
+        assert_eq!(expected, thread_of(messages).text_statistics());
+    }
+
+    #[test]
+    fn reading_time_is_rounded_up_to_the_next_minute() {
+        let text = |words: usize| vec!["word"; words].join(" ");
+        let at = |words: usize| {
+            thread_of(json!([{ "type": "message", "text": text(words) }]))
+                .text_statistics()
+                .reading_time_minutes
+        };
+
+        assert_eq!(1, at(1));
+        assert_eq!(1, at(200));
+        assert_eq!(2, at(201));
+    }
+}
@@ -0,0 +1,118 @@
+/// Link schemes slack wraps in angle brackets that [`unwrap_links`] turns into
+/// markdown links
+const LINK_SCHEMES: [&str; 4] = ["https://", "http://", "mailto:", "tel:"];
+
+/// Replaces slack links, `<https://...|label>` and `<https://...>`, with markdown
+/// links, `[label](https://...)`, and bare links. `mailto:` and `tel:` links
+/// without a label are named after their address or number. Mentions such as
+/// `<@U123>` and `<!here>` are left as they are
+pub fn unwrap_links(text: &str) -> String {
+    let mut unwrapped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, end)) = rest
+        .find('<')
+        .and_then(|start| rest[start..].find('>').map(|end| (start, start + end)))
+    {
+        unwrapped.push_str(&rest[..start]);
+        let (target, label) = match rest[start + 1..end].split_once('|') {
+            Some((target, label)) => (target, Some(label)),
+            None => (&rest[start + 1..end], None),
+        };
+        match LINK_SCHEMES
+            .iter()
+            .find(|scheme| target.starts_with(*scheme))
+        {
+            Some(scheme) => unwrapped.push_str(&markdown_link(target, scheme, label)),
+            None => unwrapped.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    unwrapped.push_str(rest);
+    unwrapped
+}
+
+fn markdown_link(target: &str, scheme: &str, label: Option<&str>) -> String {
+    // slack escapes `&` in the text it sends, links included
+    let url = target.replace("&amp;", "&");
+    let label = match label.filter(|label| !label.is_empty()) {
+        Some(label) => Some(label),
+        None if scheme.starts_with("http") => None,
+        None => Some(&target[scheme.len()..]),
+    };
+    match label {
+        Some(label) => format!("[{}]({})", escape_label(label), link_destination(&url)),
+        None => url,
+    }
+}
+
+/// `[` and `]` would end the label early
+fn escape_label(label: &str) -> String {
+    label.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// Spaces and parentheses would end the destination early, within angle brackets
+/// they do not
+fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of turning slack links into markdown, run with `cargo test`
+
+    use crate::mrkdwn::unwrap_links;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::labelled(
+        "see <https://example.com|the docs>",
+        "see [the docs](https://example.com)"
+    )]
+    #[case::bare("see <https://example.com/a>", "see https://example.com/a")]
+    #[case::http("<http://example.com|old site>", "[old site](http://example.com)")]
+    #[case::empty_label("<https://example.com|>", "https://example.com")]
+    #[case::escaped_ampersand(
+        "<https://example.com/?a=1&amp;b=2|query>",
+        "[query](https://example.com/?a=1&b=2)"
+    )]
+    #[case::brackets_in_label(
+        "<https://example.com|[draft] plan>",
+        "[\\[draft\\] plan](https://example.com)"
+    )]
+    #[case::parentheses_in_url(
+        "<https://en.wikipedia.org/wiki/Rust_(language)|Rust>",
+        "[Rust](<https://en.wikipedia.org/wiki/Rust_(language)>)"
+    )]
+    #[case::mailto(
+        "<mailto:alice@example.com|Alice>",
+        "[Alice](mailto:alice@example.com)"
+    )]
+    #[case::bare_mailto(
+        "<mailto:alice@example.com>",
+        "[alice@example.com](mailto:alice@example.com)"
+    )]
+    #[case::tel("<tel:+15551234567|call us>", "[call us](tel:+15551234567)")]
+    #[case::bare_tel("<tel:+15551234567>", "[+15551234567](tel:+15551234567)")]
+    #[case::several(
+        "<https://a.example|a> and <https://b.example>",
+        "[a](https://a.example) and https://b.example"
+    )]
+    fn unwraps_links(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(expected, unwrap_links(text));
+    }
+
+    #[rstest]
+    #[case::user_mention("<@U0ALICE> said hi")]
+    #[case::channel_mention("moved to <#C0GENERAL|general>")]
+    #[case::broadcast("<!here> deploy is done")]
+    #[case::usergroup("<!subteam^S0TEAM|@team> please review")]
+    #[case::unclosed("a < b and <https://example.com")]
+    #[case::plain("no links at all")]
+    fn leaves_everything_else_as_it_is(#[case] text: &str) {
+        assert_eq!(text, unwrap_links(text));
+    }
+}
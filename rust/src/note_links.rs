@@ -0,0 +1,63 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::slack_url::SlackUrl;
+
+/// A thread already saved to the vault, as listed by the plugin in the
+/// `saved_notes` option
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct SavedNote {
+    pub channel_id: String,
+    /// ts of the thread's root message, e.g. `1700000000.000100`
+    pub thread_ts: String,
+    /// The note as obsidian links to it, e.g. `Slack/Deploy is blocked`
+    pub note: String,
+}
+
+impl SavedNote {
+    /// Whether the link points to the thread or any message in it
+    fn is_linked_by(&self, slack_url: &SlackUrl) -> bool {
+        self.channel_id == slack_url.channel_id
+            && slack_url.thread_ts.as_ref().unwrap_or(&slack_url.ts) == &self.thread_ts
+    }
+}
+
+/// Replaces slack links to saved threads, `<https://...|label>`, with wiki links to
+/// their notes, `[[note|label]]`, so the vault links its notes to each other.
+/// Links to threads that were not saved are left as they are
+pub fn link_saved_notes(text: &str, saved_notes: &[SavedNote]) -> String {
+    let mut linked = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, end)) = rest
+        .find('<')
+        .and_then(|start| rest[start..].find('>').map(|end| (start, start + end)))
+    {
+        linked.push_str(&rest[..start]);
+        let markup = &rest[start..=end];
+        let (target, label) = match rest[start + 1..end].split_once('|') {
+            Some((target, label)) => (target, Some(label)),
+            None => (&rest[start + 1..end], None),
+        };
+        let saved_note = SlackUrl::new(target).ok().and_then(|slack_url| {
+            saved_notes
+                .iter()
+                .find(|saved_note| saved_note.is_linked_by(&slack_url))
+        });
+        match saved_note {
+            Some(saved_note) => linked.push_str(&wiki_link(&saved_note.note, label)),
+            None => linked.push_str(markup),
+        }
+        rest = &rest[end + 1..];
+    }
+    linked.push_str(rest);
+    linked
+}
+
+fn wiki_link(note: &str, label: Option<&str>) -> String {
+    // `[`, `]` and `|` would end the wiki link early
+    match label.map(|label| label.replace(['[', ']', '|'], "")) {
+        Some(label) if !label.is_empty() => format!("[[{}|{}]]", note, label),
+        _ => format!("[[{}]]", note),
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{components::ObsidianSlackComponents, messages::Message};
+
+/// The thread as an org-mode document: a headline for the root message with one
+/// below it for each reply, and the metadata of each message in its properties
+/// drawer
+pub fn render_org(components: &ObsidianSlackComponents) -> String {
+    let title = components
+        .title_suggestion
+        .clone()
+        .unwrap_or_else(|| components.file_name.0.clone());
+    let mut lines = vec![format!("#+TITLE: {}", single_line(&title))];
+    if let Some(date) = components.root_date.as_deref() {
+        lines.push(format!("#+DATE: {}", date));
+    }
+
+    let root_ts = components
+        .message_and_thread
+        .root_message()
+        .and_then(|message| message.ts.clone());
+    for message in components.message_and_thread.thread.iter() {
+        let level = if message.ts == root_ts { 1 } else { 2 };
+        lines.push(String::new());
+        lines.extend(headline(message, level));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn headline(message: &Message, level: usize) -> Vec<String> {
+    let author = message.author_name().unwrap_or_default();
+    let title = match message.date() {
+        Some(date) => format!("{} {}", author, date),
+        None => author,
+    };
+    let mut lines = vec![format!("{} {}", "*".repeat(level), single_line(&title))];
+
+    let reactions = message
+        .reactions
+        .iter()
+        .flat_map(|reactions| reactions.iter())
+        .map(|reaction| format!(":{}: {}", reaction.name, reaction.count))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let properties = [
+        ("TS", message.ts.clone()),
+        ("THREAD_TS", message.thread_ts.clone()),
+        ("USER", message.user.clone()),
+        ("BOT_ID", message.bot_id.clone()),
+        ("PERMALINK", message.permalink.clone()),
+        (
+            "REACTIONS",
+            Some(reactions).filter(|reactions| !reactions.is_empty()),
+        ),
+    ];
+    lines.push(":PROPERTIES:".to_string());
+    lines.extend(
+        properties
+            .iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| (name, value)))
+            .map(|(name, value)| format!(":{}: {}", name, single_line(value))),
+    );
+    lines.push(":END:".to_string());
+
+    let text = message
+        .markdown
+        .as_deref()
+        .or(message.text.as_deref())
+        .unwrap_or_default();
+    lines.extend(text.lines().map(body_line));
+    lines
+}
+
+/// A line of a message, indented when it would otherwise start a headline or a
+/// drawer
+fn body_line(line: &str) -> String {
+    if line.starts_with('*') || line.starts_with(':') || line.starts_with("#+") {
+        format!(" {}", line)
+    } else {
+        line.to_string()
+    }
+}
+
+fn single_line(text: &str) -> String {
+    text.lines().collect::<Vec<&str>>().join(" ")
+}
@@ -0,0 +1,102 @@
+use futures::FutureExt;
+use snafu::Snafu;
+use std::{any::Any, cell::RefCell, future::Future, panic::AssertUnwindSafe, sync::Once};
+
+static RECORD_PANICS: Once = Once::new();
+
+thread_local! {
+    /// Message and location of the last panic no one took yet
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Ran into a bug, please report it with this message: {message}"))]
+    InternalBug { message: String },
+}
+
+/// Runs `future`, turning a panic into an [`Error::InternalBug`] instead of
+/// aborting the call. Only natively, e.g. in the cli and tests, where panics
+/// unwind. In wasm this catches nothing: a panic still traps, the instance aborts
+/// the call with a `RuntimeError` that js has to catch, and only the message kept
+/// by [`record_panics`] survives for [`take_last_panic`]. So an export running its
+/// work in here can still throw
+pub async fn catch_panic<F>(future: F) -> Result<F::Output, Error>
+where
+    F: Future,
+{
+    AssertUnwindSafe(future)
+        .catch_unwind()
+        .await
+        .map_err(|payload| {
+            take_last_panic().unwrap_or_else(|| Error::InternalBug {
+                message: payload_message(payload.as_ref()),
+            })
+        })
+}
+
+/// Remembers the message and location of every panic for [`take_last_panic`],
+/// then calls the hook set before. Only sets the hook once
+pub fn record_panics() {
+    RECORD_PANICS.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(info.to_string()));
+            previous_hook(info);
+        }));
+    });
+}
+
+/// The last panic recorded since [`record_panics`], once
+pub fn take_last_panic() -> Option<Error> {
+    LAST_PANIC
+        .with(|last| last.borrow_mut().take())
+        .map(|message| Error::InternalBug { message })
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panicked without a message".to_string())
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of turning panics into errors, run with `cargo test`. A bug must fail
+    //! the call with a message the user can report, not take the plugin down
+
+    use crate::{
+        error_code::ErrorCode,
+        panics::{catch_panic, record_panics, take_last_panic, Error},
+    };
+    use futures::future;
+
+    #[async_std::test]
+    async fn catch_panic_passes_through_the_output() {
+        let output = catch_panic(future::ready(42)).await;
+
+        assert_eq!(42, output.expect("Should not panic"));
+    }
+
+    #[async_std::test]
+    async fn catch_panic_turns_a_panic_into_an_internal_bug() {
+        record_panics();
+
+        let result = catch_panic(async {
+            let users: Vec<&str> = vec![];
+            panic!("no user at {}", users.len())
+        })
+        .await;
+
+        let Err(err @ Error::InternalBug { .. }) = result else {
+            panic!("Expected an internal bug");
+        };
+        let message = err.to_string();
+        assert!(message.contains("no user at 0"), "{}", message);
+        assert!(message.contains("src/panics.rs"), "{}", message);
+        assert_eq!(ErrorCode::Internal, ErrorCode::of(&err));
+        assert!(take_last_panic().is_none());
+    }
+}
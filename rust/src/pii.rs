@@ -0,0 +1,149 @@
+use amplify_derive::Display;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use shrinkwraprs::Shrinkwrap;
+
+use crate::messages::{Message, MessageAndThread};
+
+/// Characters of a credential kept in the report, the rest is masked
+const CREDENTIAL_VISIBLE_CHARS: usize = 4;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    PhoneNumber,
+    /// Tokens, keys and passwords, e.g. `xoxb-...` or `password: hunter2`
+    Credential,
+}
+
+/// Where in a message the finding is
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiLocation {
+    Text,
+    FileName,
+}
+
+/// Something in the thread that looks like personal or secret data
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct PiiFinding {
+    pub kind: PiiKind,
+    pub location: PiiLocation,
+    /// The matched text. Credentials are masked except for their first characters
+    /// so the report itself does not leak them
+    pub text: String,
+    /// ts of the message the finding is in
+    pub message_ts: Option<String>,
+}
+
+/// Every finding in the thread, in thread order, only when the `detect_pii` option
+/// is on. Nothing is redacted, the plugin decides what to do about them
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct PiiReport(pub Vec<PiiFinding>);
+
+struct PiiScanner {
+    rules: Vec<(PiiKind, Regex)>,
+}
+
+impl PiiScanner {
+    fn new() -> PiiScanner {
+        let rules = [
+            (
+                PiiKind::Email,
+                r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b",
+            ),
+            (
+                PiiKind::PhoneNumber,
+                r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{3}\)[ .-]?|\b\d{3}[ .-])\d{3}[ .-]\d{4}\b",
+            ),
+            (
+                PiiKind::Credential,
+                r"\bxox[abposcdr]-[A-Za-z0-9-]{10,}|\bAKIA[0-9A-Z]{16}\b|\bgh[pousr]_[A-Za-z0-9]{36}\b|-----BEGIN [A-Z ]*PRIVATE KEY-----|(?i)\b(?:password|passwd|pwd|secret|api[_-]?key|token)\s*[:=]\s*\S+",
+            ),
+        ]
+        .iter()
+        .map(|(kind, pattern)| {
+            (
+                *kind,
+                Regex::new(pattern)
+                    .expect("Expected a valid pii pattern, but it was not. This is a bug"),
+            )
+        })
+        .collect();
+        PiiScanner { rules }
+    }
+
+    /// Findings in `text`, each distinct match once
+    fn scan(
+        &self,
+        text: &str,
+        location: PiiLocation,
+        message_ts: &Option<String>,
+    ) -> Vec<PiiFinding> {
+        let mut findings = self
+            .rules
+            .iter()
+            .flat_map(|(kind, regex)| {
+                regex.find_iter(text).map(move |found| PiiFinding {
+                    kind: *kind,
+                    location,
+                    text: match kind {
+                        PiiKind::Credential => mask(found.as_str()),
+                        PiiKind::Email | PiiKind::PhoneNumber => found.as_str().to_string(),
+                    },
+                    message_ts: message_ts.clone(),
+                })
+            })
+            .collect::<Vec<PiiFinding>>();
+        findings.dedup();
+        findings
+    }
+}
+
+fn mask(text: &str) -> String {
+    text.chars()
+        .enumerate()
+        .map(|(index, c)| {
+            if index < CREDENTIAL_VISIBLE_CHARS {
+                c
+            } else {
+                '*'
+            }
+        })
+        .collect()
+}
+
+impl PiiReport {
+    pub fn scan(message_and_thread: &MessageAndThread) -> PiiReport {
+        let scanner = PiiScanner::new();
+        PiiReport(
+            message_and_thread
+                .thread
+                .iter()
+                .flat_map(|message| PiiReport::scan_message(message, &scanner))
+                .collect(),
+        )
+    }
+
+    fn scan_message(message: &Message, scanner: &PiiScanner) -> Vec<PiiFinding> {
+        let text_findings = scanner.scan(
+            message.text.as_deref().unwrap_or_default(),
+            PiiLocation::Text,
+            &message.ts,
+        );
+        let file_name_findings = message
+            .files
+            .iter()
+            .flat_map(|files| files.0.iter())
+            .flat_map(|file| scanner.scan(&file.name, PiiLocation::FileName, &message.ts));
+        text_findings
+            .into_iter()
+            .chain(file_name_findings)
+            .collect()
+    }
+}
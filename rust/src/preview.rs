@@ -0,0 +1,178 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use crate::{
+    finalize_options::FinalizeOptions,
+    messages::{self, Message},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    slack_url::SlackUrl,
+    users::{self, User, Users},
+    utils::sanitize_text,
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not get the thread of {url} - source: {source}"))]
+    CouldNotGetThreadFromApi {
+        url: String,
+        source: messages::Error,
+    },
+
+    #[snafu(display("Slack returned no root message for {url}"))]
+    RootMessageNotFound { url: String },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Characters of the root message's text kept in a preview
+pub const PREVIEW_LENGTH: usize = 200;
+
+/// Marks a preview text that was cut short
+const ELLIPSIS: char = '…';
+
+/// The root message of the thread `slack_url` points to, without any of the
+/// enrichment of a saved thread. Only its author is looked up, a preview without
+/// their name is still a preview, so a failed lookup leaves the user id
+pub async fn get_message_preview_from_api<T>(
+    client: &SlackHttpClient<T>,
+    url: &str,
+    slack_url: &SlackUrl,
+    options: &FinalizeOptions,
+) -> Result<MessagePreview>
+where
+    T: TransportResponse,
+{
+    let thread_ts = slack_url.thread_ts.as_ref().unwrap_or(&slack_url.ts);
+    let root = messages::get_thread_from_api(client, &slack_url.channel_id, thread_ts)
+        .await
+        .context(CouldNotGetThreadFromApiSnafu { url })?
+        .data
+        .messages
+        .unwrap_or_default()
+        .into_iter()
+        .find(|message| message.ts.as_ref() == Some(thread_ts))
+        .context(RootMessageNotFoundSnafu { url })?;
+
+    let author = match root.user.as_ref() {
+        Some(user_id) => {
+            users::get_users_from_api(&vec![user_id.clone()], client, &mut Users::default())
+                .await
+                .ok()
+                .and_then(|users| users.get(user_id).and_then(User::display_name))
+        }
+        None => None,
+    };
+
+    Ok(MessagePreview::new(
+        url,
+        &slack_url.channel_id,
+        root,
+        author,
+        options,
+    ))
+}
+
+/// What a hover over a slack link shows before the thread is saved
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct MessagePreview {
+    pub permalink: String,
+    pub channel_id: String,
+    /// ts of the root message
+    pub ts: Option<String>,
+    /// Name of the root message's author, or their id when it could not be looked
+    /// up, see [`Message::author_name`]
+    pub author: Option<String>,
+    /// RFC 3339 UTC date time the root message was sent
+    pub date: Option<String>,
+    /// The first [`PREVIEW_LENGTH`] characters of the root message's text
+    pub text: String,
+    /// Whether `text` was cut short, it then ends with `…`
+    pub truncated: bool,
+    pub reply_count: Option<u16>,
+}
+
+impl MessagePreview {
+    fn new(
+        permalink: &str,
+        channel_id: &str,
+        mut root: Message,
+        author: Option<String>,
+        options: &FinalizeOptions,
+    ) -> MessagePreview {
+        let text = root.text.take().unwrap_or_default();
+        let text = if options.disable_text_sanitization {
+            text
+        } else {
+            sanitize_text(&text)
+        };
+        let truncated = text.chars().count() > PREVIEW_LENGTH;
+        let text = if truncated {
+            let mut preview = text.chars().take(PREVIEW_LENGTH).collect::<String>();
+            preview.push(ELLIPSIS);
+            preview
+        } else {
+            text
+        };
+        MessagePreview {
+            permalink: permalink.to_string(),
+            channel_id: channel_id.to_string(),
+            author: author.or_else(|| root.author_name()),
+            date: root.date(),
+            ts: root.ts,
+            text,
+            truncated,
+            reply_count: root.reply_count,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use serde_json::json;
+
+    use super::get_message_preview_from_api;
+    use crate::{
+        finalize_options::FinalizeOptions, slack_http_client::SlackHttpClientConfigOptions,
+        slack_url::SlackUrl, test_support::client,
+    };
+
+    #[async_std::test]
+    async fn get_message_preview_from_api_cuts_the_root_message_short() {
+        let (client, requested_urls) = client(SlackHttpClientConfigOptions::default(), |url| {
+            Ok(if url.contains("users.info") {
+                json!({ "ok": true, "user": { "id": "U1", "real_name": "Alice" } })
+            } else {
+                json!({
+                    "ok": true,
+                    "messages": [
+                        {
+                            "ts": "1700000000.000100",
+                            "user": "U1",
+                            "text": "a".repeat(250),
+                            "reply_count": 1
+                        },
+                        { "ts": "1700000100.000100", "user": "U2", "text": "a reply" }
+                    ]
+                })
+            })
+        });
+        let url =
+            "https://workspace.slack.com/archives/C1/p1700000100000100?thread_ts=1700000000.000100";
+        let slack_url = SlackUrl::new(url).expect("Should parse the link");
+
+        let preview =
+            get_message_preview_from_api(&client, url, &slack_url, &FinalizeOptions::default())
+                .await
+                .expect("Should preview the message");
+
+        assert_eq!(Some("1700000000.000100".to_string()), preview.ts);
+        assert_eq!(Some("Alice".to_string()), preview.author);
+        assert_eq!(Some("2023-11-14T22:13:20Z".to_string()), preview.date);
+        assert_eq!(format!("{}…", "a".repeat(200)), preview.text);
+        assert!(preview.truncated);
+        assert_eq!(Some(1), preview.reply_count);
+        assert_eq!(2, requested_urls.borrow().len());
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+const MS_PER_MINUTE: f64 = 60_000.0;
+
+thread_local! {
+    /// Limiters of every call, so calls running at the same time share them
+    static SHARED_LIMITERS: RefCell<HashMap<String, Rc<RateLimiter>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Token bucket of the requests to one group of slack's methods. Every request
+/// takes a token, tokens come back at `requests_per_minute`, and a request
+/// without a token waits for the next one
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_minute: Cell<u32>,
+    /// Below zero when requests are waiting for tokens
+    tokens: Cell<f64>,
+    refilled_at_ms: Cell<Option<f64>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32) -> RateLimiter {
+        let limiter = RateLimiter {
+            requests_per_minute: Cell::new(requests_per_minute.max(1)),
+            tokens: Cell::new(0.0),
+            refilled_at_ms: Cell::new(None),
+        };
+        limiter.tokens.set(limiter.capacity());
+        limiter
+    }
+
+    /// The limiter of `key` shared by every call, changed to
+    /// `requests_per_minute` when a call asks for another rate
+    pub fn shared(key: &str, requests_per_minute: u32) -> Rc<RateLimiter> {
+        SHARED_LIMITERS.with(|limiters| {
+            let limiter = limiters
+                .borrow_mut()
+                .entry(key.to_string())
+                .or_insert_with(|| Rc::new(RateLimiter::new(requests_per_minute)))
+                .clone();
+            limiter.requests_per_minute.set(requests_per_minute.max(1));
+            limiter
+        })
+    }
+
+    pub fn requests_per_minute(&self) -> u32 {
+        self.requests_per_minute.get()
+    }
+
+    /// Takes a token for a request sent at `now_ms`. The milliseconds the request
+    /// must wait before it is sent, 0 when a token was left
+    pub fn reserve(&self, now_ms: f64) -> f64 {
+        let refill_per_ms = self.requests_per_minute() as f64 / MS_PER_MINUTE;
+        let elapsed_ms = self
+            .refilled_at_ms
+            .get()
+            .map_or(0.0, |refilled_at_ms| (now_ms - refilled_at_ms).max(0.0));
+        let tokens = (self.tokens.get() + elapsed_ms * refill_per_ms).min(self.capacity()) - 1.0;
+        self.tokens.set(tokens);
+        self.refilled_at_ms.set(Some(now_ms));
+        if tokens >= 0.0 {
+            0.0
+        } else {
+            -tokens / refill_per_ms
+        }
+    }
+
+    /// A minute's worth, slack counts requests per minute
+    fn capacity(&self) -> f64 {
+        self.requests_per_minute() as f64
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of pacing requests to stay within slack's rate limits, run with
+    //! `cargo test`. Times are given, so no test waits
+
+    use crate::{
+        rate_limit::RateLimiter,
+        slack_http_client::{SlackApiEndpoints, SlackApiTier, SlackHttpClientConfigOptions},
+    };
+    use std::{collections::BTreeMap, rc::Rc};
+
+    #[test]
+    fn reserve_lets_a_minutes_worth_through_then_spaces_requests_out() {
+        let limiter = RateLimiter::new(3);
+
+        let waits = (0..5)
+            .map(|_| limiter.reserve(1_000.0))
+            .collect::<Vec<f64>>();
+
+        assert_eq!(vec![0.0, 0.0, 0.0, 20_000.0, 40_000.0], waits);
+    }
+
+    #[test]
+    fn reserve_refills_tokens_over_time() {
+        let limiter = RateLimiter::new(60);
+        (0..60).for_each(|_| {
+            limiter.reserve(0.0);
+        });
+
+        assert_eq!(500.0, limiter.reserve(500.0));
+        assert_eq!(0.0, limiter.reserve(60_000.0));
+    }
+
+    #[test]
+    fn shared_limiters_are_the_same_for_every_call() {
+        let first = RateLimiter::shared("conversations.replies", 50);
+        let second = RateLimiter::shared("conversations.replies", 20);
+
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(20, first.requests_per_minute());
+        assert!(!Rc::ptr_eq(&first, &RateLimiter::shared("users.info", 20)));
+    }
+
+    #[test]
+    fn rate_limit_of_paces_by_tier_unless_configured() {
+        let defaults = SlackHttpClientConfigOptions::default();
+        let configured = SlackHttpClientConfigOptions {
+            requests_per_minute: BTreeMap::from([
+                ("users.info".to_string(), 5),
+                ("tier3".to_string(), 10),
+            ]),
+            ..Default::default()
+        };
+        let disabled = SlackHttpClientConfigOptions {
+            disable_rate_tier_pacing: true,
+            ..configured.clone()
+        };
+
+        assert_eq!(
+            SlackApiTier::Tier3,
+            SlackApiEndpoints::ConversationsReplies.tier()
+        );
+        assert_eq!(
+            Some(("tier3".to_string(), 50)),
+            defaults.rate_limit_of("conversations.replies")
+        );
+        assert_eq!(
+            Some(("tier2".to_string(), 20)),
+            defaults.rate_limit_of("users.list")
+        );
+        assert_eq!(None, defaults.rate_limit_of("not.a.method"));
+        assert_eq!(
+            Some(("users.info".to_string(), 5)),
+            configured.rate_limit_of("users.info")
+        );
+        assert_eq!(
+            Some(("tier3".to_string(), 10)),
+            configured.rate_limit_of("conversations.replies")
+        );
+        assert_eq!(
+            Some(("tier3".to_string(), 10)),
+            disabled.rate_limit_of("conversations.replies")
+        );
+        assert_eq!(None, disabled.rate_limit_of("users.list"));
+    }
+}
@@ -0,0 +1,128 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use shrinkwraprs::Shrinkwrap;
+
+use crate::messages::{Message, MessageAndThread};
+
+/// Where a [`Reference`] points to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceSource {
+    GitHub,
+    Jira,
+}
+
+/// An issue, pull request or ticket a message links to and slack unfurled
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct Reference {
+    pub source: ReferenceSource,
+    /// e.g. `PROJ-123` or `owner/repo#42`
+    pub id: String,
+    /// Title of the unfurl, usually the issue's title
+    pub title: Option<String>,
+    pub url: String,
+}
+
+impl Reference {
+    /// The reference `url` points to, if it is a github issue or pull request or a
+    /// jira ticket
+    fn parse(url: &str, title: Option<&str>) -> Option<Reference> {
+        let parsed = url::Url::parse(url).ok()?;
+        let segments = parsed.path_segments()?.collect::<Vec<&str>>();
+        let (source, id) = match (parsed.host_str()?, segments.as_slice()) {
+            ("github.com", [owner, repo, "issues" | "pull", number, ..])
+                if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                (
+                    ReferenceSource::GitHub,
+                    format!("{}/{}#{}", owner, repo, number),
+                )
+            }
+            (_, [.., "browse", key]) if is_jira_key(key) => {
+                (ReferenceSource::Jira, key.to_string())
+            }
+            _ => return None,
+        };
+        Some(Reference {
+            source,
+            id,
+            title: title.map(str::to_string),
+            url: url.to_string(),
+        })
+    }
+}
+
+/// `PROJ-123`: a project key of capitals and digits starting with a capital, a
+/// dash and a number
+fn is_jira_key(key: &str) -> bool {
+    key.split_once('-').is_some_and(|(project, number)| {
+        project.starts_with(|c: char| c.is_ascii_uppercase())
+            && project
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// Github and jira links unfurled in the thread, once per url, in thread order
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct References(pub Vec<Reference>);
+
+impl References {
+    pub fn collect(message_and_thread: &MessageAndThread) -> References {
+        let mut references: Vec<Reference> = vec![];
+        message_and_thread
+            .thread
+            .iter()
+            .flat_map(References::of_message)
+            .for_each(|reference| {
+                if !references
+                    .iter()
+                    .any(|existing| existing.url == reference.url)
+                {
+                    references.push(reference);
+                }
+            });
+        References(references)
+    }
+
+    fn of_message(message: &Message) -> Vec<Reference> {
+        message
+            .attachments
+            .iter()
+            .flatten()
+            .filter_map(|attachment| {
+                attachment
+                    .urls()
+                    .find_map(|url| Reference::parse(url, attachment.title.as_deref()))
+            })
+            .collect()
+    }
+
+    /// Replaces slack links to the references, `<https://...|label>`, with markdown
+    /// links named after them, e.g. `[PROJ-123](https://...)`
+    pub fn link(&self, text: &str) -> String {
+        let mut linked = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some((start, end)) = rest
+            .find('<')
+            .and_then(|start| rest[start..].find('>').map(|end| (start, start + end)))
+        {
+            linked.push_str(&rest[..start]);
+            let target = rest[start + 1..end].split('|').next().unwrap_or_default();
+            match self.iter().find(|reference| reference.url == target) {
+                Some(reference) => {
+                    linked.push_str(&format!("[{}]({})", reference.id, reference.url))
+                }
+                None => linked.push_str(&rest[start..=end]),
+            }
+            rest = &rest[end + 1..];
+        }
+        linked.push_str(rest);
+        linked
+    }
+}
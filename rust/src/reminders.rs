@@ -0,0 +1,219 @@
+use amplify_derive::Display;
+use do_notation::m;
+use serde::{Deserialize, Serialize};
+use shrinkwraprs::Shrinkwrap;
+use snafu::{ResultExt, Snafu};
+use std::{fmt::Debug, iter::FromIterator};
+
+use crate::{
+    messages::MessageAndThread,
+    response::{self, SlackEnvelope},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    utils::{format_ts_as_date_time, sanitize_optional_text, SanitizeText},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
+
+    #[snafu(display("{source}"))]
+    CouldNotDeserializeRemindersResponse { source: response::Error },
+
+    #[snafu(display("The reminders response was not ok. - source: {source}"))]
+    InvalidRemindersResponse { source: response::Error },
+
+    #[snafu(display("Could not parse json from reminders response string - source: {source}"))]
+    CouldNotParseJsonFromRemindersResponse { source: response::Error },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Gets the token owner's reminders that were set on a message of the thread
+pub async fn get_reminders_from_api<T>(
+    client: &SlackHttpClient<T>,
+    channel_id: &str,
+    message_and_thread: &MessageAndThread,
+) -> Result<ThreadReminders>
+where
+    T: TransportResponse,
+{
+    let result = client.get_reminders_list().into_body().await;
+    let reminders_response: RemindersResponse = m! {
+        // mapping error instead of using snafu context because the request's error is a string, not an Error
+        val <- result.map_err(|error| Error::RequestFailed { error });
+        json <- response::parse_response_body(val).context(CouldNotParseJsonFromRemindersResponseSnafu);
+        reminders_response <- response::defined_from_json(json).context(CouldNotDeserializeRemindersResponseSnafu);
+        valid_response <- client.validate_response(reminders_response).context(InvalidRemindersResponseSnafu);
+        return valid_response;
+    }?;
+
+    // reminders about a message carry the message's permalink in their text
+    let permalink_suffixes = message_and_thread
+        .thread
+        .iter()
+        .filter_map(|message| message.ts.as_ref())
+        .map(|ts| (ts, format!("{}/p{}", channel_id, ts.replace('.', ""))))
+        .collect::<Vec<(&String, String)>>();
+
+    Ok(reminders_response
+        .data
+        .reminders
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|reminder| {
+            let text = reminder.text.as_deref().unwrap_or_default();
+            let (message_ts, _) = permalink_suffixes
+                .iter()
+                .find(|(_, suffix)| text.contains(suffix.as_str()))?;
+            Some(ThreadReminder::new(reminder.clone(), message_ts))
+        })
+        .collect())
+}
+
+/// Sets a reminder for the token owner with `reminders.add`. `time` is when it is
+/// due, a unix timestamp in seconds or how slack writes it, e.g. `in 2 days`
+pub async fn add_reminder_to_api<T>(
+    client: &SlackHttpClient<T>,
+    text: &str,
+    time: &str,
+) -> Result<Reminder>
+where
+    T: TransportResponse,
+{
+    let result = client.add_reminder(text, time).into_body().await;
+    let reminder_response: ReminderResponse = m! {
+        // mapping error instead of using snafu context because the request's error is a string, not an Error
+        val <- result.map_err(|error| Error::RequestFailed { error });
+        json <- response::parse_response_body(val).context(CouldNotParseJsonFromRemindersResponseSnafu);
+        reminder_response <- response::defined_from_json(json).context(CouldNotDeserializeRemindersResponseSnafu);
+        valid_response <- client.validate_response(reminder_response).context(InvalidRemindersResponseSnafu);
+        return valid_response;
+    }?;
+    Ok(reminder_response
+        .data
+        .reminder
+        .expect("Expected a reminder in the reminder response, but got None. This is a bug"))
+}
+
+/// A reminder as returned by `reminders.list` and `reminders.add`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct Reminder {
+    pub id: String,
+    pub creator: Option<String>,
+    pub user: Option<String>,
+    pub text: Option<String>,
+    pub recurring: Option<bool>,
+    pub time: Option<i64>,
+    pub complete_ts: Option<i64>,
+}
+
+/// A reminder set on one of the thread's messages
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct ThreadReminder {
+    pub id: String,
+    /// ts of the message the reminder is about
+    pub message_ts: String,
+    pub text: Option<String>,
+    /// When the reminder is due, see [`format_ts_as_date_time`]
+    pub date: Option<String>,
+    pub recurring: bool,
+    pub complete: bool,
+}
+
+impl ThreadReminder {
+    pub fn new(reminder: Reminder, message_ts: &str) -> ThreadReminder {
+        ThreadReminder {
+            id: reminder.id,
+            message_ts: message_ts.to_string(),
+            text: reminder.text,
+            date: reminder
+                .time
+                .and_then(|time| format_ts_as_date_time(&time.to_string())),
+            recurring: reminder.recurring.unwrap_or(false),
+            complete: reminder
+                .complete_ts
+                .is_some_and(|complete_ts| complete_ts > 0),
+        }
+    }
+}
+
+impl SanitizeText for ThreadReminder {
+    fn sanitize_text(&mut self) {
+        sanitize_optional_text(&mut self.text);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct ThreadReminders(pub Vec<ThreadReminder>);
+
+impl ThreadReminders {
+    pub fn sanitize_text(&mut self) {
+        self.0.iter_mut().for_each(SanitizeText::sanitize_text);
+    }
+}
+
+impl FromIterator<ThreadReminder> for ThreadReminders {
+    fn from_iter<T: IntoIterator<Item = ThreadReminder>>(iter: T) -> Self {
+        ThreadReminders(iter.into_iter().collect())
+    }
+}
+
+pub type RemindersResponse = SlackEnvelope<RemindersResponseData>;
+
+/// The fields of a `reminders.list` response, see [`RemindersResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RemindersResponseData {
+    pub reminders: Option<Vec<Reminder>>,
+}
+
+pub type ReminderResponse = SlackEnvelope<ReminderResponseData>;
+
+/// The fields of a `reminders.add` response, see [`ReminderResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReminderResponseData {
+    pub reminder: Option<Reminder>,
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use serde_json::json;
+
+    use super::add_reminder_to_api;
+    use crate::{slack_http_client::SlackHttpClientConfigOptions, test_support::client};
+
+    #[async_std::test]
+    async fn add_reminder_to_api_sends_the_text_and_time() {
+        let (client, requested_urls) = client(SlackHttpClientConfigOptions::default(), |_| {
+            Ok(json!({
+                "ok": true,
+                "reminder": {
+                    "id": "Rm1",
+                    "creator": "U1",
+                    "user": "U1",
+                    "text": "Follow up on obsidian://open?file=thread",
+                    "recurring": false,
+                    "time": 1700000000,
+                    "complete_ts": 0
+                }
+            }))
+        });
+
+        let reminder = add_reminder_to_api(
+            &client,
+            "Follow up on obsidian://open?file=thread",
+            "in 2 days",
+        )
+        .await
+        .expect("Should add the reminder");
+
+        assert_eq!("Rm1", reminder.id);
+        let requested_urls = requested_urls.borrow();
+        assert_eq!(1, requested_urls.len());
+        assert!(requested_urls[0].contains("reminders.add"));
+        assert!(requested_urls[0].contains("time=in+2+days"));
+    }
+}
@@ -0,0 +1,225 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{components::ObsidianSlackComponents, org::render_org};
+
+/// Version of the text [`render_note`] produces, saved in every note. It is raised
+/// whenever the same components would render differently, so the plugin can tell
+/// notes saved before apart instead of silently reformatting them on a refresh
+pub const NOTE_FORMAT_VERSION: u64 = 1;
+
+/// Key of [`NOTE_FORMAT_VERSION`] in a rendered note
+pub const NOTE_FORMAT_VERSION_KEY: &str = "note_format_version";
+
+/// What [`render`] writes the note as
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// See [`render_note`]
+    #[default]
+    Json,
+    /// For org-mode tools next to the vault, see [`render_org`]
+    Org,
+}
+
+/// The note to save for `components` in `output_format`. `strip_nulls` only
+/// applies to json
+pub fn render(
+    components: &ObsidianSlackComponents,
+    output_format: OutputFormat,
+    strip_nulls: bool,
+) -> String {
+    match output_format {
+        OutputFormat::Json => render_note(components, strip_nulls),
+        OutputFormat::Org => render_org(components),
+    }
+}
+
+/// The note to save for `components`: json indented by two spaces, with object
+/// keys sorted so the same components always give the same text. The refreshed
+/// credentials are left out, they must never end up in the vault. With
+/// `strip_nulls` properties without a value are left out as well
+pub fn render_note(components: &ObsidianSlackComponents, strip_nulls: bool) -> String {
+    let mut note = serde_json::to_value(components)
+        .expect("Expected to serialize object with serde, but was unable to. This is a bug");
+    if let Value::Object(properties) = &mut note {
+        properties.remove("refreshed_credentials");
+        properties.insert(
+            NOTE_FORMAT_VERSION_KEY.to_string(),
+            Value::from(NOTE_FORMAT_VERSION),
+        );
+    }
+    if strip_nulls {
+        strip_null_properties(&mut note);
+    }
+    serde_json::to_string_pretty(&note)
+        .expect("Expected to serialize object with serde, but was unable to. This is a bug")
+}
+
+fn strip_null_properties(value: &mut Value) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(strip_null_properties),
+        Value::Object(properties) => {
+            properties.retain(|_, property| !property.is_null());
+            properties.values_mut().for_each(strip_null_properties);
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Snapshot tests of the saved note, run with `cargo test`. A failing snapshot
+    //! means notes saved before would be reformatted on a refresh: raise
+    //! `NOTE_FORMAT_VERSION` before accepting the new snapshots with `cargo insta review`
+
+    use crate::{
+        components::{FileName, ObsidianSlackComponents, ObsidianSlackComponentsBuilder},
+        finalize_options::FinalizeOptions,
+        messages::{Message, MessageAndThread, Messages},
+        render::{render, render_note, OutputFormat},
+        slack_http_client::SlackCredentials,
+        users::Users,
+    };
+    use rstest::rstest;
+    use serde::Deserialize;
+
+    /// The time of the save, a day after the fixtures were sent
+    const NOW_MS: f64 = 1_672_617_600_000.0;
+
+    /// Messages of a thread, the first being the root, as returned by slack
+    #[derive(Deserialize)]
+    struct Fixture {
+        messages: Vec<Message>,
+        users: Option<Users>,
+    }
+
+    fn components(fixture: &str) -> ObsidianSlackComponents {
+        components_with(fixture, &FinalizeOptions::default())
+    }
+
+    fn components_with(fixture: &str, options: &FinalizeOptions) -> ObsidianSlackComponents {
+        let Fixture { messages, users } =
+            serde_json::from_str(fixture).expect("Expected the fixture to be valid");
+        let components = ObsidianSlackComponentsBuilder::default()
+            .message_and_thread(MessageAndThread {
+                message: Messages(messages.iter().take(1).cloned().collect()),
+                thread: Messages(messages),
+            })
+            .file_name(FileName("C0GENERAL-1672531200.000100".to_string()))
+            .users(users)
+            .build()
+            .expect("Expected to build the components");
+        ObsidianSlackComponents::finalize(components, options, NOW_MS)
+            .expect("Expected to finalize the components")
+    }
+
+    #[rstest]
+    #[case::mentions("mentions", include_str!("../tests/fixtures/render/mentions.json"))]
+    #[case::code_blocks("code_blocks", include_str!("../tests/fixtures/render/code_blocks.json"))]
+    #[case::files("files", include_str!("../tests/fixtures/render/files.json"))]
+    #[case::reactions("reactions", include_str!("../tests/fixtures/render/reactions.json"))]
+    #[case::scheduled("scheduled", include_str!("../tests/fixtures/render/scheduled.json"))]
+    #[case::rich_text("rich_text", include_str!("../tests/fixtures/render/rich_text.json"))]
+    #[case::deleted("deleted", include_str!("../tests/fixtures/render/deleted.json"))]
+    fn renders_the_same_note(#[case] name: &str, #[case] fixture: &str) {
+        insta::assert_snapshot!(name, render_note(&components(fixture), true));
+    }
+
+    #[test]
+    fn leaves_out_refreshed_credentials() {
+        let mut components = components(include_str!("../tests/fixtures/render/mentions.json"));
+        components.refreshed_credentials = Some(SlackCredentials {
+            token: "xoxc-refreshed".into(),
+            cookie: "xoxd-refreshed".into(),
+        });
+
+        let note = render_note(&components, false);
+
+        assert!(!note.contains("refreshed_credentials"));
+        assert!(!note.contains("xoxc-refreshed"));
+    }
+
+    #[test]
+    fn writes_users_once_when_normalized() {
+        let fixture = include_str!("../tests/fixtures/render/reactions.json");
+        let options = FinalizeOptions {
+            normalize_users: true,
+            ..FinalizeOptions::default()
+        };
+        let mut normalized = components_with(fixture, &options);
+
+        let note = render_note(&normalized, true);
+        insta::assert_snapshot!("reactions_normalized", note);
+        assert!(note.len() < render_note(&components(fixture), true).len());
+
+        normalized.join_users();
+        assert_eq!(components(fixture), normalized);
+    }
+
+    #[test]
+    fn turns_replies_into_tasks_ticked_by_the_task_reaction() {
+        let options = FinalizeOptions {
+            task_reaction: Some(":white_check_mark:".to_string()),
+            ..FinalizeOptions::default()
+        };
+        let components = components_with(
+            include_str!("../tests/fixtures/render/tasks.json"),
+            &options,
+        );
+
+        insta::assert_snapshot!("tasks", render_note(&components, true));
+    }
+
+    #[test]
+    fn links_every_message_to_slack_in_a_footnote() {
+        let Fixture { messages, users } =
+            serde_json::from_str(include_str!("../tests/fixtures/render/reactions.json"))
+                .expect("Expected the fixture to be valid");
+        let components = ObsidianSlackComponentsBuilder::default()
+            .message_and_thread(MessageAndThread {
+                message: Messages(messages.iter().take(1).cloned().collect()),
+                thread: Messages(messages),
+            })
+            .file_name(FileName("C0GENERAL-1672531200.000100".to_string()))
+            .users(users)
+            .channel_link(Some(
+                "https://workspace.slack.com/archives/C0GENERAL".to_string(),
+            ))
+            .build()
+            .expect("Expected to build the components");
+        let options = FinalizeOptions {
+            permalink_footnotes: true,
+            ..FinalizeOptions::default()
+        };
+
+        let components = ObsidianSlackComponents::finalize(components, &options, NOW_MS)
+            .expect("Expected to finalize the components");
+
+        let texts = components
+            .message_and_thread
+            .thread
+            .iter()
+            .map(|message| message.text.as_deref().unwrap_or_default())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+        vec![
+            "We shipped it! [^1672531200000100]\n\n[^1672531200000100]: https://workspace.slack.com/archives/C0GENERAL/p1672531200000100",
+            "Great work [^1672531260000200]\n\n[^1672531260000200]: https://workspace.slack.com/archives/C0GENERAL/p1672531260000200?thread_ts=1672531200.000100",
+        ],
+        texts
+    );
+    }
+
+    #[test]
+    fn renders_org_mode_with_a_headline_per_message() {
+        let components = components(include_str!("../tests/fixtures/render/reactions.json"));
+
+        insta::assert_snapshot!(
+            "reactions_org",
+            render(&components, OutputFormat::Org, true)
+        );
+    }
+}
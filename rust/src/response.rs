@@ -1,33 +1,29 @@
-use do_notation::m;
-use js_sys::JSON;
+use js_sys::{Reflect, JSON};
+use serde::{Deserialize, Serialize};
 use snafu::{ensure, ResultExt, Snafu};
 use wasm_bindgen::JsValue;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
-    #[snafu(display(
-        "serde wasm parsing errored: {the_falied_to_parse_value} - source: {source}"
-    ))]
-    SerdeWasmBindgenCouldNotParseResponse {
-        the_falied_to_parse_value: String,
-        source: serde_wasm_bindgen::Error,
+    #[snafu(display("Could not deserialize {the_failed_to_parse_value} - source: {source}"))]
+    CouldNotDeserializeResponse {
+        the_failed_to_parse_value: String,
+        source: serde_json::Error,
     },
 
     #[snafu(display("The slack response was not ok: {response}"))]
-    SlackResponseNotOk { response: String },
+    SlackResponseNotOk {
+        response: String,
+        error: Option<String>,
+    },
 
     #[snafu(display("Provided string value could not be parsed to json: {string}"))]
     CouldNotParseJsonFromString { string: String },
 
     #[snafu(display(
-        "The javascript value was not a string when it should have been: {js_value}"
-    ))]
-    JsValueNotAString { js_value: String },
-
-    #[snafu(display(
-        "Tried to parse a javascript value that was not an object into an object. value: {the_failed_to_parse_value} - type: {type}"
+        "Tried to parse a json value that was not an object into an object. value: {the_failed_to_parse_value} - type: {type}"
     ))]
-    JsValueWasNotObject {
+    JsonValueWasNotObject {
         the_failed_to_parse_value: String,
         r#type: String,
     },
@@ -37,6 +33,17 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 pub trait SlackResponseValidator {
     fn ok(&self) -> Option<bool>;
 
+    /// Slack's error code when the response is not ok, e.g. `invalid_auth`
+    fn error(&self) -> Option<&str> {
+        None
+    }
+
+    /// Problems slack tolerated but reported, e.g. `missing_charset`. Several
+    /// warnings are separated by commas
+    fn warning(&self) -> Option<&str> {
+        None
+    }
+
     fn validate_response(self) -> Result<Self>
     where
         Self: Sized,
@@ -48,38 +55,164 @@ pub trait SlackResponseValidator {
             ),
             SlackResponseNotOkSnafu {
                 response: format!("{:#?}", self),
+                error: self.error().map(str::to_string),
             }
         );
         Ok(self)
     }
 }
 
-pub fn convert_result_string_to_object(val: JsValue) -> Result<JsValue> {
-    // results from the `request` function of obsidian return strings
-    m! {
-        str_val <- val
-                   .as_string()
-                   .map_or(JsValueNotAStringSnafu {js_value: format!("{:#?}", val)}.fail(), Ok);
-        // mapping error instead of using snafu context because jsvalue is not an Error from parse method
-        obj_val <- JSON::parse(&str_val).map_err(|_err| Error::CouldNotParseJsonFromString {string: format!("{:#?}", str_val)});
-        return obj_val;
+/// A slack response: whether it is ok, its error or warnings, and the fields of
+/// the endpoint in `data`, e.g. `user` for `users.info`. Adding an endpoint only
+/// takes a struct of its fields
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SlackEnvelope<T> {
+    pub ok: Option<bool>,
+    pub error: Option<String>,
+    pub warning: Option<String>,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+impl<T> SlackResponseValidator for SlackEnvelope<T> {
+    fn ok(&self) -> Option<bool> {
+        self.ok
+    }
+
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    fn warning(&self) -> Option<&str> {
+        self.warning.as_deref()
     }
 }
 
-pub fn defined_from_js_object<T>(val: JsValue) -> Result<T>
+impl<T> PaginatedResponse for SlackEnvelope<T>
+where
+    T: PaginatedResponse,
+{
+    fn response_metadata(&self) -> Option<&ResponseMetadata> {
+        self.data.response_metadata()
+    }
+}
+
+/// Where the next page of a paginated response starts
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ResponseMetadata {
+    pub next_cursor: Option<String>,
+}
+
+/// Responses of endpoints that page with `response_metadata.next_cursor`, e.g.
+/// `conversations.replies`, `conversations.history` and `users.list`
+pub trait PaginatedResponse {
+    fn response_metadata(&self) -> Option<&ResponseMetadata>;
+
+    /// Cursor of the next page, None on the last one. Slack sends an empty cursor
+    /// on the last page of some endpoints and none on others
+    fn next_cursor(&self) -> Option<&str> {
+        self.response_metadata()
+            .and_then(|response_metadata| response_metadata.next_cursor.as_deref())
+            .filter(|next_cursor| !next_cursor.is_empty())
+    }
+}
+
+/// Follows the cursors of a paginated endpoint, a page at a time. Request each
+/// page with [`Paginator::cursor`], then [`Paginator::advance`] past it
+#[derive(Debug, Default)]
+pub struct Paginator {
+    cursor: Option<String>,
+}
+
+impl Paginator {
+    /// Starts at the page of `cursor`, e.g. one returned by an earlier call that
+    /// stopped before the last page. None starts at the first page
+    pub fn resume(cursor: Option<String>) -> Paginator {
+        Paginator { cursor }
+    }
+
+    /// The cursor to request the current page with, None for the first page
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
+
+    /// Moves past the page of `response`. False when it was the last page
+    pub fn advance<R>(&mut self, response: &R) -> bool
+    where
+        R: PaginatedResponse,
+    {
+        self.cursor = response.next_cursor().map(str::to_string);
+        self.cursor.is_some()
+    }
+
+    /// The cursor of the page after the last one advanced past, None when there is
+    /// none
+    pub fn into_cursor(self) -> Option<String> {
+        self.cursor
+    }
+}
+
+/// Reads the body of a response as json. Bodies are strings, that is what the
+/// `request` function of obsidian returns
+pub fn parse_response_body(body: String) -> Result<serde_json::Value> {
+    serde_json::from_str(&body).map_err(|_err| Error::CouldNotParseJsonFromString {
+        string: format!("{:#?}", body),
+    })
+}
+
+/// Slack error codes that mean the token or cookie is no longer accepted
+pub const AUTH_ERRORS: [&str; 4] = [
+    "invalid_auth",
+    "not_authed",
+    "token_expired",
+    "token_revoked",
+];
+
+/// Checks a raw response string from the `request` function for an auth failure.
+/// Anything that can't be read as a slack response is not considered an auth error
+pub fn is_auth_error(val: &JsValue) -> bool {
+    val.as_string()
+        .and_then(|str_val| JSON::parse(&str_val).ok())
+        .and_then(|obj_val| Reflect::get(&obj_val, &JsValue::from_str("error")).ok())
+        .and_then(|error| error.as_string())
+        .is_some_and(|error| AUTH_ERRORS.contains(&error.as_str()))
+}
+
+/// Checks a raw response string from the `request` function for `"ok": true`
+pub fn is_ok_response(val: &JsValue) -> bool {
+    val.as_string()
+        .and_then(|str_val| JSON::parse(&str_val).ok())
+        .and_then(|obj_val| Reflect::get(&obj_val, &JsValue::from_str("ok")).ok())
+        .and_then(|ok| ok.as_bool())
+        .unwrap_or(false)
+}
+
+pub fn defined_from_json<T>(val: serde_json::Value) -> Result<T>
 where
     T: serde::de::DeserializeOwned,
 {
     ensure!(
         val.is_object(),
-        JsValueWasNotObjectSnafu {
+        JsonValueWasNotObjectSnafu {
             the_failed_to_parse_value: format!("{:#?}", val),
-            r#type: format!("{:#?}", val.js_typeof())
+            r#type: json_type(&val)
         }
     );
     let val_string = format!("{:#?}", &val);
 
-    serde_wasm_bindgen::from_value(val).context(SerdeWasmBindgenCouldNotParseResponseSnafu {
-        the_falied_to_parse_value: val_string,
+    serde_json::from_value(val).context(CouldNotDeserializeResponseSnafu {
+        the_failed_to_parse_value: val_string,
     })
 }
+
+/// Name of the json type of `val`, as `typeof` would tell in javascript
+fn json_type(val: &serde_json::Value) -> &'static str {
+    match val {
+        serde_json::Value::Null | serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+            "object"
+        }
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+    }
+}
@@ -0,0 +1,357 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+
+/// Spaces a list item is indented by per level of nesting, enough for both
+/// bulleted and numbered parents
+const LIST_INDENT: &str = "    ";
+
+/// Characters that would format plain text as markdown, e.g. `*` and `_` italics
+const MARKDOWN_SIGNIFICANT: [char; 7] = ['\\', '`', '*', '_', '~', '[', ']'];
+
+/// A layout block of a message, as slack returns it in the message's `blocks`.
+/// Only `rich_text` blocks are rendered, see [`to_markdown`]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct Block {
+    /// e.g. `rich_text` or `section`
+    pub r#type: String,
+    pub block_id: Option<String>,
+    pub elements: Option<Vec<RichTextElement>>,
+}
+
+/// A part of a rich text block: a section, list, quote or preformatted text, or
+/// one of the texts, links, mentions and emoji inside them
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct RichTextElement {
+    /// e.g. `rich_text_list` or `text`
+    pub r#type: String,
+    pub elements: Option<Vec<RichTextElement>>,
+    pub style: Option<ElementStyle>,
+    /// Nesting level of a list, 0 for the outermost
+    pub indent: Option<usize>,
+    /// Items of the list that came before, for numbered lists split by nesting
+    pub offset: Option<usize>,
+    pub text: Option<String>,
+    pub url: Option<String>,
+    pub user_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub usergroup_id: Option<String>,
+    /// Name of an emoji
+    pub name: Option<String>,
+    /// `here`, `channel` or `everyone` of a broadcast
+    pub range: Option<String>,
+}
+
+/// Slack sends the style of a list as its name and that of a text as flags
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(untagged)]
+pub enum ElementStyle {
+    /// `bullet` or `ordered`
+    List(String),
+    Text(TextStyle),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct TextStyle {
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub strike: Option<bool>,
+    pub code: Option<bool>,
+}
+
+/// The markdown of the `rich_text` blocks, keeping the lists, quotes and
+/// preformatted sections the plain `text` flattens. None when there are no rich
+/// text blocks. Mentions are kept as slack writes them in `text`, e.g. `<@U123>`.
+/// Plain text is escaped so it shows as written, code keeps every character as it
+/// is
+pub fn to_markdown(blocks: &[Block]) -> Option<String> {
+    let parts = blocks
+        .iter()
+        .filter(|block| block.r#type == "rich_text")
+        .flat_map(|block| block.elements.iter().flatten())
+        .filter_map(render_element)
+        .collect::<Vec<String>>();
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("\n"))
+}
+
+fn render_element(element: &RichTextElement) -> Option<String> {
+    let rendered = match element.r#type.as_str() {
+        "rich_text_section" => render_inline(children(element)),
+        "rich_text_list" => render_list(element),
+        "rich_text_quote" => render_inline(children(element))
+            .lines()
+            .map(|line| format!("> {}", line))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        "rich_text_preformatted" => {
+            let code = children(element).iter().map(plain_text).collect::<String>();
+            let code = code.trim_end_matches('\n');
+            let fence = "`".repeat((longest_backtick_run(code) + 1).max(3));
+            format!("{}\n{}\n{}", fence, code, fence)
+        }
+        _ => return None,
+    };
+    Some(rendered.trim_end_matches('\n').to_string())
+}
+
+fn render_list(list: &RichTextElement) -> String {
+    let ordered = list.style == Some(ElementStyle::List("ordered".to_string()));
+    let indent = LIST_INDENT.repeat(list.indent.unwrap_or_default());
+    children(list)
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let marker = if ordered {
+                format!("{}.", list.offset.unwrap_or_default() + index + 1)
+            } else {
+                "-".to_string()
+            };
+            format!(
+                "{}{} {}",
+                indent,
+                marker,
+                render_inline(children(item)).trim_end_matches('\n')
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn children(element: &RichTextElement) -> &[RichTextElement] {
+    element.elements.as_deref().unwrap_or_default()
+}
+
+fn render_inline(elements: &[RichTextElement]) -> String {
+    elements.iter().map(render_inline_element).collect()
+}
+
+fn render_inline_element(element: &RichTextElement) -> String {
+    match element.r#type.as_str() {
+        "text" => styled(
+            element.text.as_deref().unwrap_or_default(),
+            text_style(element),
+        ),
+        "link" => {
+            let url = element.url.as_deref().unwrap_or_default();
+            match element.text.as_deref().filter(|text| !text.is_empty()) {
+                Some(text) => format!("[{}]({})", styled(text, text_style(element)), url),
+                None => format!("<{}>", url),
+            }
+        }
+        "user" => format!("<@{}>", element.user_id.as_deref().unwrap_or_default()),
+        "channel" => format!("<#{}>", element.channel_id.as_deref().unwrap_or_default()),
+        "usergroup" => format!(
+            "<!subteam^{}>",
+            element.usergroup_id.as_deref().unwrap_or_default()
+        ),
+        "broadcast" => format!("<!{}>", element.range.as_deref().unwrap_or_default()),
+        "emoji" => format!(":{}:", element.name.as_deref().unwrap_or_default()),
+        _ => element.text.clone().unwrap_or_default(),
+    }
+}
+
+/// Text as it is, for preformatted sections where styles do not apply
+fn plain_text(element: &RichTextElement) -> String {
+    match element.r#type.as_str() {
+        "text" => element.text.clone().unwrap_or_default(),
+        "link" => element
+            .text
+            .clone()
+            .filter(|text| !text.is_empty())
+            .or_else(|| element.url.clone())
+            .unwrap_or_default(),
+        _ => render_inline_element(element),
+    }
+}
+
+fn text_style(element: &RichTextElement) -> Option<&TextStyle> {
+    match element.style.as_ref() {
+        Some(ElementStyle::Text(style)) => Some(style),
+        _ => None,
+    }
+}
+
+/// `text` between the markers of its style. Whitespace at either end stays
+/// outside of them, markdown does not allow e.g. `**bold **`
+fn styled(text: &str, style: Option<&TextStyle>) -> String {
+    let style = match style {
+        Some(style) => style,
+        None => return escape_markdown(text),
+    };
+    let content = text.trim();
+    if content.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+
+    let mut styled = if style.code == Some(true) {
+        code_span(content)
+    } else {
+        escape_markdown(content)
+    };
+    if style.strike == Some(true) {
+        styled = format!("~~{}~~", styled);
+    }
+    if style.italic == Some(true) {
+        styled = format!("_{}_", styled);
+    }
+    if style.bold == Some(true) {
+        styled = format!("**{}**", styled);
+    }
+    format!("{}{}{}", leading, styled, trailing)
+}
+
+/// `text` with a backslash in front of every character that would format it
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if MARKDOWN_SIGNIFICANT.contains(&character) {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+/// `code` as inline code, fenced by more backticks than it contains in a row so
+/// its own backticks do not end it
+fn code_span(code: &str) -> String {
+    let fence = "`".repeat(longest_backtick_run(code) + 1);
+    let padding = if code.starts_with('`') || code.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{}{}{}{}{}", fence, padding, code, padding, fence)
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|character| character != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests of rendering rich text blocks as markdown, run with `cargo test`. Text
+    //! must show in the note as it was written in slack: plain prose is escaped, code
+    //! is kept as it is
+
+    use crate::rich_text::{escape_markdown, to_markdown, Block};
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    fn blocks(elements: Value) -> Vec<Block> {
+        serde_json::from_value(json!([{ "type": "rich_text", "elements": elements }]))
+            .expect("Expected valid rich text blocks")
+    }
+
+    fn section(text: &str, style: Value) -> Vec<Block> {
+        blocks(json!([{
+            "type": "rich_text_section",
+            "elements": [{ "type": "text", "text": text, "style": style }]
+        }]))
+    }
+
+    #[rstest]
+    #[case::plain("plain text", "plain text")]
+    #[case::asterisks("2 * 3 * 4", "2 \\* 3 \\* 4")]
+    #[case::underscores("snake_case_name", "snake\\_case\\_name")]
+    #[case::brackets("[not a link](url)", "\\[not a link\\](url)")]
+    #[case::backticks("use `code` here", "use \\`code\\` here")]
+    #[case::tildes("~approximately~", "\\~approximately\\~")]
+    #[case::backslashes("C:\\temp", "C:\\\\temp")]
+    #[case::already_escaped("\\*", "\\\\\\*")]
+    #[case::mixed("*bold* _italic_ ~strike~", "\\*bold\\* \\_italic\\_ \\~strike\\~")]
+    fn escapes_plain_prose(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(expected, escape_markdown(text));
+        assert_eq!(
+            Some(expected.to_string()),
+            to_markdown(&section(text, Value::Null))
+        );
+    }
+
+    #[rstest]
+    #[case::bold("a_b", json!({ "bold": true }), "**a\\_b**")]
+    #[case::italic("2*3", json!({ "italic": true }), "_2\\*3_")]
+    #[case::strike("[x]", json!({ "strike": true }), "~~\\[x\\]~~")]
+    #[case::code("a_b * [c]", json!({ "code": true }), "`a_b * [c]`")]
+    #[case::code_with_backtick("a`b", json!({ "code": true }), "``a`b``")]
+    #[case::code_with_backtick_runs("a``b`", json!({ "code": true }), "``` a``b` ```")]
+    #[case::code_starting_with_backtick("`tick", json!({ "code": true }), "`` `tick ``")]
+    #[case::bold_code("x*y", json!({ "bold": true, "code": true }), "**`x*y`**")]
+    #[case::surrounding_whitespace(" a_b ", json!({ "bold": true }), " **a\\_b** ")]
+    fn styles_text(#[case] text: &str, #[case] style: Value, #[case] expected: &str) {
+        assert_eq!(
+            Some(expected.to_string()),
+            to_markdown(&section(text, style))
+        );
+    }
+
+    #[rstest]
+    #[case::plain("let x = a * b;", "```\nlet x = a * b;\n```")]
+    #[case::inline_backticks("echo `date`", "```\necho `date`\n```")]
+    #[case::fence_inside("```\nnested\n```", "````\n```\nnested\n```\n````")]
+    #[case::trailing_newline("ls _dir_\n", "```\nls _dir_\n```")]
+    fn fences_preformatted_text(#[case] text: &str, #[case] expected: &str) {
+        let blocks = blocks(json!([{
+            "type": "rich_text_preformatted",
+            "elements": [{ "type": "text", "text": text }]
+        }]));
+
+        assert_eq!(Some(expected.to_string()), to_markdown(&blocks));
+    }
+
+    #[test]
+    fn escapes_link_labels_but_not_urls() {
+        let blocks = blocks(json!([{
+            "type": "rich_text_section",
+            "elements": [{
+                "type": "link",
+                "url": "https://example.com/a_b*c",
+                "text": "the *spec*"
+            }]
+        }]));
+
+        assert_eq!(
+            Some("[the \\*spec\\*](https://example.com/a_b*c)".to_string()),
+            to_markdown(&blocks)
+        );
+    }
+
+    #[test]
+    fn keeps_mentions_and_emoji_as_slack_writes_them() {
+        let blocks = blocks(json!([{
+            "type": "rich_text_section",
+            "elements": [
+                { "type": "user", "user_id": "U0ALICE" },
+                { "type": "text", "text": " " },
+                { "type": "emoji", "name": "white_check_mark" },
+                { "type": "text", "text": " " },
+                { "type": "broadcast", "range": "here" }
+            ]
+        }]));
+
+        assert_eq!(
+            Some("<@U0ALICE> :white_check_mark: <!here>".to_string()),
+            to_markdown(&blocks)
+        );
+    }
+
+    #[test]
+    fn gives_none_without_rich_text_blocks() {
+        let blocks: Vec<Block> =
+            serde_json::from_value(json!([{ "type": "divider", "block_id": "d" }]))
+                .expect("Expected a valid block");
+
+        assert_eq!(None, to_markdown(&blocks));
+    }
+}
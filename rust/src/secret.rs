@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Display};
+
+/// Characters of a secret left readable when it is written out, enough to tell
+/// a token, `xoxc`, from a cookie, `xoxd`
+const VISIBLE_PREFIX_CHARS: usize = 4;
+
+/// A credential that never shows in logs or error messages: Debug and Display
+/// write only its first characters, e.g. `xoxc-…`, so console logs can be pasted
+/// into bug reports. Serializes as the value it holds
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Secret<T> {
+        Secret(value)
+    }
+
+    /// The credential itself, only for building requests
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Secret<T>
+where
+    T: AsRef<str>,
+{
+    fn masked(&self) -> String {
+        let value = self.0.as_ref();
+        if value.is_empty() {
+            return String::new();
+        }
+        let prefix = value.chars().take(VISIBLE_PREFIX_CHARS).collect::<String>();
+        format!("{}-…", prefix.trim_end_matches('-'))
+    }
+}
+
+impl From<String> for Secret<String> {
+    fn from(value: String) -> Secret<String> {
+        Secret(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Secret<String> {
+        Secret(value.to_string())
+    }
+}
+
+impl<T> Debug for Secret<T>
+where
+    T: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({:?})", self.masked())
+    }
+}
+
+impl<T> Display for Secret<T>
+where
+    T: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.masked())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Tests that credentials stay out of logs and error messages, run with
+    //! `cargo test`. The plugin's console is pasted into bug reports, so only the
+    //! first characters of a token or cookie may show
+
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        finalize_options::FinalizeOptions,
+        secret::Secret,
+        slack_http_client::{
+            ApiBudget, Error, RequestUrlParam, SlackHttpClient, SlackHttpClientConfig,
+            SlackHttpClientConfigFeatureFlags, SlackHttpClientConfigOptions,
+        },
+    };
+    use futures::future::{self, FutureExt, LocalBoxFuture};
+
+    const TOKEN: &str = "xoxc-1234-secret-token";
+    const COOKIE: &str = "xoxd-secret%2Bcookie";
+
+    fn config(token: &str, cookie: &str) -> Result<SlackHttpClientConfig, Error> {
+        SlackHttpClientConfig::new(
+            url::Url::parse("https://slack.com/api").unwrap(),
+            token.to_string(),
+            cookie.to_string(),
+            SlackHttpClientConfigFeatureFlags {
+                get_users: false,
+                get_channel_info: false,
+                get_team_info: false,
+                get_file_data: false,
+            },
+            SlackHttpClientConfigOptions::default(),
+        )
+    }
+
+    #[test]
+    fn secret_shows_only_its_first_characters() {
+        let secret = Secret::from(TOKEN);
+
+        assert_eq!("xoxc-…", secret.to_string());
+        assert_eq!("Secret(\"xoxc-…\")", format!("{:?}", secret));
+        assert_eq!(TOKEN, secret.expose());
+        assert_eq!(
+            format!("\"{}\"", TOKEN),
+            serde_json::to_string(&secret).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_credentials_are_masked_in_errors() {
+        let token_error = config("secret-token", COOKIE).unwrap_err();
+        let cookie_error = config(TOKEN, "secret-cookie").unwrap_err();
+
+        for error in [token_error, cookie_error] {
+            assert!(!error.to_string().contains("secret"), "{}", error);
+            assert!(!format!("{:?}", error).contains("secret"), "{:?}", error);
+        }
+    }
+
+    #[async_std::test]
+    async fn requests_mask_credentials_when_logged_but_send_them() {
+        let requests = Rc::new(RefCell::new(vec![]));
+        let client_requests = requests.clone();
+        let request_func =
+            move |params: RequestUrlParam| -> LocalBoxFuture<'static, Result<String, String>> {
+                client_requests.borrow_mut().push(params);
+                future::ready(Ok("{\"ok\":true}".to_string())).boxed_local()
+            };
+        let client = SlackHttpClient::new(
+            config(TOKEN, COOKIE).expect("Should build config"),
+            Box::new(request_func),
+            Rc::new(ApiBudget::new(None)),
+        );
+
+        client
+            .get_conversations_info("C123")
+            .await
+            .expect("Should get a response");
+        client
+            .get_emoji_list()
+            .await
+            .expect("Should get a response");
+
+        for request in requests.borrow().iter() {
+            let logged = format!("{:?}{:#?}{}", request, request, request);
+            assert!(!logged.contains("secret"), "{}", logged);
+
+            let sent = serde_json::to_string(request).unwrap();
+            assert!(sent.contains(COOKIE), "{}", sent);
+            assert!(sent.contains(TOKEN), "{}", sent);
+        }
+    }
+
+    #[test]
+    fn the_encryption_passphrase_is_masked_in_options() {
+        let options: FinalizeOptions =
+            serde_json::from_str("{\"encryption_passphrase\": \"my secret passphrase\"}").unwrap();
+
+        let logged = format!("{:?}{:#?}{}", options, options, options);
+        assert!(!logged.contains("secret"), "{}", logged);
+        assert_eq!(
+            Some("my secret passphrase"),
+            options
+                .encryption_passphrase
+                .as_ref()
+                .map(|passphrase| passphrase.expose().as_str())
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use amplify_derive::Display;
+use serde::{Deserialize, Serialize};
+use snafu::{ensure, ResultExt, Snafu};
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::slack_http_client::SlackApiEndpoints;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("Could not parse the cache to import - source: {source}"))]
+    CouldNotParseCache { source: serde_json::Error },
+
+    #[snafu(display(
+        "The cache to import has version {version}, only version {CACHE_FORMAT_VERSION} is supported"
+    ))]
+    UnsupportedCacheVersion { version: u32 },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Version of [`PersistedCache`], bumped whenever its format changes
+pub const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// How long imported entries stay usable unless the plugin asks otherwise, a week
+pub const DEFAULT_CACHE_TTL_SECONDS: f64 = 7.0 * 24.0 * 60.0 * 60.0;
+
+thread_local! {
+    // wasm runs on a single thread, so a thread local lives as long as the plugin
+    // and needs no locking
+    static SESSION_CACHE: RefCell<SessionCache> = RefCell::new(SessionCache::default());
+}
+
+/// Slack responses kept across calls while the `session_cache` option is on, so
+/// saving several threads in one obsidian session does not fetch the same users,
+/// teams and channels again
+#[derive(Debug, Default)]
+struct SessionCache {
+    /// Responses per endpoint, keyed by
+    /// [`crate::slack_http_client::RequestUrlParam`]'s cache key
+    responses: HashMap<SlackApiEndpoints, HashMap<String, CachedResponse>>,
+    hits: usize,
+    misses: usize,
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    /// The raw response string
+    response: String,
+    /// `Date.now()` when the response was received
+    cached_at_ms: f64,
+}
+
+/// The session cache as the plugin stores it between obsidian restarts, see
+/// [`crate::export_cache`]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Display)]
+#[display(Debug)]
+pub struct PersistedCache {
+    pub version: u32,
+    pub entries: Vec<PersistedCacheEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Display)]
+#[display(Debug)]
+pub struct PersistedCacheEntry {
+    /// e.g. `users.info`
+    pub endpoint: String,
+    pub cache_key: String,
+    pub response: String,
+    pub cached_at_ms: f64,
+}
+
+/// Size and hit rate of the session cache, see [`crate::cache_stats`]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct SessionCacheStats {
+    pub users: usize,
+    pub teams: usize,
+    pub channels: usize,
+    /// Requests answered from the cache
+    pub hits: usize,
+    /// Requests to cached endpoints that had to be sent to slack
+    pub misses: usize,
+}
+
+/// Whether responses of the endpoint are kept. Only endpoints describing things
+/// that rarely change are, including the channel listing `#name`s are looked up
+/// in
+pub fn is_cached_endpoint(endpoint: SlackApiEndpoints) -> bool {
+    matches!(
+        endpoint,
+        SlackApiEndpoints::UsersInfo
+            | SlackApiEndpoints::TeamInfo
+            | SlackApiEndpoints::ConversationsInfo
+            | SlackApiEndpoints::ConversationsList
+    )
+}
+
+/// The kept response for the request, counting the lookup as a hit or miss
+pub fn get(endpoint: SlackApiEndpoints, cache_key: &str) -> Option<String> {
+    SESSION_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let response = cache
+            .responses
+            .get(&endpoint)
+            .and_then(|responses| responses.get(cache_key))
+            .map(|cached| cached.response.clone());
+        if response.is_some() {
+            cache.hits += 1;
+        } else {
+            cache.misses += 1;
+        }
+        response
+    })
+}
+
+pub fn insert(endpoint: SlackApiEndpoints, cache_key: String, response: String) {
+    SESSION_CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .responses
+            .entry(endpoint)
+            .or_default()
+            .insert(
+                cache_key,
+                CachedResponse {
+                    response,
+                    cached_at_ms: js_sys::Date::now(),
+                },
+            );
+    })
+}
+
+/// Drops every kept response and resets the hit and miss counts
+pub fn clear() {
+    SESSION_CACHE.with(|cache| *cache.borrow_mut() = SessionCache::default())
+}
+
+pub fn stats() -> SessionCacheStats {
+    SESSION_CACHE.with(|cache| {
+        let cache = cache.borrow();
+        let count = |endpoint| cache.responses.get(&endpoint).map_or(0, HashMap::len);
+        SessionCacheStats {
+            users: count(SlackApiEndpoints::UsersInfo),
+            teams: count(SlackApiEndpoints::TeamInfo),
+            channels: count(SlackApiEndpoints::ConversationsInfo),
+            hits: cache.hits,
+            misses: cache.misses,
+        }
+    })
+}
+
+/// Every kept response with the time it was received
+pub fn export() -> PersistedCache {
+    SESSION_CACHE.with(|cache| PersistedCache {
+        version: CACHE_FORMAT_VERSION,
+        entries: cache
+            .borrow()
+            .responses
+            .iter()
+            .flat_map(|(endpoint, responses)| {
+                responses
+                    .iter()
+                    .map(move |(cache_key, cached)| PersistedCacheEntry {
+                        endpoint: endpoint.to_string(),
+                        cache_key: cache_key.clone(),
+                        response: cached.response.clone(),
+                        cached_at_ms: cached.cached_at_ms,
+                    })
+            })
+            .collect(),
+    })
+}
+
+/// Adds the entries of an exported cache that are younger than `ttl_seconds`,
+/// keeping whichever of two entries for the same request is newer. Entries of
+/// endpoints that are not cached are dropped. Returns the number of entries added
+pub fn import(json: &str, ttl_seconds: f64) -> Result<usize> {
+    let persisted =
+        serde_json::from_str::<PersistedCache>(json).context(CouldNotParseCacheSnafu)?;
+    ensure!(
+        persisted.version == CACHE_FORMAT_VERSION,
+        UnsupportedCacheVersionSnafu {
+            version: persisted.version
+        }
+    );
+
+    let oldest_allowed_ms = js_sys::Date::now() - ttl_seconds * 1000.0;
+    SESSION_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let mut imported = 0;
+        for entry in persisted.entries {
+            let endpoint = match entry.endpoint.parse::<SlackApiEndpoints>() {
+                Ok(endpoint) if is_cached_endpoint(endpoint) => endpoint,
+                _ => continue,
+            };
+            let responses = cache.responses.entry(endpoint).or_default();
+            let is_stale = entry.cached_at_ms < oldest_allowed_ms
+                || responses
+                    .get(&entry.cache_key)
+                    .is_some_and(|cached| cached.cached_at_ms >= entry.cached_at_ms);
+            if is_stale {
+                continue;
+            }
+            responses.insert(
+                entry.cache_key,
+                CachedResponse {
+                    response: entry.response,
+                    cached_at_ms: entry.cached_at_ms,
+                },
+            );
+            imported += 1;
+        }
+        Ok(imported)
+    })
+}
@@ -1,19 +1,45 @@
 use amplify_derive::Display;
 use derive_builder::Builder;
+use futures::future::{FutureExt, LocalBoxFuture};
+use js_sys::{ArrayBuffer, Promise, Uint8Array};
 use serde::{Deserialize, Serialize};
 use snafu::{ensure, Snafu};
-use std::{borrow::Borrow, collections::HashMap, fmt::Debug, str::FromStr};
+use std::{
+    borrow::Borrow,
+    cell::{Cell, RefCell},
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap},
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    str::FromStr,
+};
 use url::Url;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{
+    attachments::SavedContents,
+    fetch_stats::FILES_STATS_KEY,
+    memory::MemoryLimitAction,
+    messages::{FilterProfile, MessageFilters},
+    response::{self, SlackResponseValidator},
+    secret::Secret,
+};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display(
         "Provided api token was invalid. Api token must start with 'xoxc': {api_token}"
     ))]
-    InvalidSlackApiToken { api_token: String },
+    InvalidSlackApiToken { api_token: Secret<String> },
 
     #[snafu(display("Provided api cookie was invalid. Cookie must start with 'xoxd': {cookie}"))]
-    InvalidSlackApiCookie { cookie: String },
+    InvalidSlackApiCookie { cookie: Secret<String> },
+
+    #[snafu(display(
+        "Provided api base was invalid. Api base must be an https url that can have a path: {api_base}"
+    ))]
+    InvalidSlackApiBase { api_base: String },
 }
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -21,7 +47,49 @@ pub fn get_api_base() -> Url {
     Url::from_str("https://slack.com/api").unwrap()
 }
 
-fn validate_slack_api_token(api_token: &str) -> Result<&str> {
+/// Workspace hosts that are served by an api other than `slack.com`, mapped to
+/// that api's base
+const API_BASES_BY_HOST_SUFFIX: [(&str, &str); 1] =
+    [("slack-gov.com", "https://slack-gov.com/api")];
+
+/// Picks the api base to talk to. An explicitly configured `api_base_override`
+/// wins, otherwise it is inferred from the host of the pasted slack url, falling
+/// back to [`get_api_base`]
+pub fn resolve_api_base(
+    api_base_override: Option<&str>,
+    slack_url_host: Option<&str>,
+) -> Result<Url> {
+    let log_prefix = "rust|resolve_api_base";
+    log::info!(
+        "{}|api_base_override={:?}|slack_url_host={:?}",
+        &log_prefix,
+        api_base_override,
+        slack_url_host
+    );
+
+    if let Some(api_base) = api_base_override {
+        return validate_slack_api_base(api_base);
+    }
+
+    Ok(slack_url_host
+        .and_then(|host| {
+            API_BASES_BY_HOST_SUFFIX
+                .iter()
+                .find(|(suffix, _)| host == *suffix || host.ends_with(&format!(".{}", suffix)))
+        })
+        .map_or_else(get_api_base, |(_, api_base)| {
+            Url::from_str(api_base).unwrap()
+        }))
+}
+
+fn validate_slack_api_base(api_base: &str) -> Result<Url> {
+    let url = Url::from_str(api_base)
+        .ok()
+        .filter(|url| url.scheme() == "https" && url.has_host() && !url.cannot_be_a_base());
+    url.map_or(InvalidSlackApiBaseSnafu { api_base }.fail(), Ok)
+}
+
+pub(crate) fn validate_slack_api_token(api_token: &str) -> Result<&str> {
     ensure!(
         api_token.starts_with("xoxc"),
         InvalidSlackApiTokenSnafu { api_token }
@@ -29,7 +97,7 @@ fn validate_slack_api_token(api_token: &str) -> Result<&str> {
     Ok(api_token)
 }
 
-fn validate_slack_api_cookie(cookie: &str) -> Result<&str> {
+pub(crate) fn validate_slack_api_cookie(cookie: &str) -> Result<&str> {
     ensure!(
         cookie.starts_with("xoxd"),
         InvalidSlackApiCookieSnafu { cookie }
@@ -37,53 +105,390 @@ fn validate_slack_api_cookie(cookie: &str) -> Result<&str> {
     Ok(cookie)
 }
 
-#[derive(Debug, Serialize, Deserialize, Display)]
+#[derive(Serialize, Deserialize, Clone, Display)]
 #[display(Debug)]
 pub struct RequestUrlParam {
     url: String,
     method: String,
     headers: HashMap<String, String>,
     body: Option<String>,
+    /// See [`RequestUrlParam::request_id`]
+    #[serde(default)]
+    request_id: String,
+    /// See [`RequestUrlParam::response_type`]
+    #[serde(default)]
+    response_type: ResponseType,
+    /// See [`RequestUrlParam::max_response_bytes`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_response_bytes: Option<u64>,
+}
+
+/// Start of the error a `request_func` rejects with for a response larger than
+/// [`RequestUrlParam::max_response_bytes`], e.g. `response_too_large: 1048577 bytes`
+pub const RESPONSE_TOO_LARGE_ERROR: &str = "response_too_large";
+
+/// What the `request_func` resolves to for a request
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseType {
+    /// The body as a string, for slack's json responses
+    #[default]
+    Text,
+    /// The body as a `Uint8Array`, for files, which a string would corrupt
+    ArrayBuffer,
+}
+
+/// Requests are logged before they are sent, so the credentials in their headers
+/// and body are masked, see [`Secret`]
+impl Debug for RequestUrlParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let headers = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value = match name.as_str() {
+                    "cookie" => mask_after(value, "d="),
+                    "authorization" => mask_after(value, "Bearer "),
+                    _ => value.to_string(),
+                };
+                (name, value)
+            })
+            .collect::<HashMap<&String, String>>();
+        f.debug_struct("RequestUrlParam")
+            .field("url", &self.url)
+            .field("method", &self.method)
+            .field("headers", &headers)
+            .field(
+                "body",
+                &self.body.as_deref().map(|body| mask_after(body, "token=")),
+            )
+            .field("request_id", &self.request_id)
+            .field("response_type", &self.response_type)
+            .field("max_response_bytes", &self.max_response_bytes)
+            .finish()
+    }
+}
+
+/// `value` with whatever follows `prefix` masked, or all of it without `prefix`
+fn mask_after(value: &str, prefix: &str) -> String {
+    match value.strip_prefix(prefix) {
+        Some(secret) => format!("{}{}", prefix, Secret::from(secret)),
+        None => Secret::from(value).to_string(),
+    }
 }
 
 impl RequestUrlParam {
     fn with_url(mut self, url: String) -> Self {
+        let mut hasher = DefaultHasher::new();
+        (&self.method, &url).hash(&mut hasher);
+        self.request_id = format!("{:016x}", hasher.finish());
         self.url = url;
         self
     }
+
+    /// Identifies the logical call of the request: requests asking slack the same,
+    /// e.g. a retry with refreshed credentials, have the same id. Logged with the
+    /// request and sent to the `request_func`
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Whether the `request_func` should resolve to the body's text or bytes
+    pub fn response_type(&self) -> ResponseType {
+        self.response_type
+    }
+
+    /// Largest body the `request_func` should read. For a larger one it rejects
+    /// with [`RESPONSE_TOO_LARGE_ERROR`] instead, as soon as its content-length or
+    /// the part read so far shows it, so the body is never held whole
+    pub fn max_response_bytes(&self) -> Option<u64> {
+        self.max_response_bytes
+    }
+
+    /// `GET` or `POST`
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn headers(&self) -> &HashMap<String, String> {
+        &self.headers
+    }
+
+    /// Form encoded, only for `POST` requests
+    pub fn body(&self) -> Option<&str> {
+        self.body.as_deref()
+    }
+
+    /// The slack method the request calls, e.g. `users.info`
+    pub fn endpoint(&self) -> Option<String> {
+        Url::parse(&self.url)
+            .ok()?
+            .path_segments()?
+            .next_back()
+            .map(str::to_string)
+    }
+
+    pub(crate) fn slack_api_endpoint(&self) -> Option<SlackApiEndpoints> {
+        self.endpoint()?.parse().ok()
+    }
+
+    /// Key the request is counted under in [`crate::fetch_stats::FetchStats`]: the
+    /// slack method, or [`FILES_STATS_KEY`] for a file download. None for anything
+    /// else
+    pub(crate) fn stats_key(&self) -> Option<String> {
+        match self.response_type {
+            ResponseType::ArrayBuffer => Some(FILES_STATS_KEY.to_string()),
+            ResponseType::Text => self
+                .slack_api_endpoint()
+                .map(|endpoint| endpoint.to_string()),
+        }
+    }
+
+    /// Requests with the same key ask slack the same, so one response can answer
+    /// all of them. None for requests that change something in slack
+    pub(crate) fn cache_key(&self) -> Option<String> {
+        self.slack_api_endpoint()
+            .filter(SlackApiEndpoints::is_read_only)
+            .map(|_| format!("{} {}", self.method, self.url))
+    }
+
+    /// Swaps the token and cookie of an already built request, keeping the
+    /// request strategy it was built with
+    pub(crate) fn with_credentials(mut self, credentials: &SlackCredentials) -> Self {
+        self.headers.insert(
+            "cookie".to_string(),
+            format!("d={}", credentials.cookie.expose()),
+        );
+        if self.headers.contains_key("authorization") {
+            self.headers.insert(
+                "authorization".to_string(),
+                format!("Bearer {}", credentials.token.expose()),
+            );
+        }
+        if self.body.is_some() {
+            self.body = Some(format!("token={}", credentials.token.expose()));
+        }
+        self
+    }
+}
+
+/// A token and cookie pair, as returned by the plugin's `refresh_credentials`
+/// callback when Slack rejects the current ones
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct SlackCredentials {
+    pub token: Secret<String>,
+    pub cookie: Secret<String>,
+}
+
+impl SlackCredentials {
+    pub fn validate(self) -> Result<SlackCredentials> {
+        validate_slack_api_token(self.token.expose())?;
+        validate_slack_api_cookie(self.cookie.expose())?;
+        Ok(self)
+    }
 }
 
 #[derive(Builder, Debug, Clone)]
 pub struct SlackHttpClientConfig {
     api_base: url::Url,
-    token: String,
-    cookie: String,
+    token: Secret<String>,
+    cookie: Secret<String>,
     pub feature_flags: SlackHttpClientConfigFeatureFlags,
+    #[builder(default)]
+    pub options: SlackHttpClientConfigOptions,
 }
 
-#[derive(Debug, Serialize, Deserialize, Builder, Clone, Display)]
+#[derive(Debug, Serialize, Deserialize, Builder, Clone, Default, Display)]
 #[display(Debug)]
 pub struct SlackHttpClientConfigFeatureFlags {
+    /// Turned on during the run when `get_channel_info` finds the conversation is a
+    /// direct message, so its note says who it was with
     pub get_users: bool,
     pub get_channel_info: bool,
     pub get_team_info: bool,
+    /// Collect the files of the thread into `file_links`, for the plugin to
+    /// download. With the `embed_file_data` option they are downloaded here too
     pub get_file_data: bool,
 }
 
+/// Optional settings that tune how the client talks to Slack and what it fetches.
+/// Every field has a default so the plugin only needs to pass the ones it cares
+/// about. How the result becomes a note is set by the
+/// [`crate::finalize_options::FinalizeOptions`] of the same `options` object
+#[derive(Debug, Serialize, Deserialize, Builder, Clone, Default, Display)]
+#[display(Debug)]
+#[serde(default)]
+#[builder(default)]
+pub struct SlackHttpClientConfigOptions {
+    /// Forces every endpoint to use the given request strategy, for environments
+    /// where a proxy blocks one of the two styles
+    pub request_strategy: Option<SlackApiRequestStrategy>,
+
+    /// Api base to use instead of the one inferred from the slack url, e.g.
+    /// `https://slack-gov.com/api`
+    pub api_base: Option<String>,
+
+    /// Number of distinct users from which paging through `users.list` is used
+    /// instead of one `users.info` call per user, see
+    /// [`crate::users::DEFAULT_USERS_LIST_THRESHOLD`]
+    pub users_list_threshold: Option<usize>,
+
+    /// List the members of usergroups mentioned in the thread, see
+    /// [`crate::components::ObsidianSlackComponents::mentioned_groups`]
+    pub expand_usergroups: bool,
+
+    /// Record the reminders you set on the thread's messages, see
+    /// [`crate::components::ObsidianSlackComponents::reminders`]
+    pub include_reminders: bool,
+
+    /// Record whether each participant was active or away, and their status, at
+    /// the time of the save, see [`crate::users::Participant::presence`]
+    pub capture_presence: bool,
+
+    /// Fetch the name and icon of the bots that wrote messages, see
+    /// [`crate::messages::Message::bot_info`]
+    pub include_bot_profiles: bool,
+
+    /// Bookmark the saved note in the slack channel, see
+    /// [`crate::add_slack_bookmark`]
+    pub bookmark_note_in_channel: bool,
+
+    /// Predefined filters applied to every thread, on top of `message_filters`
+    pub filter_profile: FilterProfile,
+
+    /// Messages to drop from the thread before users are fetched, see
+    /// [`MessageFilters`]
+    pub message_filters: MessageFilters,
+
+    /// Memory in bytes a channel export may need, see
+    /// [`crate::memory::DEFAULT_MEMORY_LIMIT_BYTES`]
+    pub memory_limit_bytes: Option<usize>,
+
+    /// Whether a channel export over `memory_limit_bytes` warns or stops early
+    pub memory_limit_action: MemoryLimitAction,
+
+    /// Milliseconds a channel export may spend paging through the channel's
+    /// history before it stops and returns a continuation token
+    pub export_time_budget_ms: Option<f64>,
+
+    /// Root messages a channel export gets from the channel's history before it
+    /// stops and returns a continuation token. Checked after every page
+    pub export_message_budget: Option<usize>,
+
+    /// Token of the export part before, to continue where it stopped, see
+    /// [`crate::continuation::ContinuationToken`]
+    pub continuation_token: Option<String>,
+
+    /// Export only the messages of a channel after your last read one, when it is
+    /// later than the start of the period, to catch up on a channel and archive it
+    pub only_unread: bool,
+
+    /// Last read message for `only_unread`, e.g. `1700000000.000100`, instead of
+    /// the channel's `last_read` from `conversations.info`
+    pub last_read_ts: Option<String>,
+
+    /// Mark the channel as read up to the newest exported message, see
+    /// [`crate::mark_channel_read`]. Off by default, it changes your unread state
+    /// in slack
+    pub mark_read_after_export: bool,
+
+    /// Allow setting slack reminders, see [`crate::add_reminder`]. Off by default,
+    /// it adds to your reminders in slack
+    pub allow_add_reminder: bool,
+
+    /// Whether direct messages, one-to-one or group, may be saved. Unset allows
+    /// them, see [`crate::channels::ensure_export_allowed`]
+    pub allow_dm_export: Option<bool>,
+
+    /// Language of error messages, e.g. `de` or `de-DE`, see
+    /// [`crate::i18n::Language`]. Defaults to English
+    pub locale: Option<String>,
+
+    /// Level to log at during this call only, e.g. `debug` to look into a single
+    /// thread. Defaults to the level set with [`crate::set_log_level`]
+    pub log_level: Option<String>,
+
+    /// Return the steps and api calls a save would take instead of saving, see
+    /// [`crate::dry_run::DryRunPlan`]
+    pub dry_run: bool,
+
+    /// Most requests one call may send, see [`ApiBudget`]. Unlimited by default
+    pub max_api_calls: Option<usize>,
+
+    /// Download the files in `file_links` too and put them in `files_data`, for
+    /// saving without the plugin, e.g. with the cli. The plugin downloads them
+    /// itself. Only used with the `get_file_data` feature flag, which is what
+    /// collects `file_links`, so without it no file is downloaded
+    pub embed_file_data: bool,
+
+    /// Remove the Exif, XMP and IPTC metadata, e.g. where a photo was taken, of
+    /// the jpeg and png images in `files_data`, so it does not end up in a synced
+    /// vault
+    pub strip_image_metadata: bool,
+
+    /// Leave files with the same content as one saved before out of `files_data`,
+    /// point their `file_links` at the name of the one that was saved and list
+    /// them in `duplicate_files`, e.g. an image reshared in the threads of a
+    /// batch. Contents are hashed as they are downloaded, so this needs
+    /// `embed_file_data`. Without it, e.g. in the plugin, which downloads the
+    /// files itself, nothing is deduped
+    pub dedupe_file_data: bool,
+
+    /// Contents saved by the saves before this one in a batch, the
+    /// `saved_contents` they returned, see `dedupe_file_data`. Only what is
+    /// passed here counts, so a file deleted since is saved again when it is left
+    /// out
+    pub saved_contents: SavedContents,
+
+    /// Files larger than this are left out of `file_links`, so the plugin does not
+    /// download them, and listed in `skipped_files` instead. Defaults to
+    /// [`crate::messages::DEFAULT_MAX_FILE_SIZE_BYTES`]
+    pub max_file_size_bytes: Option<u64>,
+
+    /// Most requests per minute to send to each method or rate limit tier, e.g.
+    /// `{"conversations.replies": 10, "tier2": 5}`. Tiers not given are paced at
+    /// the rate slack documents for them, see [`SlackApiTier`]. Calls running at
+    /// the same time, such as the saves of a batch, share these limits, so
+    /// together they stay within slack's rate limits. Requests over a limit wait,
+    /// see [`crate::rate_limit::RateLimiter`]
+    pub requests_per_minute: BTreeMap<String, u32>,
+
+    /// Send requests as fast as they come unless their method or tier is in
+    /// `requests_per_minute`
+    pub disable_rate_tier_pacing: bool,
+
+    /// Milliseconds a request that changes something in slack, e.g.
+    /// `bookmarks.add`, is answered with the response of the same request sent
+    /// before, e.g. when the plugin retries a call that timed out while the first
+    /// one was still running. A request that failed is sent again. Off by default,
+    /// every request is sent
+    pub write_dedup_window_ms: Option<u64>,
+
+    /// Keep users, teams and channels fetched by one call for the following ones,
+    /// until [`crate::clear_cache`] is called or the plugin is reloaded
+    pub session_cache: bool,
+}
+
 impl SlackHttpClientConfig {
     pub fn new(
         api_base: Url,
         token: String,
         cookie: String,
         feature_flags: SlackHttpClientConfigFeatureFlags,
+        options: SlackHttpClientConfigOptions,
     ) -> Result<SlackHttpClientConfig> {
         let log_prefix = "rust|SlackHttpClientConfig|new";
         log::info!(
             "{}|api_base={}|token={}|cookie={}",
             &log_prefix,
             api_base,
-            &token,
-            &cookie
+            Secret::from(token.as_str()),
+            Secret::from(cookie.as_str())
         );
 
         log::info!("{}|validate token", &log_prefix);
@@ -94,9 +499,10 @@ impl SlackHttpClientConfig {
 
         Ok(SlackHttpClientConfig {
             api_base,
-            token: token.to_string(),
-            cookie: cookie.to_string(),
+            token: Secret::from(token),
+            cookie: Secret::from(cookie),
             feature_flags,
+            options,
         })
     }
 }
@@ -109,11 +515,322 @@ pub enum SlackApiQueryParams {
     channel,
     inclusive,
     pretty,
+    include_locale,
+    user,
+    cursor,
+    limit,
+    usergroup,
+    channel_id,
+    title,
+    #[strum(serialize = "type")]
+    r#type,
+    link,
+    oldest,
+    latest,
+    bot,
+    types,
+    exclude_archived,
+    text,
+    time,
+}
+
+/// How a request authenticates with Slack
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SlackApiRequestStrategy {
+    /// POST with the token in the form encoded body
+    Post,
+    /// GET with the token in a bearer authorization header
+    Get,
+}
+
+/// Members per `users.list` page, slack recommends no more than 200
+const USERS_LIST_PAGE_SIZE: &str = "200";
+
+impl SlackHttpClientConfigOptions {
+    /// Key and requests per minute of the limiter requests to `endpoint` wait for,
+    /// that of the endpoint itself when configured, otherwise that of its tier
+    pub fn rate_limit_of(&self, endpoint: &str) -> Option<(String, u32)> {
+        if let Some(limit) = self.requests_per_minute.get(endpoint) {
+            return Some((endpoint.to_string(), *limit));
+        }
+        let tier = SlackApiEndpoints::from_str(endpoint)
+            .ok()?
+            .tier()
+            .to_string();
+        match self.requests_per_minute.get(&tier) {
+            Some(limit) => Some((tier, *limit)),
+            None if self.disable_rate_tier_pacing => None,
+            None => {
+                let limit = SlackApiTier::from_str(&tier).ok()?.requests_per_minute();
+                Some((tier, limit))
+            }
+        }
+    }
+}
+
+/// Members per `conversations.members` page, slack recommends no more than 200
+const CONVERSATIONS_MEMBERS_PAGE_SIZE: &str = "200";
+
+/// Conversations per `users.conversations` and `conversations.list` page, slack
+/// recommends no more than 200
+const CONVERSATIONS_LIST_PAGE_SIZE: &str = "200";
+
+#[derive(
+    strum_macros::Display, strum_macros::EnumString, Debug, Clone, Copy, PartialEq, Eq, Hash,
+)]
+pub enum SlackApiEndpoints {
+    #[strum(serialize = "conversations.replies")]
+    ConversationsReplies,
+    #[strum(serialize = "conversations.history")]
+    ConversationsHistory,
+    #[strum(serialize = "conversations.info")]
+    ConversationsInfo,
+    #[strum(serialize = "conversations.members")]
+    ConversationsMembers,
+    #[strum(serialize = "users.info")]
+    UsersInfo,
+    #[strum(serialize = "users.list")]
+    UsersList,
+    #[strum(serialize = "users.getPresence")]
+    UsersGetPresence,
+    #[strum(serialize = "team.info")]
+    TeamInfo,
+    #[strum(serialize = "bots.info")]
+    BotsInfo,
+    #[strum(serialize = "usergroups.users.list")]
+    UsergroupsUsersList,
+    #[strum(serialize = "reminders.list")]
+    RemindersList,
+    #[strum(serialize = "reminders.add")]
+    RemindersAdd,
+    #[strum(serialize = "bookmarks.add")]
+    BookmarksAdd,
+    #[strum(serialize = "conversations.mark")]
+    ConversationsMark,
+    #[strum(serialize = "users.conversations")]
+    UsersConversations,
+    #[strum(serialize = "conversations.list")]
+    ConversationsList,
+    #[strum(serialize = "emoji.list")]
+    EmojiList,
+}
+
+impl SlackApiEndpoints {
+    /// The request strategy each endpoint uses unless overridden by config
+    pub fn default_request_strategy(&self) -> SlackApiRequestStrategy {
+        match self {
+            SlackApiEndpoints::ConversationsReplies
+            | SlackApiEndpoints::ConversationsHistory
+            | SlackApiEndpoints::BookmarksAdd
+            | SlackApiEndpoints::ConversationsMark
+            | SlackApiEndpoints::RemindersAdd => SlackApiRequestStrategy::Post,
+            SlackApiEndpoints::ConversationsInfo
+            | SlackApiEndpoints::ConversationsMembers
+            | SlackApiEndpoints::UsersInfo
+            | SlackApiEndpoints::UsersList
+            | SlackApiEndpoints::UsersGetPresence
+            | SlackApiEndpoints::TeamInfo
+            | SlackApiEndpoints::BotsInfo
+            | SlackApiEndpoints::UsergroupsUsersList
+            | SlackApiEndpoints::RemindersList
+            | SlackApiEndpoints::UsersConversations
+            | SlackApiEndpoints::ConversationsList
+            | SlackApiEndpoints::EmojiList => SlackApiRequestStrategy::Get,
+        }
+    }
+
+    /// See https://api.slack.com/methods
+    pub fn tier(&self) -> SlackApiTier {
+        match self {
+            SlackApiEndpoints::UsersList
+            | SlackApiEndpoints::UsergroupsUsersList
+            | SlackApiEndpoints::RemindersList
+            | SlackApiEndpoints::RemindersAdd
+            | SlackApiEndpoints::BookmarksAdd
+            | SlackApiEndpoints::ConversationsList
+            | SlackApiEndpoints::EmojiList => SlackApiTier::Tier2,
+            SlackApiEndpoints::ConversationsReplies
+            | SlackApiEndpoints::ConversationsHistory
+            | SlackApiEndpoints::ConversationsInfo
+            | SlackApiEndpoints::UsersGetPresence
+            | SlackApiEndpoints::TeamInfo
+            | SlackApiEndpoints::BotsInfo
+            | SlackApiEndpoints::ConversationsMark
+            | SlackApiEndpoints::UsersConversations => SlackApiTier::Tier3,
+            SlackApiEndpoints::ConversationsMembers | SlackApiEndpoints::UsersInfo => {
+                SlackApiTier::Tier4
+            }
+        }
+    }
+
+    /// Whether calling the endpoint only reads from slack, so calling it again with
+    /// the same params within one call returns the same
+    pub fn is_read_only(&self) -> bool {
+        !matches!(
+            self,
+            SlackApiEndpoints::BookmarksAdd
+                | SlackApiEndpoints::ConversationsMark
+                | SlackApiEndpoints::RemindersAdd
+        )
+    }
+}
+
+/// Slack's rate limit tiers of its methods, see
+/// https://api.slack.com/apis/rate-limits
+#[derive(
+    strum_macros::Display, strum_macros::EnumString, Debug, Clone, Copy, PartialEq, Eq, Hash,
+)]
+pub enum SlackApiTier {
+    #[strum(serialize = "tier1")]
+    Tier1,
+    #[strum(serialize = "tier2")]
+    Tier2,
+    #[strum(serialize = "tier3")]
+    Tier3,
+    #[strum(serialize = "tier4")]
+    Tier4,
+}
+
+impl SlackApiTier {
+    /// The rate slack documents for the tier
+    pub fn requests_per_minute(&self) -> u32 {
+        match self {
+            SlackApiTier::Tier1 => 1,
+            SlackApiTier::Tier2 => 20,
+            SlackApiTier::Tier3 => 50,
+            SlackApiTier::Tier4 => 100,
+        }
+    }
+}
+
+/// Warning added to the result when requests were dropped because of the
+/// `max_api_calls` option
+pub const API_BUDGET_EXCEEDED_WARNING: &str = "api_budget_exceeded";
+
+/// Counts the requests of one call against the `max_api_calls` option. The
+/// request function checks it before every request, see
+/// [`crate::utils::limit_api_calls`]
+#[derive(Debug, Default)]
+pub struct ApiBudget {
+    max_calls: Option<usize>,
+    calls: Cell<usize>,
+    exceeded: Cell<bool>,
+}
+
+impl ApiBudget {
+    pub fn new(max_calls: Option<usize>) -> ApiBudget {
+        ApiBudget {
+            max_calls,
+            ..Default::default()
+        }
+    }
+
+    /// Counts a request. False if the budget is used up, the request must then not
+    /// be sent
+    pub fn spend(&self) -> bool {
+        if self
+            .max_calls
+            .is_some_and(|max_calls| self.calls.get() >= max_calls)
+        {
+            self.exceeded.set(true);
+            return false;
+        }
+        self.calls.set(self.calls.get() + 1);
+        true
+    }
+
+    /// Whether any request was refused
+    pub fn was_exceeded(&self) -> bool {
+        self.exceeded.get()
+    }
+}
+
+/// What the `request_func` of a [`SlackHttpClient`] returns. In wasm that is the
+/// promise of obsidian's `request` function, but the api functions work with any
+/// future of the response body, e.g. to run them in `cargo test` without a js
+/// runtime
+pub trait TransportResponse {
+    /// Resolves to the body of slack's response, or to why the request failed
+    fn into_body(self) -> LocalBoxFuture<'static, Result<String, String>>;
+
+    /// Resolves to the body of a [`ResponseType::ArrayBuffer`] request, e.g. a
+    /// file, or to why the request failed
+    fn into_bytes(self) -> LocalBoxFuture<'static, Result<Vec<u8>, String>>
+    where
+        Self: Sized,
+    {
+        self.into_body()
+            .map(|body| body.map(String::into_bytes))
+            .boxed_local()
+    }
+}
+
+impl TransportResponse for Promise {
+    fn into_body(self) -> LocalBoxFuture<'static, Result<String, String>> {
+        JsFuture::from(self)
+            .map(|result| match result {
+                Ok(val) => val.as_string().ok_or_else(|| {
+                    format!(
+                        "The response was not a string when it should have been: {:#?}",
+                        val
+                    )
+                }),
+                Err(err) => Err(format!("{:#?}", err)),
+            })
+            .boxed_local()
+    }
+
+    /// Accepts a `Uint8Array` or `ArrayBuffer`, and a string from a
+    /// `request_func` that ignores the `response_type`
+    fn into_bytes(self) -> LocalBoxFuture<'static, Result<Vec<u8>, String>> {
+        JsFuture::from(self)
+            .map(|result| match result {
+                Ok(val)
+                    if val.is_instance_of::<Uint8Array>()
+                        || val.is_instance_of::<ArrayBuffer>() =>
+                {
+                    Ok(Uint8Array::new(&val).to_vec())
+                }
+                Ok(val) => val.as_string().map(String::into_bytes).ok_or_else(|| {
+                    format!(
+                        "The response was not a Uint8Array when it should have been: {:#?}",
+                        val
+                    )
+                }),
+                Err(err) => Err(format!("{:#?}", err)),
+            })
+            .boxed_local()
+    }
+}
+
+impl TransportResponse for LocalBoxFuture<'static, Result<String, String>> {
+    fn into_body(self) -> LocalBoxFuture<'static, Result<String, String>> {
+        self
+    }
+}
+
+/// For transports that return bytes, such as the cli's, so files are not
+/// corrupted by decoding them as text
+impl TransportResponse for LocalBoxFuture<'static, Result<Vec<u8>, String>> {
+    fn into_body(self) -> LocalBoxFuture<'static, Result<String, String>> {
+        self.map(|body| {
+            body.and_then(|body| String::from_utf8(body).map_err(|err| err.to_string()))
+        })
+        .boxed_local()
+    }
+
+    fn into_bytes(self) -> LocalBoxFuture<'static, Result<Vec<u8>, String>> {
+        self
+    }
 }
 
 pub struct SlackHttpClient<ClientReturnType> {
     pub config: SlackHttpClientConfig,
+    pub api_budget: Rc<ApiBudget>,
     request_func: Box<dyn Fn(RequestUrlParam) -> ClientReturnType>,
+    warnings: RefCell<BTreeSet<String>>,
 }
 
 impl<ClientReturnType> Debug for SlackHttpClient<ClientReturnType> {
@@ -125,17 +842,49 @@ impl<ClientReturnType> Debug for SlackHttpClient<ClientReturnType> {
 }
 
 impl<ClientReturnType> SlackHttpClient<ClientReturnType> {
+    /// The `request_func` should check the `api_budget` before sending anything
     pub fn new(
         config: SlackHttpClientConfig,
         request_func: Box<dyn Fn(RequestUrlParam) -> ClientReturnType>,
+        api_budget: Rc<ApiBudget>,
     ) -> SlackHttpClient<ClientReturnType> {
         SlackHttpClient {
             config,
+            api_budget,
             request_func,
+            warnings: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    /// Validates the response, see [`SlackResponseValidator::validate_response`],
+    /// and keeps its warnings for [`SlackHttpClient::warnings`]
+    pub fn validate_response<R>(&self, response: R) -> Result<R, response::Error>
+    where
+        R: SlackResponseValidator + Debug,
+    {
+        if let Some(warning) = response.warning() {
+            log::warn!("rust|SlackHttpClient|validate_response|warning={}", warning);
+            self.warnings.borrow_mut().extend(
+                warning
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|warning| !warning.is_empty())
+                    .map(str::to_string),
+            );
         }
+        response.validate_response()
     }
 
-    fn build_request_uri<I, K, V>(&self, endpoint: &str, iter: I) -> Url
+    pub fn add_warning(&self, warning: &str) {
+        self.warnings.borrow_mut().insert(warning.to_string());
+    }
+
+    /// Every distinct warning of the responses validated so far
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.borrow().iter().cloned().collect()
+    }
+
+    fn build_request_uri<I, K, V>(&self, endpoint: SlackApiEndpoints, iter: I) -> Url
     where
         I: IntoIterator,
         I::Item: Borrow<(K, V)>,
@@ -147,7 +896,7 @@ impl<ClientReturnType> SlackHttpClient<ClientReturnType> {
         request_url
             .path_segments_mut()
             .expect("Expected a url that can be a base and has path segments, but did not get that. This is a bug")
-            .push(endpoint);
+            .push(endpoint.to_string().as_str());
 
         request_url
             .query_pairs_mut()
@@ -157,6 +906,20 @@ impl<ClientReturnType> SlackHttpClient<ClientReturnType> {
         request_url
     }
 
+    fn request_strategy_for(&self, endpoint: SlackApiEndpoints) -> SlackApiRequestStrategy {
+        self.config
+            .options
+            .request_strategy
+            .unwrap_or_else(|| endpoint.default_request_strategy())
+    }
+
+    fn build_base_request(&self, endpoint: SlackApiEndpoints) -> RequestUrlParam {
+        match self.request_strategy_for(endpoint) {
+            SlackApiRequestStrategy::Post => self.build_base_post_request(),
+            SlackApiRequestStrategy::Get => self.build_base_get_request(),
+        }
+    }
+
     fn build_base_post_request(&self) -> RequestUrlParam {
         RequestUrlParam {
             url: "".to_string(),
@@ -166,9 +929,15 @@ impl<ClientReturnType> SlackHttpClient<ClientReturnType> {
                     "content-type".to_string(),
                     "application/x-www-form-urlencoded".to_string(),
                 ),
-                ("cookie".to_string(), "d=".to_string() + &self.config.cookie),
+                (
+                    "cookie".to_string(),
+                    format!("d={}", self.config.cookie.expose()),
+                ),
             ]),
-            body: Some(format!("token={}", self.config.token)),
+            body: Some(format!("token={}", self.config.token.expose())),
+            request_id: String::new(),
+            response_type: ResponseType::Text,
+            max_response_bytes: None,
         }
     }
 
@@ -181,54 +950,212 @@ impl<ClientReturnType> SlackHttpClient<ClientReturnType> {
                     "content-type".to_string(),
                     "application/x-www-form-urlencoded".to_string(),
                 ),
-                ("cookie".to_string(), "d=".to_string() + &self.config.cookie),
+                (
+                    "cookie".to_string(),
+                    format!("d={}", self.config.cookie.expose()),
+                ),
                 (
                     "authorization".to_string(),
-                    format!("Bearer {}", self.config.token),
+                    format!("Bearer {}", self.config.token.expose()),
                 ),
             ]),
             body: None,
+            request_id: String::new(),
+            response_type: ResponseType::Text,
+            max_response_bytes: None,
         }
     }
 
-    pub fn get_conversations_replies(&self, channel_id: &str, timestamp: &str) -> ClientReturnType {
+    /// One page of the thread's messages, oldest first, the first page when
+    /// `cursor` is None
+    pub fn get_conversations_replies(
+        &self,
+        channel_id: &str,
+        timestamp: &str,
+        cursor: Option<&str>,
+    ) -> ClientReturnType {
         let log_prefix = "rust|get_conversations_replies";
         log::info!(
-            "{}|channel_id={}|timestamp={}",
+            "{}|channel_id={}|timestamp={}|cursor={:?}",
+            &log_prefix,
+            channel_id,
+            timestamp,
+            cursor
+        );
+
+        log::info!("{}|build request url", &log_prefix);
+        let mut query_params = vec![
+            (SlackApiQueryParams::channel.to_string(), channel_id),
+            (SlackApiQueryParams::ts.to_string(), timestamp),
+            (SlackApiQueryParams::inclusive.to_string(), "true"),
+        ];
+        if let Some(cursor) = cursor {
+            query_params.push((SlackApiQueryParams::cursor.to_string(), cursor));
+        }
+        let request_url =
+            self.build_request_uri(SlackApiEndpoints::ConversationsReplies, query_params);
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::ConversationsReplies)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    /// One page of the channel's messages between `oldest` and `latest`, newest
+    /// first, without thread replies
+    pub fn get_conversations_history(
+        &self,
+        channel_id: &str,
+        oldest: &str,
+        latest: &str,
+        cursor: Option<&str>,
+    ) -> ClientReturnType {
+        let log_prefix = "rust|get_conversations_history";
+        log::info!(
+            "{}|channel_id={}|oldest={}|latest={}|cursor={:?}",
             &log_prefix,
             channel_id,
-            timestamp
+            oldest,
+            latest,
+            cursor
         );
 
+        log::info!("{}|build request url", &log_prefix);
+        let mut query_params = vec![
+            (SlackApiQueryParams::channel.to_string(), channel_id),
+            (SlackApiQueryParams::oldest.to_string(), oldest),
+            (SlackApiQueryParams::latest.to_string(), latest),
+            (SlackApiQueryParams::inclusive.to_string(), "true"),
+        ];
+        if let Some(cursor) = cursor {
+            query_params.push((SlackApiQueryParams::cursor.to_string(), cursor));
+        }
+        let request_url =
+            self.build_request_uri(SlackApiEndpoints::ConversationsHistory, query_params);
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::ConversationsHistory)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_users_info(&self, user_id: &str) -> ClientReturnType {
+        let log_prefix = "rust|get_users_info";
+        log::info!("{}|user_id={}", &log_prefix, user_id);
+
         log::info!("{}|build request url", &log_prefix);
         let request_url = self.build_request_uri(
-            "conversations.replies",
+            SlackApiEndpoints::UsersInfo,
             vec![
-                (SlackApiQueryParams::channel.to_string(), channel_id),
-                (SlackApiQueryParams::ts.to_string(), timestamp),
-                (SlackApiQueryParams::inclusive.to_string(), "true"),
+                (SlackApiQueryParams::user.to_string(), user_id),
+                (SlackApiQueryParams::include_locale.to_string(), "true"),
             ],
         );
 
         log::info!("{}|build request object", &log_prefix);
         let the_request = self
-            .build_base_post_request()
+            .build_base_request(SlackApiEndpoints::UsersInfo)
             .with_url(request_url.to_string());
 
         log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
         (self.request_func)(the_request)
     }
 
-    pub fn get_users_info(&self, user_id: &str) -> ClientReturnType {
-        let log_prefix = "rust|get_users_info";
+    pub fn get_bots_info(&self, bot_id: &str) -> ClientReturnType {
+        let log_prefix = "rust|get_bots_info";
+        log::info!("{}|bot_id={}", &log_prefix, bot_id);
+
+        log::info!("{}|build request url", &log_prefix);
+        let request_url = self.build_request_uri(
+            SlackApiEndpoints::BotsInfo,
+            vec![(SlackApiQueryParams::bot.to_string(), bot_id)],
+        );
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::BotsInfo)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    /// Downloads a file of a message from its `url_private`, as bytes, failing
+    /// with [`RESPONSE_TOO_LARGE_ERROR`] once it is larger than `max_size` bytes
+    pub fn get_file(&self, file_url: &str, max_size: u64) -> ClientReturnType {
+        let log_prefix = "rust|get_file";
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = RequestUrlParam {
+            body: None,
+            response_type: ResponseType::ArrayBuffer,
+            max_response_bytes: Some(max_size),
+            ..self.build_base_get_request()
+        }
+        .with_url(file_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_emoji_list(&self) -> ClientReturnType {
+        let log_prefix = "rust|get_emoji_list";
+
+        log::info!("{}|build request url", &log_prefix);
+        let request_url =
+            self.build_request_uri(SlackApiEndpoints::EmojiList, Vec::<(String, &str)>::new());
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::EmojiList)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_users_presence(&self, user_id: &str) -> ClientReturnType {
+        let log_prefix = "rust|get_users_presence";
         log::info!("{}|user_id={}", &log_prefix, user_id);
 
         log::info!("{}|build request url", &log_prefix);
-        let request_url = self.build_request_uri("users.info", vec![("user", user_id)]);
+        let request_url = self.build_request_uri(
+            SlackApiEndpoints::UsersGetPresence,
+            vec![(SlackApiQueryParams::user.to_string(), user_id)],
+        );
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::UsersGetPresence)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_users_list(&self, cursor: Option<&str>) -> ClientReturnType {
+        let log_prefix = "rust|get_users_list";
+        log::info!("{}|cursor={:?}", &log_prefix, cursor);
+
+        log::info!("{}|build request url", &log_prefix);
+        let mut query_params = vec![
+            (SlackApiQueryParams::limit.to_string(), USERS_LIST_PAGE_SIZE),
+            (SlackApiQueryParams::include_locale.to_string(), "true"),
+        ];
+        if let Some(cursor) = cursor {
+            query_params.push((SlackApiQueryParams::cursor.to_string(), cursor));
+        }
+        let request_url = self.build_request_uri(SlackApiEndpoints::UsersList, query_params);
 
         log::info!("{}|build request object", &log_prefix);
         let the_request = self
-            .build_base_get_request()
+            .build_base_request(SlackApiEndpoints::UsersList)
             .with_url(request_url.to_string());
 
         log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
@@ -240,12 +1167,108 @@ impl<ClientReturnType> SlackHttpClient<ClientReturnType> {
         log::info!("{}|channel_id={}", &log_prefix, channel_id);
 
         log::info!("{}|build request url", &log_prefix);
+        let request_url = self.build_request_uri(
+            SlackApiEndpoints::ConversationsInfo,
+            vec![
+                (SlackApiQueryParams::channel.to_string(), channel_id),
+                (SlackApiQueryParams::include_locale.to_string(), "true"),
+            ],
+        );
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::ConversationsInfo)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_conversations_members(
+        &self,
+        channel_id: &str,
+        cursor: Option<&str>,
+    ) -> ClientReturnType {
+        let log_prefix = "rust|get_conversations_members";
+        log::info!(
+            "{}|channel_id={}|cursor={:?}",
+            &log_prefix,
+            channel_id,
+            cursor
+        );
+
+        log::info!("{}|build request url", &log_prefix);
+        let mut query_params = vec![
+            (SlackApiQueryParams::channel.to_string(), channel_id),
+            (
+                SlackApiQueryParams::limit.to_string(),
+                CONVERSATIONS_MEMBERS_PAGE_SIZE,
+            ),
+        ];
+        if let Some(cursor) = cursor {
+            query_params.push((SlackApiQueryParams::cursor.to_string(), cursor));
+        }
         let request_url =
-            self.build_request_uri("conversations.info", vec![("channel", channel_id)]);
+            self.build_request_uri(SlackApiEndpoints::ConversationsMembers, query_params);
 
         log::info!("{}|build request object", &log_prefix);
         let the_request = self
-            .build_base_get_request()
+            .build_base_request(SlackApiEndpoints::ConversationsMembers)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_users_conversations(&self, types: &str, cursor: Option<&str>) -> ClientReturnType {
+        let log_prefix = "rust|get_users_conversations";
+        log::info!("{}|types={}|cursor={:?}", &log_prefix, types, cursor);
+
+        log::info!("{}|build request url", &log_prefix);
+        let mut query_params = vec![
+            (SlackApiQueryParams::types.to_string(), types),
+            (SlackApiQueryParams::exclude_archived.to_string(), "true"),
+            (
+                SlackApiQueryParams::limit.to_string(),
+                CONVERSATIONS_LIST_PAGE_SIZE,
+            ),
+        ];
+        if let Some(cursor) = cursor {
+            query_params.push((SlackApiQueryParams::cursor.to_string(), cursor));
+        }
+        let request_url =
+            self.build_request_uri(SlackApiEndpoints::UsersConversations, query_params);
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::UsersConversations)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_conversations_list(&self, types: &str, cursor: Option<&str>) -> ClientReturnType {
+        let log_prefix = "rust|get_conversations_list";
+        log::info!("{}|types={}|cursor={:?}", &log_prefix, types, cursor);
+
+        log::info!("{}|build request url", &log_prefix);
+        let mut query_params = vec![
+            (SlackApiQueryParams::types.to_string(), types),
+            (
+                SlackApiQueryParams::limit.to_string(),
+                CONVERSATIONS_LIST_PAGE_SIZE,
+            ),
+        ];
+        if let Some(cursor) = cursor {
+            query_params.push((SlackApiQueryParams::cursor.to_string(), cursor));
+        }
+        let request_url =
+            self.build_request_uri(SlackApiEndpoints::ConversationsList, query_params);
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::ConversationsList)
             .with_url(request_url.to_string());
 
         log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
@@ -257,11 +1280,121 @@ impl<ClientReturnType> SlackHttpClient<ClientReturnType> {
         log::info!("{}|team_id={}", &log_prefix, team_id);
 
         log::info!("{}|build request url", &log_prefix);
-        let request_url = self.build_request_uri("team.info", vec![("team", team_id)]);
+        let request_url =
+            self.build_request_uri(SlackApiEndpoints::TeamInfo, vec![("team", team_id)]);
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::TeamInfo)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_usergroups_users_list(&self, usergroup_id: &str) -> ClientReturnType {
+        let log_prefix = "rust|get_usergroups_users_list";
+        log::info!("{}|usergroup_id={}", &log_prefix, usergroup_id);
+
+        log::info!("{}|build request url", &log_prefix);
+        let request_url = self.build_request_uri(
+            SlackApiEndpoints::UsergroupsUsersList,
+            vec![(SlackApiQueryParams::usergroup.to_string(), usergroup_id)],
+        );
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::UsergroupsUsersList)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn get_reminders_list(&self) -> ClientReturnType {
+        let log_prefix = "rust|get_reminders_list";
+
+        log::info!("{}|build request url", &log_prefix);
+        let request_url =
+            self.build_request_uri(SlackApiEndpoints::RemindersList, Vec::<(&str, &str)>::new());
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::RemindersList)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn add_bookmark(&self, channel_id: &str, title: &str, link: &str) -> ClientReturnType {
+        let log_prefix = "rust|add_bookmark";
+        log::info!(
+            "{}|channel_id={}|title={}|link={}",
+            &log_prefix,
+            channel_id,
+            title,
+            link
+        );
+
+        log::info!("{}|build request url", &log_prefix);
+        let request_url = self.build_request_uri(
+            SlackApiEndpoints::BookmarksAdd,
+            vec![
+                (SlackApiQueryParams::channel_id.to_string(), channel_id),
+                (SlackApiQueryParams::title.to_string(), title),
+                (SlackApiQueryParams::r#type.to_string(), "link"),
+                (SlackApiQueryParams::link.to_string(), link),
+            ],
+        );
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::BookmarksAdd)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn add_reminder(&self, text: &str, time: &str) -> ClientReturnType {
+        let log_prefix = "rust|add_reminder";
+        log::info!("{}|text={}|time={}", &log_prefix, text, time);
+
+        log::info!("{}|build request url", &log_prefix);
+        let request_url = self.build_request_uri(
+            SlackApiEndpoints::RemindersAdd,
+            vec![
+                (SlackApiQueryParams::text.to_string(), text),
+                (SlackApiQueryParams::time.to_string(), time),
+            ],
+        );
+
+        log::info!("{}|build request object", &log_prefix);
+        let the_request = self
+            .build_base_request(SlackApiEndpoints::RemindersAdd)
+            .with_url(request_url.to_string());
+
+        log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
+        (self.request_func)(the_request)
+    }
+
+    pub fn mark_conversation(&self, channel_id: &str, ts: &str) -> ClientReturnType {
+        let log_prefix = "rust|mark_conversation";
+        log::info!("{}|channel_id={}|ts={}", &log_prefix, channel_id, ts);
+
+        log::info!("{}|build request url", &log_prefix);
+        let request_url = self.build_request_uri(
+            SlackApiEndpoints::ConversationsMark,
+            vec![
+                (SlackApiQueryParams::channel.to_string(), channel_id),
+                (SlackApiQueryParams::ts.to_string(), ts),
+            ],
+        );
 
         log::info!("{}|build request object", &log_prefix);
         let the_request = self
-            .build_base_get_request()
+            .build_base_request(SlackApiEndpoints::ConversationsMark)
             .with_url(request_url.to_string());
 
         log::info!("{}|submit request|request={:#?}", &log_prefix, the_request);
@@ -1,8 +1,7 @@
 use crate::slack_http_client::SlackApiQueryParams;
 use do_notation::m;
-use snafu::{ResultExt, Snafu};
+use snafu::{ensure, ResultExt, Snafu};
 use std::str::FromStr;
-use tuple_conv::RepeatedTuple;
 use url::ParseError;
 
 #[derive(Debug, Snafu)]
@@ -23,10 +22,16 @@ pub enum Error {
 
     #[snafu(display("There was an issue parsing the timestamp for the url: {url}"))]
     TimestampCouldNotBeParsed { url: String },
+
+    #[snafu(display("Not a channel name, expected e.g. #general: #{name}"))]
+    ChannelNameInvalid { name: String },
 }
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Digits of a ts before the `.`, the unix seconds
+const TS_SECONDS_DIGITS: usize = 10;
+
 #[allow(unused)]
 #[derive(Debug, Clone)]
 pub struct SlackUrl {
@@ -55,6 +60,17 @@ impl SlackUrl {
         }
     }
 
+    pub fn host(&self) -> Option<&str> {
+        self.url.host_str()
+    }
+
+    /// Link to the channel of the message, e.g.
+    /// `https://workspace.slack.com/archives/C0000000000`
+    pub fn channel_link(&self) -> Option<String> {
+        self.host()
+            .map(|host| format!("https://{}/archives/{}", host, self.channel_id))
+    }
+
     fn parse_path_segments(url: &url::Url) -> Result<Vec<String>> {
         url.path_segments().map_or(
             PathSegmentsNotFoundSnafu { url: url.as_str() }.fail(),
@@ -84,19 +100,20 @@ impl SlackUrl {
             )
     }
 
+    /// The ts of a `p1700000000000100` segment, `1700000000.000100`. Anything but
+    /// digits after the `p` is rejected, so pasted links that were cut short or
+    /// mangled fail with an error
     fn parse_ts(url: &url::Url, path_segments: &[String]) -> Result<String> {
-        path_segments
+        let digits = path_segments
             .iter()
-            .find(|segment| segment.starts_with('p'))
-            .map_or(
-                TimestampNotFoundSnafu { url: url.as_str() }.fail(),
-                |segment| {
-                    segment.split_terminator('p').last().map_or(
-                        TimestampCouldNotBeParsedSnafu { url: url.as_str() }.fail(),
-                        |item| Ok(item.split_at(10).to_vec().join(".")),
-                    )
-                },
-            )
+            .find_map(|segment| segment.strip_prefix('p'))
+            .map_or(TimestampNotFoundSnafu { url: url.as_str() }.fail(), Ok)?;
+        ensure!(
+            digits.len() > TS_SECONDS_DIGITS && digits.bytes().all(|byte| byte.is_ascii_digit()),
+            TimestampCouldNotBeParsedSnafu { url: url.as_str() }
+        );
+        let (seconds, fraction) = digits.split_at(TS_SECONDS_DIGITS);
+        Ok(format!("{}.{}", seconds, fraction))
     }
 
     fn parse_thread_ts(url: &url::Url) -> Option<String> {
@@ -105,3 +122,179 @@ impl SlackUrl {
             .map(|(_, value)| value.to_string())
     }
 }
+
+/// A link to a channel rather than a message, e.g.
+/// `https://workspace.slack.com/archives/C0000000000`
+#[derive(Debug, Clone)]
+pub struct SlackChannelUrl {
+    pub channel_id: String,
+    url: Option<url::Url>,
+}
+
+impl SlackChannelUrl {
+    pub fn new(url_string: &str) -> Result<SlackChannelUrl> {
+        m! {
+            url <- url::Url::from_str(url_string).context(UrlCrateCouldNotParseSnafu { url: url_string});
+            path_segments <- SlackUrl::parse_path_segments(&url);
+            channel_id <- SlackUrl::parse_channel_id(&path_segments);
+            return SlackChannelUrl { channel_id, url: Some(url) };
+        }
+    }
+
+    /// Either a link to the channel, see [`SlackChannelUrl::new`], or the bare
+    /// channel id, e.g. `C0000000000`. A bare id has no host, so requests go to
+    /// the default api base
+    pub fn from_url_or_id(url_or_id: &str) -> Result<SlackChannelUrl> {
+        let url_or_id = url_or_id.trim();
+        let is_channel_id = url_or_id.starts_with(['C', 'D', 'G'])
+            && url_or_id
+                .bytes()
+                .all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit());
+        if is_channel_id {
+            Ok(SlackChannelUrl {
+                channel_id: url_or_id.to_string(),
+                url: None,
+            })
+        } else {
+            SlackChannelUrl::new(url_or_id)
+        }
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        self.url.as_ref().and_then(|url| url.host_str())
+    }
+}
+
+/// A channel as it is typed into the plugin: a link, see [`SlackChannelUrl`], the
+/// bare id, or `#channel-name`. A name needs a request to find its channel, see
+/// [`crate::channels::resolve_channel_id`]
+#[derive(Debug, Clone)]
+pub enum ChannelReference {
+    Url(SlackChannelUrl),
+    /// Without the `#`
+    Name(String),
+}
+
+impl ChannelReference {
+    pub fn new(input: &str) -> Result<ChannelReference> {
+        match input.trim().strip_prefix('#') {
+            Some(name) => {
+                ensure!(
+                    !name.is_empty() && !name.contains(char::is_whitespace),
+                    ChannelNameInvalidSnafu { name }
+                );
+                Ok(ChannelReference::Name(name.to_string()))
+            }
+            None => SlackChannelUrl::from_url_or_id(input).map(ChannelReference::Url),
+        }
+    }
+
+    /// Host of the link, none for an id or a name
+    pub fn host(&self) -> Option<&str> {
+        match self {
+            ChannelReference::Url(slack_channel_url) => slack_channel_url.host(),
+            ChannelReference::Name(_) => None,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    //! Property tests of parsing pasted slack links, run with `cargo test`. Whatever
+    //! is pasted, parsing must fail with an error rather than panic, which in wasm
+    //! aborts the plugin
+
+    use crate::slack_url::{ChannelReference, SlackChannelUrl, SlackUrl};
+    use proptest::prelude::*;
+
+    const WORKSPACE: &str = "https://workspace.slack.com";
+
+    proptest! {
+        #[test]
+        fn parsing_any_text_does_not_panic(text in any::<String>()) {
+            let _ = SlackUrl::new(&text);
+            let _ = SlackChannelUrl::new(&text);
+            let _ = SlackChannelUrl::from_url_or_id(&text);
+            let _ = ChannelReference::new(&text);
+        }
+
+        #[test]
+        fn parsing_any_path_does_not_panic(path in "(/[^/?#]{0,20}){0,4}(\\?[a-z_]{0,10}=[^#]{0,20})?") {
+            let _ = SlackUrl::new(&format!("{}{}", WORKSPACE, path));
+            let _ = SlackChannelUrl::new(&format!("{}{}", WORKSPACE, path));
+        }
+
+        #[test]
+        fn message_links_give_their_channel_and_ts(
+            channel_id in "[CDG][A-Z0-9]{8,10}",
+            seconds in "[0-9]{10}",
+            fraction in "[0-9]{6}",
+            thread_ts in proptest::option::of("[0-9]{10}\\.[0-9]{6}"),
+        ) {
+            let query = thread_ts
+                .as_ref()
+                .map(|thread_ts| format!("?thread_ts={}&cid={}", thread_ts, channel_id))
+                .unwrap_or_default();
+            let url = format!(
+                "{}/archives/{}/p{}{}{}",
+                WORKSPACE, channel_id, seconds, fraction, query
+            );
+
+            let slack_url = SlackUrl::new(&url).expect("Should parse a message link");
+
+            prop_assert_eq!(channel_id, slack_url.channel_id);
+            prop_assert_eq!(format!("{}.{}", seconds, fraction), slack_url.ts);
+            prop_assert_eq!(thread_ts, slack_url.thread_ts);
+        }
+
+        #[test]
+        fn channel_links_and_bare_ids_give_the_same_channel(channel_id in "[CDG][A-Z0-9]{8,10}") {
+            let url = format!("{}/archives/{}", WORKSPACE, channel_id);
+
+            let from_url = SlackChannelUrl::from_url_or_id(&url).expect("Should parse a channel link");
+            let from_id = SlackChannelUrl::from_url_or_id(&channel_id).expect("Should parse a channel id");
+
+            prop_assert_eq!(&channel_id, &from_url.channel_id);
+            prop_assert_eq!(&channel_id, &from_id.channel_id);
+            prop_assert_eq!(Some("workspace.slack.com"), from_url.host());
+            prop_assert_eq!(None, from_id.host());
+        }
+
+        #[test]
+        fn timestamps_of_anything_but_digits_are_rejected(ts in "[0-9]{0,9}[a-zA-Zé._~:-]{1,3}[0-9]{0,8}") {
+            let url = format!("{}/archives/C0000000000/p{}", WORKSPACE, ts);
+
+            prop_assert!(SlackUrl::new(&url).is_err());
+        }
+    }
+
+    #[test]
+    fn timestamps_too_short_to_split_are_rejected() {
+        for ts in vec!["", "1", "1700000000"] {
+            let url = format!("{}/archives/C0000000000/p{}", WORKSPACE, ts);
+
+            assert!(SlackUrl::new(&url).is_err(), "Parsed {}", url);
+        }
+    }
+
+    #[test]
+    fn channel_references_are_links_ids_or_names() {
+        let channel_id = |input: &str| match ChannelReference::new(input) {
+            Ok(ChannelReference::Url(slack_channel_url)) => Some(slack_channel_url.channel_id),
+            _ => None,
+        };
+        let name = |input: &str| match ChannelReference::new(input) {
+            Ok(ChannelReference::Name(name)) => Some(name),
+            _ => None,
+        };
+
+        assert_eq!(
+            Some("C0000000000".to_string()),
+            channel_id(&format!("{}/archives/C0000000000", WORKSPACE))
+        );
+        assert_eq!(Some("C0000000000".to_string()), channel_id("C0000000000"));
+        assert_eq!(Some("general".to_string()), name(" #general "));
+        assert!(ChannelReference::new("#").is_err());
+        assert!(ChannelReference::new("#two words").is_err());
+    }
+}
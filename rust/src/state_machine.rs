@@ -1,16 +1,22 @@
 use amplify_derive::Display;
 
-use js_sys::Promise;
-use snafu::{ResultExt, Snafu};
+use snafu::{ensure, ResultExt, Snafu};
 
 use crate::{
+    attachments, bots,
     channels::{self},
     components::{self, ObsidianSlackComponentsBuilder},
-    messages::{self},
-    slack_http_client::{SlackHttpClient, SlackHttpClientConfigFeatureFlags},
+    finalize_options::FinalizeOptions,
+    messages::{self, SkippedFiles, DEFAULT_DO_NOT_ARCHIVE_EMOJI, DEFAULT_MAX_FILE_SIZE_BYTES},
+    reminders,
+    slack_http_client::{
+        SlackHttpClient, SlackHttpClientConfigFeatureFlags, SlackHttpClientConfigOptions,
+        TransportResponse, API_BUDGET_EXCEEDED_WARNING,
+    },
     slack_url::SlackUrl,
     team::{self, CollectTeams},
-    users::{self, CollectUsers},
+    usergroups::{self, MentionedGroup},
+    users::{self, CollectUsers, Users},
 };
 
 #[derive(Debug, Snafu)]
@@ -27,15 +33,38 @@ pub enum Error {
     #[snafu(display("Could not get channel from api - source: {source}"))]
     CouldNotGetChannelFromApi { source: channels::Error },
 
+    #[snafu(display("{source}"))]
+    ExportNotAllowed { source: channels::Error },
+
     #[snafu(display("Could not get users from components - source: {source}"))]
     CouldNotCollectUsersFromComponents { source: components::Error },
 
     #[snafu(display("Could not get teams from components - source: {source}"))]
     CouldNotCollectTeamsFromComponents { source: components::Error },
 
+    #[snafu(display("Could not get usergroups from api - source: {source}"))]
+    CouldNotGetUsergroupsFromApi { source: usergroups::Error },
+
+    #[snafu(display("Could not get usergroup members from api - source: {source}"))]
+    CouldNotGetUsergroupMembersFromApi { source: users::Error },
+
+    #[snafu(display("Could not get bots from api - source: {source}"))]
+    CouldNotGetBotsFromApi { source: bots::Error },
+
+    #[snafu(display("Could not get presences from api - source: {source}"))]
+    CouldNotGetPresencesFromApi { source: users::Error },
+
+    #[snafu(display("Could not get reminders from api - source: {source}"))]
+    CouldNotGetRemindersFromApi { source: reminders::Error },
+
     #[snafu(display("Could not get file data from slack - source: {source}"))]
     CouldNotGetFileDataFromSlack { source: messages::Error },
 
+    #[snafu(display(
+        "The author of thread {thread_ts} marked it as not to be archived with :{emoji}:"
+    ))]
+    ThreadMarkedDoNotArchive { thread_ts: String, emoji: String },
+
     #[snafu(display("Transition from state: {state} with flags {flags} was invalid"))]
     InvalidStateTransition {
         state: ObsidianSlackStates,
@@ -45,7 +74,7 @@ pub enum Error {
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug, Display, PartialEq, Eq)]
+#[derive(Debug, Display, PartialEq, Eq, Clone, Copy)]
 #[display(Debug)]
 pub enum ObsidianSlackStates {
     Start,
@@ -54,22 +83,163 @@ pub enum ObsidianSlackStates {
     UserInfo,
     TeamInfo,
     Files,
+    Usergroups,
+    Reminders,
+    Presence,
+    BotInfo,
     End,
 }
+
+/// States enabled by options, in the order they run after the ones enabled by
+/// feature flags
+const OPTION_STATES: [ObsidianSlackStates; 4] = [
+    ObsidianSlackStates::Usergroups,
+    ObsidianSlackStates::Reminders,
+    ObsidianSlackStates::Presence,
+    ObsidianSlackStates::BotInfo,
+];
+
 #[derive(Debug)]
 pub struct ObsidianSlackStateMachineInput<T> {
     pub components: ObsidianSlackComponentsBuilder,
     pub client: SlackHttpClient<T>,
     pub slack_url: SlackUrl,
+    /// Every user fetched during this call, so no user is requested twice
+    pub users_cache: Users,
+    /// How the gathered components are finalized. Its ignored users are not
+    /// fetched, and a thread it does not archive is refused before anything else
+    pub finalize_options: FinalizeOptions,
 }
 pub struct ObsidianSlackStateMachine;
 
 impl ObsidianSlackStateMachine {
-    pub async fn transition(
+    /// Runs every transition the feature flags and options ask for. Once the
+    /// thread is fetched, running out of api budget only skips the rest
+    pub async fn run<T>(input: &mut ObsidianSlackStateMachineInput<T>) -> Result<()>
+    where
+        T: TransportResponse,
+    {
+        let mut current_state = ObsidianSlackStates::Start;
+        while current_state != ObsidianSlackStates::End {
+            current_state = match ObsidianSlackStateMachine::transition(current_state, input).await
+            {
+                Ok(x) => x,
+                Err(_)
+                    if input.client.api_budget.was_exceeded()
+                        && input.components.message_and_thread.is_some() =>
+                {
+                    input.client.add_warning(API_BUDGET_EXCEEDED_WARNING);
+                    ObsidianSlackStates::End
+                }
+                Err(err) => return Err(err),
+            };
+        }
+        Ok(())
+    }
+
+    pub async fn transition<T>(
+        state: ObsidianSlackStates,
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        let next_state = ObsidianSlackStateMachine::next_state(
+            state,
+            &input.client.config.feature_flags,
+            &input.client.config.options,
+        )?;
+        match next_state {
+            ObsidianSlackStates::MessageAndThread => {
+                ObsidianSlackStateMachine::transition_to_message_and_thread(input).await
+            }
+            ObsidianSlackStates::ChannelInfo => {
+                ObsidianSlackStateMachine::transition_to_channel_info(input).await
+            }
+            ObsidianSlackStates::UserInfo => {
+                ObsidianSlackStateMachine::transition_to_user_info(input).await
+            }
+            ObsidianSlackStates::TeamInfo => {
+                ObsidianSlackStateMachine::transition_to_team_info(input).await
+            }
+            ObsidianSlackStates::Files => {
+                ObsidianSlackStateMachine::transition_to_files(input).await
+            }
+            ObsidianSlackStates::Usergroups => {
+                ObsidianSlackStateMachine::transition_to_usergroups(input).await
+            }
+            ObsidianSlackStates::Reminders => {
+                ObsidianSlackStateMachine::transition_to_reminders(input).await
+            }
+            ObsidianSlackStates::Presence => {
+                ObsidianSlackStateMachine::transition_to_presence(input).await
+            }
+            ObsidianSlackStates::BotInfo => {
+                ObsidianSlackStateMachine::transition_to_bot_info(input).await
+            }
+            ObsidianSlackStates::Start | ObsidianSlackStates::End => Ok(next_state),
+        }
+    }
+
+    /// The states a run with the given flags and options goes through, in order and
+    /// without `Start` and `End`. Nothing is requested
+    pub fn plan(
+        feature_flags: &SlackHttpClientConfigFeatureFlags,
+        options: &SlackHttpClientConfigOptions,
+    ) -> Result<Vec<ObsidianSlackStates>> {
+        let mut states = vec![];
+        let mut state = ObsidianSlackStates::Start;
+        loop {
+            state = ObsidianSlackStateMachine::next_state(state, feature_flags, options)?;
+            if state == ObsidianSlackStates::End {
+                return Ok(states);
+            }
+            states.push(state);
+        }
+    }
+
+    fn next_state(
         state: ObsidianSlackStates,
-        input: &mut ObsidianSlackStateMachineInput<Promise>,
+        feature_flags: &SlackHttpClientConfigFeatureFlags,
+        options: &SlackHttpClientConfigOptions,
     ) -> Result<ObsidianSlackStates> {
-        match (&state, &input.client.config.feature_flags) {
+        let option_state_position = OPTION_STATES
+            .iter()
+            .position(|option_state| *option_state == state);
+        if option_state_position.is_none() {
+            let next_state =
+                ObsidianSlackStateMachine::next_state_by_feature_flags(state, feature_flags)?;
+            if next_state != ObsidianSlackStates::End {
+                return Ok(next_state);
+            }
+        }
+
+        Ok(
+            OPTION_STATES[option_state_position.map_or(0, |position| position + 1)..]
+                .iter()
+                .find(|option_state| {
+                    ObsidianSlackStateMachine::is_enabled_by(option_state, options)
+                })
+                .copied()
+                .unwrap_or(ObsidianSlackStates::End),
+        )
+    }
+
+    fn is_enabled_by(state: &ObsidianSlackStates, options: &SlackHttpClientConfigOptions) -> bool {
+        match state {
+            ObsidianSlackStates::Usergroups => options.expand_usergroups,
+            ObsidianSlackStates::Reminders => options.include_reminders,
+            ObsidianSlackStates::Presence => options.capture_presence,
+            ObsidianSlackStates::BotInfo => options.include_bot_profiles,
+            _ => false,
+        }
+    }
+
+    fn next_state_by_feature_flags(
+        state: ObsidianSlackStates,
+        feature_flags: &SlackHttpClientConfigFeatureFlags,
+    ) -> Result<ObsidianSlackStates> {
+        match (&state, feature_flags) {
             (
                 ObsidianSlackStates::Start,
                 SlackHttpClientConfigFeatureFlags {
@@ -78,7 +248,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: _,
                     get_file_data: _,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_message_and_thread(input).await,
+            ) => Ok(ObsidianSlackStates::MessageAndThread),
             (
                 ObsidianSlackStates::MessageAndThread,
                 SlackHttpClientConfigFeatureFlags {
@@ -96,7 +266,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: _,
                     get_file_data: _,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_channel_info(input).await,
+            ) => Ok(ObsidianSlackStates::ChannelInfo),
             (
                 ObsidianSlackStates::MessageAndThread,
                 SlackHttpClientConfigFeatureFlags {
@@ -105,7 +275,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: _,
                     get_file_data: _,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_user_info(input).await,
+            ) => Ok(ObsidianSlackStates::UserInfo),
             (
                 ObsidianSlackStates::MessageAndThread,
                 SlackHttpClientConfigFeatureFlags {
@@ -114,7 +284,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: false,
                     get_file_data: true,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_files(input).await,
+            ) => Ok(ObsidianSlackStates::Files),
             (
                 ObsidianSlackStates::ChannelInfo,
                 SlackHttpClientConfigFeatureFlags {
@@ -132,7 +302,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: _,
                     get_file_data: _,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_user_info(input).await,
+            ) => Ok(ObsidianSlackStates::UserInfo),
             (
                 ObsidianSlackStates::ChannelInfo,
                 SlackHttpClientConfigFeatureFlags {
@@ -141,7 +311,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: _,
                     get_file_data: true,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_files(input).await,
+            ) => Ok(ObsidianSlackStates::Files),
             (
                 ObsidianSlackStates::UserInfo,
                 SlackHttpClientConfigFeatureFlags {
@@ -159,7 +329,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: true,
                     get_file_data: _,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_team_info(input).await,
+            ) => Ok(ObsidianSlackStates::TeamInfo),
             (
                 ObsidianSlackStates::UserInfo,
                 SlackHttpClientConfigFeatureFlags {
@@ -168,7 +338,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: false,
                     get_file_data: true,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_files(input).await,
+            ) => Ok(ObsidianSlackStates::Files),
             (
                 ObsidianSlackStates::TeamInfo,
                 SlackHttpClientConfigFeatureFlags {
@@ -186,7 +356,7 @@ impl ObsidianSlackStateMachine {
                     get_team_info: _,
                     get_file_data: true,
                 },
-            ) => ObsidianSlackStateMachine::transition_to_files(input).await,
+            ) => Ok(ObsidianSlackStates::Files),
             (
                 ObsidianSlackStates::Files,
                 SlackHttpClientConfigFeatureFlags {
@@ -198,49 +368,110 @@ impl ObsidianSlackStateMachine {
             ) => Ok(ObsidianSlackStates::End),
             (_, _) => InvalidStateTransitionSnafu {
                 state,
-                flags: input.client.config.feature_flags.clone(),
+                flags: feature_flags.clone(),
             }
             .fail(),
         }
     }
 
-    async fn transition_to_message_and_thread(
-        input: &mut ObsidianSlackStateMachineInput<Promise>,
-    ) -> Result<ObsidianSlackStates> {
+    async fn transition_to_message_and_thread<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        channels::ensure_export_allowed(&input.client, &input.slack_url.channel_id)
+            .await
+            .context(ExportNotAllowedSnafu)?;
         let message_and_thread = messages::get_messages_from_api(&input.client, &input.slack_url)
             .await
             .context(CouldNotGetMessagesFromApiSnafu)?;
-        input.components.message_and_thread(message_and_thread);
+        let finalize_options = &input.finalize_options;
+        let emoji = finalize_options
+            .do_not_archive_emoji
+            .as_deref()
+            .unwrap_or(DEFAULT_DO_NOT_ARCHIVE_EMOJI)
+            .trim_matches(':');
+        ensure!(
+            !finalize_options.respect_do_not_archive
+                || !message_and_thread.thread.is_marked_do_not_archive(emoji),
+            ThreadMarkedDoNotArchiveSnafu {
+                thread_ts: input
+                    .slack_url
+                    .thread_ts
+                    .as_ref()
+                    .unwrap_or(&input.slack_url.ts),
+                emoji,
+            }
+        );
+        let options = &input.client.config.options;
+        let filters = options
+            .filter_profile
+            .filters()
+            .merge(&options.message_filters);
+        input.components.message_and_thread(if filters.is_empty() {
+            message_and_thread
+        } else {
+            message_and_thread.filter(&filters)
+        });
         Ok(ObsidianSlackStates::MessageAndThread)
     }
 
-    async fn transition_to_user_info(
-        input: &mut ObsidianSlackStateMachineInput<Promise>,
-    ) -> Result<ObsidianSlackStates> {
-        let user_ids = input
+    async fn transition_to_user_info<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        let mut user_ids = input
             .components
             .collect_users()
             .context(CouldNotCollectUsersFromComponentsSnafu)?;
-        let users = users::get_users_from_api(&user_ids, &input.client)
+        // ignored users are left out of the note, so they are not looked up
+        let ignored_users = &input.finalize_options.ignored_users;
+        user_ids
+            .0
+            .retain(|user_id| !ignored_users.contains(user_id));
+        let users = users::get_users_from_api(&user_ids, &input.client, &mut input.users_cache)
             .await
             .context(CouldNotGetUsersFromApiSnafu)?;
         input.components.users(Some(users));
         Ok(ObsidianSlackStates::UserInfo)
     }
 
-    async fn transition_to_channel_info(
-        input: &mut ObsidianSlackStateMachineInput<Promise>,
-    ) -> Result<ObsidianSlackStates> {
-        let channel = channels::get_channel_from_api(&input.client, &input.slack_url.channel_id)
-            .await
-            .context(CouldNotGetChannelFromApiSnafu)?;
+    async fn transition_to_channel_info<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        let mut channel =
+            channels::get_channel_from_api(&input.client, &input.slack_url.channel_id)
+                .await
+                .context(CouldNotGetChannelFromApiSnafu)?;
+        // a group DM is named after its members, see `Channel::group_dm_label`
+        if channel.is_mpim.unwrap_or(false) {
+            channel.members = Some(
+                channels::get_channel_members_from_api(&input.client, &input.slack_url.channel_id)
+                    .await
+                    .context(CouldNotGetChannelFromApiSnafu)?,
+            );
+        }
+        // a DM's note should always say who it was with, so its users are fetched
+        // even when `get_users` is off
+        if channel.is_im.unwrap_or(false) || channel.is_mpim.unwrap_or(false) {
+            input.client.config.feature_flags.get_users = true;
+        }
         input.components.channel(Some(channel));
         Ok(ObsidianSlackStates::ChannelInfo)
     }
 
-    async fn transition_to_team_info(
-        input: &mut ObsidianSlackStateMachineInput<Promise>,
-    ) -> Result<ObsidianSlackStates> {
+    async fn transition_to_team_info<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
         let team_ids = input
             .components
             .collect_teams()
@@ -252,20 +483,148 @@ impl ObsidianSlackStateMachine {
         Ok(ObsidianSlackStates::TeamInfo)
     }
 
-    async fn transition_to_files(
-        input: &mut ObsidianSlackStateMachineInput<Promise>,
-    ) -> Result<ObsidianSlackStates> {
-        input.components.file_links(Some(
-            input
-                .components
-                .message_and_thread
-                .as_ref()
-                .expect(
-                    "Expected message and thread to look for file info, found None. This is a bug",
-                )
-                .collect_file_links(),
-        ));
+    async fn transition_to_files<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        let max_file_size = input
+            .client
+            .config
+            .options
+            .max_file_size_bytes
+            .unwrap_or(DEFAULT_MAX_FILE_SIZE_BYTES);
+        let (mut file_links, skipped_files) = input
+            .components
+            .message_and_thread
+            .as_ref()
+            .expect("Expected message and thread to look for file info, found None. This is a bug")
+            .collect_file_links_up_to(max_file_size);
+        let skipped_files = if input.client.config.options.embed_file_data {
+            let (files_data, skipped_data) =
+                messages::get_files_data_from_api(&input.client, &file_links, max_file_size).await;
+            let mut files_data = if input.client.config.options.strip_image_metadata {
+                files_data.strip_image_metadata()
+            } else {
+                files_data
+            };
+            // after stripping, that is what gets saved
+            if input.client.config.options.dedupe_file_data {
+                let mut saved_contents = input.client.config.options.saved_contents.clone();
+                let (unique_data, unique_links, duplicates) =
+                    attachments::dedupe(files_data, file_links, &mut saved_contents);
+                files_data = unique_data;
+                file_links = unique_links;
+                input.components.duplicate_files(Some(duplicates));
+                input.components.saved_contents(Some(saved_contents));
+            }
+            input.components.files_data(Some(files_data));
+            SkippedFiles(skipped_files.0.into_iter().chain(skipped_data.0).collect())
+        } else {
+            skipped_files
+        };
+        input.components.file_links(Some(file_links));
+        input.components.skipped_files(skipped_files);
 
         Ok(ObsidianSlackStates::Files)
     }
+
+    async fn transition_to_usergroups<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        let mentions = input
+            .components
+            .message_and_thread
+            .as_ref()
+            .expect("Expected message and thread to look for usergroups, found None. This is a bug")
+            .collect_usergroup_mentions();
+        let usergroup_ids = mentions
+            .iter()
+            .map(|mention| mention.id.to_string())
+            .collect::<Vec<String>>();
+        let members = usergroups::get_usergroup_members_from_api(&usergroup_ids, &input.client)
+            .await
+            .context(CouldNotGetUsergroupsFromApiSnafu)?;
+        let member_ids = members.values().flatten().cloned().collect::<Vec<String>>();
+        let users = users::get_users_from_api(&member_ids, &input.client, &mut input.users_cache)
+            .await
+            .context(CouldNotGetUsergroupMembersFromApiSnafu)?;
+
+        input.components.mentioned_groups(Some(
+            mentions
+                .into_iter()
+                .map(|mention| {
+                    let member_ids = members.get(&mention.id).cloned().unwrap_or_default();
+                    MentionedGroup::new(mention, &member_ids, &users)
+                })
+                .collect(),
+        ));
+        Ok(ObsidianSlackStates::Usergroups)
+    }
+
+    async fn transition_to_reminders<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        let reminders = reminders::get_reminders_from_api(
+            &input.client,
+            &input.slack_url.channel_id,
+            input.components.message_and_thread.as_ref().expect(
+                "Expected message and thread to look for reminders, found None. This is a bug",
+            ),
+        )
+        .await
+        .context(CouldNotGetRemindersFromApiSnafu)?;
+        input.components.reminders(Some(reminders));
+        Ok(ObsidianSlackStates::Reminders)
+    }
+
+    /// Participants are only known when users were retrieved, without them there is
+    /// nobody to ask the presence of
+    async fn transition_to_presence<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        let user_ids = input
+            .components
+            .users
+            .as_ref()
+            .and_then(Option::as_ref)
+            .map(|users| users.keys().cloned().collect::<Vec<String>>())
+            .unwrap_or_default();
+        if !user_ids.is_empty() {
+            let presences = users::get_presences_from_api(&user_ids, &input.client)
+                .await
+                .context(CouldNotGetPresencesFromApiSnafu)?;
+            input.components.presences(Some(presences));
+        }
+        Ok(ObsidianSlackStates::Presence)
+    }
+
+    async fn transition_to_bot_info<T>(
+        input: &mut ObsidianSlackStateMachineInput<T>,
+    ) -> Result<ObsidianSlackStates>
+    where
+        T: TransportResponse,
+    {
+        let bot_ids =
+            bots::collect_bot_ids(
+                input.components.message_and_thread.as_ref().expect(
+                    "Expected message and thread to look for bots, found None. This is a bug",
+                ),
+            );
+        let bots = bots::get_bots_from_api(&bot_ids, &input.client)
+            .await
+            .context(CouldNotGetBotsFromApiSnafu)?;
+        input.components.bots(Some(bots));
+        Ok(ObsidianSlackStates::BotInfo)
+    }
 }
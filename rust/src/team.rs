@@ -10,20 +10,20 @@ use std::{
     iter::FromIterator,
     ops::DerefMut,
 };
-use wasm_bindgen_futures::JsFuture;
 
 use crate::{
-    response::{self, convert_result_string_to_object, SlackResponseValidator},
-    slack_http_client::SlackHttpClient,
+    response::{self, SlackEnvelope},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    utils::{sanitize_text, SanitizeText},
 };
 
 #[derive(Debug, Snafu)]
 pub enum Error {
-    #[snafu(display("Awaiting a JsFuture returned an error: {error}"))]
-    WasmErrorFromJsFuture { error: String },
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
 
     #[snafu(display("{source}"))]
-    SerdeWasmBindgenCouldNotParseTeamResponse { source: response::Error },
+    CouldNotDeserializeTeamResponse { source: response::Error },
 
     #[snafu(display("The team response was not ok. - source: {source}"))]
     InvalidTeamResponse { source: response::Error },
@@ -39,25 +39,23 @@ pub async fn get_teams_from_api<T>(
     client: &SlackHttpClient<T>,
 ) -> Result<Teams>
 where
-    wasm_bindgen_futures::JsFuture: std::convert::From<T>,
+    T: TransportResponse,
 {
     let teams = team_ids
         .iter()
-        .map(|team_id| JsFuture::from(client.get_team_info(team_id)))
-        .collect::<Vec<JsFuture>>();
+        .map(|team_id| client.get_team_info(team_id).into_body())
+        .collect::<Vec<_>>();
 
     let team_responses = join_all(teams)
         .await
         .into_iter()
         .map(|result| {
             m! {
-                // mapping error instead of using snafu context because jsvalue is not an Error from parse method
-                val <- result.map_err(|err| Error::WasmErrorFromJsFuture {
-                    error: format!("{:#?}", err),
-                });
-                js_obj <- convert_result_string_to_object(val).context(CouldNotParseJsonFromTeamResponseSnafu);
-                team_response <- response::defined_from_js_object(js_obj).context(SerdeWasmBindgenCouldNotParseTeamResponseSnafu);
-                valid_response <- TeamResponse::validate_response(team_response).context(InvalidTeamResponseSnafu);
+                // mapping error instead of using snafu context because the request's error is a string, not an Error
+                val <- result.map_err(|error| Error::RequestFailed { error });
+                json <- response::parse_response_body(val).context(CouldNotParseJsonFromTeamResponseSnafu);
+                team_response <- response::defined_from_json(json).context(CouldNotDeserializeTeamResponseSnafu);
+                valid_response <- client.validate_response(team_response).context(InvalidTeamResponseSnafu);
                 return valid_response;
             }
         })
@@ -69,6 +67,7 @@ where
             .map(String::to_string)
             .zip(team_responses.into_iter().map(|team_response| {
                 team_response
+                    .data
                     .team
                     .expect("Expected a team in the team response, but got None. This is a bug")
             }))
@@ -109,17 +108,18 @@ impl DerefMut for TeamIds {
 #[display(Debug)]
 pub struct Teams(pub HashMap<String, Team>);
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct TeamResponse {
-    pub ok: Option<bool>,
-    pub error: Option<String>,
-    pub team: Option<Team>,
+impl Teams {
+    pub fn sanitize_text(&mut self) {
+        self.0.values_mut().for_each(SanitizeText::sanitize_text);
+    }
 }
 
-impl SlackResponseValidator for TeamResponse {
-    fn ok(&self) -> Option<bool> {
-        self.ok
-    }
+pub type TeamResponse = SlackEnvelope<TeamResponseData>;
+
+/// The fields of a `team.info` response, see [`TeamResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TeamResponseData {
+    pub team: Option<Team>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
@@ -132,3 +132,9 @@ pub struct Team {
     pub enterprise_id: Option<String>,
     pub enterprise_name: Option<String>,
 }
+
+impl SanitizeText for Team {
+    fn sanitize_text(&mut self) {
+        self.name = sanitize_text(&self.name);
+    }
+}
@@ -0,0 +1,73 @@
+//! What the tests of the modules share. Most run with `cargo test`, without a
+//! js runtime, so the client's requests are answered by plain futures instead of
+//! the promises of obsidian's `request` function
+
+use std::{cell::RefCell, rc::Rc};
+
+use futures::future::{self, FutureExt, LocalBoxFuture};
+
+use crate::slack_http_client::{
+    ApiBudget, RequestUrlParam, SlackHttpClient, SlackHttpClientConfig,
+    SlackHttpClientConfigFeatureFlags, SlackHttpClientConfigOptions,
+};
+
+pub type Response = LocalBoxFuture<'static, Result<String, String>>;
+
+/// A client sending its requests with `request_func`
+pub fn client_sending<T>(
+    feature_flags: SlackHttpClientConfigFeatureFlags,
+    options: SlackHttpClientConfigOptions,
+    request_func: impl Fn(RequestUrlParam) -> T + 'static,
+) -> SlackHttpClient<T> {
+    let config = SlackHttpClientConfig::new(
+        url::Url::parse("https://slack.com/api").unwrap(),
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        feature_flags,
+        options,
+    )
+    .expect("Should build config");
+    SlackHttpClient::new(
+        config,
+        Box::new(request_func),
+        Rc::new(ApiBudget::new(None)),
+    )
+}
+
+/// A client answering each request with `respond`, and the urls it requested.
+/// An error fails the request like a network error would
+pub fn client<F>(
+    options: SlackHttpClientConfigOptions,
+    respond: F,
+) -> (SlackHttpClient<Response>, Rc<RefCell<Vec<String>>>)
+where
+    F: Fn(&str) -> Result<serde_json::Value, String> + 'static,
+{
+    let feature_flags = SlackHttpClientConfigFeatureFlags {
+        get_users: true,
+        get_channel_info: false,
+        get_team_info: false,
+        get_file_data: false,
+    };
+    client_with(feature_flags, options, respond)
+}
+
+/// Like [`client`], with other feature flags than only `get_users`
+pub fn client_with<F>(
+    feature_flags: SlackHttpClientConfigFeatureFlags,
+    options: SlackHttpClientConfigOptions,
+    respond: F,
+) -> (SlackHttpClient<Response>, Rc<RefCell<Vec<String>>>)
+where
+    F: Fn(&str) -> Result<serde_json::Value, String> + 'static,
+{
+    let requested_urls = Rc::new(RefCell::new(vec![]));
+    let client_requested_urls = requested_urls.clone();
+    let client = client_sending(feature_flags, options, move |params: RequestUrlParam| {
+        client_requested_urls
+            .borrow_mut()
+            .push(params.url().to_string());
+        future::ready(respond(params.url()).map(|body| body.to_string())).boxed_local()
+    });
+    (client, requested_urls)
+}
@@ -0,0 +1,167 @@
+use amplify_derive::Display;
+use do_notation::m;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use shrinkwraprs::Shrinkwrap;
+use snafu::{ResultExt, Snafu};
+use std::{collections::HashMap, fmt::Debug, iter::FromIterator};
+
+use crate::{
+    response::{self, SlackEnvelope},
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    users::Users,
+    utils::{sanitize_optional_text, SanitizeText},
+};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
+
+    #[snafu(display("{source}"))]
+    CouldNotDeserializeUsergroupUsersResponse { source: response::Error },
+
+    #[snafu(display("The usergroup users response was not ok. - source: {source}"))]
+    InvalidUsergroupUsersResponse { source: response::Error },
+
+    #[snafu(display(
+        "Could not parse json from usergroup users response string - source: {source}"
+    ))]
+    CouldNotParseJsonFromUsergroupUsersResponse { source: response::Error },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Gets the user ids of every member of each usergroup, keyed by usergroup id
+pub async fn get_usergroup_members_from_api<T>(
+    usergroup_ids: &[String],
+    client: &SlackHttpClient<T>,
+) -> Result<HashMap<String, Vec<String>>>
+where
+    T: TransportResponse,
+{
+    let usergroups = usergroup_ids
+        .iter()
+        .map(|usergroup_id| client.get_usergroups_users_list(usergroup_id).into_body())
+        .collect::<Vec<_>>();
+
+    let usergroup_users_responses = join_all(usergroups)
+        .await
+        .into_iter()
+        .map(|result| {
+            m! {
+                // mapping error instead of using snafu context because the request's error is a string, not an Error
+                val <- result.map_err(|error| Error::RequestFailed { error });
+                json <- response::parse_response_body(val).context(CouldNotParseJsonFromUsergroupUsersResponseSnafu);
+                usergroup_users_response <- response::defined_from_json(json).context(CouldNotDeserializeUsergroupUsersResponseSnafu);
+                valid_response <- client.validate_response(usergroup_users_response).context(InvalidUsergroupUsersResponseSnafu);
+                return valid_response;
+            }
+        })
+        .collect::<Result<Vec<UsergroupUsersResponse>>>()?;
+
+    Ok(usergroup_ids
+        .iter()
+        .map(String::to_string)
+        .zip(
+            usergroup_users_responses
+                .into_iter()
+                .map(|usergroup_users_response| {
+                    usergroup_users_response.data.users.unwrap_or_default()
+                }),
+        )
+        .collect())
+}
+
+/// A `<!subteam^S0000000000|@handle>` mention in a message
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct UsergroupMention {
+    pub id: String,
+    /// The handle without the `@`, slack leaves it out of some mentions
+    pub handle: Option<String>,
+}
+
+impl UsergroupMention {
+    /// Finds every usergroup mention in slack formatted text
+    pub fn extract(text: &str) -> Vec<UsergroupMention> {
+        text.match_indices("<!subteam^")
+            .filter_map(|(index, prefix)| {
+                let rest = &text[index + prefix.len()..];
+                let mention = &rest[..rest.find('>')?];
+                let mut parts = mention.splitn(2, '|');
+                let id = parts.next()?.to_string();
+                let handle = parts
+                    .next()
+                    .map(|handle| handle.trim_start_matches('@').to_string())
+                    .filter(|handle| !handle.is_empty());
+                Some(UsergroupMention { id, handle })
+            })
+            .filter(|mention| !mention.id.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct MentionedGroup {
+    pub id: String,
+    pub name: Option<String>,
+    pub members: Vec<MentionedGroupMember>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct MentionedGroupMember {
+    pub id: String,
+    pub name: Option<String>,
+}
+
+impl MentionedGroup {
+    pub fn new(mention: UsergroupMention, member_ids: &[String], users: &Users) -> MentionedGroup {
+        MentionedGroup {
+            id: mention.id,
+            name: mention.handle,
+            members: member_ids
+                .iter()
+                .map(|member_id| MentionedGroupMember {
+                    id: member_id.to_string(),
+                    name: users.get(member_id).and_then(|user| user.display_name()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl SanitizeText for MentionedGroup {
+    fn sanitize_text(&mut self) {
+        sanitize_optional_text(&mut self.name);
+        self.members
+            .iter_mut()
+            .for_each(|member| sanitize_optional_text(&mut member.name));
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct MentionedGroups(pub Vec<MentionedGroup>);
+
+impl MentionedGroups {
+    pub fn sanitize_text(&mut self) {
+        self.0.iter_mut().for_each(SanitizeText::sanitize_text);
+    }
+}
+
+impl FromIterator<MentionedGroup> for MentionedGroups {
+    fn from_iter<T: IntoIterator<Item = MentionedGroup>>(iter: T) -> Self {
+        MentionedGroups(iter.into_iter().collect())
+    }
+}
+
+pub type UsergroupUsersResponse = SlackEnvelope<UsergroupUsersResponseData>;
+
+/// The fields of a `usergroups.users.list` response, see [`UsergroupUsersResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UsergroupUsersResponseData {
+    pub users: Option<Vec<String>>,
+}
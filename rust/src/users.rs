@@ -3,28 +3,32 @@ use do_notation::m;
 use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use shrinkwraprs::Shrinkwrap;
-use snafu::{ResultExt, Snafu};
+use snafu::{OptionExt, ResultExt, Snafu};
 use std::{
     collections::HashMap,
     fmt::{Debug, Display},
     iter::FromIterator,
     ops::DerefMut,
+    sync::Arc,
 };
-use wasm_bindgen_futures::JsFuture;
 
 use crate::{
-    response::{self, convert_result_string_to_object, SlackResponseValidator},
-    slack_http_client::SlackHttpClient,
-    team::{CollectTeams, Team, TeamIds, Teams},
+    messages::Messages,
+    response::{
+        self, PaginatedResponse, Paginator, ResponseMetadata, SlackEnvelope, SlackResponseValidator,
+    },
+    slack_http_client::{SlackHttpClient, TransportResponse},
+    team::{self, CollectTeams, Team, TeamIds, Teams},
+    utils::{sanitize_optional_text, SanitizeText},
 };
 
 #[derive(Debug, Snafu)]
 pub enum Error {
-    #[snafu(display("Awaiting a JsFuture returned an error: {error}"))]
-    WasmErrorFromJsFuture { error: String },
+    #[snafu(display("The request to slack failed: {error}"))]
+    RequestFailed { error: String },
 
     #[snafu(display("{source}"))]
-    SerdeWasmBindgenCouldNotParseUserResponse { source: response::Error },
+    CouldNotDeserializeUserResponse { source: response::Error },
 
     #[snafu(display("The user response was not ok. - source: {source}"))]
     InvalidUserResponse { source: response::Error },
@@ -39,37 +43,168 @@ pub enum Error {
 
     #[snafu(display("When mapping team ids from response to retrieved team info, team id was not in team map. team_id: {team_id} - team_map: {team_map}"))]
     TeamIdNotFoundInTeamMap { team_id: String, team_map: String },
+
+    #[snafu(display(
+        "Not a user id or mention, expected e.g. U0000000000 or <@U0000000000>: {user_id_or_mention}"
+    ))]
+    InvalidUserId { user_id_or_mention: String },
+
+    #[snafu(display("Slack returned no user for {user_id}"))]
+    UserNotFound { user_id: String },
+
+    #[snafu(display("Could not get the team of the user - source: {source}"))]
+    CouldNotGetTeamOfUser { source: team::Error },
 }
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Distinct users from which `users.list` is paged instead of calling `users.info`
+/// once per user, unless overridden by `users_list_threshold`
+pub const DEFAULT_USERS_LIST_THRESHOLD: usize = 100;
+
+/// Gets the given users, from `cache` where possible. Past the users list
+/// threshold the workspace's `users.list` is paged instead of calling `users.info`
+/// for each user; every user fetched either way is added to `cache`
 pub async fn get_users_from_api<T>(
     user_ids: &Vec<String>,
     client: &SlackHttpClient<T>,
+    cache: &mut Users,
 ) -> Result<Users>
 where
-    wasm_bindgen_futures::JsFuture: std::convert::From<T>,
+    T: TransportResponse,
+{
+    let log_prefix = "rust|get_users_from_api";
+    let mut missing_user_ids = user_ids
+        .iter()
+        .filter(|user_id| !cache.contains_key(*user_id))
+        .cloned()
+        .collect::<Vec<String>>();
+    missing_user_ids.sort();
+    missing_user_ids.dedup();
+
+    let threshold = client
+        .config
+        .options
+        .users_list_threshold
+        .unwrap_or(DEFAULT_USERS_LIST_THRESHOLD);
+    if missing_user_ids.len() >= threshold {
+        log::info!(
+            "{}|page users.list|missing_users={}|threshold={}",
+            &log_prefix,
+            missing_user_ids.len(),
+            threshold
+        );
+        get_users_from_users_list(&missing_user_ids, client, cache).await?;
+        // users.list leaves out some users, e.g. ones from other orgs in shared channels
+        missing_user_ids.retain(|user_id| !cache.contains_key(user_id));
+    }
+
+    log::info!(
+        "{}|call users.info|missing_users={}",
+        &log_prefix,
+        missing_user_ids.len()
+    );
+    let users = get_users_from_users_info(&missing_user_ids, client).await?;
+    cache.0.extend(users.0);
+
+    Ok(user_ids
+        .iter()
+        .filter_map(|user_id| {
+            cache
+                .get(user_id)
+                .map(|user| (user_id.to_string(), user.to_owned()))
+        })
+        .collect())
+}
+
+/// The id of a user given as the id itself, e.g. `U0000000000`, or a mention as
+/// slack writes it, e.g. `<@U0000000000>` or `<@U0000000000|alice>`
+pub fn parse_user_id(user_id_or_mention: &str) -> Result<String> {
+    let trimmed = user_id_or_mention.trim();
+    let user_id = trimmed
+        .strip_prefix("<@")
+        .and_then(|mention| mention.strip_suffix('>'))
+        .map(|mention| mention.split('|').next().unwrap_or(mention))
+        .or_else(|| trimmed.strip_prefix('@'))
+        .unwrap_or(trimmed);
+    let is_user_id = user_id.starts_with(['U', 'W'])
+        && user_id.len() > 1
+        && user_id
+            .bytes()
+            .all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit());
+    if is_user_id {
+        Ok(user_id.to_string())
+    } else {
+        InvalidUserIdSnafu { user_id_or_mention }.fail()
+    }
+}
+
+/// A single user with the workspace they belong to as their `team_info`
+pub async fn get_user_info_from_api<T>(user_id: &str, client: &SlackHttpClient<T>) -> Result<User>
+where
+    T: TransportResponse,
+{
+    let mut users = get_users_from_users_info(&[user_id.to_string()], client).await?;
+    let mut user = users
+        .0
+        .remove(user_id)
+        .context(UserNotFoundSnafu { user_id })?;
+    if let Some(team_id) = user.team_id.clone() {
+        let mut teams = team::get_teams_from_api(&vec![team_id.clone()], client)
+            .await
+            .context(CouldNotGetTeamOfUserSnafu)?;
+        user.team_info = teams.0.remove(&team_id);
+    }
+    Ok(user)
+}
+
+/// Presence, `active` or `away`, of each of the users with `users.getPresence`
+pub async fn get_presences_from_api<T>(
+    user_ids: &[String],
+    client: &SlackHttpClient<T>,
+) -> Result<Presences>
+where
+    T: TransportResponse,
+{
+    let presences = user_ids
+        .iter()
+        .map(|user_id| client.get_users_presence(user_id).into_body())
+        .collect::<Vec<_>>();
+
+    let presence_responses = join_all(presences)
+        .await
+        .into_iter()
+        .map(|result| parse_users_response::<PresenceResponse, T>(result, client))
+        .collect::<Result<Vec<PresenceResponse>>>()?;
+
+    Ok(user_ids
+        .iter()
+        .zip(presence_responses)
+        .filter_map(|(user_id, presence_response)| {
+            presence_response
+                .data
+                .presence
+                .map(|presence| (user_id.to_string(), presence))
+        })
+        .collect())
+}
+
+async fn get_users_from_users_info<T>(
+    user_ids: &[String],
+    client: &SlackHttpClient<T>,
+) -> Result<Users>
+where
+    T: TransportResponse,
 {
     let users = user_ids
         .iter()
-        .map(|user_id| JsFuture::from(client.get_users_info(user_id)))
-        .collect::<Vec<JsFuture>>();
+        .map(|user_id| client.get_users_info(user_id).into_body())
+        .collect::<Vec<_>>();
 
     let user_responses = join_all(users)
         .await
         .into_iter()
-        .map(|result| {
-            m! {
-                // mapping error instead of using snafu context because jsvalue is not an Error from parse method
-                val <- result.map_err(|err| Error::WasmErrorFromJsFuture {
-                    error: format!("{:#?}", err),
-                });
-                js_obj <- convert_result_string_to_object(val).context(CouldNotParseJsonFromUserResponseSnafu);
-                user_response <- response::defined_from_js_object(js_obj).context(SerdeWasmBindgenCouldNotParseUserResponseSnafu);
-                valid_response <- UserResponse::validate_response(user_response).context(InvalidUserResponseSnafu);
-                return valid_response;
-            }
-        })
+        .map(|result| parse_users_response::<UserResponse, T>(result, client))
         .collect::<Result<Vec<UserResponse>>>()?;
 
     Ok(Users(
@@ -78,6 +213,7 @@ where
             .map(String::to_string)
             .zip(user_responses.into_iter().map(|user_response| {
                 user_response
+                    .data
                     .user
                     .expect("Expected a user in the user response, but got None. This is a bug")
             }))
@@ -85,6 +221,54 @@ where
     ))
 }
 
+/// Pages through `users.list` into `cache`, stopping early once every one of
+/// `user_ids` was found
+async fn get_users_from_users_list<T>(
+    user_ids: &[String],
+    client: &SlackHttpClient<T>,
+    cache: &mut Users,
+) -> Result<()>
+where
+    T: TransportResponse,
+{
+    let mut paginator = Paginator::default();
+    loop {
+        let result = client.get_users_list(paginator.cursor()).into_body().await;
+        let users_list_response = parse_users_response::<UsersListResponse, T>(result, client)?;
+
+        let has_next_page = paginator.advance(&users_list_response);
+        cache.0.extend(
+            users_list_response
+                .data
+                .members
+                .unwrap_or_default()
+                .into_iter()
+                .map(|user| (user.id.to_string(), user)),
+        );
+
+        if !has_next_page || user_ids.iter().all(|user_id| cache.contains_key(user_id)) {
+            return Ok(());
+        }
+    }
+}
+
+fn parse_users_response<R, T>(
+    result: std::result::Result<String, String>,
+    client: &SlackHttpClient<T>,
+) -> Result<R>
+where
+    R: serde::de::DeserializeOwned + SlackResponseValidator + Debug,
+{
+    m! {
+        // mapping error instead of using snafu context because the request's error is a string, not an Error
+        val <- result.map_err(|error| Error::RequestFailed { error });
+        json <- response::parse_response_body(val).context(CouldNotParseJsonFromUserResponseSnafu);
+        users_response <- response::defined_from_json(json).context(CouldNotDeserializeUserResponseSnafu);
+        valid_response <- client.validate_response(users_response).context(InvalidUserResponseSnafu);
+        return valid_response;
+    }
+}
+
 pub trait CollectUsers<T>: Debug + Display
 where
     T: snafu::Error,
@@ -114,7 +298,7 @@ impl DerefMut for UserIds {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display)]
 #[display(Debug)]
 pub struct User {
     pub id: String,
@@ -122,23 +306,222 @@ pub struct User {
     pub team_info: Option<Team>,
     pub name: Option<String>,
     pub real_name: Option<String>,
+    pub is_admin: Option<bool>,
+    pub is_owner: Option<bool>,
+    pub is_restricted: Option<bool>,
+    pub is_ultra_restricted: Option<bool>,
+    pub is_bot: Option<bool>,
+    /// IANA time zone name, e.g. `Europe/Berlin`
+    pub tz: Option<String>,
+    /// Seconds east of UTC, including daylight saving time at the time of fetching
+    pub tz_offset: Option<i64>,
+    /// IETF language tag, e.g. `en-US`, only sent when requested with `include_locale`
+    pub locale: Option<String>,
+    /// Only the parts of the slack profile that are kept
+    pub profile: Option<UserProfile>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct UserProfile {
+    pub status_text: Option<String>,
+    /// e.g. `:palm_tree:`
+    pub status_emoji: Option<String>,
+}
+
+/// How much of every user is kept in the saved note, see the `user_detail_level`
+/// option
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum UserDetailLevel {
+    /// Only the id
+    IdsOnly,
+    /// The id, workspace and names
+    Basic,
+    /// Everything slack returned
+    #[default]
+    Full,
+}
+
+impl User {
+    /// Drops the fields `level` does not keep
+    pub fn trim_to(&mut self, level: UserDetailLevel) {
+        *self = match level {
+            UserDetailLevel::Full => return,
+            UserDetailLevel::Basic => User {
+                id: self.id.clone(),
+                team_id: self.team_id.take(),
+                name: self.name.take(),
+                real_name: self.real_name.take(),
+                ..User::default()
+            },
+            UserDetailLevel::IdsOnly => User {
+                id: self.id.clone(),
+                ..User::default()
+            },
+        };
+    }
+
+    pub fn display_name(&self) -> Option<String> {
+        self.real_name.clone().or_else(|| self.name.clone())
+    }
+
+    /// The workspace roles of the user, `member` if they have no special role
+    pub fn roles(&self) -> Vec<ParticipantRole> {
+        let roles = [
+            (self.is_owner, ParticipantRole::Owner),
+            (self.is_admin, ParticipantRole::Admin),
+            (
+                self.is_restricted
+                    .map(|is_restricted| is_restricted && self.is_ultra_restricted != Some(true)),
+                ParticipantRole::Guest,
+            ),
+            (
+                self.is_ultra_restricted,
+                ParticipantRole::SingleChannelGuest,
+            ),
+            (self.is_bot, ParticipantRole::Bot),
+        ]
+        .iter()
+        .filter(|(flag, _)| flag.unwrap_or(false))
+        .map(|(_, role)| *role)
+        .collect::<Vec<ParticipantRole>>();
+
+        if roles.is_empty() {
+            vec![ParticipantRole::Member]
+        } else {
+            roles
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ParticipantRole {
+    Owner,
+    Admin,
+    Member,
+    /// Multi channel guest
+    Guest,
+    SingleChannelGuest,
+    Bot,
 }
 
+/// Who took part in a thread, keyed by user id. Includes users who only reacted
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display, Shrinkwrap)]
 #[display(Debug)]
+pub struct Participants(pub HashMap<String, Participant>);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct Participant {
+    pub name: Option<String>,
+    pub message_count: usize,
+    pub roles: Vec<ParticipantRole>,
+    pub tz: Option<String>,
+    pub locale: Option<String>,
+    /// Only with the `capture_presence` option
+    pub presence: Option<PresenceSnapshot>,
+}
+
+/// What a participant was up to when the thread was saved
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display)]
+#[display(Debug)]
+pub struct PresenceSnapshot {
+    /// `active` or `away`
+    pub presence: Option<String>,
+    pub status_text: Option<String>,
+    pub status_emoji: Option<String>,
+}
+
+/// Presence of users by user id, see [`get_presences_from_api`]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
+pub struct Presences(pub HashMap<String, String>);
+
+impl FromIterator<(String, String)> for Presences {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        Presences(iter.into_iter().collect())
+    }
+}
+
+impl Participants {
+    pub fn summarize(users: &Users, thread: &Messages) -> Participants {
+        users
+            .iter()
+            .map(|(user_id, user)| {
+                let message_count = thread
+                    .iter()
+                    .filter(|message| message.user.as_ref() == Some(user_id))
+                    .count();
+                (
+                    user_id.to_string(),
+                    Participant {
+                        name: user.display_name(),
+                        message_count,
+                        roles: user.roles(),
+                        tz: user.tz.clone(),
+                        locale: user.locale.clone(),
+                        presence: None,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+impl Participants {
+    /// Adds the presence and status of every participant at the time of the save
+    pub fn capture_presence(&mut self, users: &Users, presences: &Presences) {
+        self.0.iter_mut().for_each(|(user_id, participant)| {
+            let profile = users.get(user_id).and_then(|user| user.profile.as_ref());
+            participant.presence = Some(PresenceSnapshot {
+                presence: presences.get(user_id).cloned(),
+                status_text: profile
+                    .and_then(|profile| profile.status_text.clone())
+                    .filter(|status_text| !status_text.is_empty()),
+                status_emoji: profile
+                    .and_then(|profile| profile.status_emoji.clone())
+                    .filter(|status_emoji| !status_emoji.is_empty()),
+            });
+        });
+    }
+}
+
+impl FromIterator<(String, Participant)> for Participants {
+    fn from_iter<T: IntoIterator<Item = (String, Participant)>>(iter: T) -> Self {
+        Participants(iter.into_iter().collect())
+    }
+}
+
+impl SanitizeText for User {
+    fn sanitize_text(&mut self) {
+        sanitize_optional_text(&mut self.name);
+        sanitize_optional_text(&mut self.real_name);
+        if let Some(profile) = self.profile.as_mut() {
+            sanitize_optional_text(&mut profile.status_text);
+        }
+        if let Some(team) = self.team_info.as_mut() {
+            team.sanitize_text();
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Display, Shrinkwrap)]
+#[display(Debug)]
 pub struct Users(pub HashMap<String, User>);
 
 impl CollectTeams<Error> for Users {
     fn collect_teams(&self) -> Result<TeamIds> {
         self.iter()
             .map(|(_, user)| {
-                user.team_id.as_ref().map_or(
-                    TeamIdWasNoneInUserSnafu {
+                user.team_id
+                    .clone()
+                    .with_context(|| TeamIdWasNoneInUserSnafu {
                         container: user.clone(),
-                    }
-                    .fail(),
-                    |team_id| Ok(team_id.to_owned()),
-                )
+                    })
             })
             .collect()
     }
@@ -150,7 +533,38 @@ impl FromIterator<(String, User)> for Users {
     }
 }
 
+/// The users of a [`Users`] map behind reference counts, so the messages and
+/// reactions of an export share one copy of each user instead of cloning it
+pub(crate) struct SharedUsers<'a> {
+    users: &'a Users,
+    shared: HashMap<&'a str, Arc<User>>,
+}
+
+impl<'a> SharedUsers<'a> {
+    pub(crate) fn new(users: &'a Users) -> SharedUsers<'a> {
+        SharedUsers {
+            users,
+            shared: users
+                .iter()
+                .map(|(user_id, user)| (user_id.as_str(), Arc::new(user.clone())))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn get(&self, user_id: &str) -> Option<&Arc<User>> {
+        self.shared.get(user_id)
+    }
+
+    pub(crate) fn users(&self) -> &'a Users {
+        self.users
+    }
+}
+
 impl Users {
+    pub fn sanitize_text(&mut self) {
+        self.0.values_mut().for_each(SanitizeText::sanitize_text);
+    }
+
     pub fn finalize_users(users: Users, teams: &Teams) -> Result<Users> {
         users
             .0
@@ -178,15 +592,88 @@ impl Users {
     }
 }
 
+pub type PresenceResponse = SlackEnvelope<PresenceResponseData>;
+
+/// The fields of a `users.getPresence` response, see [`PresenceResponse`]
 #[derive(Serialize, Deserialize, Debug)]
-pub struct UserResponse {
-    pub ok: Option<bool>,
-    pub error: Option<String>,
+pub struct PresenceResponseData {
+    pub presence: Option<String>,
+}
+
+pub type UserResponse = SlackEnvelope<UserResponseData>;
+
+/// The fields of a `users.info` response, see [`UserResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserResponseData {
     pub user: Option<User>,
 }
 
-impl SlackResponseValidator for UserResponse {
-    fn ok(&self) -> Option<bool> {
-        self.ok
+pub type UsersListResponse = SlackEnvelope<UsersListResponseData>;
+
+/// The fields of a `users.list` response, see [`UsersListResponse`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UsersListResponseData {
+    pub members: Option<Vec<User>>,
+    pub response_metadata: Option<ResponseMetadata>,
+}
+
+impl PaginatedResponse for UsersListResponseData {
+    fn response_metadata(&self) -> Option<&ResponseMetadata> {
+        self.response_metadata.as_ref()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use serde_json::json;
+
+    use super::{get_users_from_api, Users};
+    use crate::{slack_http_client::SlackHttpClientConfigOptions, test_support::client};
+
+    #[async_std::test]
+    async fn get_users_from_api_pages_users_list_until_every_user_is_found() {
+        let options = SlackHttpClientConfigOptions {
+            users_list_threshold: Some(1),
+            ..Default::default()
+        };
+        let (client, requested_urls) = client(options, |url| {
+            Ok(if url.contains("cursor=page2") {
+                json!({ "ok": true, "members": [{ "id": "U2", "name": "two" }] })
+            } else {
+                json!({
+                    "ok": true,
+                    "members": [{ "id": "U1", "name": "one" }],
+                    "response_metadata": { "next_cursor": "page2" }
+                })
+            })
+        });
+
+        let users = get_users_from_api(
+            &vec!["U1".to_string(), "U2".to_string()],
+            &client,
+            &mut Users::default(),
+        )
+        .await
+        .expect("Should get users");
+
+        assert_eq!(Some("one".to_string()), users["U1"].name);
+        assert_eq!(Some("two".to_string()), users["U2"].name);
+        assert_eq!(2, requested_urls.borrow().len());
+        assert!(requested_urls
+            .borrow()
+            .iter()
+            .all(|url| url.contains("users.list")));
+    }
+
+    #[async_std::test]
+    async fn get_users_from_api_fails_on_a_failed_request() {
+        let (client, _) = client(SlackHttpClientConfigOptions::default(), |_| {
+            Err("offline".to_string())
+        });
+
+        let result =
+            get_users_from_api(&vec!["U1".to_string()], &client, &mut Users::default()).await;
+
+        assert!(result.unwrap_err().to_string().contains("offline"));
     }
 }
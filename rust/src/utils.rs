@@ -1,9 +1,30 @@
-use crate::{slack_http_client::RequestUrlParam, slack_url::SlackUrl};
+use crate::{
+    dedup::RecentRequests,
+    error_code::{ErrorCode, Suggestion},
+    fetch_stats::FetchStats,
+    i18n::{error_details_label, error_summary, suggestion_text, Language},
+    rate_limit::RateLimiter,
+    response::{is_auth_error, is_ok_response},
+    session_cache,
+    slack_http_client::{
+        ApiBudget, RequestUrlParam, SlackCredentials, SlackHttpClientConfigOptions,
+        TransportResponse, RESPONSE_TOO_LARGE_ERROR,
+    },
+    slack_url::SlackUrl,
+};
+use futures::FutureExt;
 use js_sys::Promise;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use serde::Serialize;
 use serde_wasm_bindgen::Serializer;
-use std::collections::HashSet;
-use wasm_bindgen::JsValue;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+use unicode_normalization::UnicodeNormalization;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
 
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
@@ -14,6 +35,7 @@ pub fn set_panic_hook() {
     // https://github.com/rustwasm/console_error_panic_hook#readme
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
+    crate::panics::record_panics();
 }
 
 pub fn create_file_name(slack_url: &SlackUrl) -> String {
@@ -37,31 +59,812 @@ pub fn create_file_name(slack_url: &SlackUrl) -> String {
     items.join("-") + ".json"
 }
 
+fn call_request_func(request_func: &js_sys::Function, params: &RequestUrlParam) -> Promise {
+    let serializer = Serializer::json_compatible();
+    js_sys::Promise::from(
+        request_func
+            .call1(
+                &JsValue::NULL,
+                &params
+                    .serialize(&serializer)
+                    .expect("Expected to serialize params, but was unable to. This is a bug"),
+            )
+            .expect("Expected to create a js promise in rust, but was unable too. This is a bug"),
+    )
+}
+
+/// A request function like obsidian's `request` that sends requests with `fetch`,
+/// for running in node where there is no obsidian. Like `request` it resolves to
+/// the body of the response and rejects for an error status. Bytes are read
+/// only up to the request's `max_response_bytes`, see [`read_body_bytes`]. Keep
+/// the closure until the call using it is done
+pub fn fetch_request_func(fetch: js_sys::Function) -> Closure<dyn Fn(JsValue) -> Promise> {
+    Closure::wrap(Box::new(move |params: JsValue| -> Promise {
+        let fetch = fetch.clone();
+        future_to_promise(async move {
+            let url = js_sys::Reflect::get(&params, &JsValue::from_str("url"))?;
+            // the params have the method, headers and body, fetch ignores the url
+            let response = JsFuture::from(Promise::resolve(&fetch.call2(
+                &JsValue::NULL,
+                &url,
+                &params,
+            )?))
+            .await?;
+            let ok = js_sys::Reflect::get(&response, &JsValue::from_str("ok"))?.is_truthy();
+            let status = js_sys::Reflect::get(&response, &JsValue::from_str("status"))?;
+            if !ok {
+                return Err(JsValue::from_str(&format!(
+                    "Request failed, status {}",
+                    status.as_f64().unwrap_or_default()
+                )));
+            }
+            let response_type = js_sys::Reflect::get(&params, &JsValue::from_str("response_type"))?;
+            if response_type.as_string().as_deref() == Some("arraybuffer") {
+                let max_bytes =
+                    js_sys::Reflect::get(&params, &JsValue::from_str("max_response_bytes"))?
+                        .as_f64();
+                return read_body_bytes(&response, max_bytes)
+                    .await
+                    .map(JsValue::from);
+            }
+            JsFuture::from(Promise::resolve(&call_method(&response, "text")?)).await
+        })
+    }))
+}
+
+/// Calls the method `name` of `target` without arguments
+fn call_method(target: &JsValue, name: &str) -> Result<JsValue, JsValue> {
+    js_sys::Function::from(js_sys::Reflect::get(target, &JsValue::from_str(name))?).call0(target)
+}
+
+/// The body of a fetch `response` as bytes. Once it is larger than `max_bytes`,
+/// from its content-length or else from the chunks read so far, it rejects with
+/// [`RESPONSE_TOO_LARGE_ERROR`] and stops reading, so a large file is never
+/// held whole
+async fn read_body_bytes(
+    response: &JsValue,
+    max_bytes: Option<f64>,
+) -> Result<js_sys::Uint8Array, JsValue> {
+    let too_large =
+        |size: f64| JsValue::from_str(&format!("{}: {} bytes", RESPONSE_TOO_LARGE_ERROR, size));
+    let headers = js_sys::Reflect::get(response, &JsValue::from_str("headers"))?;
+    let content_length =
+        js_sys::Function::from(js_sys::Reflect::get(&headers, &JsValue::from_str("get"))?)
+            .call1(&headers, &JsValue::from_str("content-length"))?
+            .as_string()
+            .and_then(|length| length.parse::<f64>().ok());
+    if let (Some(length), Some(max_bytes)) = (content_length, max_bytes) {
+        if length > max_bytes {
+            return Err(too_large(length));
+        }
+    }
+    let body = js_sys::Reflect::get(response, &JsValue::from_str("body"))?;
+    if body.is_null() || body.is_undefined() {
+        let buffer =
+            JsFuture::from(Promise::resolve(&call_method(response, "arrayBuffer")?)).await?;
+        return Ok(js_sys::Uint8Array::new(&buffer));
+    }
+    let reader = call_method(&body, "getReader")?;
+    let mut bytes = Vec::new();
+    loop {
+        let chunk = JsFuture::from(Promise::resolve(&call_method(&reader, "read")?)).await?;
+        if js_sys::Reflect::get(&chunk, &JsValue::from_str("done"))?.is_truthy() {
+            return Ok(js_sys::Uint8Array::from(&bytes[..]));
+        }
+        let value = js_sys::Reflect::get(&chunk, &JsValue::from_str("value"))?;
+        bytes.extend(js_sys::Uint8Array::new(&value).to_vec());
+        if max_bytes.is_some_and(|max_bytes| bytes.len() as f64 > max_bytes) {
+            // not awaited, the download is given up either way
+            let _ = call_method(&reader, "cancel");
+            return Err(too_large(bytes.len() as f64));
+        }
+    }
+}
+
+/// Calls the js `request` function, counting the request and the size of its
+/// response in `fetch_stats`
+fn call_request_func_with_stats(
+    request_func: &js_sys::Function,
+    params: &RequestUrlParam,
+    fetch_stats: Rc<RefCell<FetchStats>>,
+) -> Promise {
+    if let Some(stats_key) = params.stats_key() {
+        fetch_stats.borrow_mut().record_call(&stats_key);
+    }
+    let request = JsFuture::from(call_request_func(request_func, params));
+    future_to_promise(async move {
+        let result = request.await?;
+        if let Some(body) = result.as_string() {
+            fetch_stats.borrow_mut().bytes_downloaded += body.len();
+        } else if let Some(body) = result.dyn_ref::<js_sys::Uint8Array>() {
+            fetch_stats.borrow_mut().bytes_downloaded += body.length() as usize;
+        }
+        Ok(result)
+    })
+}
+
+/// Characters that are not allowed (or have meaning) in obsidian note names
+const INVALID_NOTE_TITLE_CHARS: [char; 13] = [
+    '\\', '/', ':', '*', '?', '"', '<', '>', '|', '#', '^', '[', ']',
+];
+
+const MAX_NOTE_TITLE_CHARS: usize = 100;
+
+/// Makes a line of slack text usable as a note title: slack links and mentions
+/// (`<https://x|label>`) are reduced to their label, characters obsidian does not
+/// allow in note names are dropped, whitespace is collapsed, and the result is
+/// capped at [`MAX_NOTE_TITLE_CHARS`] characters
+pub fn sanitize_note_title(title: &str) -> String {
+    let mut unwrapped = String::with_capacity(title.len());
+    let mut rest = title;
+    while let Some(start) = rest.find('<') {
+        unwrapped.push_str(&rest[..start]);
+        match rest[start..].find('>') {
+            Some(end) => {
+                let inner = &rest[start + 1..start + end];
+                unwrapped.push_str(inner.rsplit('|').next().unwrap_or(inner));
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    unwrapped.push_str(rest);
+
+    unwrapped
+        .replace(&INVALID_NOTE_TITLE_CHARS[..], " ")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .chars()
+        .take(MAX_NOTE_TITLE_CHARS)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+pub fn create_file_name_from_title(title: &str) -> String {
+    title.to_string() + ".json"
+}
+
+/// Removes slack's `<...>` markup (links, user/channel mentions) so text inside
+/// it is not mistaken for prose, e.g. the `#` in `<#C0000000000|general>`
+pub fn strip_slack_markup(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find('>')
+            .map_or("", |end| &rest[start + end + 1..]);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '/'
+}
+
+/// Finds `#hashtags` in text, returned without the `#`. Like obsidian, a tag must
+/// contain at least one non-numeric character so `#1` is not a tag
+pub fn extract_hashtags(text: &str) -> Vec<String> {
+    let text = strip_slack_markup(text);
+    text.match_indices('#')
+        .filter(|(index, _)| {
+            text[..*index]
+                .chars()
+                .next_back()
+                .is_none_or(|previous| !is_tag_char(previous) && previous != '&')
+        })
+        .filter_map(|(index, _)| {
+            let tag = text[index + 1..]
+                .chars()
+                .take_while(|c| is_tag_char(*c))
+                .collect::<String>();
+            let tag = tag.trim_end_matches(['-', '/']).to_string();
+            if tag.chars().any(|c| !c.is_numeric()) {
+                Some(tag)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Case insensitive check for `word` appearing in `text` with no letters or
+/// digits directly around it, so `prod` does not match `product`
+pub fn contains_word(text: &str, word: &str) -> bool {
+    let text = text.to_lowercase();
+    let word = word.to_lowercase();
+    if word.is_empty() {
+        return false;
+    }
+    text.match_indices(&word).any(|(index, _)| {
+        let before = text[..index].chars().next_back();
+        let after = text[index + word.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Invisible characters that break searching in obsidian. Zero width (non)
+/// joiners are kept since emoji sequences rely on them
+const ZERO_WIDTH_CHARS: [char; 3] = ['\u{200B}', '\u{2060}', '\u{FEFF}'];
+
+/// Space characters that look like a space but don't match one when searching
+const NON_BREAKING_SPACES: [char; 3] = ['\u{00A0}', '\u{2007}', '\u{202F}'];
+
+/// NFC normalizes text, turns non-breaking spaces into spaces, and drops zero width
+/// and control characters other than newlines and tabs
+pub fn sanitize_text(text: &str) -> String {
+    text.nfc()
+        .filter(|c| !ZERO_WIDTH_CHARS.contains(c))
+        .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+        .map(|c| {
+            if NON_BREAKING_SPACES.contains(&c) {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+pub fn sanitize_optional_text(text: &mut Option<String>) {
+    if let Some(value) = text.as_mut() {
+        *value = sanitize_text(value);
+    }
+}
+
+/// Applies [`sanitize_text`] to every free text field of a type
+pub trait SanitizeText {
+    fn sanitize_text(&mut self);
+}
+
+/// Converts days since the unix epoch into a (year, month, day) civil date.
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Formats a slack timestamp (e.g. `1700000000.000100`) as an RFC 3339 UTC date
+/// time, e.g. `2023-11-14T22:13:20Z`. Returns None if the ts is not a timestamp
+pub fn format_ts_as_date_time(ts: &str) -> Option<String> {
+    format_ts_as_local_date_time(ts, 0)
+}
+
+/// Like [`format_ts_as_date_time`], but in the time zone `offset_seconds` east of
+/// UTC, e.g. `2023-11-14T23:13:20+01:00` for an offset of 3600
+pub fn format_ts_as_local_date_time(ts: &str, offset_seconds: i64) -> Option<String> {
+    let seconds = ts.split('.').next()?.parse::<i64>().ok()? + offset_seconds;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+    let seconds_of_day = seconds.rem_euclid(86400);
+    let offset = if offset_seconds == 0 {
+        "Z".to_string()
+    } else {
+        format!(
+            "{}{:02}:{:02}",
+            if offset_seconds < 0 { '-' } else { '+' },
+            offset_seconds.abs() / 3600,
+            offset_seconds.abs() % 3600 / 60
+        )
+    };
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        offset
+    ))
+}
+
+/// Characters `encodeURIComponent` leaves as is, the encoding obsidian expects in
+/// uri parameters
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+/// Builds an `obsidian://open` uri that opens `file_path` (relative to the vault
+/// root) in the vault named `vault`
+pub fn obsidian_uri(vault: &str, file_path: &str) -> String {
+    format!(
+        "obsidian://open?vault={}&file={}",
+        utf8_percent_encode(vault, URI_COMPONENT),
+        utf8_percent_encode(file_path, URI_COMPONENT)
+    )
+}
+
+/// The request function of an export, see [`prepare_request_func`]
+pub struct PreparedRequestFunc {
+    pub make_request: Box<dyn Fn(RequestUrlParam) -> Promise>,
+    /// Every request sent with `make_request`
+    pub fetch_stats: Rc<RefCell<FetchStats>>,
+    /// Credentials refreshed while sending, see [`curry_request_func`]
+    pub refreshed_credentials: Rc<RefCell<Option<SlackCredentials>>>,
+}
+
+/// The request function an export gives [`crate::create_client`]: the js
+/// `request_func` wrapped by [`curry_request_func`], refreshing credentials only
+/// when `refresh_credentials_func` is a function
+pub fn prepare_request_func(
+    request_func: JsValue,
+    refresh_credentials_func: JsValue,
+) -> PreparedRequestFunc {
+    let refresh_credentials_func = if refresh_credentials_func.is_function() {
+        Some(js_sys::Function::from(refresh_credentials_func))
+    } else {
+        None
+    };
+    let fetch_stats = Rc::new(RefCell::new(FetchStats::default()));
+    let refreshed_credentials = Rc::new(RefCell::new(None));
+    let make_request = curry_request_func(
+        js_sys::Function::from(request_func),
+        refresh_credentials_func,
+        refreshed_credentials.clone(),
+        fetch_stats.clone(),
+    );
+    PreparedRequestFunc {
+        make_request,
+        fetch_stats,
+        refreshed_credentials,
+    }
+}
+
+/// Wraps the js `request` function for use by the http client.
+///
+/// When a `refresh_credentials_func` is given, a request that fails with an auth
+/// error calls it once to get new credentials and is retried with them. The new
+/// credentials are kept in `refreshed_credentials`, used for every later request,
+/// and can be handed back to the caller once all requests are done.
+///
+/// Every request, retries included, is counted in `fetch_stats`
 pub fn curry_request_func(
     request_func: js_sys::Function,
+    refresh_credentials_func: Option<js_sys::Function>,
+    refreshed_credentials: Rc<RefCell<Option<SlackCredentials>>>,
+    fetch_stats: Rc<RefCell<FetchStats>>,
+) -> Box<dyn Fn(RequestUrlParam) -> Promise> {
+    let refresh_credentials_func = match refresh_credentials_func {
+        Some(func) => func,
+        None => {
+            return Box::new(move |params: RequestUrlParam| -> Promise {
+                call_request_func_with_stats(&request_func, &params, fetch_stats.clone())
+            })
+        }
+    };
+
+    Box::new(move |params: RequestUrlParam| -> Promise {
+        let log_prefix = "rust|request_with_credential_refresh";
+        let request_func = request_func.clone();
+        let refresh_credentials_func = refresh_credentials_func.clone();
+        let refreshed_credentials = refreshed_credentials.clone();
+        let fetch_stats = fetch_stats.clone();
+
+        future_to_promise(async move {
+            let current_credentials = refreshed_credentials.borrow().clone();
+            let params = match current_credentials {
+                Some(credentials) => params.with_credentials(&credentials),
+                None => params,
+            };
+
+            let result = JsFuture::from(call_request_func_with_stats(
+                &request_func,
+                &params,
+                fetch_stats.clone(),
+            ))
+            .await?;
+            if !is_auth_error(&result) {
+                return Ok(result);
+            }
+
+            log::info!("{}|auth failed, refreshing credentials", &log_prefix);
+            let credentials = JsFuture::from(Promise::resolve(
+                &refresh_credentials_func.call0(&JsValue::NULL)?,
+            ))
+            .await?;
+            let credentials = serde_wasm_bindgen::from_value::<SlackCredentials>(credentials)
+                .map_err(JsValue::from)?
+                .validate()
+                .map_err(|err| JsValue::from_str(&err.to_string()))?;
+            *refreshed_credentials.borrow_mut() = Some(credentials.clone());
+
+            log::info!("{}|retry request with refreshed credentials", &log_prefix);
+            fetch_stats.borrow_mut().retries += 1;
+            JsFuture::from(call_request_func_with_stats(
+                &request_func,
+                &params.with_credentials(&credentials),
+                fetch_stats,
+            ))
+            .await
+        })
+    })
+}
+
+/// Serializes a result for the plugin. With `strip_nulls` every property without a
+/// value is removed, so saved json only contains what slack returned
+pub fn to_js_value<T: Serialize>(value: &T, strip_nulls: bool) -> JsValue {
+    let js_value = serde_wasm_bindgen::to_value(value)
+        .expect("Expected to serialize object with serde, but was unable to. This is a bug");
+    if strip_nulls {
+        strip_null_properties(&js_value);
+    }
+    js_value
+}
+
+/// Deletes the null and undefined properties of js objects, recursing into arrays,
+/// maps and nested objects
+fn strip_null_properties(value: &JsValue) {
+    if let Some(array) = value.dyn_ref::<js_sys::Array>() {
+        array.for_each(&mut |item, _, _| strip_null_properties(&item));
+        return;
+    }
+    if let Some(map) = value.dyn_ref::<js_sys::Map>() {
+        map.for_each(&mut |item, _| strip_null_properties(&item));
+        return;
+    }
+    if let Some(object) = value.dyn_ref::<js_sys::Object>() {
+        js_sys::Object::keys(object).for_each(&mut |key, _, _| {
+            let property = js_sys::Reflect::get(object, &key).unwrap_or(JsValue::UNDEFINED);
+            if property.is_null() || property.is_undefined() {
+                let _ = js_sys::Reflect::delete_property(object, &key);
+            } else {
+                strip_null_properties(&property);
+            }
+        });
+    }
+}
+
+/// The `locale` option, read straight from the js value so it is known even when
+/// the options as a whole are invalid
+pub fn locale_from_options(options: &JsValue) -> Option<String> {
+    string_from_options(options, "locale")
+}
+
+/// The `log_level` of the options before they are parsed, so the whole call logs
+/// at it
+pub fn log_level_from_options(options: &JsValue) -> Option<String> {
+    string_from_options(options, "log_level")
+}
+
+fn string_from_options(options: &JsValue, key: &str) -> Option<String> {
+    if !options.is_object() {
+        return None;
+    }
+    js_sys::Reflect::get(options, &JsValue::from_str(key))
+        .ok()
+        .and_then(|value| value.as_string())
+}
+
+/// Wraps a request function so requests wait for their turn at the rate limits
+/// of `options`, see [`SlackHttpClientConfigOptions::rate_limit_of`]. The
+/// limiters are shared with every other call, see [`RateLimiter::shared`]
+pub fn pace_requests(
+    request_func: Box<dyn Fn(RequestUrlParam) -> Promise>,
+    options: &SlackHttpClientConfigOptions,
 ) -> Box<dyn Fn(RequestUrlParam) -> Promise> {
+    let options = options.clone();
+    let request_func = Rc::new(request_func);
     Box::new(move |params: RequestUrlParam| -> Promise {
-        let serializer = Serializer::json_compatible();
-        js_sys::Promise::from(
-            request_func
-                .call1(
+        let wait_ms = params
+            .endpoint()
+            .and_then(|endpoint| options.rate_limit_of(&endpoint))
+            .map_or(0.0, |(key, limit)| {
+                RateLimiter::shared(&key, limit).reserve(js_sys::Date::now())
+            });
+        if wait_ms <= 0.0 {
+            return request_func(params);
+        }
+
+        log::info!("rust|pace_requests|wait {}ms|url={}", wait_ms, params.url());
+        let request_func = request_func.clone();
+        future_to_promise(async move {
+            sleep(wait_ms).await?;
+            JsFuture::from(request_func(params)).await
+        })
+    })
+}
+
+/// Resolves after `ms` milliseconds, with js's `setTimeout`
+fn sleep(ms: f64) -> JsFuture {
+    JsFuture::from(Promise::new(&mut |resolve, reject| {
+        let scheduled = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .and_then(|set_timeout| {
+                set_timeout.dyn_into::<js_sys::Function>()?.call2(
                     &JsValue::NULL,
-                    &params
-                        .serialize(&serializer)
-                        .expect("Expected to serialize params, but was unable to. This is a bug"),
+                    &resolve,
+                    &JsValue::from_f64(ms),
                 )
-                .expect(
-                    "Expected to create a js promise in rust, but was unable too. This is a bug",
-                ),
-        )
+            });
+        if let Err(err) = scheduled {
+            let _ = reject.call1(&JsValue::NULL, &err);
+        }
+    }))
+}
+
+/// Wraps a request function so it refuses, with a rejected promise, every request
+/// the `api_budget` has no room for
+pub fn limit_api_calls(
+    request_func: Box<dyn Fn(RequestUrlParam) -> Promise>,
+    api_budget: Rc<ApiBudget>,
+) -> Box<dyn Fn(RequestUrlParam) -> Promise> {
+    Box::new(move |params: RequestUrlParam| -> Promise {
+        if !api_budget.spend() {
+            log::warn!("rust|limit_api_calls|refused request over the max_api_calls budget");
+            return Promise::reject(&JsValue::from_str(
+                "The max_api_calls budget is used up, the request was not sent",
+            ));
+        }
+        request_func(params)
     })
 }
 
-pub fn top_level_fail(err: &dyn snafu::Error) -> JsValue {
+/// Wraps a request function so identical read-only requests, e.g. the
+/// `conversations.info` of a channel linked twice, are sent once per call. Later
+/// ones get the first one's promise and are counted as cache hits in `fetch_stats`.
+/// A request that fails, or is answered without `"ok": true`, is forgotten once it
+/// settles, so the next identical request is sent again
+pub fn memoize_requests(
+    request_func: Box<dyn Fn(RequestUrlParam) -> Promise>,
+    fetch_stats: Rc<RefCell<FetchStats>>,
+) -> Box<dyn Fn(RequestUrlParam) -> Promise> {
+    let responses = Rc::new(RefCell::new(HashMap::<String, Promise>::new()));
+    Box::new(move |params: RequestUrlParam| -> Promise {
+        let cache_key = match params.cache_key() {
+            Some(cache_key) => cache_key,
+            None => return request_func(params),
+        };
+        if let Some(response) = responses.borrow().get(&cache_key) {
+            log::info!("rust|memoize_requests|cache hit|key={}", &cache_key);
+            if let Some(stats_key) = params.stats_key() {
+                fetch_stats.borrow_mut().record_cache_hit(&stats_key);
+            }
+            return response.clone();
+        }
+        let request = JsFuture::from(request_func(params));
+        let settled_responses = responses.clone();
+        let settled_cache_key = cache_key.clone();
+        let response = future_to_promise(async move {
+            let result = request.await;
+            if !result.as_ref().is_ok_and(is_ok_response) {
+                settled_responses.borrow_mut().remove(&settled_cache_key);
+            }
+            result
+        });
+        responses.borrow_mut().insert(cache_key, response.clone());
+        response
+    })
+}
+
+thread_local! {
+    /// Writes of every call, so a call retried by the plugin does not repeat them
+    static RECENT_WRITES: RecentRequests = RecentRequests::default();
+}
+
+/// Wraps a request function so a request that changes something in slack, sent
+/// again within `window_ms` by this or any other call while the first is pending
+/// or after it succeeded, gets the response of the first instead of being sent
+/// twice. Counted as a cache hit in `fetch_stats`. Without a window every request
+/// is sent
+pub fn dedupe_writes(
+    request_func: Box<dyn Fn(RequestUrlParam) -> Promise>,
+    window_ms: Option<u64>,
+    fetch_stats: Rc<RefCell<FetchStats>>,
+) -> Box<dyn Fn(RequestUrlParam) -> Promise> {
+    let window_ms = match window_ms {
+        Some(window_ms) if window_ms > 0 => window_ms as f64,
+        _ => return request_func,
+    };
+    Box::new(move |params: RequestUrlParam| -> Promise {
+        let endpoint = match params.slack_api_endpoint() {
+            Some(endpoint) if !endpoint.is_read_only() => endpoint,
+            _ => return request_func(params),
+        };
+        let request_id = params.request_id().to_string();
+        let (response, sent) = RECENT_WRITES.with(|recent_writes| {
+            recent_writes.send_once(&request_id, js_sys::Date::now(), window_ms, || {
+                request_func(params).into_body()
+            })
+        });
+        if !sent {
+            log::info!("rust|dedupe_writes|sent before|request_id={}", &request_id);
+            fetch_stats
+                .borrow_mut()
+                .record_cache_hit(&endpoint.to_string());
+        }
+        future_to_promise(response.map(|response| {
+            response
+                .map(|body| JsValue::from_str(&body))
+                .map_err(|error| JsValue::from_str(&error))
+        }))
+    })
+}
+
+/// Wraps a request function so users, teams and channels are answered from the
+/// [`session_cache`] when an earlier call already fetched them. Hits are counted as
+/// cache hits in `fetch_stats`, and only ok responses are kept
+pub fn cache_session_requests(
+    request_func: Box<dyn Fn(RequestUrlParam) -> Promise>,
+    fetch_stats: Rc<RefCell<FetchStats>>,
+) -> Box<dyn Fn(RequestUrlParam) -> Promise> {
+    Box::new(move |params: RequestUrlParam| -> Promise {
+        let (endpoint, cache_key) = match (params.slack_api_endpoint(), params.cache_key()) {
+            (Some(endpoint), Some(cache_key)) if session_cache::is_cached_endpoint(endpoint) => {
+                (endpoint, cache_key)
+            }
+            _ => return request_func(params),
+        };
+        if let Some(response) = session_cache::get(endpoint, &cache_key) {
+            log::info!("rust|cache_session_requests|cache hit|key={}", &cache_key);
+            fetch_stats
+                .borrow_mut()
+                .record_cache_hit(&endpoint.to_string());
+            return Promise::resolve(&JsValue::from_str(&response));
+        }
+        let request = JsFuture::from(request_func(params));
+        future_to_promise(async move {
+            let result = request.await?;
+            if let Some(response) = result.as_string().filter(|_| is_ok_response(&result)) {
+                session_cache::insert(endpoint, cache_key, response);
+            }
+            Ok(result)
+        })
+    })
+}
+
+pub fn top_level_fail(err: &(dyn snafu::Error + 'static), locale: Option<&str>) -> JsValue {
+    let code = ErrorCode::of(err);
+    let language = locale.map(Language::from_locale).unwrap_or_default();
+    let summary = match Suggestion::of(err) {
+        Some(suggestion) => format!(
+            "{} {}",
+            error_summary(code, language),
+            suggestion_text(suggestion, language)
+        ),
+        None => error_summary(code, language).to_string(),
+    };
     let message = format!(
-        "There was a problem getting slack messages. Error message: {} - Error struct: {:#?}",
-        &err, &err
+        "[{}] {} {}: {} - Error struct: {:#?}",
+        code,
+        summary,
+        error_details_label(language),
+        &err,
+        &err
     );
     log::error!("{}", &message);
     JsValue::from_str(&message)
 }
+
+#[cfg(test)]
+mod tests {
+    //! Tests of the request functions, run in wasm with `wasm-pack test` since
+    //! the requests are answered by js promises
+
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    use js_sys::Promise;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_futures::JsFuture;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::{memoize_requests, prepare_request_func, PreparedRequestFunc};
+    use crate::{
+        channels,
+        fetch_stats::{FetchStats, FILES_STATS_KEY},
+        slack_http_client::{
+            RequestUrlParam, SlackHttpClientConfigFeatureFlags, SlackHttpClientConfigOptions,
+        },
+        test_support::client_sending,
+    };
+
+    /// The first request is answered with `first_response`, the ones after with the
+    /// channel. Returns how many requests were sent after fetching the channel twice
+    async fn send_twice(first_response: fn() -> Promise) -> usize {
+        let sent = Rc::new(Cell::new(0));
+        let request_sent = sent.clone();
+        let request_func = Box::new(move |_: RequestUrlParam| -> Promise {
+            request_sent.set(request_sent.get() + 1);
+            if request_sent.get() == 1 {
+                first_response()
+            } else {
+                Promise::resolve(&JsValue::from_str(
+                    r#"{"ok": true, "channel": {"id": "C1"}}"#,
+                ))
+            }
+        });
+        let client = client_sending(
+            SlackHttpClientConfigFeatureFlags::default(),
+            SlackHttpClientConfigOptions::default(),
+            memoize_requests(request_func, Rc::new(RefCell::new(FetchStats::default()))),
+        );
+
+        assert!(channels::get_channel_from_api(&client, "C1").await.is_err());
+        let channel = channels::get_channel_from_api(&client, "C1")
+            .await
+            .expect("Should send the request again");
+        assert_eq!(Some("C1".to_string()), channel.id);
+        sent.get()
+    }
+
+    #[wasm_bindgen_test]
+    async fn memoize_requests_forgets_a_rejected_request() {
+        let sent = send_twice(|| Promise::reject(&JsValue::from_str("network error"))).await;
+
+        assert_eq!(2, sent);
+    }
+
+    #[wasm_bindgen_test]
+    async fn memoize_requests_forgets_a_response_that_is_not_ok() {
+        let sent = send_twice(|| {
+            Promise::resolve(&JsValue::from_str(
+                r#"{"ok": false, "error": "internal_error"}"#,
+            ))
+        })
+        .await;
+
+        assert_eq!(2, sent);
+    }
+
+    #[wasm_bindgen_test]
+    async fn file_downloads_are_counted_under_one_key() {
+        let request_func = js_sys::Function::new_with_args(
+            "params",
+            "return Promise.resolve(new Uint8Array([1, 2, 3]))",
+        );
+        let PreparedRequestFunc {
+            make_request,
+            fetch_stats,
+            ..
+        } = prepare_request_func(request_func.into(), JsValue::UNDEFINED);
+        let client = client_sending(
+            SlackHttpClientConfigFeatureFlags::default(),
+            SlackHttpClientConfigOptions::default(),
+            make_request,
+        );
+
+        for name in ["a.png", "b.png"].iter() {
+            let url = format!("https://files.slack.com/files-pri/T1-F1/{}", name);
+            JsFuture::from(client.get_file(&url, 1024))
+                .await
+                .expect("Should download the file");
+        }
+
+        let fetch_stats = fetch_stats.borrow();
+        assert_eq!(
+            vec![(FILES_STATS_KEY.to_string(), 2)],
+            fetch_stats
+                .calls_per_endpoint
+                .clone()
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(6, fetch_stats.bytes_downloaded);
+    }
+}
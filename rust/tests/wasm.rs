@@ -3,17 +3,56 @@ use core::panic;
 
 use js_sys::JSON;
 use obsidian_slack::{
-    channels::{Channel, ChannelResponse},
-    components::{FileName, ObsidianSlackComponents},
-    get_slack_message,
+    add_slack_bookmark,
+    bookmarks::Bookmark,
+    bots::{Bot, BotIcons},
+    channels::{Channel, ChannelDetailLevel, ChannelInfo, ChannelResponse, ChannelResponseData, ConversationEntry},
+    clippings::ObsidianSlackClippings,
+    components::{FileName, ObsidianSlackComponents, SavePath},
+    continuation::ContinuationToken,
+    digest::{Digest, ReactionCount, ThreadIndexEntry},
+    dry_run::DryRunPlan,
+    extractors::{ExtractedItem, Extractions, Extractor, PrefixExtractor},
+    fetch_stats::FetchStats,
+    graph::{GraphEdge, GraphEdgeKind, GraphNode, ParticipationGraph},
+    highlights::{HighlightMatch, Highlights},
+    huddles::{HuddleParticipant, HuddleRoom, HuddleSummary},
+    memory::MemoryLimitAction,
+    build_digest, cache_stats, clear_cache, decrypt_components,
+    encryption::EncryptedComponents,
+    export_cache,
+    error_code::ErrorCode,
+    finalize_options::{FinalizeOptions, NoteTitleStyle},
+    get_channel_info, get_error_code, get_slack_clippings, get_slack_message, get_slack_message_node,
+    get_user_info,
+    import_cache, init_wasm, list_conversations, set_log_level, take_internal_bug,
+    mark_channel_read,
+    memory_usage,
+    merge_saved_note,
+    pii::{PiiFinding, PiiKind, PiiLocation, PiiReport},
+    note_links::SavedNote,
     messages::{
-        File, FileLinks, Files, Message, MessageAndThread, MessageResponse, Messages, Reaction,
-        Reactions,
+        Attachment, File, FileLinks, Files, FilterProfile, IgnoredUserHandling, Message, MessageAndThread, MessageFilters,
+        MessageResponse, MessageResponseData, Messages, Reaction, ReactionSnapshot, Reactions, SkippedFiles, Tags,
+        TextStatistics, ThreadOrder,
+    },
+    references::{Reference, ReferenceSource, References},
+    reminders::{ThreadReminder, ThreadReminders},
+    session_cache::{PersistedCache, PersistedCacheEntry, SessionCacheStats},
+    set_log_sink,
+    slack_http_client::{
+        SlackApiRequestStrategy, SlackCredentials, SlackHttpClientConfigFeatureFlags,
+        SlackHttpClientConfigOptions,
+    },
+    team::{Team, TeamResponse, TeamResponseData, Teams},
+    usergroups::{MentionedGroup, MentionedGroupMember, MentionedGroups},
+    users::{
+        Participant, ParticipantRole, Participants, PresenceSnapshot, User, UserDetailLevel,
+        UserProfile, UserResponse, UserResponseData, Users,
     },
-    slack_http_client::SlackHttpClientConfigFeatureFlags,
-    team::{Team, TeamResponse, Teams},
-    users::{User, UserResponse, Users},
 };
+use serde::Serialize;
+use std::sync::Arc;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
 
@@ -53,15 +92,14 @@ fn get_mock_request_function(
             }}
         }}
     "#,
-        Into::<String>::into(
-            JSON::stringify(&serde_wasm_bindgen::to_value(&message_response).unwrap()).unwrap()
-        ),
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
         Into::<String>::into(
             JSON::stringify(
-                &serde_wasm_bindgen::to_value(&user_response.unwrap_or(UserResponse {
+                &response_value(&user_response.unwrap_or(UserResponse {
                     error: None,
+                    warning: None,
                     ok: Some(true),
-                    user: None
+                    data: UserResponseData { user: None },
                 }))
                 .unwrap()
             )
@@ -69,11 +107,12 @@ fn get_mock_request_function(
         ),
         Into::<String>::into(
             JSON::stringify(
-                &serde_wasm_bindgen::to_value(&channel_response.unwrap_or({
+                &response_value(&channel_response.unwrap_or({
                     ChannelResponse {
                         error: None,
+                        warning: None,
                         ok: Some(true),
-                        channel: None,
+                        data: ChannelResponseData { channel: None },
                     }
                 }))
                 .unwrap()
@@ -82,10 +121,11 @@ fn get_mock_request_function(
         ),
         Into::<String>::into(
             JSON::stringify(
-                &serde_wasm_bindgen::to_value(&team_response.unwrap_or(TeamResponse {
+                &response_value(&team_response.unwrap_or(TeamResponse {
                     error: None,
+                    warning: None,
                     ok: Some(true),
-                    team: None
+                    data: TeamResponseData { team: None },
                 }))
                 .unwrap()
             )
@@ -129,6 +169,7 @@ fn channel(user: Option<User>, user_id: Option<String>) -> Channel {
         unread_count_display: None,
         is_open: None,
         priority: None,
+        members: None,
     }
 }
 
@@ -150,6 +191,15 @@ fn user(team: Option<Team>) -> User {
         team_info: team,
         name: Some("mock_name".to_string()),
         real_name: Some("mock_real_name".to_string()),
+        is_admin: None,
+        is_owner: None,
+        is_restricted: None,
+        is_ultra_restricted: None,
+        is_bot: None,
+        tz: None,
+        tz_offset: None,
+        locale: None,
+        profile: None,
     }
 }
 
@@ -157,7 +207,7 @@ fn reaction(user: Option<User>) -> Reaction {
     Reaction {
         name: "mock reaction".to_string(),
         users: vec![DEFAULT_USER_ID.to_string()],
-        users_info: user.map(|user| vec![user]),
+        users_info: user.map(|user| vec![Arc::new(user)]),
         count: 1,
     }
 }
@@ -171,14 +221,34 @@ fn message(
 ) -> Message {
     Message {
         r#type: Some("mock_type".to_string()),
+        subtype: None,
+        hidden: None,
+        bot_id: None,
+        client_msg_id: None,
         user: Some(DEFAULT_USER_ID.to_string()),
-        user_info: user,
+        user_info: user.map(Arc::new),
         text: Some("mock_text".to_string()),
         thread_ts: Some(thread_timestamp),
         reply_count: None,
         ts: Some(timestamp),
         reactions,
         files,
+        lang: None,
+        author_local_time: None,
+        reader_local_time: None,
+        highlighted_text: None,
+        attachments: None,
+        room: None,
+        huddle: None,
+        reaction_history: None,
+        is_scheduled: None,
+        scheduled_message_id: None,
+        bot_info: None,
+        blocks: None,
+        markdown: None,
+        folded_line_count: None,
+        permalink: None,
+        task_done: None,
     }
 }
 
@@ -225,7 +295,12 @@ fn file_links() -> FileLinks {
 }
 
 fn team_response(ok: Option<bool>, error: Option<String>, team: Option<Team>) -> TeamResponse {
-    TeamResponse { ok, error, team }
+    TeamResponse {
+        ok,
+        error,
+        warning: None,
+        data: TeamResponseData { team },
+    }
 }
 
 fn channel_response(
@@ -233,11 +308,21 @@ fn channel_response(
     error: Option<String>,
     channel: Option<Channel>,
 ) -> ChannelResponse {
-    ChannelResponse { ok, error, channel }
+    ChannelResponse {
+        ok,
+        error,
+        warning: None,
+        data: ChannelResponseData { channel },
+    }
 }
 
 fn user_response(ok: Option<bool>, error: Option<String>, user: Option<User>) -> UserResponse {
-    UserResponse { ok, error, user }
+    UserResponse {
+        ok,
+        error,
+        warning: None,
+        data: UserResponseData { user },
+    }
 }
 
 fn message_response(
@@ -246,12 +331,25 @@ fn message_response(
     messages: Option<Messages>,
 ) -> MessageResponse {
     MessageResponse {
-        messages: messages.map(|messages| messages.0),
         ok,
         error,
+        warning: None,
+        data: MessageResponseData {
+            messages: messages.map(|messages| messages.0),
+            response_metadata: None,
+        },
     }
 }
 
+/// The response as slack sends it. Responses flatten the fields of their endpoint
+/// into themselves, which serde_wasm_bindgen would turn into a js `Map`
+fn response_value<T>(response: &T) -> Result<JsValue, JsValue>
+where
+    T: Serialize,
+{
+    JSON::parse(&serde_json::to_string(response).unwrap())
+}
+
 fn url(channel_id: Option<String>, ts: Option<String>, thread_ts: Option<String>) -> String {
     match (channel_id, ts, thread_ts) {
         (Some(cid), None, None) => format!("https://mock.slack.com/archives/{cid}"),
@@ -297,13 +395,59 @@ fn obsidian_slack_components(
     teams: Option<Teams>,
     file_links: Option<FileLinks>,
 ) -> ObsidianSlackComponents {
+    let root_message = message_and_thread.root_message().cloned();
+    let save_path = SavePath::new(None, &file_name);
+    let default_extractors = PrefixExtractor::defaults()
+        .into_iter()
+        .map(|extractor| Box::new(extractor) as Box<dyn Extractor>)
+        .collect::<Vec<Box<dyn Extractor>>>();
+    let extractions = Extractions::extract(&message_and_thread, &default_extractors);
+    let graph = ParticipationGraph::build(&message_and_thread);
     ObsidianSlackComponents {
+        title_suggestion: root_message.as_ref().and_then(Message::title_suggestion),
+        root_author: root_message.as_ref().and_then(Message::author_name),
+        root_date: root_message.as_ref().and_then(Message::date),
+        tags: Tags::default(),
+        text_statistics: message_and_thread.text_statistics(),
+        participants: users
+            .as_ref()
+            .map(|users| Participants::summarize(users, &message_and_thread.thread)),
+        locale: channel
+            .as_ref()
+            .and_then(|channel| channel.locale.clone())
+            .or_else(|| {
+                root_message
+                    .as_ref()
+                    .and_then(|message| message.user_info.as_ref())
+                    .and_then(|user| user.locale.clone())
+            }),
         message_and_thread,
         file_name,
         users,
         channel,
         teams,
         file_links,
+        files_data: None,
+        duplicate_files: None,
+        saved_contents: None,
+        skipped_files: SkippedFiles::default(),
+        refreshed_credentials: None,
+        mentioned_groups: None,
+        reminders: None,
+        save_path,
+        obsidian_uri: None,
+        extractions,
+        highlights: Highlights::default(),
+        warnings: vec![],
+        fetch_stats: FetchStats::default(),
+        pii_report: None,
+        references: References::default(),
+        graph,
+        due_tasks: None,
+        activity: None,
+        presences: None,
+        bots: None,
+        channel_link: None,
     }
 }
 
@@ -356,6 +500,8 @@ async fn get_slack_message_returns_data_correctly_common(
         url,
         feature_flags,
         request_func,
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
     )
     .await;
 
@@ -364,6 +510,10 @@ async fn get_slack_message_returns_data_correctly_common(
     let result: ObsidianSlackComponents =
         serde_wasm_bindgen::from_value(result).expect("Should parse return object");
 
+    // the stats depend on timing, they have a test of their own
+    assert!(result.fetch_stats.total_calls() > 0);
+    let mut expected_return_data = expected_return_data;
+    expected_return_data.fetch_stats = result.fetch_stats.clone();
     assert_eq!(expected_return_data, result);
 }
 
@@ -1389,6 +1539,8 @@ async fn get_slack_message_returns_error_messages_correctly_base(
         url,
         feature_flags,
         request_func,
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
     )
     .await;
 
@@ -1659,3 +1811,4012 @@ async fn get_slack_message_returns_invalid_team_response() {
     )
     .await;
 }
+
+fn default_message_response_json() -> String {
+    let message_response = message_response(
+        Some(true),
+        None,
+        Some(messages(
+            vec![(DEFAULT_TS_PARSED.to_string(), DEFAULT_THREAD_TS.to_string())],
+            None,
+            None,
+            None,
+        )),
+    );
+    JSON::stringify(&response_value(&message_response).unwrap())
+        .unwrap()
+        .into()
+}
+
+/// Runs `get_slack_message` for the default url against a request function built
+/// from `func_body`, for tests that need to inspect the outgoing requests
+async fn get_slack_message_with_request_func_body(
+    func_body: &str,
+    url: String,
+    feature_flags: SlackHttpClientConfigFeatureFlags,
+    options: JsValue,
+    refresh_credentials_func: JsValue,
+) -> JsValue {
+    let request_func = JsValue::from(js_sys::Function::new_with_args("params", func_body));
+
+    get_slack_message(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url,
+        serde_wasm_bindgen::to_value(&feature_flags).unwrap(),
+        request_func,
+        options,
+        refresh_credentials_func,
+    )
+    .await
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_uses_request_strategy_override_for_every_endpoint() {
+    let func_body = format!(
+        r#"
+        {{
+            if (params.method !== "GET" || params.body !== null) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": false,
+                    "error": "expected a GET request"
+                }}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        default_message_response_json(),
+    );
+    let options = SlackHttpClientConfigOptions {
+        request_strategy: Some(SlackApiRequestStrategy::Get),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_refreshes_credentials_and_retries_on_auth_error() {
+    let func_body = format!(
+        r#"
+        {{
+            if (params.headers.cookie !== "d=xoxd-refreshed") {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": false,
+                    "error": "invalid_auth"
+                }}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        default_message_response_json(),
+    );
+    let refresh_credentials_func = JsValue::from(js_sys::Function::new_no_args(
+        r#"return Promise.resolve({"token": "xoxc-refreshed", "cookie": "xoxd-refreshed"})"#,
+    ));
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        JsValue::UNDEFINED,
+        refresh_credentials_func,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some(SlackCredentials {
+            token: "xoxc-refreshed".into(),
+            cookie: "xoxd-refreshed".into(),
+        }),
+        result.refreshed_credentials
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_infers_api_base_from_govslack_url() {
+    let func_body = format!(
+        r#"
+        {{
+            if (!params.url.startsWith("https://slack-gov.com/api/")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": false,
+                    "error": "expected the govslack api"
+                }}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        default_message_response_json(),
+    );
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        format!("https://mock.slack-gov.com/archives/{DEFAULT_CHANNEL_ID}/{DEFAULT_TS}"),
+        feature_flags(false, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_returns_invalid_api_base() {
+    let options = SlackHttpClientConfigOptions {
+        api_base: Some("http://insecure.example.com/api".to_string()),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &format!("return Promise.resolve(JSON.stringify({}))", default_message_response_json()),
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(result.is_string(), "Result was not a string: {:#?}", result);
+    assert!(result.as_string().unwrap().contains("InvalidSlackApiBase"));
+}
+
+#[wasm_bindgen_test]
+fn merge_messages_prefers_client_msg_id_and_is_idempotent() {
+    let mut original = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    original.client_msg_id = Some("mock_client_msg_id".to_string());
+    let mut edited = original.clone();
+    edited.text = Some("edited mock_text".to_string());
+    edited.ts = Some(DEFAULT_TS_PARSED.to_string() + "1");
+    let reply = message(
+        DEFAULT_TS_PARSED.to_string() + "2",
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+
+    let merged = Messages::merge_messages(
+        Messages(vec![original]),
+        Messages(vec![edited.clone(), reply.clone()]),
+    );
+    assert_eq!(Messages(vec![edited.clone(), reply.clone()]), merged);
+
+    let merged_again = Messages::merge_messages(merged.clone(), merged.clone());
+    assert_eq!(merged, merged_again);
+}
+
+#[wasm_bindgen_test]
+fn merge_messages_appends_messages_without_identity_once() {
+    let mut keyless = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    keyless.ts = None;
+    keyless.client_msg_id = None;
+    let mut other_keyless = keyless.clone();
+    other_keyless.text = Some("other mock_text".to_string());
+
+    let merged = Messages::merge_messages(
+        Messages(vec![keyless.clone()]),
+        Messages(vec![keyless.clone(), other_keyless.clone()]),
+    );
+    assert_eq!(
+        Messages(vec![keyless.clone(), other_keyless.clone()]),
+        merged
+    );
+
+    let merged_again = Messages::merge_messages(merged.clone(), merged.clone());
+    assert_eq!(merged, merged_again);
+}
+
+#[wasm_bindgen_test]
+fn merge_messages_records_reaction_count_changes() {
+    let snapshot = |fetched_at: &str, count: u16| ReactionSnapshot {
+        fetched_at: Some(fetched_at.to_string()),
+        counts: vec![("mock reaction".to_string(), count)]
+            .into_iter()
+            .collect(),
+    };
+    let fetch = |fetched_at: &str, count: u16| {
+        let mut message = message(
+            DEFAULT_TS_PARSED.to_string(),
+            DEFAULT_THREAD_TS.to_string(),
+            None,
+            Some(Reactions(vec![Reaction {
+                count,
+                ..reaction(None)
+            }])),
+            None,
+        );
+        message.reaction_history = Some(vec![snapshot(fetched_at, count)]);
+        message
+    };
+
+    let merged = vec![
+        fetch("2024-01-01T00:00:00Z", 2),
+        fetch("2024-01-01T01:00:00Z", 2),
+        fetch("2024-01-02T00:00:00Z", 5),
+    ]
+    .into_iter()
+    .fold(
+        Messages(vec![fetch("2024-01-01T00:00:00Z", 1)]),
+        |existing, newer| Messages::merge_messages(existing, Messages(vec![newer])),
+    );
+
+    assert_eq!(1, merged.len());
+    assert_eq!(5, merged[0].reactions.as_ref().unwrap()[0].count);
+    assert_eq!(
+        Some(vec![
+            snapshot("2024-01-01T00:00:00Z", 1),
+            snapshot("2024-01-01T00:00:00Z", 2),
+            snapshot("2024-01-02T00:00:00Z", 5),
+        ]),
+        merged[0].reaction_history
+    );
+    assert_eq!(
+        merged,
+        Messages::merge_messages(merged.clone(), merged.clone())
+    );
+}
+
+#[wasm_bindgen_test]
+async fn merge_saved_note_keeps_the_messages_of_a_note_saved_without_client_msg_id() {
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        default_message_response_json()
+    );
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let saved = JSON::parse(&format!(
+        r#"{{"message_and_thread": {{
+            "message": [{{ "ts": "{ts}", "text": "saved mock_text" }}],
+            "thread": [
+                {{ "ts": "{ts}", "text": "saved mock_text" }},
+                {{ "ts": "{ts}1", "text": "deleted since" }}
+            ]
+        }}}}"#,
+        ts = DEFAULT_TS_PARSED
+    ))
+    .unwrap();
+
+    let merged = merge_saved_note(saved, result, false);
+
+    assert!(!merged.is_string(), "Result was a string: {:#?}", merged);
+    let merged: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(merged).expect("Should parse return object");
+    assert_eq!(1, merged.message_and_thread.message.len());
+    assert_eq!(
+        vec![Some("mock_text"), Some("deleted since")],
+        merged
+            .message_and_thread
+            .thread
+            .iter()
+            .map(|message| message.text.as_deref())
+            .collect::<Vec<Option<&str>>>()
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_promotes_root_message_metadata() {
+    let mut root_message = message(
+        "1700000000.000100".to_string(),
+        "1700000000.000100".to_string(),
+        None,
+        None,
+        None,
+    );
+    root_message.text = Some("\n   Incident: db is down  \nmore details".to_string());
+    let reply = message(
+        "1700000001.000100".to_string(),
+        "1700000000.000100".to_string(),
+        None,
+        None,
+        None,
+    );
+    let message_response =
+        message_response(Some(true), None, Some(Messages(vec![root_message, reply])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some("p1700000001000100".to_string()),
+            Some("1700000000.000100".to_string()),
+        ),
+        feature_flags(false, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some("Incident: db is down".to_string()),
+        result.title_suggestion
+    );
+    assert_eq!(Some(DEFAULT_USER_ID.to_string()), result.root_author);
+    assert_eq!(Some("2023-11-14T22:13:20Z".to_string()), result.root_date);
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_counts_the_text_of_the_thread() {
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    root_message.text = Some("Hi <@U123ABC>, see *bold* _it_".to_string());
+    let message_response = message_response(Some(true), None, Some(Messages(vec![root_message])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        TextStatistics {
+            word_count: 5,
+            character_count: 30,
+            reading_time_minutes: 1,
+        },
+        result.text_statistics
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_names_note_after_content_with_channel_prefix() {
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    root_message.text = Some("Deploy <https://ci.example.com|pipeline> failed?".to_string());
+    let message_response = message_response(Some(true), None, Some(Messages(vec![root_message])));
+    let mut the_channel = channel(None, None);
+    the_channel.name = Some("incidents".to_string());
+    let channel_response = channel_response(Some(true), None, Some(the_channel));
+    let request_func =
+        get_mock_request_function(message_response, None, Some(channel_response), None);
+    let options = FinalizeOptions {
+        note_title_style: NoteTitleStyle::Content,
+        title_channel_prefix: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        serde_wasm_bindgen::to_value(&feature_flags(false, true, false, false)).unwrap(),
+        request_func,
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some("incidents - Deploy pipeline failed".to_string()),
+        result.title_suggestion
+    );
+    assert_eq!(
+        FileName("incidents - Deploy pipeline failed.json".to_string()),
+        result.file_name
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_extracts_hashtags_and_keyword_tags() {
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    root_message.text =
+        Some("#release blocked in <#C0000000001|general>, see issue #42 #release".to_string());
+    let mut reply = message(
+        DEFAULT_TS_PARSED.to_string() + "1",
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    reply.text = Some("Another Outage, not an outages list".to_string());
+    let message_response =
+        message_response(Some(true), None, Some(Messages(vec![root_message, reply])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+    let options = FinalizeOptions {
+        keyword_tags: [("outage".to_string(), "#incident".to_string())].into(),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Tags(vec!["incident".to_string(), "release".to_string()]),
+        result.tags
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_tags_reactions_in_reaction_tags() {
+    let reacted = |name: &str| Reaction {
+        name: name.to_string(),
+        ..reaction(None)
+    };
+    let root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        Some(Reactions(vec![reacted("bookmark"), reacted("tada")])),
+        None,
+    );
+    let reply = message(
+        DEFAULT_TS_PARSED.to_string() + "1",
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        Some(Reactions(vec![reacted("bulb::skin-tone-2")])),
+        None,
+    );
+    let message_response =
+        message_response(Some(true), None, Some(Messages(vec![root_message, reply])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+    let options = FinalizeOptions {
+        reaction_tags: [
+            (":bookmark:".to_string(), "#to-read".to_string()),
+            ("bulb".to_string(), "idea".to_string()),
+            ("eyes".to_string(), "in-review".to_string()),
+        ]
+        .into(),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Tags(vec!["idea".to_string(), "to-read".to_string()]),
+        result.tags
+    );
+}
+
+#[cfg(feature = "language_detection")]
+#[wasm_bindgen_test]
+async fn get_slack_message_labels_message_language() {
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    root_message.text = Some(
+        "Wir haben heute das neue Release ausgerollt und alle Tests sind erfolgreich gelaufen."
+            .to_string(),
+    );
+    let message_response = message_response(Some(true), None, Some(Messages(vec![root_message])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+    let options = FinalizeOptions {
+        detect_language: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some("deu".to_string()),
+        result.message_and_thread.thread[0].lang
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_sanitizes_text_unless_disabled() {
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    let raw_text = "zero\u{200B}width\u{00A0}space\u{0007} cafe\u{0301}";
+    root_message.text = Some(raw_text.to_string());
+    let message_response = message_response(Some(true), None, Some(Messages(vec![root_message])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+
+    for (options, expected_text) in [
+        (FinalizeOptions::default(), "zerowidth space caf\u{00E9}"),
+        (
+            FinalizeOptions {
+                disable_text_sanitization: true,
+                ..Default::default()
+            },
+            raw_text,
+        ),
+    ] {
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(false, false, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        let result: ObsidianSlackComponents =
+            serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+        assert_eq!(
+            Some(expected_text.to_string()),
+            result.message_and_thread.thread[0].text
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_annotates_participant_roles() {
+    let message_response = message_response(
+        Some(true),
+        None,
+        Some(messages(
+            vec![
+                (DEFAULT_TS_PARSED.to_string(), DEFAULT_THREAD_TS.to_string()),
+                (
+                    DEFAULT_TS_PARSED.to_string() + "1",
+                    DEFAULT_THREAD_TS.to_string(),
+                ),
+            ],
+            None,
+            None,
+            None,
+        )),
+    );
+    let mut admin_guest = user(None);
+    admin_guest.is_admin = Some(true);
+    admin_guest.is_restricted = Some(true);
+    let request_func = get_mock_request_function(
+        message_response,
+        Some(user_response(Some(true), None, Some(admin_guest))),
+        None,
+        None,
+    );
+
+    let result = get_slack_message(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        serde_wasm_bindgen::to_value(&feature_flags(true, false, false, false)).unwrap(),
+        request_func,
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some(Participants(
+            [(
+                DEFAULT_USER_ID.to_string(),
+                Participant {
+                    name: Some("mock_real_name".to_string()),
+                    message_count: 2,
+                    roles: vec![ParticipantRole::Admin, ParticipantRole::Guest],
+                    tz: None,
+                    locale: None,
+                    presence: None,
+                }
+            )]
+            .into()
+        )),
+        result.participants
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_renders_author_and_reader_local_times() {
+    let message_response = message_response(
+        Some(true),
+        None,
+        Some(messages(
+            vec![(
+                "1700000000.000100".to_string(),
+                "1700000000.000100".to_string(),
+            )],
+            None,
+            None,
+            None,
+        )),
+    );
+    let mut the_user = user(None);
+    the_user.tz = Some("America/New_York".to_string());
+    the_user.tz_offset = Some(-18000);
+    let request_func = get_mock_request_function(
+        message_response,
+        Some(user_response(Some(true), None, Some(the_user))),
+        None,
+        None,
+    );
+    let options = FinalizeOptions {
+        render_local_times: true,
+        reader_tz_offset: Some(3600),
+        ..Default::default()
+    };
+
+    let result = get_slack_message(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some("p1700000000000100".to_string()),
+            None,
+        ),
+        serde_wasm_bindgen::to_value(&feature_flags(true, false, false, false)).unwrap(),
+        request_func,
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    let the_message = &result.message_and_thread.thread[0];
+    assert_eq!(
+        Some("2023-11-14T17:13:20-05:00".to_string()),
+        the_message.author_local_time
+    );
+    assert_eq!(
+        Some("2023-11-14T23:13:20+01:00".to_string()),
+        the_message.reader_local_time
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_requests_and_exposes_locales() {
+    let mut the_user = user(None);
+    the_user.locale = Some("de-DE".to_string());
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("users.info")) {{
+                if (!params.url.includes("include_locale=true")) {{
+                    return Promise.resolve(JSON.stringify({{
+                        "ok": false,
+                        "error": "expected include_locale"
+                    }}))
+                }}
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        Into::<String>::into(
+            JSON::stringify(
+                &response_value(&user_response(Some(true), None, Some(the_user))).unwrap()
+            )
+            .unwrap()
+        ),
+        default_message_response_json(),
+    );
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(Some("de-DE".to_string()), result.locale);
+    assert_eq!(
+        Some("de-DE".to_string()),
+        result.participants.unwrap().0[DEFAULT_USER_ID].locale
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_pages_users_list_past_threshold() {
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": false,
+                    "error": "expected users.list instead of users.info"
+                }}))
+            }}
+            if (params.url.includes("users.list")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": true,
+                    "members": [{}],
+                    "response_metadata": {{ "next_cursor": "" }}
+                }}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        Into::<String>::into(
+            JSON::stringify(&serde_wasm_bindgen::to_value(&user(None)).unwrap()).unwrap()
+        ),
+        default_message_response_json(),
+    );
+    let options = SlackHttpClientConfigOptions {
+        users_list_threshold: Some(1),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some(Users([(DEFAULT_USER_ID.to_string(), user(None))].into())),
+        result.users
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_expands_mentioned_usergroups() {
+    let mut the_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    the_message.text = Some("<!subteam^S0000000000|@oncall> please take a look".to_string());
+    let message_response = message_response(Some(true), None, Some(Messages(vec![the_message])));
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("usergroups.users.list")) {{
+                if (!params.url.includes("usergroup=S0000000000")) {{
+                    return Promise.resolve(JSON.stringify({{
+                        "ok": false,
+                        "error": "no_such_subteam"
+                    }}))
+                }}
+                return Promise.resolve(JSON.stringify({{
+                    "ok": true,
+                    "users": ["{DEFAULT_USER_ID}"]
+                }}))
+            }}
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        Into::<String>::into(
+            JSON::stringify(
+                &response_value(&user_response(Some(true), None, Some(user(None)))).unwrap()
+            )
+            .unwrap()
+        ),
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+    let options = SlackHttpClientConfigOptions {
+        expand_usergroups: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some(MentionedGroups(vec![MentionedGroup {
+            id: "S0000000000".to_string(),
+            name: Some("oncall".to_string()),
+            members: vec![MentionedGroupMember {
+                id: DEFAULT_USER_ID.to_string(),
+                name: Some("mock_real_name".to_string()),
+            }],
+        }])),
+        result.mentioned_groups
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_includes_reminders_on_thread_messages() {
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("reminders.list")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": true,
+                    "reminders": [
+                        {{
+                            "id": "Rm0000000001",
+                            "text": "<https://mock.slack.com/archives/{DEFAULT_CHANNEL_ID}/p0000000000000000>",
+                            "recurring": false,
+                            "time": 1700000000,
+                            "complete_ts": 0
+                        }},
+                        {{
+                            "id": "Rm0000000002",
+                            "text": "water the plants",
+                            "recurring": false,
+                            "time": 1700000000,
+                            "complete_ts": 0
+                        }}
+                    ]
+                }}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        default_message_response_json(),
+    );
+    let options = SlackHttpClientConfigOptions {
+        include_reminders: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some(ThreadReminders(vec![ThreadReminder {
+            id: "Rm0000000001".to_string(),
+            message_ts: DEFAULT_TS_PARSED.to_string(),
+            text: Some(format!(
+                "<https://mock.slack.com/archives/{DEFAULT_CHANNEL_ID}/p0000000000000000>"
+            )),
+            date: Some("2023-11-14T22:13:20Z".to_string()),
+            recurring: false,
+            complete: false,
+        }])),
+        result.reminders
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_captures_participant_presence() {
+    let user_response = user_response(
+        Some(true),
+        None,
+        Some(User {
+            profile: Some(UserProfile {
+                status_text: Some("On vacation".to_string()),
+                status_emoji: Some(":palm_tree:".to_string()),
+            }),
+            ..user(None)
+        }),
+    );
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.replies")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("users.getPresence") && params.url.includes("user={DEFAULT_USER_ID}")) {{
+                return Promise.resolve(JSON.stringify({{"ok": true, "presence": "away"}}))
+            }}
+            return Promise.resolve(JSON.stringify({{
+                "ok": false,
+                "error": "endpoint not supported in test for obsidian-slack"
+            }}))
+        }}
+    "#,
+        default_message_response_json(),
+        serde_json::to_string(&user_response).unwrap(),
+    );
+    let options = SlackHttpClientConfigOptions {
+        capture_presence: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some(PresenceSnapshot {
+            presence: Some("away".to_string()),
+            status_text: Some("On vacation".to_string()),
+            status_emoji: Some(":palm_tree:".to_string()),
+        }),
+        result
+            .participants
+            .and_then(|participants| participants.get(DEFAULT_USER_ID).cloned())
+            .and_then(|participant| participant.presence)
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_adds_bot_profiles_to_bot_messages() {
+    let bot_message = Message {
+        bot_id: Some("B0000000001".to_string()),
+        subtype: Some("bot_message".to_string()),
+        ..message(
+            DEFAULT_TS_PARSED.to_string(),
+            DEFAULT_THREAD_TS.to_string(),
+            None,
+            None,
+            None,
+        )
+    };
+    let message_response = message_response(Some(true), None, Some(Messages(vec![bot_message])));
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.replies")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("bots.info") && params.url.includes("bot=B0000000001")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": true,
+                    "bot": {{
+                        "id": "B0000000001",
+                        "name": "GitHub",
+                        "app_id": "A0000000001",
+                        "icons": {{ "image_48": "https://mock.slack.com/github_48.png" }}
+                    }}
+                }}))
+            }}
+            return Promise.resolve(JSON.stringify({{
+                "ok": false,
+                "error": "endpoint not supported in test for obsidian-slack"
+            }}))
+        }}
+    "#,
+        serde_json::to_string(&message_response).unwrap(),
+    );
+    let options = SlackHttpClientConfigOptions {
+        include_bot_profiles: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    let bot = Bot {
+        id: "B0000000001".to_string(),
+        name: "GitHub".to_string(),
+        app_id: Some("A0000000001".to_string()),
+        icons: Some(BotIcons {
+            image_48: Some("https://mock.slack.com/github_48.png".to_string()),
+            ..Default::default()
+        }),
+    };
+    assert_eq!(Some(bot), result.message_and_thread.thread[0].bot_info);
+    assert_eq!(Some("GitHub APP".to_string()), result.root_author);
+}
+
+async fn add_slack_bookmark_with_request_func_body(func_body: &str, options: JsValue) -> JsValue {
+    add_slack_bookmark(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        "mock note".to_string(),
+        "obsidian://open?vault=mock&file=mock%20note".to_string(),
+        JsValue::from(js_sys::Function::new_with_args("params", func_body)),
+        options,
+    )
+    .await
+}
+
+#[wasm_bindgen_test]
+async fn add_slack_bookmark_adds_link_to_channel() {
+    let func_body = format!(
+        r#"
+        {{
+            if (!params.url.includes("bookmarks.add") || !params.url.includes("channel_id={DEFAULT_CHANNEL_ID}")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": false,
+                    "error": "expected bookmarks.add for the channel"
+                }}))
+            }}
+            return Promise.resolve(JSON.stringify({{
+                "ok": true,
+                "bookmark": {{
+                    "id": "Bk0000000000",
+                    "channel_id": "{DEFAULT_CHANNEL_ID}",
+                    "title": "mock note",
+                    "link": "obsidian://open?vault=mock&file=mock%20note",
+                    "type": "link"
+                }}
+            }}))
+        }}
+    "#
+    );
+    let options = SlackHttpClientConfigOptions {
+        bookmark_note_in_channel: true,
+        ..Default::default()
+    };
+
+    let result = add_slack_bookmark_with_request_func_body(
+        &func_body,
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: Bookmark = serde_wasm_bindgen::from_value(result).expect("Should parse bookmark");
+    assert_eq!(
+        Bookmark {
+            id: "Bk0000000000".to_string(),
+            channel_id: Some(DEFAULT_CHANNEL_ID.to_string()),
+            title: Some("mock note".to_string()),
+            link: Some("obsidian://open?vault=mock&file=mock%20note".to_string()),
+            r#type: Some("link".to_string()),
+        },
+        result
+    );
+}
+
+#[wasm_bindgen_test]
+async fn add_slack_bookmark_does_nothing_unless_enabled() {
+    let result = add_slack_bookmark_with_request_func_body(
+        r#"return Promise.reject("should not make a request")"#,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(result.is_null(), "Result was not null: {:#?}", result);
+}
+
+#[wasm_bindgen_test]
+async fn add_slack_bookmark_returns_permission_denied() {
+    let options = SlackHttpClientConfigOptions {
+        bookmark_note_in_channel: true,
+        ..Default::default()
+    };
+
+    let result = add_slack_bookmark_with_request_func_body(
+        r#"return Promise.resolve(JSON.stringify({"ok": false, "error": "restricted_action"}))"#,
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+    )
+    .await;
+
+    assert!(result.is_string(), "Result was not a string: {:#?}", result);
+    assert!(result
+        .as_string()
+        .unwrap()
+        .contains("BookmarkPermissionDenied"));
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_returns_save_path_and_obsidian_uri() {
+    let options = FinalizeOptions {
+        vault_name: Some("My Vault".to_string()),
+        attachment_folder: Some("/slack/threads/".to_string()),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &format!(
+            "return Promise.resolve(JSON.stringify({}))",
+            default_message_response_json()
+        ),
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    let expected_file_name = file_name(
+        Some(DEFAULT_CHANNEL_ID.to_string()),
+        Some(DEFAULT_TS_PARSED.to_string()),
+        None,
+    );
+    assert_eq!(
+        Some(SavePath(format!("slack/threads/{}", expected_file_name.0))),
+        result.save_path
+    );
+    assert_eq!(
+        Some(format!(
+            "obsidian://open?vault=My%20Vault&file=slack%2Fthreads%2F{}",
+            expected_file_name.0
+        )),
+        result.obsidian_uri
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_clippings_returns_each_linked_message() {
+    let other_channel_id = "C1111111111";
+    let request_func = JsValue::from(js_sys::Function::new_with_args(
+        "params",
+        &format!(
+            "return Promise.resolve(JSON.stringify({}))",
+            default_message_response_json()
+        ),
+    ));
+    let urls = vec![
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        url(
+            Some(other_channel_id.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+    ];
+
+    let result = get_slack_clippings(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        serde_wasm_bindgen::to_value(&urls).unwrap(),
+        serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+        request_func,
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackClippings =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        vec![DEFAULT_CHANNEL_ID, other_channel_id],
+        result
+            .clippings
+            .iter()
+            .map(|clipping| clipping.channel_id.as_str())
+            .collect::<Vec<&str>>()
+    );
+    assert_eq!(urls[1], result.clippings[1].permalink);
+    assert_eq!(
+        Some(DEFAULT_TS_PARSED.to_string()),
+        result.clippings[1].message.ts
+    );
+    assert_eq!(
+        FileName(format!(
+            "clippings-{}-{}.json",
+            DEFAULT_CHANNEL_ID, DEFAULT_TS_PARSED
+        )),
+        result.file_name
+    );
+    assert_eq!(None, result.users);
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_clippings_sends_repeated_requests_once() {
+    let request_func = JsValue::from(js_sys::Function::new_with_args(
+        "params",
+        &format!(
+            "return Promise.resolve(JSON.stringify({}))",
+            default_message_response_json()
+        ),
+    ));
+    let permalink = url(
+        Some(DEFAULT_CHANNEL_ID.to_string()),
+        Some(DEFAULT_TS.to_string()),
+        None,
+    );
+    let urls = vec![permalink.clone(), permalink];
+
+    let result = get_slack_clippings(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        serde_wasm_bindgen::to_value(&urls).unwrap(),
+        serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+        request_func,
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackClippings =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(2, result.clippings.len());
+    assert_eq!(result.clippings[0], result.clippings[1]);
+    let expected_counts = vec![("conversations.replies".to_string(), 1)]
+        .into_iter()
+        .collect::<std::collections::BTreeMap<String, usize>>();
+    assert_eq!(expected_counts, result.fetch_stats.calls_per_endpoint);
+    assert_eq!(expected_counts, result.fetch_stats.cache_hits_per_endpoint);
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_clippings_returns_error_without_urls() {
+    let result = get_slack_clippings(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        serde_wasm_bindgen::to_value(&Vec::<String>::new()).unwrap(),
+        serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+        JsValue::from(js_sys::Function::new_no_args("return Promise.reject()")),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(result.is_string(), "Result was not a string: {:#?}", result);
+    assert!(result.as_string().unwrap().contains("NoClippingUrls"));
+}
+
+#[wasm_bindgen_test]
+async fn build_digest_summarizes_threads_in_period() {
+    let mut thread_root = message(
+        "1700000000.000100".to_string(),
+        "1700000000.000100".to_string(),
+        None,
+        Some(Reactions(vec![reaction(None)])),
+        None,
+    );
+    thread_root.text = Some("Deploy is blocked".to_string());
+    thread_root.reply_count = Some(1);
+    let mut thread_reply = message(
+        "1700000100.000100".to_string(),
+        "1700000000.000100".to_string(),
+        None,
+        Some(Reactions(vec![reaction(None)])),
+        None,
+    );
+    thread_reply.user = Some("other_user".to_string());
+    let lone_message = message(
+        "1700000200.000100".to_string(),
+        "1700000200.000100".to_string(),
+        None,
+        None,
+        None,
+    );
+    let to_json = |messages: Vec<Message>| -> String {
+        JSON::stringify(
+            &response_value(&message_response(
+                Some(true),
+                None,
+                Some(Messages(messages)),
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+        .into()
+    };
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.history")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("conversations.replies")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        to_json(vec![lone_message, thread_root.clone()]),
+        to_json(vec![thread_root, thread_reply]),
+    );
+
+    let result = build_digest(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(Some(DEFAULT_CHANNEL_ID.to_string()), None, None),
+        1699999999.0,
+        1700086399.0,
+        serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+        JsValue::from(js_sys::Function::new_with_args("params", &func_body)),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: Digest = serde_wasm_bindgen::from_value(result).expect("Should parse digest");
+    assert_eq!(3, result.message_count);
+    assert_eq!(2, result.summaries.len());
+    let thread_summary = &result.summaries[0];
+    assert_eq!(
+        Some("1700000000.000100".to_string()),
+        thread_summary.thread_ts
+    );
+    assert_eq!(
+        Some("Deploy is blocked".to_string()),
+        thread_summary.title_suggestion
+    );
+    assert_eq!(2, thread_summary.message_count);
+    assert_eq!(
+        vec![DEFAULT_USER_ID, "other_user"],
+        thread_summary
+            .participants
+            .iter()
+            .map(|participant| participant.id.as_str())
+            .collect::<Vec<&str>>()
+    );
+    assert_eq!(
+        vec![ReactionCount {
+            name: "mock reaction".to_string(),
+            count: 2,
+        }],
+        thread_summary.top_reactions
+    );
+    assert_eq!(1, result.summaries[1].message_count);
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_extracts_decisions_and_custom_items() {
+    let mut the_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    the_message.text = Some(
+        "Some discussion\n  decision: roll back the release\nRISK: the cache may be cold"
+            .to_string(),
+    );
+    let message_response = message_response(Some(true), None, Some(Messages(vec![the_message])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap())
+    );
+    let mut extractors = PrefixExtractor::defaults();
+    extractors.push(PrefixExtractor {
+        kind: "risks".to_string(),
+        prefixes: vec!["RISK:".to_string()],
+    });
+    let options = FinalizeOptions {
+        extractors: Some(extractors),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    let item = |text: &str| ExtractedItem {
+        text: text.to_string(),
+        message_ts: Some(DEFAULT_TS_PARSED.to_string()),
+        author: None,
+    };
+    assert_eq!(
+        Extractions(
+            [
+                ("action_items".to_string(), vec![]),
+                ("decisions".to_string(), vec![item("roll back the release")]),
+                ("risks".to_string(), vec![item("the cache may be cold")]),
+            ]
+            .into()
+        ),
+        result.extractions
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_highlights_keywords_and_patterns() {
+    let mut the_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    the_message.text = Some("Database OUTAGE started\nerror rate at 42%".to_string());
+    let message_response = message_response(Some(true), None, Some(Messages(vec![the_message])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap())
+    );
+    let options = FinalizeOptions {
+        highlight_keywords: vec!["outage".to_string()],
+        highlight_patterns: vec![r"\d+%".to_string()],
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some("Database ==OUTAGE== started\nerror rate at ==42%==".to_string()),
+        result.message_and_thread.thread[0].highlighted_text
+    );
+    let highlight = |rule: &str, text: &str, line: &str| HighlightMatch {
+        rule: rule.to_string(),
+        text: text.to_string(),
+        line: line.to_string(),
+        message_ts: Some(DEFAULT_TS_PARSED.to_string()),
+        author: None,
+    };
+    assert_eq!(
+        Highlights(vec![
+            highlight("outage", "OUTAGE", "Database OUTAGE started"),
+            highlight(r"\d+%", "42%", "error rate at 42%"),
+        ]),
+        result.highlights
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_filters_messages_before_fetching_users() {
+    let reply = |ts: &str, user_id: &str, text: &str| {
+        let mut reply = message(
+            ts.to_string(),
+            DEFAULT_THREAD_TS.to_string(),
+            None,
+            None,
+            None,
+        );
+        reply.user = Some(user_id.to_string());
+        reply.text = Some(text.to_string());
+        reply
+    };
+    let mut bot_reply = reply("0000000001.000000", "U_BOT", "Deploy finished");
+    bot_reply.bot_id = Some("B0000000000".to_string());
+    let mut join_reply = reply("0000000002.000000", "U_JOIN", "joined the channel");
+    join_reply.subtype = Some("channel_join".to_string());
+    let message_response = message_response(
+        Some(true),
+        None,
+        Some(Messages(vec![
+            reply(DEFAULT_TS_PARSED, DEFAULT_USER_ID, "ok"),
+            bot_reply,
+            join_reply,
+            reply("0000000003.000000", "U_SHORT", " +1 "),
+            reply("0000000004.000000", DEFAULT_USER_ID, "Rolled back"),
+        ])),
+    );
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("users.info")) {{
+                if (!params.url.includes("user={}")) {{
+                    return Promise.resolve(JSON.stringify({{
+                        "ok": false,
+                        "error": "requested a user of a filtered message"
+                    }}))
+                }}
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        DEFAULT_USER_ID,
+        Into::<String>::into(
+            JSON::stringify(
+                &response_value(&user_response(Some(true), None, Some(user(None)))).unwrap()
+            )
+            .unwrap()
+        ),
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+    let options = SlackHttpClientConfigOptions {
+        message_filters: MessageFilters {
+            exclude_bots: true,
+            exclude_subtypes: vec!["channel_join".to_string()],
+            only_users: vec![],
+            min_text_length: Some(3),
+            exclude_reactions: false,
+        },
+        filter_profile: FilterProfile::Faithful,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        vec![
+            Some(DEFAULT_TS_PARSED.to_string()),
+            Some("0000000004.000000".to_string())
+        ],
+        result
+            .message_and_thread
+            .thread
+            .iter()
+            .map(|message| message.ts.clone())
+            .collect::<Vec<Option<String>>>()
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_applies_filter_profile() {
+    for (filter_profile, expected_thread_length, expected_reactions) in [
+        (FilterProfile::Faithful, 3, true),
+        (FilterProfile::Clean, 1, true),
+        (FilterProfile::Minimal, 1, false),
+    ] {
+        assert_filter_profile(filter_profile, expected_thread_length, expected_reactions).await;
+    }
+}
+
+async fn assert_filter_profile(
+    filter_profile: FilterProfile,
+    expected_thread_length: usize,
+    expected_reactions: bool,
+) {
+    let reactions = Some(Reactions(vec![reaction(None)]));
+    let mut bot_reply = message(
+        "0000000001.000000".to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    bot_reply.subtype = Some("bot_message".to_string());
+    let mut join_reply = message(
+        "0000000002.000000".to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    join_reply.subtype = Some("channel_join".to_string());
+    let message_response = message_response(
+        Some(true),
+        None,
+        Some(Messages(vec![
+            message(
+                DEFAULT_TS_PARSED.to_string(),
+                DEFAULT_THREAD_TS.to_string(),
+                None,
+                reactions,
+                None,
+            ),
+            bot_reply,
+            join_reply,
+        ])),
+    );
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap())
+    );
+    let options = SlackHttpClientConfigOptions {
+        filter_profile,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        expected_thread_length,
+        result.message_and_thread.thread.len(),
+        "{}",
+        filter_profile
+    );
+    assert_eq!(
+        expected_reactions,
+        result.message_and_thread.thread[0].reactions.is_some(),
+        "{}",
+        filter_profile
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_returns_response_warnings() {
+    let func_body = format!(
+        r#"
+        {{
+            const response = {};
+            response.warning = "missing_charset,superfluous_charset";
+            return Promise.resolve(JSON.stringify(response))
+        }}
+    "#,
+        default_message_response_json(),
+    );
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        vec![
+            "missing_charset".to_string(),
+            "superfluous_charset".to_string()
+        ],
+        result.warnings
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_prefixes_errors_with_their_code() {
+    for (slack_error, expected_code) in [
+        ("invalid_auth", ErrorCode::AuthExpired),
+        ("ratelimited", ErrorCode::RateLimit),
+        ("channel_not_found", ErrorCode::SlackApi),
+    ] {
+        let func_body = format!(
+            r#"return Promise.resolve(JSON.stringify({{"ok": false, "error": "{}"}}))"#,
+            slack_error
+        );
+
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(false, false, false, false),
+            JsValue::UNDEFINED,
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert!(result.is_string(), "Result was not a string: {:#?}", result);
+        let message = result.as_string().unwrap();
+        assert!(
+            message.starts_with(&format!("[{}] ", expected_code)),
+            "{}",
+            message
+        );
+        assert_eq!(Some(expected_code.to_string()), get_error_code(message));
+    }
+
+    let result = get_slack_message_with_request_func_body(
+        "return Promise.resolve(\"{}\")",
+        "not a url".to_string(),
+        feature_flags(false, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert_eq!(
+        Some("E_URL_PARSE".to_string()),
+        get_error_code(result.as_string().unwrap())
+    );
+    assert_eq!(None, get_error_code("not an error".to_string()));
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_localizes_error_messages() {
+    let options = SlackHttpClientConfigOptions {
+        locale: Some("de-DE".to_string()),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        r#"return Promise.resolve(JSON.stringify({"ok": false, "error": "invalid_auth"}))"#,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(result.is_string(), "Result was not a string: {:#?}", result);
+    let message = result.as_string().unwrap();
+    assert!(
+        message.starts_with(
+            "[E_AUTH_EXPIRED] Slack akzeptiert dein Token oder Cookie nicht mehr. \
+            Aktualisiere sie in den Plugin-Einstellungen. Kopiere ein neues xoxc-Token \
+            und xoxd-Cookie aus Slack in deinem Browser. Fehlermeldung: "
+        ),
+        "{}",
+        message
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_plans_api_calls_on_dry_run() {
+    let options = SlackHttpClientConfigOptions {
+        dry_run: true,
+        include_reminders: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        r#"throw new Error("a dry run must not request anything")"#,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, true, true, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: DryRunPlan =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(DEFAULT_CHANNEL_ID, result.channel_id);
+    assert_eq!(DEFAULT_TS_PARSED, result.ts);
+    assert_eq!(
+        vec![
+            "MessageAndThread",
+            "ChannelInfo",
+            "UserInfo",
+            "TeamInfo",
+            "Reminders"
+        ],
+        result.steps
+    );
+    assert_eq!(
+        vec![
+            "conversations.replies",
+            "conversations.info",
+            "conversations.members",
+            "users.info",
+            "users.list",
+            "team.info",
+            "reminders.list"
+        ],
+        result
+            .calls
+            .iter()
+            .map(|call| call.endpoint.as_str())
+            .collect::<Vec<&str>>()
+    );
+    assert_eq!(5, result.min_calls);
+    assert_eq!(None, result.max_calls);
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_stops_enriching_once_api_budget_is_used_up() {
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        default_message_response_json()
+    );
+    let options = SlackHttpClientConfigOptions {
+        max_api_calls: Some(1),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(1, result.message_and_thread.thread.len());
+    assert_eq!(None, result.users);
+    assert_eq!(vec!["api_budget_exceeded".to_string()], result.warnings);
+
+    let options = SlackHttpClientConfigOptions {
+        max_api_calls: Some(0),
+        ..Default::default()
+    };
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(result.is_string(), "Result was not a string: {:#?}", result);
+    assert!(result.as_string().unwrap().contains("max_api_calls"));
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_returns_fetch_stats() {
+    let message_json = default_message_response_json();
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        Into::<String>::into(
+            JSON::stringify(
+                &response_value(&user_response(Some(true), None, Some(user(None)))).unwrap()
+            )
+            .unwrap()
+        ),
+        message_json,
+    );
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        vec![
+            ("conversations.replies".to_string(), 1),
+            ("users.info".to_string(), 1),
+        ]
+        .into_iter()
+        .collect::<std::collections::BTreeMap<String, usize>>(),
+        result.fetch_stats.calls_per_endpoint
+    );
+    assert!(result.fetch_stats.bytes_downloaded > message_json.len());
+    assert_eq!(0, result.fetch_stats.retries);
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_reuses_users_from_session_cache() {
+    clear_cache();
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        Into::<String>::into(
+            JSON::stringify(
+                &response_value(&user_response(Some(true), None, Some(user(None)))).unwrap()
+            )
+            .unwrap()
+        ),
+        default_message_response_json(),
+    );
+    let options = SlackHttpClientConfigOptions {
+        session_cache: true,
+        ..Default::default()
+    };
+
+    let mut results = vec![];
+    for _ in 0..2 {
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(true, false, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        let result: ObsidianSlackComponents =
+            serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+        results.push(result);
+    }
+
+    assert_eq!(results[0].users, results[1].users);
+    assert_eq!(
+        Some(&1),
+        results[0].fetch_stats.calls_per_endpoint.get("users.info")
+    );
+    assert_eq!(
+        None,
+        results[1].fetch_stats.calls_per_endpoint.get("users.info")
+    );
+    assert_eq!(
+        Some(&1),
+        results[1]
+            .fetch_stats
+            .cache_hits_per_endpoint
+            .get("users.info")
+    );
+    let stats: SessionCacheStats = serde_wasm_bindgen::from_value(cache_stats()).unwrap();
+    assert_eq!(
+        SessionCacheStats {
+            users: 1,
+            teams: 0,
+            channels: 0,
+            hits: 1,
+            misses: 1,
+        },
+        stats
+    );
+
+    clear_cache();
+    let stats: SessionCacheStats = serde_wasm_bindgen::from_value(cache_stats()).unwrap();
+    assert_eq!(SessionCacheStats::default(), stats);
+}
+
+#[wasm_bindgen_test]
+fn import_cache_drops_expired_entries() {
+    clear_cache();
+    let entry = |user_id: &str, cached_at_ms: f64| PersistedCacheEntry {
+        endpoint: "users.info".to_string(),
+        cache_key: format!("GET https://slack.com/api/users.info?user={}", user_id),
+        response: JSON::stringify(
+            &response_value(&user_response(Some(true), None, Some(user(None)))).unwrap(),
+        )
+        .unwrap()
+        .into(),
+        cached_at_ms,
+    };
+    let persisted = PersistedCache {
+        version: 1,
+        entries: vec![
+            entry("fresh", js_sys::Date::now()),
+            entry("expired", js_sys::Date::now() - 120_000.0),
+        ],
+    };
+
+    let imported = import_cache(serde_json::to_string(&persisted).unwrap(), Some(60.0));
+
+    assert_eq!(Some(1.0), imported.as_f64());
+    let exported: PersistedCache = serde_json::from_str(&export_cache()).unwrap();
+    assert_eq!(vec![persisted.entries[0].clone()], exported.entries);
+    let stats: SessionCacheStats = serde_wasm_bindgen::from_value(cache_stats()).unwrap();
+    assert_eq!(1, stats.users);
+
+    let imported = import_cache(export_cache(), None);
+    assert_eq!(Some(0.0), imported.as_f64());
+
+    let imported = import_cache("not a cache".to_string(), None);
+    assert_eq!(
+        Some(ErrorCode::InvalidInput),
+        ErrorCode::from_error_message(&imported.as_string().unwrap())
+    );
+    clear_cache();
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_trims_users_to_user_detail_level() {
+    let full_user = User {
+        is_admin: Some(true),
+        tz: Some("Europe/Berlin".to_string()),
+        locale: Some("de-DE".to_string()),
+        ..user(None)
+    };
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        Into::<String>::into(
+            JSON::stringify(
+                &response_value(&user_response(Some(true), None, Some(full_user.clone()))).unwrap()
+            )
+            .unwrap()
+        ),
+        default_message_response_json(),
+    );
+
+    for (user_detail_level, expected_user) in vec![
+        (UserDetailLevel::Full, full_user.clone()),
+        (
+            UserDetailLevel::Basic,
+            User {
+                id: DEFAULT_USER_ID.to_string(),
+                team_id: Some(DEFAULT_TEAM_ID.to_string()),
+                name: Some("mock_name".to_string()),
+                real_name: Some("mock_real_name".to_string()),
+                ..User::default()
+            },
+        ),
+        (
+            UserDetailLevel::IdsOnly,
+            User {
+                id: DEFAULT_USER_ID.to_string(),
+                ..User::default()
+            },
+        ),
+    ] {
+        let options = FinalizeOptions {
+            user_detail_level,
+            ..Default::default()
+        };
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(true, false, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        let result: ObsidianSlackComponents =
+            serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+        assert_eq!(
+            Some(&expected_user),
+            result.users.as_ref().unwrap().get(DEFAULT_USER_ID)
+        );
+        assert_eq!(
+            Some(Arc::new(expected_user)),
+            result.message_and_thread.thread[0].user_info
+        );
+        assert_eq!(
+            Some("Europe/Berlin".to_string()),
+            result.participants.unwrap().0[DEFAULT_USER_ID].tz
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_trims_channel_to_channel_detail_level() {
+    let full_channel = Channel {
+        id: Some(DEFAULT_CHANNEL_ID.to_string()),
+        name: Some("general".to_string()),
+        is_private: Some(false),
+        last_read: Some(DEFAULT_TS_PARSED.to_string()),
+        unread_count: Some(3),
+        ..channel(None, None)
+    };
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.resolve(JSON.stringify({}))
+        }}
+    "#,
+        Into::<String>::into(
+            JSON::stringify(
+                &response_value(&channel_response(
+                    Some(true),
+                    None,
+                    Some(full_channel.clone())
+                ))
+                .unwrap()
+            )
+            .unwrap()
+        ),
+        default_message_response_json(),
+    );
+
+    for (channel_detail_level, expected_channel) in vec![
+        (ChannelDetailLevel::Full, full_channel.clone()),
+        (
+            ChannelDetailLevel::Standard,
+            Channel {
+                last_read: None,
+                unread_count: None,
+                ..full_channel.clone()
+            },
+        ),
+        (
+            ChannelDetailLevel::Minimal,
+            Channel {
+                id: Some(DEFAULT_CHANNEL_ID.to_string()),
+                name: Some("general".to_string()),
+                ..channel(None, None)
+            },
+        ),
+    ] {
+        let options = FinalizeOptions {
+            channel_detail_level,
+            ..Default::default()
+        };
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(false, true, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        let result: ObsidianSlackComponents =
+            serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+        assert_eq!(Some(expected_channel), result.channel);
+    }
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_strips_nulls_when_asked() {
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        default_message_response_json()
+    );
+
+    for strip_nulls in vec![false, true] {
+        let options = FinalizeOptions {
+            strip_nulls,
+            ..Default::default()
+        };
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(false, false, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        let has_channel = js_sys::Reflect::has(&result, &JsValue::from_str("channel")).unwrap();
+        assert_eq!(!strip_nulls, has_channel);
+        let root_message = js_sys::Reflect::get(&result, &JsValue::from_str("message_and_thread"))
+            .and_then(|message_and_thread| {
+                js_sys::Reflect::get(&message_and_thread, &JsValue::from_str("message"))
+            })
+            .and_then(|message| js_sys::Reflect::get(&message, &JsValue::from_f64(0.0)))
+            .unwrap();
+        let has_subtype =
+            js_sys::Reflect::has(&root_message, &JsValue::from_str("subtype")).unwrap();
+        assert_eq!(!strip_nulls, has_subtype);
+        let result: ObsidianSlackComponents =
+            serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+        assert_eq!(None, result.channel);
+    }
+}
+
+#[cfg(feature = "encryption")]
+#[wasm_bindgen_test]
+async fn get_slack_message_encrypts_components_with_passphrase() {
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        default_message_response_json()
+    );
+    let options = FinalizeOptions {
+        encryption_passphrase: Some("correct horse battery staple".into()),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: EncryptedComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert!(!result.encrypted.contains(DEFAULT_CHANNEL_ID));
+
+    let decrypted = decrypt_components(
+        result.encrypted.clone(),
+        "correct horse battery staple".to_string(),
+    );
+    assert!(
+        !decrypted.is_string(),
+        "Result was a string: {:#?}",
+        decrypted
+    );
+    let decrypted: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(decrypted).expect("Should parse return object");
+    assert_eq!(result.file_name, decrypted.file_name);
+    assert_eq!(
+        Some(DEFAULT_TS_PARSED.to_string()),
+        decrypted.message_and_thread.message[0].ts
+    );
+
+    let decrypted = decrypt_components(result.encrypted, "wrong passphrase".to_string());
+    assert!(
+        decrypted.is_string(),
+        "Result was not a string: {:#?}",
+        decrypted
+    );
+    assert!(decrypted.as_string().unwrap().contains("CouldNotDecrypt"));
+}
+
+#[cfg(not(feature = "encryption"))]
+#[wasm_bindgen_test]
+async fn get_slack_message_fails_to_encrypt_without_encryption_feature() {
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        default_message_response_json()
+    );
+    let options = FinalizeOptions {
+        encryption_passphrase: Some("correct horse battery staple".into()),
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(result.is_string(), "Result was not a string: {:#?}", result);
+    assert!(result
+        .as_string()
+        .unwrap()
+        .contains("EncryptionNotSupported"));
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_reports_pii_when_asked() {
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        Some(Files(vec![File {
+            name: "passport jane@example.com scan.pdf".to_string(),
+            ..files().0[0].clone()
+        }])),
+    );
+    root_message.text = Some(
+        "Mail <mailto:jane@example.com|jane@example.com> or call +1 555-123-4567. password: hunter2"
+            .to_string(),
+    );
+    let message_json: String = JSON::stringify(
+        &response_value(&message_response(
+            Some(true),
+            None,
+            Some(Messages(vec![root_message])),
+        ))
+        .unwrap(),
+    )
+    .unwrap()
+    .into();
+    let func_body = format!("return Promise.resolve(JSON.stringify({}))", message_json);
+    let finding = |kind: PiiKind, location: PiiLocation, text: &str| PiiFinding {
+        kind,
+        location,
+        text: text.to_string(),
+        message_ts: Some(DEFAULT_TS_PARSED.to_string()),
+    };
+
+    for (detect_pii, expected_report) in vec![
+        (false, None),
+        (
+            true,
+            Some(PiiReport(vec![
+                finding(PiiKind::Email, PiiLocation::Text, "jane@example.com"),
+                finding(PiiKind::PhoneNumber, PiiLocation::Text, "+1 555-123-4567"),
+                finding(PiiKind::Credential, PiiLocation::Text, "pass*************"),
+                finding(PiiKind::Email, PiiLocation::FileName, "jane@example.com"),
+            ])),
+        ),
+    ] {
+        let options = FinalizeOptions {
+            detect_pii,
+            ..Default::default()
+        };
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(false, false, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        let result: ObsidianSlackComponents =
+            serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+        assert_eq!(expected_report, result.pii_report);
+    }
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_links_saved_threads_to_their_notes() {
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    root_message.text = Some(format!(
+        "See <https://workspace.slack.com/archives/{channel}/p1700000000000100|the deploy thread>, \
+         <https://workspace.slack.com/archives/{channel}/p1700000005000200?thread_ts=1700000000.000100> \
+         and <https://workspace.slack.com/archives/{channel}/p1700000999000100|[unsaved]>",
+        channel = DEFAULT_CHANNEL_ID
+    ));
+    let message_json: String = JSON::stringify(
+        &response_value(&message_response(
+            Some(true),
+            None,
+            Some(Messages(vec![root_message])),
+        ))
+        .unwrap(),
+    )
+    .unwrap()
+    .into();
+    let func_body = format!("return Promise.resolve(JSON.stringify({}))", message_json);
+    let options = FinalizeOptions {
+        saved_notes: vec![SavedNote {
+            channel_id: DEFAULT_CHANNEL_ID.to_string(),
+            thread_ts: "1700000000.000100".to_string(),
+            note: "Slack/Deploy is blocked".to_string(),
+        }],
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some(format!(
+            "See [[Slack/Deploy is blocked|the deploy thread]], [[Slack/Deploy is blocked]] \
+             and <https://workspace.slack.com/archives/{}/p1700000999000100|[unsaved]>",
+            DEFAULT_CHANNEL_ID
+        )),
+        result.message_and_thread.message.0[0].text
+    );
+}
+
+#[wasm_bindgen_test]
+async fn build_digest_indexes_threads_for_channel_note() {
+    let mut thread_root = message(
+        "1700000000.000100".to_string(),
+        "1700000000.000100".to_string(),
+        None,
+        None,
+        None,
+    );
+    thread_root.text = Some("Deploy is blocked".to_string());
+    thread_root.reply_count = Some(2);
+    let thread_replies = vec![
+        message(
+            "1700000100.000100".to_string(),
+            "1700000000.000100".to_string(),
+            None,
+            None,
+            None,
+        ),
+        message(
+            "1700000200.000100".to_string(),
+            "1700000000.000100".to_string(),
+            None,
+            None,
+            None,
+        ),
+    ];
+    let mut lone_message = message(
+        "1700000300.000100".to_string(),
+        "1700000300.000100".to_string(),
+        None,
+        None,
+        None,
+    );
+    lone_message.text = None;
+    let to_json = |messages: Vec<Message>| -> String {
+        JSON::stringify(
+            &response_value(&message_response(
+                Some(true),
+                None,
+                Some(Messages(messages)),
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+        .into()
+    };
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.history")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("conversations.replies")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        to_json(vec![lone_message, thread_root.clone()]),
+        to_json([vec![thread_root], thread_replies].concat()),
+    );
+    let options = FinalizeOptions {
+        note_title_style: NoteTitleStyle::Content,
+        ..Default::default()
+    };
+
+    let result = build_digest(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(Some(DEFAULT_CHANNEL_ID.to_string()), None, None),
+        1699999999.0,
+        1700086399.0,
+        serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+        JsValue::from(js_sys::Function::new_with_args("params", &func_body)),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: Digest = serde_wasm_bindgen::from_value(result).expect("Should parse digest");
+    assert_eq!(
+        vec![
+            ThreadIndexEntry {
+                root_ts: "1700000000.000100".to_string(),
+                title_suggestion: Some("Deploy is blocked".to_string()),
+                reply_count: 2,
+                file_name: FileName("Deploy is blocked.json".to_string()),
+            },
+            ThreadIndexEntry {
+                root_ts: "1700000300.000100".to_string(),
+                title_suggestion: None,
+                reply_count: 0,
+                file_name: FileName(format!("{}-1700000300.000100.json", DEFAULT_CHANNEL_ID)),
+            },
+        ],
+        result.index
+    );
+}
+
+#[wasm_bindgen_test]
+async fn build_digest_chunks_threads_over_memory_limit() {
+    let history = message_response(
+        Some(true),
+        None,
+        Some(Messages(vec![
+            message(
+                "1700000300.000100".to_string(),
+                "1700000300.000100".to_string(),
+                None,
+                None,
+                None,
+            ),
+            message(
+                "1700000000.000100".to_string(),
+                "1700000000.000100".to_string(),
+                None,
+                None,
+                None,
+            ),
+        ])),
+    );
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.history")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        String::from(JSON::stringify(&serde_wasm_bindgen::to_value(&history).unwrap()).unwrap()),
+    );
+    let options = SlackHttpClientConfigOptions {
+        memory_limit_bytes: Some(1),
+        memory_limit_action: MemoryLimitAction::Chunk,
+        ..Default::default()
+    };
+
+    let result = build_digest(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(Some(DEFAULT_CHANNEL_ID.to_string()), None, None),
+        1699999999.0,
+        1700086399.0,
+        serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+        JsValue::from(js_sys::Function::new_with_args("params", &func_body)),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: Digest = serde_wasm_bindgen::from_value(result).expect("Should parse digest");
+    assert_eq!(1, result.threads.len());
+    assert_eq!(
+        Some("1700000000.000100".to_string()),
+        result.threads[0][0].ts
+    );
+    assert_eq!(Some("1700000300.000100".to_string()), result.continue_from);
+    assert!(result.warnings.is_empty());
+    assert!(memory_usage().is_object());
+}
+
+#[wasm_bindgen_test]
+async fn build_digest_resumes_from_continuation_token() {
+    async fn export(func_body: &str, options: SlackHttpClientConfigOptions) -> Digest {
+        let result = build_digest(
+            "xoxc...".to_string(),
+            "xoxd...".to_string(),
+            url(Some(DEFAULT_CHANNEL_ID.to_string()), None, None),
+            1699999999.0,
+            1700086399.0,
+            serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+            JsValue::from(js_sys::Function::new_with_args("params", func_body)),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        serde_wasm_bindgen::from_value(result).expect("Should parse digest")
+    }
+    let to_json = |ts: &str| -> String {
+        JSON::stringify(
+            &serde_wasm_bindgen::to_value(&message(
+                ts.to_string(),
+                ts.to_string(),
+                None,
+                None,
+                None,
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+        .into()
+    };
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("cursor=page2")) {{
+                return Promise.resolve(JSON.stringify({{ "ok": true, "messages": [{}] }}))
+            }}
+            if (params.url.includes("conversations.history")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": true,
+                    "messages": [{}],
+                    "response_metadata": {{ "next_cursor": "page2" }}
+                }}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        to_json("1700000000.000100"),
+        to_json("1700000300.000100"),
+    );
+    let options = SlackHttpClientConfigOptions {
+        export_message_budget: Some(1),
+        ..Default::default()
+    };
+
+    let first_part = export(&func_body, options.clone()).await;
+    let token = ContinuationToken::parse(
+        first_part
+            .continuation
+            .as_deref()
+            .expect("Should stop at the message budget"),
+    )
+    .expect("Should parse the token");
+    assert_eq!(Some("page2".to_string()), token.cursor);
+    assert_eq!(1, token.exported_threads);
+    assert_eq!(None, first_part.read_up_to);
+
+    let second_part = export(
+        &func_body,
+        SlackHttpClientConfigOptions {
+            continuation_token: first_part.continuation,
+            ..options
+        },
+    )
+    .await;
+    assert_eq!(
+        vec![Some("1700000000.000100".to_string())],
+        second_part
+            .threads
+            .iter()
+            .map(|thread| thread[0].ts.clone())
+            .collect::<Vec<Option<String>>>()
+    );
+    assert_eq!(None, second_part.continuation);
+    assert_eq!(
+        Some("1700000300.000100".to_string()),
+        second_part.read_up_to
+    );
+}
+
+#[wasm_bindgen_test]
+async fn build_digest_groups_threads_by_day_when_asked() {
+    let to_json = |ts: &str| -> String {
+        JSON::stringify(
+            &serde_wasm_bindgen::to_value(&message(
+                ts.to_string(),
+                ts.to_string(),
+                None,
+                None,
+                None,
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+        .into()
+    };
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.history")) {{
+                return Promise.resolve(JSON.stringify({{ "ok": true, "messages": [{}, {}, {}] }}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        to_json("1700100000.000100"),
+        to_json("1700000300.000100"),
+        to_json("1700000000.000100"),
+    );
+    let options = FinalizeOptions {
+        group_by_day: true,
+        ..Default::default()
+    };
+
+    let result = build_digest(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(Some(DEFAULT_CHANNEL_ID.to_string()), None, None),
+        1699999999.0,
+        1700186399.0,
+        serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+        JsValue::from(js_sys::Function::new_with_args("params", &func_body)),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: Digest = serde_wasm_bindgen::from_value(result).expect("Should parse digest");
+    let chronicle = result.chronicle.expect("Should group the threads by day");
+    assert_eq!(
+        vec![
+            ("2023-11-14".to_string(), 2, 2),
+            ("2023-11-16".to_string(), 1, 1)
+        ],
+        chronicle
+            .days
+            .iter()
+            .map(|day| (day.date.clone(), day.thread_count, day.message_count))
+            .collect::<Vec<(String, usize, usize)>>()
+    );
+    assert_eq!(1, chronicle.days[0].participant_count);
+    let canvas: serde_json::Value =
+        serde_json::from_str(&chronicle.canvas).expect("Should be a json canvas");
+    let nodes = canvas["nodes"].as_array().expect("Should have nodes");
+    assert_eq!(
+        vec!["2023-11-14", "2023-11-16"],
+        nodes
+            .iter()
+            .filter(|node| node["type"] == "group")
+            .filter_map(|node| node["label"].as_str())
+            .collect::<Vec<&str>>()
+    );
+    assert_eq!(7, nodes.len());
+}
+
+#[wasm_bindgen_test]
+async fn get_channel_info_gets_the_channel_and_its_creators_without_messages() {
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.info") && params.url.includes("channel={}")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": true,
+                    "channel": {{
+                        "id": "{}",
+                        "name": "general",
+                        "creator": "{}",
+                        "topic": {{ "value": "Announcements", "creator": "{}", "last_set": 1700000000 }}
+                    }}
+                }}))
+            }}
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        DEFAULT_CHANNEL_ID,
+        DEFAULT_CHANNEL_ID,
+        DEFAULT_USER_ID,
+        DEFAULT_USER_ID,
+        Into::<String>::into(
+            JSON::stringify(
+                &response_value(&user_response(Some(true), None, Some(user(None)))).unwrap()
+            )
+            .unwrap()
+        ),
+    );
+
+    let result = get_channel_info(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        DEFAULT_CHANNEL_ID.to_string(),
+        JsValue::from(js_sys::Function::new_with_args("params", &func_body)),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ChannelInfo =
+        serde_wasm_bindgen::from_value(result).expect("Should parse channel info");
+    assert_eq!(Some("general".to_string()), result.channel.name);
+    assert_eq!(
+        vec![DEFAULT_USER_ID.to_string()],
+        result
+            .users
+            .expect("Should get the creators")
+            .0
+            .keys()
+            .cloned()
+            .collect::<Vec<String>>()
+    );
+    assert_eq!(None, result.label);
+    assert_eq!(2, result.fetch_stats.total_calls());
+}
+
+#[wasm_bindgen_test]
+async fn get_user_info_gets_the_mentioned_user_with_their_team() {
+    const MENTIONED_USER_ID: &str = "U0000000000";
+    async fn get(user_id_or_mention: &str) -> JsValue {
+        let func_body = format!(
+            r#"
+            {{
+                if (params.url.includes("users.info") && params.url.includes("user={}")) {{
+                    return Promise.resolve(JSON.stringify({}))
+                }}
+                if (params.url.includes("team.info") && params.url.includes("team={}")) {{
+                    return Promise.resolve(JSON.stringify({}))
+                }}
+                return Promise.reject("unexpected request " + params.url)
+            }}
+        "#,
+            MENTIONED_USER_ID,
+            Into::<String>::into(
+                JSON::stringify(
+                    &response_value(&user_response(
+                        Some(true),
+                        None,
+                        Some(User {
+                            id: MENTIONED_USER_ID.to_string(),
+                            ..user(None)
+                        })
+                    ))
+                    .unwrap()
+                )
+                .unwrap()
+            ),
+            DEFAULT_TEAM_ID,
+            Into::<String>::into(
+                JSON::stringify(
+                    &response_value(&team_response(Some(true), None, Some(team()))).unwrap()
+                )
+                .unwrap()
+            ),
+        );
+        get_user_info(
+            "xoxc...".to_string(),
+            "xoxd...".to_string(),
+            user_id_or_mention.to_string(),
+            JsValue::from(js_sys::Function::new_with_args("params", &func_body)),
+            JsValue::UNDEFINED,
+            JsValue::UNDEFINED,
+        )
+        .await
+    }
+
+    let result = get("<@U0000000000|alice>").await;
+    let result: User = serde_wasm_bindgen::from_value(result).expect("Should parse the user");
+    assert_eq!(MENTIONED_USER_ID, result.id);
+    assert_eq!(Some(team()), result.team_info);
+
+    let result = get("#general").await;
+    assert_eq!(
+        Some(ErrorCode::InvalidInput),
+        ErrorCode::from_error_message(&result.as_string().expect("Should fail"))
+    );
+}
+
+#[wasm_bindgen_test]
+async fn list_conversations_pages_through_your_conversations() {
+    async fn list(limit: Option<usize>) -> Vec<ConversationEntry> {
+        let func_body = r#"
+            {
+                if (!params.url.includes("users.conversations")
+                    || !params.url.includes("types=public_channel%2Cim")) {
+                    return Promise.reject("unexpected request " + params.url)
+                }
+                if (params.url.includes("cursor=page2")) {
+                    return Promise.resolve(JSON.stringify({
+                        "ok": true,
+                        "channels": [{ "id": "D0000000000", "is_im": true, "user": "U0000000000" }],
+                        "response_metadata": { "next_cursor": "" }
+                    }))
+                }
+                return Promise.resolve(JSON.stringify({
+                    "ok": true,
+                    "channels": [
+                        { "id": "C0000000000", "name": "general" },
+                        { "id": "C0000000001", "name": "random" }
+                    ],
+                    "response_metadata": { "next_cursor": "page2" }
+                }))
+            }
+        "#;
+        let result = list_conversations(
+            "xoxc...".to_string(),
+            "xoxd...".to_string(),
+            Some("public_channel,im".to_string()),
+            limit,
+            JsValue::from(js_sys::Function::new_with_args("params", func_body)),
+            JsValue::UNDEFINED,
+            JsValue::UNDEFINED,
+        )
+        .await;
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        serde_wasm_bindgen::from_value(result).expect("Should parse the conversations")
+    }
+    let entry = |id: &str, name: &str| ConversationEntry {
+        id: id.to_string(),
+        name: Some(name.to_string()),
+    };
+
+    assert_eq!(
+        vec![
+            entry("C0000000000", "general"),
+            entry("C0000000001", "random"),
+            entry("D0000000000", "U0000000000"),
+        ],
+        list(None).await
+    );
+    assert_eq!(vec![entry("C0000000000", "general")], list(Some(1)).await);
+}
+
+#[wasm_bindgen_test]
+async fn mark_channel_read_marks_the_channel_when_asked() {
+    async fn mark(options: SlackHttpClientConfigOptions) -> JsValue {
+        let func_body = r#"
+            {
+                if (params.url.includes("conversations.mark")
+                    && params.url.includes("channel=C0000000000")
+                    && params.url.includes("ts=1700000300.000100")) {
+                    return Promise.resolve(JSON.stringify({ "ok": true }))
+                }
+                return Promise.reject("unexpected request " + params.url)
+            }
+        "#;
+        mark_channel_read(
+            "xoxc...".to_string(),
+            "xoxd...".to_string(),
+            url(Some(DEFAULT_CHANNEL_ID.to_string()), None, None),
+            "1700000300.000100".to_string(),
+            JsValue::from(js_sys::Function::new_with_args("params", func_body)),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+        )
+        .await
+    }
+
+    assert!(mark(SlackHttpClientConfigOptions::default())
+        .await
+        .is_null());
+    assert_eq!(
+        JsValue::TRUE,
+        mark(SlackHttpClientConfigOptions {
+            mark_read_after_export: true,
+            ..Default::default()
+        })
+        .await
+    );
+}
+
+#[wasm_bindgen_test]
+async fn build_digest_exports_only_unread_messages() {
+    async fn export_from(oldest: &str, options: SlackHttpClientConfigOptions) -> JsValue {
+        let func_body = format!(
+            r#"
+            {{
+                if (params.url.includes("conversations.info")) {{
+                    return Promise.resolve(JSON.stringify({{
+                        "ok": true,
+                        "channel": {{ "id": "{}", "last_read": "1700000500.000100" }}
+                    }}))
+                }}
+                if (params.url.includes("conversations.history") && params.url.includes("oldest={}")) {{
+                    return Promise.resolve(JSON.stringify({{ "ok": true, "messages": [] }}))
+                }}
+                return Promise.reject("unexpected request " + params.url)
+            }}
+        "#,
+            DEFAULT_CHANNEL_ID, oldest
+        );
+        build_digest(
+            "xoxc...".to_string(),
+            "xoxd...".to_string(),
+            url(Some(DEFAULT_CHANNEL_ID.to_string()), None, None),
+            1699999999.0,
+            1700086399.0,
+            serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+            JsValue::from(js_sys::Function::new_with_args("params", &func_body)),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await
+    }
+    let only_unread = |last_read_ts: Option<&str>| SlackHttpClientConfigOptions {
+        only_unread: true,
+        last_read_ts: last_read_ts.map(str::to_string),
+        ..Default::default()
+    };
+
+    for (oldest, options) in vec![
+        ("1700000500.000100", only_unread(None)),
+        ("1700000900.000100", only_unread(Some("1700000900.000100"))),
+        ("1699999999.000000", only_unread(Some("1600000000.000100"))),
+        ("1699999999.000000", SlackHttpClientConfigOptions::default()),
+    ] {
+        let result = export_from(oldest, options).await;
+        assert!(
+            !result.is_string(),
+            "Should export from {}, but was: {:#?}",
+            oldest,
+            result
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_hides_ignored_users() {
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        Some(Reactions(vec![Reaction {
+            users: vec![DEFAULT_USER_ID.to_string(), "ignored_user".to_string()],
+            count: 2,
+            ..reaction(None)
+        }])),
+        None,
+    );
+    root_message.text = Some("Ask <@ignored_user> about it".to_string());
+    let mut reply = message(
+        DEFAULT_TS_PARSED.to_string() + "1",
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    reply.user = Some("ignored_user".to_string());
+    reply.text = Some("Please do not archive me".to_string());
+    let message_response =
+        message_response(Some(true), None, Some(Messages(vec![root_message, reply])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+
+    for (ignored_user_handling, expected_reply_authors) in vec![
+        (IgnoredUserHandling::Exclude, vec![]),
+        (IgnoredUserHandling::Anonymize, vec![None]),
+    ] {
+        let options = FinalizeOptions {
+            ignored_users: vec!["ignored_user".to_string()],
+            ignored_user_handling,
+            ..Default::default()
+        };
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(false, false, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert!(!result.is_string(), "Result was a string: {:#?}", result);
+        let result: ObsidianSlackComponents =
+            serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+        let thread = &result.message_and_thread.thread;
+        assert_eq!(
+            Some("Ask @someone about it".to_string()),
+            thread[0].text,
+            "{}",
+            ignored_user_handling
+        );
+        let reaction = &thread[0].reactions.as_ref().unwrap()[0];
+        assert_eq!(vec![DEFAULT_USER_ID.to_string()], reaction.users);
+        assert_eq!(1, reaction.count);
+        assert_eq!(
+            expected_reply_authors,
+            thread
+                .iter()
+                .skip(1)
+                .map(|message| message.user.clone())
+                .collect::<Vec<Option<String>>>(),
+            "{}",
+            ignored_user_handling
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_refuses_threads_marked_do_not_archive() {
+    let root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        Some(Reactions(vec![Reaction {
+            name: "no_entry_sign".to_string(),
+            ..reaction(None)
+        }])),
+        None,
+    );
+    let message_response = message_response(Some(true), None, Some(Messages(vec![root_message])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+
+    for respect_do_not_archive in vec![false, true] {
+        let options = FinalizeOptions {
+            respect_do_not_archive,
+            ..Default::default()
+        };
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(DEFAULT_CHANNEL_ID.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(false, false, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert_eq!(
+            respect_do_not_archive,
+            result.is_string(),
+            "Unexpected result: {:#?}",
+            result
+        );
+        if respect_do_not_archive {
+            assert_eq!(
+                Some(ErrorCode::DoNotArchive.to_string()),
+                get_error_code(result.as_string().unwrap())
+            );
+        }
+    }
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_refuses_direct_messages_when_not_allowed() {
+    let im_channel_json: String = JSON::stringify(
+        &response_value(&channel_response(
+            Some(true),
+            None,
+            Some(Channel {
+                is_im: Some(true),
+                ..channel(None, None)
+            }),
+        ))
+        .unwrap(),
+    )
+    .unwrap()
+    .into();
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        im_channel_json
+    );
+    let options = SlackHttpClientConfigOptions {
+        allow_dm_export: Some(false),
+        ..Default::default()
+    };
+
+    // a `D` id is known to be a direct message without asking slack
+    for channel_id in vec!["D0000000000", DEFAULT_CHANNEL_ID] {
+        let result = get_slack_message_with_request_func_body(
+            &func_body,
+            url(
+                Some(channel_id.to_string()),
+                Some(DEFAULT_TS.to_string()),
+                None,
+            ),
+            feature_flags(false, false, false, false),
+            serde_wasm_bindgen::to_value(&options).unwrap(),
+            JsValue::UNDEFINED,
+        )
+        .await;
+
+        assert!(result.is_string(), "Result was not a string: {:#?}", result);
+        let message = result.as_string().unwrap();
+        assert_eq!(
+            Some(ErrorCode::Policy.to_string()),
+            get_error_code(message.clone()),
+            "{}",
+            message
+        );
+    }
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_gets_users_of_direct_messages() {
+    let message_response = message_response(
+        Some(true),
+        None,
+        Some(Messages(vec![message(
+            DEFAULT_TS_PARSED.to_string(),
+            DEFAULT_THREAD_TS.to_string(),
+            None,
+            None,
+            None,
+        )])),
+    );
+    let channel_response = channel_response(
+        Some(true),
+        None,
+        Some(Channel {
+            is_im: Some(true),
+            ..channel(None, Some(DEFAULT_USER_ID.to_string()))
+        }),
+    );
+    let request_func = get_mock_request_function(
+        message_response,
+        Some(user_response(Some(true), None, Some(user(None)))),
+        Some(channel_response),
+        None,
+    );
+
+    let result = get_slack_message(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(
+            Some("D0000000000".to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        serde_wasm_bindgen::to_value(&feature_flags(false, true, false, false)).unwrap(),
+        request_func,
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        Some(user(None)),
+        result.channel.and_then(|channel| channel.user_info)
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_names_group_dm_notes_after_members() {
+    let group_dm_id = "G0000000000";
+    let to_json = |value: JsValue| -> String { JSON::stringify(&value).unwrap().into() };
+    let message_json = to_json(
+        response_value(&message_response(
+            Some(true),
+            None,
+            Some(Messages(vec![message(
+                DEFAULT_TS_PARSED.to_string(),
+                DEFAULT_THREAD_TS.to_string(),
+                None,
+                None,
+                None,
+            )])),
+        ))
+        .unwrap(),
+    );
+    let channel_json = to_json(
+        response_value(&channel_response(
+            Some(true),
+            None,
+            Some(Channel {
+                id: Some(group_dm_id.to_string()),
+                name: Some("mpdm-alice--bob--mock_name-1".to_string()),
+                is_mpim: Some(true),
+                ..channel(None, None)
+            }),
+        ))
+        .unwrap(),
+    );
+    let user_json = |id: &str, name: &str| {
+        to_json(
+            response_value(&user_response(
+                Some(true),
+                None,
+                Some(User {
+                    id: id.to_string(),
+                    name: Some(name.to_string()),
+                    ..user(None)
+                }),
+            ))
+            .unwrap(),
+        )
+    };
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.replies")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("conversations.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("conversations.members")) {{
+                return Promise.resolve(JSON.stringify({{
+                    "ok": true,
+                    "members": ["U_ALICE", "U_BOB", "{}"]
+                }}))
+            }}
+            if (params.url.includes("users.info") && params.url.includes("U_ALICE")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("users.info") && params.url.includes("U_BOB")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        message_json,
+        channel_json,
+        DEFAULT_USER_ID,
+        user_json("U_ALICE", "alice"),
+        user_json("U_BOB", "bob"),
+        user_json(DEFAULT_USER_ID, "mock_name"),
+    );
+    let options = FinalizeOptions {
+        title_channel_prefix: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(group_dm_id.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, true, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        FileName(format!("alice-bob-mock_name-{}.json", DEFAULT_TS_PARSED)),
+        result.file_name
+    );
+    assert_eq!(
+        Some("alice-bob-mock_name - mock_text".to_string()),
+        result.title_suggestion
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_summarizes_huddles() {
+    let mut huddle_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    huddle_message.subtype = Some("huddle_thread".to_string());
+    huddle_message.text = Some("".to_string());
+    huddle_message.room = Some(HuddleRoom {
+        date_start: Some(1700000000),
+        date_end: Some(1700001920),
+        has_ended: Some(true),
+        participant_history: Some(vec![DEFAULT_USER_ID.to_string(), "U_ALICE".to_string()]),
+        ..Default::default()
+    });
+    let to_json = |value: JsValue| -> String { JSON::stringify(&value).unwrap().into() };
+    let user_json = |id: &str, real_name: &str| {
+        to_json(
+            response_value(&user_response(
+                Some(true),
+                None,
+                Some(User {
+                    id: id.to_string(),
+                    real_name: Some(real_name.to_string()),
+                    ..user(None)
+                }),
+            ))
+            .unwrap(),
+        )
+    };
+    let func_body = format!(
+        r#"
+        {{
+            if (params.url.includes("conversations.replies")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("users.info") && params.url.includes("U_ALICE")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            if (params.url.includes("users.info")) {{
+                return Promise.resolve(JSON.stringify({}))
+            }}
+            return Promise.reject("unexpected request " + params.url)
+        }}
+    "#,
+        to_json(
+            response_value(&message_response(
+                Some(true),
+                None,
+                Some(Messages(vec![huddle_message])),
+            ))
+            .unwrap()
+        ),
+        user_json("U_ALICE", "Alice"),
+        user_json(DEFAULT_USER_ID, "Mock"),
+    );
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(true, false, false, false),
+        JsValue::UNDEFINED,
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    let huddle_message = &result.message_and_thread.thread[0];
+    assert_eq!(
+        Some("Huddle: 32 min, 2 participants".to_string()),
+        huddle_message.text
+    );
+    assert_eq!(
+        Some(HuddleSummary {
+            duration_minutes: Some(32),
+            participants: vec![
+                HuddleParticipant {
+                    id: DEFAULT_USER_ID.to_string(),
+                    name: Some("Mock".to_string()),
+                },
+                HuddleParticipant {
+                    id: "U_ALICE".to_string(),
+                    name: Some("Alice".to_string()),
+                },
+            ],
+            line: "Huddle: 32 min, 2 participants".to_string(),
+        }),
+        huddle_message.huddle
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_collects_and_links_unfurled_references() {
+    let jira_url = "https://example.atlassian.net/browse/PROJ-123";
+    let github_url = "https://github.com/cartwmic/obsidian-slack/pull/42";
+    let mut root_message = message(
+        DEFAULT_TS_PARSED.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    root_message.text = Some(format!(
+        "Blocked by <{}>, fixed in <{}|this PR>, see <https://example.com|docs>",
+        jira_url, github_url
+    ));
+    root_message.attachments = Some(vec![
+        Attachment {
+            service_name: Some("Jira".to_string()),
+            title: Some("Deploys fail on Mondays".to_string()),
+            from_url: Some(jira_url.to_string()),
+            ..Default::default()
+        },
+        Attachment {
+            service_name: Some("GitHub".to_string()),
+            title: Some("Retry deploys".to_string()),
+            title_link: Some(github_url.to_string()),
+            ..Default::default()
+        },
+        Attachment {
+            title: Some("Docs".to_string()),
+            from_url: Some("https://example.com".to_string()),
+            ..Default::default()
+        },
+    ]);
+    let message_response = message_response(Some(true), None, Some(Messages(vec![root_message])));
+    let func_body = format!(
+        "return Promise.resolve(JSON.stringify({}))",
+        Into::<String>::into(JSON::stringify(&response_value(&message_response).unwrap()).unwrap()),
+    );
+    let options = FinalizeOptions {
+        link_references: true,
+        ..Default::default()
+    };
+
+    let result = get_slack_message_with_request_func_body(
+        &func_body,
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        feature_flags(false, false, false, false),
+        serde_wasm_bindgen::to_value(&options).unwrap(),
+        JsValue::UNDEFINED,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        References(vec![
+            Reference {
+                source: ReferenceSource::Jira,
+                id: "PROJ-123".to_string(),
+                title: Some("Deploys fail on Mondays".to_string()),
+                url: jira_url.to_string(),
+            },
+            Reference {
+                source: ReferenceSource::GitHub,
+                id: "cartwmic/obsidian-slack#42".to_string(),
+                title: Some("Retry deploys".to_string()),
+                url: github_url.to_string(),
+            },
+        ]),
+        result.references
+    );
+    assert_eq!(
+        Some(format!(
+            "Blocked by [PROJ-123]({}), fixed in [cartwmic/obsidian-slack#42]({}), see <https://example.com|docs>",
+            jira_url, github_url
+        )),
+        result.message_and_thread.thread[0].text
+    );
+}
+
+#[wasm_bindgen_test]
+fn participation_graph_counts_replies_and_reactions_between_users() {
+    let by = |ts: &str, user_id: &str, reactions: Option<Reactions>| Message {
+        user: Some(user_id.to_string()),
+        ..message(
+            ts.to_string(),
+            DEFAULT_THREAD_TS.to_string(),
+            None,
+            reactions,
+            None,
+        )
+    };
+    let reacted_by = |user_ids: &[&str]| {
+        Some(Reactions(vec![Reaction {
+            users: user_ids.iter().map(|user_id| user_id.to_string()).collect(),
+            count: user_ids.len() as u16,
+            ..reaction(None)
+        }]))
+    };
+    let thread = Messages(vec![
+        by("1.0", "alice", reacted_by(&["bob", "carol", "alice"])),
+        by("2.0", "bob", None),
+        by("3.0", "bob", reacted_by(&["alice"])),
+        by("4.0", "alice", None),
+    ]);
+
+    let graph = ParticipationGraph::build(&message_and_thread(Messages(vec![]), thread));
+
+    let graph_node = |id: &str, message_count: usize| GraphNode {
+        id: id.to_string(),
+        name: None,
+        message_count,
+    };
+    let edge = |from: &str, to: &str, kind: GraphEdgeKind, weight: usize| GraphEdge {
+        from: from.to_string(),
+        to: to.to_string(),
+        kind,
+        weight,
+    };
+    assert_eq!(
+        vec![
+            graph_node("alice", 2),
+            graph_node("bob", 2),
+            graph_node("carol", 0)
+        ],
+        graph.nodes
+    );
+    assert_eq!(
+        vec![
+            edge("alice", "bob", GraphEdgeKind::Reply, 1),
+            edge("alice", "bob", GraphEdgeKind::Reaction, 1),
+            edge("bob", "alice", GraphEdgeKind::Reply, 1),
+            edge("bob", "alice", GraphEdgeKind::Reaction, 1),
+            edge("carol", "alice", GraphEdgeKind::Reaction, 1),
+        ],
+        graph.edges
+    );
+}
+
+#[wasm_bindgen_test]
+fn finalize_message_and_thread_shares_one_copy_of_each_user() {
+    let root = message(
+        DEFAULT_THREAD_TS.to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        Some(Reactions(vec![reaction(None)])),
+        None,
+    );
+    let reply = message(
+        "0000000001.000000".to_string(),
+        DEFAULT_THREAD_TS.to_string(),
+        None,
+        None,
+        None,
+    );
+    let mut message_and_thread =
+        message_and_thread(Messages(vec![root.clone()]), Messages(vec![root, reply]));
+    let users = Users([(DEFAULT_USER_ID.to_string(), user(None))].into());
+
+    message_and_thread
+        .finalize_message_and_thread(Some(&users), &FinalizeOptions::default(), 0.0)
+        .expect("Expected to finalize the messages");
+
+    let root_author = message_and_thread.message[0].user_info.as_ref().unwrap();
+    let reactor = &message_and_thread.message[0].reactions.as_ref().unwrap()[0]
+        .users_info
+        .as_ref()
+        .unwrap()[0];
+    let reply_author = message_and_thread.thread[1].user_info.as_ref().unwrap();
+    assert_eq!(&user(None), root_author.as_ref());
+    assert!(Arc::ptr_eq(root_author, reactor));
+    assert!(Arc::ptr_eq(root_author, reply_author));
+}
+
+#[wasm_bindgen_test]
+fn order_thread_puts_messages_in_the_thread_order() {
+    let at = |ts: &str| message(ts.to_string(), "1.0".to_string(), None, None, None);
+    let ordered = |order: ThreadOrder| {
+        let mut message_and_thread = message_and_thread(
+            Messages(vec![at("1.0")]),
+            Messages(vec![at("1.0"), at("2.0"), at("3.0")]),
+        );
+        message_and_thread.order_thread(order);
+        message_and_thread
+            .thread
+            .iter()
+            .filter_map(|message| message.ts.clone())
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(vec!["1.0", "2.0", "3.0"], ordered(ThreadOrder::OldestFirst));
+    assert_eq!(vec!["3.0", "2.0", "1.0"], ordered(ThreadOrder::NewestFirst));
+    assert_eq!(vec!["1.0", "3.0", "2.0"], ordered(ThreadOrder::RootThenNewest));
+}
+
+#[wasm_bindgen_test]
+fn set_log_sink_sends_records_to_the_plugin() {
+    init_wasm(Some("debug".to_string())).expect("Should init logging");
+    let records = js_sys::Array::new();
+    let sink = js_sys::Function::new_with_args(
+        "level, target, message",
+        "this.push([level, target, message].join('|'))",
+    )
+    .bind0(&records);
+
+    set_log_sink(Some(sink), Some(false));
+    log::debug!(target: "obsidian_slack::test", "to the sink");
+    set_log_sink(None, None);
+    log::debug!(target: "obsidian_slack::test", "to the console");
+
+    assert_eq!(
+        vec!["DEBUG|obsidian_slack::test|to the sink".to_string()],
+        records
+            .iter()
+            .filter_map(|record| record.as_string())
+            .collect::<Vec<String>>()
+    );
+}
+
+#[wasm_bindgen_test]
+fn init_wasm_can_be_called_again_to_change_the_level() {
+    init_wasm(None).expect("Should init logging");
+    init_wasm(Some("warn".to_string())).expect("Should init logging again");
+    assert_eq!(log::LevelFilter::Warn, log::max_level());
+
+    set_log_level("TRACE".to_string()).expect("Should set the log level");
+    assert_eq!(log::LevelFilter::Trace, log::max_level());
+
+    let result = set_log_level("loud".to_string());
+    assert!(result.unwrap_err().as_string().unwrap().contains("loud"));
+    assert_eq!(log::LevelFilter::Trace, log::max_level());
+}
+
+#[wasm_bindgen_test]
+async fn get_slack_message_node_sends_requests_with_fetch() {
+    let ts = (DEFAULT_TS_PARSED.to_string(), DEFAULT_THREAD_TS.to_string());
+    let request_func = get_mock_request_function(
+        message_response(
+            Some(true),
+            None,
+            Some(messages(vec![ts.clone()], None, None, None)),
+        ),
+        None,
+        None,
+        None,
+    );
+    // answers like node's fetch, with the bodies of the mock request function
+    let fetch = js_sys::Function::new_with_args(
+        "request",
+        "return (url, init) => Promise.resolve(request({ ...init, url }))
+            .then(body => ({ ok: true, status: 200, text: () => Promise.resolve(body) }))",
+    )
+    .call1(&JsValue::NULL, &request_func)
+    .unwrap();
+
+    let result = get_slack_message_node(
+        "xoxc...".to_string(),
+        "xoxd...".to_string(),
+        url(
+            Some(DEFAULT_CHANNEL_ID.to_string()),
+            Some(DEFAULT_TS.to_string()),
+            None,
+        ),
+        serde_wasm_bindgen::to_value(&feature_flags(false, false, false, false)).unwrap(),
+        JsValue::UNDEFINED,
+        fetch,
+    )
+    .await;
+
+    assert!(!result.is_string(), "Result was a string: {:#?}", result);
+    let result: ObsidianSlackComponents =
+        serde_wasm_bindgen::from_value(result).expect("Should parse return object");
+    assert_eq!(
+        message_and_thread(
+            messages(vec![ts.clone()], None, None, None),
+            messages(vec![ts], None, None, None),
+        ),
+        result.message_and_thread
+    );
+}
+
+#[wasm_bindgen_test]
+fn take_internal_bug_is_null_without_a_panic() {
+    init_wasm(None).expect("Should init logging");
+
+    // a panic aborts the test in wasm, so only the plugin's path without one is
+    // covered here, see tests/panics.rs for recording panics
+    assert!(take_internal_bug(None).is_null());
+    assert!(take_internal_bug(Some("de-DE".to_string())).is_null());
+}